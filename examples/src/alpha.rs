@@ -0,0 +1,198 @@
+// ============================================================
+// アルファ処理（不透明化・乗算済み変換・乗算済み判定）
+// ============================================================
+//
+// センダーによって乗算済み（premultiplied）かどうかが異なるため、
+// 受信・出力の前に揃えられるようにする。
+// 計算は格納値（sRGB エンコードのまま）で行う。
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::Serialize;
+
+use crate::hdr::{decode_pixel, encode_pixel};
+use crate::image::{DxgiFormat, ImageBuf, ImageError, ImageView};
+
+/// 受信・出力時に適用するアルファ操作
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AlphaOp {
+    #[default]
+    None,
+    /// アルファを不透明に（SpoutCopy::ClearAlpha 相当）
+    ClearAlpha,
+    /// ストレート → 乗算済み
+    Premultiply,
+    /// 乗算済み → ストレート
+    Unpremultiply,
+}
+
+impl AlphaOp {
+    pub fn apply(self, buf: &mut ImageBuf) -> Result<(), ImageError> {
+        match self {
+            Self::None => Ok(()),
+            Self::ClearAlpha => clear_alpha(buf),
+            Self::Premultiply => premultiply(buf),
+            Self::Unpremultiply => unpremultiply(buf),
+        }
+    }
+}
+
+impl fmt::Display for AlphaOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::None => write!(f, "none"),
+            Self::ClearAlpha => write!(f, "opaque"),
+            Self::Premultiply => write!(f, "premultiply"),
+            Self::Unpremultiply => write!(f, "unpremultiply"),
+        }
+    }
+}
+
+impl FromStr for AlphaOp {
+    type Err = String;
+
+    /// `none` / `opaque` / `premultiply` / `unpremultiply`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(Self::None),
+            "opaque" => Ok(Self::ClearAlpha),
+            "premultiply" => Ok(Self::Premultiply),
+            "unpremultiply" => Ok(Self::Unpremultiply),
+            _ => Err(format!("unknown alpha op: {} (none, opaque, premultiply, unpremultiply)", s)),
+        }
+    }
+}
+
+fn is_8bit(format: DxgiFormat) -> bool {
+    format.bytes_per_pixel() == Some(4) && format != DxgiFormat::R10G10B10A2Unorm
+}
+
+/// 全ピクセルに f32 の [R, G, B, A] で処理を適用（8bit 以外の共通経路）
+fn for_each_pixel(buf: &mut ImageBuf, f: impl Fn([f32; 4]) -> [f32; 4]) -> Result<(), ImageError> {
+    let format = buf.format();
+    let bpp = buf.view().bytes_per_pixel();
+    for y in 0..buf.height() {
+        for px in buf.row_mut(y).chunks_exact_mut(bpp) {
+            let rgba = decode_pixel(px, format)?;
+            encode_pixel(f(rgba), format, px)?;
+        }
+    }
+    Ok(())
+}
+
+/// アルファを不透明（1.0）にする
+pub fn clear_alpha(buf: &mut ImageBuf) -> Result<(), ImageError> {
+    if is_8bit(buf.format()) {
+        for y in 0..buf.height() {
+            for px in buf.row_mut(y).chunks_exact_mut(4) {
+                px[3] = 255;
+            }
+        }
+        return Ok(());
+    }
+    for_each_pixel(buf, |[r, g, b, _]| [r, g, b, 1.0])
+}
+
+/// カラーにアルファを掛ける
+pub fn premultiply(buf: &mut ImageBuf) -> Result<(), ImageError> {
+    if is_8bit(buf.format()) {
+        for y in 0..buf.height() {
+            for px in buf.row_mut(y).chunks_exact_mut(4) {
+                let a = px[3] as u32;
+                for c in &mut px[..3] {
+                    *c = ((*c as u32 * a + 127) / 255) as u8;
+                }
+            }
+        }
+        return Ok(());
+    }
+    for_each_pixel(buf, |[r, g, b, a]| [r * a, g * a, b * a, a])
+}
+
+/// カラーをアルファで割る（アルファ 0 のピクセルは黒）
+pub fn unpremultiply(buf: &mut ImageBuf) -> Result<(), ImageError> {
+    if is_8bit(buf.format()) {
+        for y in 0..buf.height() {
+            for px in buf.row_mut(y).chunks_exact_mut(4) {
+                let a = px[3] as u32;
+                for c in &mut px[..3] {
+                    *c = (*c as u32 * 255 + a / 2).checked_div(a).map_or(0, |v| v.min(255) as u8);
+                }
+            }
+        }
+        return Ok(());
+    }
+    for_each_pixel(buf, |[r, g, b, a]| {
+        if a <= 0.0 { [0.0, 0.0, 0.0, 0.0] } else { [r / a, g / a, b / a, a] }
+    })
+}
+
+/// 乗算済み判定の結果
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AlphaMode {
+    /// 全ピクセル不透明（乗算済みかどうかは区別できない）
+    Opaque,
+    /// 半透明ピクセルがあり、カラーがアルファを超えるピクセルがない
+    Premultiplied,
+    /// カラーがアルファを超えるピクセルがある
+    Straight,
+}
+
+impl fmt::Display for AlphaMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Opaque => write!(f, "opaque"),
+            Self::Premultiplied => write!(f, "premultiplied"),
+            Self::Straight => write!(f, "straight"),
+        }
+    }
+}
+
+/// 乗算済み判定の詳細
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct AlphaAnalysis {
+    pub mode: AlphaMode,
+    /// アルファが 1.0 未満のピクセル数
+    pub translucent_pixels: u64,
+    /// いずれかのカラーがアルファを超えたピクセル数
+    pub violations: u64,
+    pub total_pixels: u64,
+}
+
+/// 乗算済みかどうかを判定（カラー > アルファ + tolerance なら乗算済みではない）
+///
+/// `tolerance` は 0..1 の格納値で指定（8bit なら 1/255 程度）。
+pub fn detect_premultiplied(view: &ImageView, tolerance: f32) -> Result<AlphaAnalysis, ImageError> {
+    let format = view.format();
+    let bpp = view.bytes_per_pixel();
+    let mut translucent_pixels = 0;
+    let mut violations = 0;
+
+    for y in 0..view.height() {
+        for px in view.row(y).chunks_exact(bpp) {
+            let [r, g, b, a] = decode_pixel(px, format)?;
+            if a < 1.0 {
+                translucent_pixels += 1;
+            }
+            if r.max(g).max(b) > a + tolerance {
+                violations += 1;
+            }
+        }
+    }
+
+    let mode = if translucent_pixels == 0 {
+        AlphaMode::Opaque
+    } else if violations == 0 {
+        AlphaMode::Premultiplied
+    } else {
+        AlphaMode::Straight
+    };
+    Ok(AlphaAnalysis {
+        mode,
+        translucent_pixels,
+        violations,
+        total_pixels: view.width() as u64 * view.height() as u64,
+    })
+}
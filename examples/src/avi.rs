@@ -0,0 +1,459 @@
+// ============================================================
+// MJPEG の AVI 書き出し（外部エンコーダーなしで再生できる録画）
+// ============================================================
+//
+// AVI 1.0（RIFF）に JPEG フレームを 1 本の映像ストリームとして並べ、
+// 最後に idx1 インデックスを書く。フレームレートは受信時刻から推定し、
+// finish() でヘッダーを書き直す。検証用に最小限のパーサーも置く。
+
+use std::fmt;
+use std::io::{self, Seek, SeekFrom, Write};
+use std::ops::Range;
+
+use crate::image::{ImageError, ImageView};
+use crate::jpeg::{encode_jpeg, JpegOptions};
+use crate::stream::{FpsEstimator, FrameRate};
+
+/// AVI 1.0 の 1 ファイルの上限（古いプレーヤーとの互換のため 1 GiB）
+pub const MAX_FILE_SIZE: u64 = 1 << 30;
+/// movi の前までのヘッダーの長さ（idx1 のオフセットは最後の 'movi' の 4 文字から数える）
+const HEADER_LEN: u64 = 224;
+
+const AVIF_HASINDEX: u32 = 0x10;
+const AVIF_ISINTERLEAVED: u32 = 0x100;
+const AVIIF_KEYFRAME: u32 = 0x10;
+
+/// AVI 書き出しの失敗
+#[derive(Debug)]
+pub enum AviError {
+    Io(io::Error),
+    Image(ImageError),
+    /// 最初のフレームとサイズが違う
+    SizeChanged { expected: (u32, u32), actual: (u32, u32) },
+    /// ファイルサイズの上限に達した（それまでのフレームは finish で保存できる）
+    TooLarge,
+}
+
+impl fmt::Display for AviError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "I/O error: {}", e),
+            Self::Image(e) => write!(f, "{}", e),
+            Self::SizeChanged { expected, actual } => write!(
+                f,
+                "frame size changed from {}x{} to {}x{}",
+                expected.0, expected.1, actual.0, actual.1
+            ),
+            Self::TooLarge => write!(f, "AVI file size limit ({} MiB) reached", MAX_FILE_SIZE >> 20),
+        }
+    }
+}
+
+impl std::error::Error for AviError {}
+
+impl From<io::Error> for AviError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<ImageError> for AviError {
+    fn from(e: ImageError) -> Self {
+        Self::Image(e)
+    }
+}
+
+/// チャンク（4 文字 + サイズ）を追加
+fn push_chunk(out: &mut Vec<u8>, id: &[u8; 4], data: &[u8]) {
+    out.extend(id);
+    out.extend((data.len() as u32).to_le_bytes());
+    out.extend(data);
+}
+
+/// LIST チャンクの先頭（4 文字 + サイズ + 種類）を追加
+fn push_list(out: &mut Vec<u8>, size: u32, kind: &[u8; 4]) {
+    out.extend(b"LIST");
+    out.extend(size.to_le_bytes());
+    out.extend(kind);
+}
+
+fn u32s(values: &[u32]) -> Vec<u8> {
+    values.iter().flat_map(|v| v.to_le_bytes()).collect()
+}
+
+/// MJPEG の AVI を書き出す（フレームを順に書き、最後に finish）
+pub struct AviWriter<W: Write + Seek> {
+    out: W,
+    options: JpegOptions,
+    size: Option<(u32, u32)>,
+    /// (movi からのオフセット, JPEG のサイズ)
+    index: Vec<(u32, u32)>,
+    /// movi チャンクの中身の長さ（'movi' の 4 文字を含む）
+    movi_len: u64,
+    max_frame: u32,
+    estimator: FpsEstimator,
+}
+
+impl<W: Write + Seek> AviWriter<W> {
+    /// 仮のヘッダーを書いて始める（サイズ・レートは finish で確定）
+    pub fn new(mut out: W, options: JpegOptions) -> io::Result<Self> {
+        out.write_all(&[0; HEADER_LEN as usize])?;
+        Ok(Self {
+            out,
+            options,
+            size: None,
+            index: Vec::new(),
+            movi_len: 4,
+            max_frame: 0,
+            estimator: FpsEstimator::new(),
+        })
+    }
+
+    pub fn frames(&self) -> u64 {
+        self.index.len() as u64
+    }
+
+    /// これまでのファイルサイズ（idx1 を除く）
+    pub fn bytes_written(&self) -> u64 {
+        HEADER_LEN + self.movi_len - 4
+    }
+
+    /// 受信時刻から推定したフレームレート
+    pub fn estimated_rate(&self) -> Option<FrameRate> {
+        self.estimator.rate()
+    }
+
+    /// JPEG にエンコードして 1 フレーム追加（`timestamp_us` はフレームレートの推定に使う）
+    pub fn write_image(&mut self, view: &ImageView, timestamp_us: u64) -> Result<(), AviError> {
+        let actual = (view.width(), view.height());
+        let expected = *self.size.get_or_insert(actual);
+        if actual != expected {
+            return Err(AviError::SizeChanged { expected, actual });
+        }
+        let jpeg = encode_jpeg(view, &self.options)?;
+        self.write_jpeg(&jpeg, timestamp_us)
+    }
+
+    /// エンコード済みの JPEG を 1 フレーム追加（サイズは呼び出し側でそろえる）
+    pub fn write_jpeg(&mut self, jpeg: &[u8], timestamp_us: u64) -> Result<(), AviError> {
+        let padded = jpeg.len() as u64 + (jpeg.len() as u64 & 1);
+        // idx1 の分も見込む
+        let index_len = 8 + 16 * (self.index.len() as u64 + 1);
+        if self.bytes_written() + 8 + padded + index_len > MAX_FILE_SIZE {
+            return Err(AviError::TooLarge);
+        }
+        self.out.write_all(b"00dc")?;
+        self.out.write_all(&(jpeg.len() as u32).to_le_bytes())?;
+        self.out.write_all(jpeg)?;
+        if jpeg.len() % 2 == 1 {
+            self.out.write_all(&[0])?;
+        }
+        self.index.push((self.movi_len as u32, jpeg.len() as u32));
+        self.movi_len += 8 + padded;
+        self.max_frame = self.max_frame.max(jpeg.len() as u32);
+        self.estimator.record(timestamp_us);
+        Ok(())
+    }
+
+    /// ヘッダー（movi の LIST の先頭まで）
+    fn header(&self, rate: FrameRate, riff_len: u64) -> Vec<u8> {
+        let (width, height) = self.size.unwrap_or((0, 0));
+        let frames = self.index.len() as u32;
+        let buffer = self.max_frame + 8;
+        let micro_sec_per_frame = (1_000_000.0 / rate.fps()).round() as u32;
+        let max_bytes_per_sec = (buffer as f64 * rate.fps()).ceil() as u32;
+
+        let avih = u32s(&[
+            micro_sec_per_frame,
+            max_bytes_per_sec,
+            0,
+            AVIF_HASINDEX | AVIF_ISINTERLEAVED,
+            frames,
+            0,
+            1,
+            buffer,
+            width,
+            height,
+            0,
+            0,
+            0,
+            0,
+        ]);
+        let mut strh = Vec::with_capacity(56);
+        strh.extend(b"vidsMJPG");
+        // フラグ, 優先度・言語, 初期フレーム, scale, rate, 開始, 長さ, バッファ, 品質, サンプルサイズ
+        strh.extend(u32s(&[0, 0, 0, rate.den, rate.num, 0, frames, buffer, u32::MAX, 0]));
+        for v in [0, 0, width as i16, height as i16] {
+            strh.extend(v.to_le_bytes());
+        }
+        // BITMAPINFOHEADER
+        let mut strf = u32s(&[40, width, height]);
+        strf.extend(1u16.to_le_bytes());
+        strf.extend(24u16.to_le_bytes());
+        strf.extend(b"MJPG");
+        strf.extend(u32s(&[width * height * 3, 0, 0, 0, 0]));
+
+        let mut strl = Vec::with_capacity(112);
+        push_chunk(&mut strl, b"strh", &strh);
+        push_chunk(&mut strl, b"strf", &strf);
+        let mut out = Vec::with_capacity(HEADER_LEN as usize);
+        out.extend(b"RIFF");
+        out.extend((riff_len as u32).to_le_bytes());
+        out.extend(b"AVI ");
+        push_list(&mut out, 4 + 8 + avih.len() as u32 + 12 + strl.len() as u32, b"hdrl");
+        push_chunk(&mut out, b"avih", &avih);
+        push_list(&mut out, 4 + strl.len() as u32, b"strl");
+        out.extend(strl);
+        push_list(&mut out, self.movi_len as u32, b"movi");
+        debug_assert_eq!(out.len() as u64, HEADER_LEN);
+        out
+    }
+
+    /// idx1 を書き、ヘッダーを確定させる（`rate` が None なら推定値、推定できなければ 60 fps）
+    pub fn finish(mut self, rate: Option<FrameRate>) -> io::Result<W> {
+        let rate = rate.or(self.estimator.rate()).unwrap_or_default();
+        let mut idx1 = Vec::with_capacity(self.index.len() * 16);
+        for &(offset, size) in &self.index {
+            idx1.extend(b"00dc");
+            idx1.extend(u32s(&[AVIIF_KEYFRAME, offset, size]));
+        }
+        let mut chunk = Vec::with_capacity(idx1.len() + 8);
+        push_chunk(&mut chunk, b"idx1", &idx1);
+        self.out.write_all(&chunk)?;
+
+        let riff_len = self.bytes_written() + chunk.len() as u64 - 8;
+        let header = self.header(rate, riff_len);
+        self.out.seek(SeekFrom::Start(0))?;
+        self.out.write_all(&header)?;
+        self.out.seek(SeekFrom::End(0))?;
+        self.out.flush()?;
+        Ok(self.out)
+    }
+}
+
+/// 読み取った AVI の情報
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AviInfo {
+    pub width: u32,
+    pub height: u32,
+    pub codec: [u8; 4],
+    pub rate: FrameRate,
+    pub micro_sec_per_frame: u32,
+    /// avih の総フレーム数
+    pub total_frames: u32,
+    /// movi 内のフレームのデータ範囲（ファイル先頭から）
+    pub frames: Vec<Range<usize>>,
+}
+
+fn read_u32(bytes: &[u8], at: usize) -> Result<u32, String> {
+    bytes
+        .get(at..at + 4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .ok_or_else(|| format!("truncated at offset {}", at))
+}
+
+/// チャンクの 4 文字とデータ範囲
+type Chunk = ([u8; 4], Range<usize>);
+
+/// チャンクを順に読む
+fn chunks(bytes: &[u8], range: Range<usize>) -> Result<Vec<Chunk>, String> {
+    let mut out = Vec::new();
+    let mut at = range.start;
+    while at + 8 <= range.end {
+        let id: [u8; 4] = bytes[at..at + 4].try_into().map_err(|_| "truncated chunk id".to_string())?;
+        let len = read_u32(bytes, at + 4)? as usize;
+        let data = at + 8..at + 8 + len;
+        if data.end > range.end {
+            return Err(format!("chunk {} at {} overruns its parent", String::from_utf8_lossy(&id), at));
+        }
+        out.push((id, data.clone()));
+        at = data.end + (len & 1);
+    }
+    Ok(out)
+}
+
+/// MJPEG の AVI を読み、ヘッダー・movi・idx1 の整合性を確かめる
+pub fn parse_avi(bytes: &[u8]) -> Result<AviInfo, String> {
+    if bytes.len() < 12 || &bytes[0..4] != b"RIFF" || &bytes[8..12] != b"AVI " {
+        return Err("not a RIFF AVI file".to_string());
+    }
+    if read_u32(bytes, 4)? as usize + 8 != bytes.len() {
+        return Err(format!("RIFF size {} does not match file size {}", read_u32(bytes, 4)?, bytes.len()));
+    }
+
+    let mut avih = None;
+    let mut strh = None;
+    let mut strf = None;
+    let mut movi = None;
+    let mut idx1 = None;
+    for (id, data) in chunks(bytes, 12..bytes.len())? {
+        match (&id, bytes.get(data.start..data.start + 4)) {
+            (b"LIST", Some(b"hdrl")) => {
+                for (id, data) in chunks(bytes, data.start + 4..data.end)? {
+                    match (&id, bytes.get(data.start..data.start + 4)) {
+                        (b"avih", _) => avih = Some(data),
+                        (b"LIST", Some(b"strl")) => {
+                            for (id, data) in chunks(bytes, data.start + 4..data.end)? {
+                                match &id {
+                                    b"strh" => strh = Some(data),
+                                    b"strf" => strf = Some(data),
+                                    _ => {}
+                                }
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            (b"LIST", Some(b"movi")) => movi = Some(data),
+            (b"idx1", _) => idx1 = Some(data),
+            _ => {}
+        }
+    }
+    let avih = avih.ok_or("missing avih")?;
+    let strh = strh.ok_or("missing strh")?;
+    let strf = strf.ok_or("missing strf")?;
+    let movi = movi.ok_or("missing movi")?;
+    let idx1 = idx1.ok_or("missing idx1")?;
+
+    if &bytes[strh.start..strh.start + 4] != b"vids" {
+        return Err("first stream is not video".to_string());
+    }
+    let codec: [u8; 4] = bytes[strh.start + 4..strh.start + 8].try_into().map_err(|_| "truncated strh")?;
+    let scale = read_u32(bytes, strh.start + 20)?;
+    let rate = read_u32(bytes, strh.start + 24)?;
+    let length = read_u32(bytes, strh.start + 32)?;
+    if scale == 0 || rate == 0 {
+        return Err(format!("invalid rate {}/{}", rate, scale));
+    }
+    let total_frames = read_u32(bytes, avih.start + 16)?;
+    let (width, height) = (read_u32(bytes, avih.start + 32)?, read_u32(bytes, avih.start + 36)?);
+    if (read_u32(bytes, strf.start + 4)?, read_u32(bytes, strf.start + 8)?) != (width, height) {
+        return Err("strf size does not match avih".to_string());
+    }
+
+    let movi_base = movi.start;
+    let frames: Vec<Range<usize>> = chunks(bytes, movi.start + 4..movi.end)?
+        .into_iter()
+        .filter(|(id, _)| &id[2..] == b"dc" || &id[2..] == b"db")
+        .map(|(_, data)| data)
+        .collect();
+    if frames.len() != total_frames as usize || length != total_frames {
+        return Err(format!(
+            "frame count mismatch: avih {}, strh {}, movi {}",
+            total_frames,
+            length,
+            frames.len()
+        ));
+    }
+
+    let entries = &bytes[idx1.clone()];
+    if entries.len() != frames.len() * 16 {
+        return Err(format!("idx1 has {} bytes for {} frames", entries.len(), frames.len()));
+    }
+    for (i, (entry, frame)) in entries.chunks_exact(16).zip(&frames).enumerate() {
+        let offset = read_u32(entry, 8)? as usize;
+        let size = read_u32(entry, 12)? as usize;
+        // オフセットは 'movi' の 4 文字から数える
+        if movi_base + offset + 8 != frame.start || size != frame.len() {
+            return Err(format!("idx1 entry {} does not point at frame {}", i, i));
+        }
+        if read_u32(entry, 4)? & AVIIF_KEYFRAME == 0 {
+            return Err(format!("idx1 entry {} is not a key frame", i));
+        }
+    }
+
+    Ok(AviInfo {
+        width,
+        height,
+        codec,
+        rate: FrameRate { num: rate, den: scale },
+        micro_sec_per_frame: read_u32(bytes, avih.start)?,
+        total_frames,
+        frames,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    use crate::image::{DxgiFormat, ImageBuf};
+
+    fn frame(width: u32, height: u32, seed: u8) -> ImageBuf {
+        let data = (0..width * height * 4).map(|i| (i as u8).wrapping_mul(seed)).collect();
+        ImageBuf::from_vec(data, width, height, width as usize * 4, DxgiFormat::B8G8R8A8Unorm).unwrap()
+    }
+
+    fn u32_at(bytes: &[u8], at: usize) -> u32 {
+        u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap())
+    }
+
+    #[test]
+    fn written_frames_parse_back() {
+        const FRAMES: u32 = 12;
+        let mut writer = AviWriter::new(Cursor::new(Vec::new()), JpegOptions::default()).unwrap();
+        let mut jpegs = Vec::new();
+        for i in 0..FRAMES {
+            let image = frame(32, 16, i as u8 + 1);
+            jpegs.push(encode_jpeg(&image.view(), &JpegOptions::default()).unwrap());
+            // 29.97 fps の間隔
+            writer.write_image(&image.view(), i as u64 * 1_001_000 / 30).unwrap();
+        }
+        assert_eq!(writer.estimated_rate(), Some(FrameRate { num: 30000, den: 1001 }));
+        let bytes = writer.finish(None).unwrap().into_inner();
+
+        let info = parse_avi(&bytes).unwrap();
+        assert_eq!((info.width, info.height, &info.codec), (32, 16, b"MJPG"));
+        assert_eq!(info.total_frames, FRAMES);
+        assert_eq!(info.frames.len(), FRAMES as usize);
+        assert_eq!(info.rate, FrameRate { num: 30000, den: 1001 });
+        assert_eq!(info.micro_sec_per_frame, 33367);
+        for (range, jpeg) in info.frames.iter().zip(&jpegs) {
+            assert_eq!(&bytes[range.clone()], &jpeg[..]);
+        }
+
+        // RIFF のサイズはファイル全体から 8 バイト（'RIFF' とサイズ）を引いたもの
+        assert_eq!(u32_at(&bytes, 4) as usize, bytes.len() - 8);
+        // avih の先頭は dwMicroSecPerFrame
+        assert_eq!(&bytes[24..28], b"avih");
+        assert_eq!(u32_at(&bytes, 32), 33367);
+
+        // idx1 のオフセットは movi の LIST の 'movi' の 4 文字から数える
+        let movi = HEADER_LEN as usize - 4;
+        assert_eq!(&bytes[movi..movi + 4], b"movi");
+        let idx1 = info.frames.last().unwrap().end + (info.frames.last().unwrap().len() & 1);
+        assert_eq!(&bytes[idx1..idx1 + 4], b"idx1");
+        assert_eq!(u32_at(&bytes, idx1 + 4), FRAMES * 16);
+        for (i, range) in info.frames.iter().enumerate() {
+            let entry = idx1 + 8 + i * 16;
+            assert_eq!(&bytes[entry..entry + 4], b"00dc");
+            assert_eq!(u32_at(&bytes, entry + 8) as usize, range.start - 8 - movi);
+            assert_eq!(u32_at(&bytes, entry + 12) as usize, range.len());
+        }
+        assert_eq!(idx1 + 8 + FRAMES as usize * 16, bytes.len());
+    }
+
+    #[test]
+    fn odd_sized_frames_are_padded() {
+        let mut writer = AviWriter::new(Cursor::new(Vec::new()), JpegOptions::default()).unwrap();
+        for (i, len) in [5usize, 8, 3].into_iter().enumerate() {
+            writer.write_jpeg(&vec![0xAB; len], i as u64 * 40_000).unwrap();
+        }
+        let bytes = writer.finish(Some(FrameRate { num: 25, den: 1 })).unwrap().into_inner();
+        let info = parse_avi(&bytes).unwrap();
+        assert_eq!(info.frames.iter().map(|range| range.len()).collect::<Vec<_>>(), [5, 8, 3]);
+        assert_eq!(info.micro_sec_per_frame, 40_000);
+        assert_eq!(u32_at(&bytes, 4) as usize, bytes.len() - 8);
+    }
+
+    #[test]
+    fn frame_size_must_not_change() {
+        let mut writer = AviWriter::new(Cursor::new(Vec::new()), JpegOptions::default()).unwrap();
+        writer.write_image(&frame(16, 16, 1).view(), 0).unwrap();
+        let error = writer.write_image(&frame(8, 16, 1).view(), 1).unwrap_err();
+        assert!(matches!(error, AviError::SizeChanged { expected: (16, 16), actual: (8, 16) }));
+        assert_eq!(writer.frames(), 1);
+    }
+}
@@ -0,0 +1,136 @@
+// ============================================================
+// 生フレームのダンプを調べる（センダー・DLL なしで動く）
+// ============================================================
+//
+// `--dump` で保存したダンプを読み込み、診断・比較・画像の書き出しをする。
+// 比較の基準はダンプのほか、読み込める画像ファイル（PNG / BMP / QOI など）でもよい。
+// 例: `inspect_dump frame.json --compare golden.json --output frame.png`
+
+use std::path::{Path, PathBuf};
+
+use ping::color::ColorSpace;
+use ping::compare::{compare, heatmap, AlphaCompare, CompareTolerances};
+use ping::diagnostics::{DiagnosticReport, DiagnosticThresholds};
+use ping::dump::{load_dump, load_reference};
+use ping::export::save_image;
+use ping::png::save_png;
+
+/// 引数
+#[derive(Debug, Default)]
+struct Options {
+    dump: Option<PathBuf>,
+    assume_linear: bool,
+    thresholds: DiagnosticThresholds,
+    compare: Option<PathBuf>,
+    compare_tolerances: CompareTolerances,
+    compare_alpha: AlphaCompare,
+    output: Option<PathBuf>,
+}
+
+/// 引数を解析
+/// 例: `frame.json --assume-linear --thresholds black=1,white=254`
+///     `frame.json --compare golden.json --compare-tolerance psnr=40 --compare-alpha premultiplied`
+///     `frame.json --compare golden.png`（画像ファイルはダンプのフォーマットに変換して比較）
+///     `frame.json --output frame.exr`（拡張子で形式を選ぶ）
+fn parse_args(args: &[String]) -> Options {
+    let mut options = Options::default();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--assume-linear" => options.assume_linear = true,
+            "--thresholds" => match iter.next().map(|v| v.parse::<DiagnosticThresholds>()) {
+                Some(Ok(thresholds)) => options.thresholds = thresholds,
+                Some(Err(e)) => println!("Ignoring --thresholds: {}", e),
+                None => println!("Ignoring --thresholds: missing value"),
+            },
+            "--compare" => match iter.next() {
+                Some(path) => options.compare = Some(PathBuf::from(path)),
+                None => println!("Ignoring --compare: missing value"),
+            },
+            "--compare-tolerance" => match iter.next().map(|v| v.parse::<CompareTolerances>()) {
+                Some(Ok(tolerances)) => options.compare_tolerances = tolerances,
+                Some(Err(e)) => println!("Ignoring --compare-tolerance: {}", e),
+                None => println!("Ignoring --compare-tolerance: missing value"),
+            },
+            "--compare-alpha" => match iter.next().map(|v| v.parse::<AlphaCompare>()) {
+                Some(Ok(alpha)) => options.compare_alpha = alpha,
+                Some(Err(e)) => println!("Ignoring --compare-alpha: {}", e),
+                None => println!("Ignoring --compare-alpha: missing value"),
+            },
+            "--output" => match iter.next() {
+                Some(path) => options.output = Some(PathBuf::from(path)),
+                None => println!("Ignoring --output: missing value"),
+            },
+            path if options.dump.is_none() && !path.starts_with("--") => options.dump = Some(PathBuf::from(path)),
+            other => println!("Ignoring unknown argument: {}", other),
+        }
+    }
+    options
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let options = parse_args(&args);
+    let Some(dump_path) = &options.dump else {
+        println!("Usage: inspect_dump <dump.json> [--compare <dump.json|image>] [--output <image>]");
+        std::process::exit(2);
+    };
+
+    let dump = match load_dump(dump_path) {
+        Ok(dump) => dump,
+        Err(e) => {
+            println!("Failed to load {}: {}", dump_path.display(), e);
+            std::process::exit(1);
+        }
+    };
+    println!("Dump: {}", dump_path.display());
+    println!("  {}", dump.info);
+    let view = dump.image.view();
+
+    // 画像診断（受信時と同じく元のフォーマットのまま）
+    let color_space = ColorSpace::for_format(view.format(), options.assume_linear);
+    match DiagnosticReport::analyze(&view, color_space, options.thresholds) {
+        Ok(report) => {
+            println!();
+            for line in report.to_string().lines() {
+                println!("  {}", line);
+            }
+        }
+        Err(e) => println!("\n  [Image Diagnostics] skipped: {}", e),
+    }
+
+    if let Some(reference_path) = &options.compare {
+        match load_reference(reference_path, view.format()) {
+            Ok(reference) => {
+                let reference = reference.view();
+                match compare(&reference, &view, options.compare_alpha, options.compare_tolerances) {
+                    Ok(comparison) => {
+                        println!();
+                        for line in comparison.to_string().lines() {
+                            println!("  {}", line);
+                        }
+                        // 許容値の 2 倍で赤になるスケール
+                        let scale = options.compare_tolerances.max_abs_error * 2.0;
+                        let diff_path = Path::new("dump_diff.png");
+                        match heatmap(&reference, &view, options.compare_alpha, scale) {
+                            Ok(diff) => match save_png(diff_path, &diff.view()) {
+                                Ok(()) => println!("    ✓ Saved diff heatmap: {}", diff_path.display()),
+                                Err(e) => println!("    ✗ Failed to save diff heatmap: {}", e),
+                            },
+                            Err(e) => println!("    ✗ Failed to render diff heatmap: {}", e),
+                        }
+                    }
+                    Err(e) => println!("\n  [Compare] skipped: {}", e),
+                }
+            }
+            Err(e) => println!("\n  [Compare] Failed to load {}: {}", reference_path.display(), e),
+        }
+    }
+
+    if let Some(path) = &options.output {
+        match save_image(path, &view) {
+            Ok(()) => println!("\n  ✓ Saved image: {}", path.display()),
+            Err(e) => println!("\n  ✗ Failed to save {}: {}", path.display(), e),
+        }
+    }
+}
@@ -0,0 +1,272 @@
+// ============================================================
+// 共有メモリのセンダー一覧で送信を試す（DLL なしで動く）
+// ============================================================
+//
+// Linux で Spout のセンダー登録を再現した shm モジュールを使い、
+// フレームカウンター（または行帯）パターンを送るセンダーを登録したり、一覧を表示したり、
+// 共有メモリのフレームを受信してスタンプやティアリングを確かめたりする。
+// 例: `shm_sender --send 10 --name "ping pattern"` / 別の端末で `shm_sender --receive 5`
+
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use ping::image::DxgiFormat;
+use ping::latency::{Clock, MonotonicClock};
+use ping::ffi::SpoutDxResult;
+use ping::pattern::{FrameStamp, Pattern};
+use ping::sender::Sender;
+use ping::shm::{ShmReceiver, ShmRegistry, ShmSender};
+use ping::tearing::{RowBands, SendPattern, TearingTracker};
+
+/// 引数
+#[derive(Debug)]
+struct Options {
+    list: bool,
+    send_seconds: u32,
+    receive_seconds: u32,
+    name: Option<String>,
+    size: (u32, u32),
+    fps: u32,
+    format: DxgiFormat,
+    pattern: SendPattern,
+    bands: RowBands,
+    root: Option<PathBuf>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            list: false,
+            send_seconds: 0,
+            receive_seconds: 0,
+            name: None,
+            size: (640, 360),
+            fps: 60,
+            format: DxgiFormat::B8G8R8A8Unorm,
+            pattern: SendPattern::FrameCounter,
+            bands: RowBands::default(),
+            root: None,
+        }
+    }
+}
+
+/// `<幅>x<高さ>`
+fn parse_size(value: &str) -> Option<(u32, u32)> {
+    let (width, height) = value.split_once('x')?;
+    let size = (width.parse().ok()?, height.parse().ok()?);
+    (size.0 > 0 && size.1 > 0).then_some(size)
+}
+
+/// 引数を解析
+/// 例: `--list`
+///     `--send 10 --name "ping pattern" --size 1280x720 --fps 30`
+///     `--send 10 --format 10`（センダーのフォーマットを DXGI_FORMAT の数値で指定、パターンは変換して送る）
+///     `--receive 5 --name "ping pattern"`（--name がなければアクティブなセンダー）
+///     `--send 10 --pattern bands --bands 16` / `--receive 10 --pattern bands --bands 16`（ティアリング検査）
+///     `--root /tmp/spoutdx --list`（既定は $SPOUTDX_SHM_DIR、なければ /dev/shm/spoutdx）
+fn parse_args(args: &[String]) -> Options {
+    let mut options = Options::default();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--list" => options.list = true,
+            "--send" => match iter.next().and_then(|v| v.parse::<u32>().ok()) {
+                Some(seconds) if seconds > 0 => options.send_seconds = seconds,
+                _ => println!("Ignoring --send: expected a positive number of seconds"),
+            },
+            "--receive" => match iter.next().and_then(|v| v.parse::<u32>().ok()) {
+                Some(seconds) if seconds > 0 => options.receive_seconds = seconds,
+                _ => println!("Ignoring --receive: expected a positive number of seconds"),
+            },
+            "--name" => match iter.next() {
+                Some(name) => options.name = Some(name.clone()),
+                None => println!("Ignoring --name: missing value"),
+            },
+            "--size" => match iter.next().and_then(|v| parse_size(v)) {
+                Some(size) => options.size = size,
+                None => println!("Ignoring --size: expected <width>x<height>"),
+            },
+            "--fps" => match iter.next().and_then(|v| v.parse::<u32>().ok()) {
+                Some(fps) if (1..=1000).contains(&fps) => options.fps = fps,
+                _ => println!("Ignoring --fps: expected 1-1000"),
+            },
+            "--format" => {
+                let format = iter.next().and_then(|v| v.parse::<u32>().ok()).map(DxgiFormat::from_raw);
+                match format.filter(|format| format.bytes_per_pixel().is_some()) {
+                    Some(format) => options.format = format,
+                    None => println!("Ignoring --format: expected a supported DXGI_FORMAT number"),
+                }
+            }
+            "--pattern" => match iter.next().map(|v| v.parse::<SendPattern>()) {
+                Some(Ok(pattern)) => options.pattern = pattern,
+                Some(Err(e)) => println!("Ignoring --pattern: {}", e),
+                None => println!("Ignoring --pattern: missing value"),
+            },
+            "--bands" => match iter.next().and_then(|v| v.parse::<u32>().ok()) {
+                Some(bands) if bands > 0 => options.bands = RowBands::new(bands),
+                _ => println!("Ignoring --bands: expected a positive integer"),
+            },
+            "--root" => match iter.next() {
+                Some(path) => options.root = Some(PathBuf::from(path)),
+                None => println!("Ignoring --root: missing value"),
+            },
+            other => println!("Ignoring unknown argument: {}", other),
+        }
+    }
+    options
+}
+
+/// 登録されているセンダーを表示
+fn list_senders(registry: &ShmRegistry) {
+    println!("Senders in {}:", registry.root().display());
+    let senders = match registry.senders() {
+        Ok(senders) => senders,
+        Err(e) => {
+            println!("  Failed to read the registry: {}", e);
+            return;
+        }
+    };
+    if senders.is_empty() {
+        println!("  (none)");
+        return;
+    }
+    let active = registry.active_sender().ok().flatten().map(|entry| entry.name);
+    for entry in senders {
+        let marker = if active.as_deref() == Some(entry.name.as_str()) { "*" } else { " " };
+        println!("  {} {}", marker, entry);
+    }
+}
+
+/// `--pattern` のパターンを `seconds` 秒、CPU の画素として送信（1 秒ごとに登録内容を表示）
+fn send_pattern(registry: ShmRegistry, options: &Options) {
+    let (width, height) = options.size;
+    let frames = options.send_seconds as u64 * options.fps as u64;
+    let interval_us = 1_000_000 / options.fps as u64;
+
+    let mut sender = Sender::new(ShmSender::new(registry.clone()));
+    let named = match &options.name {
+        Some(name) => sender.set_name(name),
+        None => sender.set_default_name(),
+    };
+    if let Err(e) = named.and_then(|()| sender.set_format(options.format)) {
+        println!("Failed to configure sender: {:?}", e);
+        std::process::exit(1);
+    }
+
+    let clock = MonotonicClock;
+    let start = Instant::now();
+    for frame in 0..frames {
+        let stamp = FrameStamp { frame, timestamp_us: clock.now_us() };
+        let image = match options.pattern.generate(options.bands, width, height, DxgiFormat::B8G8R8A8Unorm, stamp) {
+            Ok(image) => image,
+            Err(e) => {
+                println!("Failed to generate pattern: {}", e);
+                std::process::exit(1);
+            }
+        };
+        if let Err(e) = sender.send_image(&image.view()) {
+            println!("Frame {}: send failed: {:?}", frame, e);
+            std::process::exit(1);
+        }
+        if frame.is_multiple_of(options.fps as u64) {
+            let name = sender.name().unwrap_or_default();
+            match registry.entry(&name) {
+                Ok(Some(entry)) => println!("  {}", entry),
+                Ok(None) => println!("  {}: not registered", name),
+                Err(e) => println!("  {}: {}", name, e),
+            }
+        }
+        let next = Duration::from_micros((frame + 1) * interval_us);
+        if let Some(wait) = next.checked_sub(start.elapsed()) {
+            std::thread::sleep(wait);
+        }
+    }
+    println!("Sent {} frames ({:.2} fps)", sender.frame(), sender.fps());
+}
+
+/// 共有メモリのフレームを `seconds` 秒受信し、埋め込まれたスタンプを確かめる（行帯ならティアリングを数える）
+fn receive_pattern(registry: ShmRegistry, options: &Options) {
+    let mut receiver = ShmReceiver::new(registry, options.name.as_deref());
+    let interval = Duration::from_micros(1_000_000 / options.fps as u64);
+    let deadline = Instant::now() + Duration::from_secs(options.receive_seconds as u64);
+    let clock = MonotonicClock;
+    let (mut received, mut unreadable, mut latency_us) = (0u64, 0u64, 0u64);
+    let mut tracker = TearingTracker::new(options.bands);
+    let mut next_report = Instant::now();
+    while Instant::now() < deadline {
+        match receiver.receive() {
+            Ok(()) => {}
+            Err(SpoutDxResult::ErrorNotConnected) => {
+                if Instant::now() >= next_report {
+                    println!("  Waiting for a sender...");
+                    next_report = Instant::now() + Duration::from_secs(1);
+                }
+                std::thread::sleep(interval);
+                continue;
+            }
+            Err(e) => {
+                println!("Receive failed: {:?}", e);
+                std::process::exit(1);
+            }
+        }
+        if receiver.is_updated()
+            && let Some(info) = receiver.sender_info()
+        {
+            println!("  Connected to \"{}\" ({}x{} {})", info.name, info.width, info.height, info.format);
+        }
+        if receiver.is_frame_new() {
+            received += 1;
+            let image = receiver.receive_image().ok();
+            match (options.pattern, image) {
+                (SendPattern::FrameCounter, image) => {
+                    match image.and_then(|image| Pattern::read_stamp(&image.view())) {
+                        Some(stamp) => latency_us += clock.now_us().saturating_sub(stamp.timestamp_us),
+                        None => unreadable += 1,
+                    }
+                }
+                (SendPattern::RowBands, Some(image)) => match options.bands.inspect(&image.view()) {
+                    Ok(report) => {
+                        if report.is_torn() {
+                            print!("  {}", report);
+                        }
+                        tracker.record(&report, image.height());
+                    }
+                    Err(_) => unreadable += 1,
+                },
+                (SendPattern::RowBands, None) => unreadable += 1,
+            }
+            if Instant::now() >= next_report {
+                let frame = receiver.frame_count();
+                println!("  Frame {}: {} received, {} without a readable stamp", frame, received, unreadable);
+                next_report = Instant::now() + Duration::from_secs(1);
+            }
+        }
+        std::thread::sleep(interval / 2);
+    }
+    if options.pattern == SendPattern::RowBands {
+        print!("{}", tracker.summary());
+        return;
+    }
+    let stamped = received - unreadable;
+    let average_ms = if stamped > 0 { latency_us as f64 / stamped as f64 / 1000.0 } else { 0.0 };
+    println!(
+        "Received {} frames ({} without a readable stamp), average latency {:.2} ms",
+        received, unreadable, average_ms
+    );
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let options = parse_args(&args);
+    let registry = options.root.clone().map_or_else(ShmRegistry::open, ShmRegistry::new);
+
+    if options.send_seconds > 0 {
+        send_pattern(registry.clone(), &options);
+    }
+    if options.receive_seconds > 0 {
+        receive_pattern(registry.clone(), &options);
+    }
+    if options.list || (options.send_seconds == 0 && options.receive_seconds == 0) {
+        list_senders(&registry);
+    }
+}
@@ -0,0 +1,219 @@
+// ============================================================
+// BMP 書き出し（32bit BGRA、アルファ付き）・読み込み
+// ============================================================
+//
+// SpoutCopy の SaveTextureToBMP と同じく無圧縮の 32bit。アルファを
+// 読めるように BITMAPV4HEADER + BI_BITFIELDS でマスクを明示する。
+// 行は下から上の順（多くのビューアーが前提にしている向き）。
+// 読み込みは無圧縮の 1/4/8/16/24/32bit（パレット・ビットフィールド）に対応する。
+
+use crate::export::push_row8;
+use crate::image::{ImageBuf, ImageError, ImageView};
+use crate::import::{check_dimensions, corrupt, le_u16, le_u32, rgba8_image, slice, ImportError};
+
+const FILE_HEADER_SIZE: u32 = 14;
+const V4_HEADER_SIZE: u32 = 108;
+const BI_RGB: u32 = 0;
+const BI_BITFIELDS: u32 = 3;
+const BI_ALPHABITFIELDS: u32 = 6;
+/// BITMAPCOREHEADER（OS/2 形式、パレットは 3 バイト）
+const CORE_HEADER_SIZE: u32 = 12;
+const INFO_HEADER_SIZE: u32 = 40;
+/// LCS_sRGB（'sRGB'）
+const LCS_SRGB: u32 = 0x7352_4742;
+/// 72 dpi（ピクセル / メートル）
+const PIXELS_PER_METER: u32 = 2835;
+
+/// BMP ファイルのバイト列にエンコード
+pub fn encode_bmp(view: &ImageView) -> Result<Vec<u8>, ImageError> {
+    let (width, height) = (view.width(), view.height());
+    let offset = FILE_HEADER_SIZE + V4_HEADER_SIZE;
+    // ファイルサイズが 32bit に収まること
+    let image_size = (width as u64 * height as u64 * 4) as usize;
+    let (Ok(image_size_u32), Ok(file_size)) = (u32::try_from(image_size), u32::try_from(offset as usize + image_size))
+    else {
+        return Err(ImageError::InvalidDimensions { width, height, row_pitch: view.row_pitch() });
+    };
+
+    let mut out = Vec::with_capacity(file_size as usize);
+    // BITMAPFILEHEADER
+    out.extend(b"BM");
+    out.extend(file_size.to_le_bytes());
+    out.extend([0u8; 4]);
+    out.extend(offset.to_le_bytes());
+    // BITMAPV4HEADER
+    out.extend(V4_HEADER_SIZE.to_le_bytes());
+    out.extend((width as i32).to_le_bytes());
+    out.extend((height as i32).to_le_bytes());
+    out.extend(1u16.to_le_bytes());
+    out.extend(32u16.to_le_bytes());
+    out.extend(BI_BITFIELDS.to_le_bytes());
+    out.extend(image_size_u32.to_le_bytes());
+    out.extend(PIXELS_PER_METER.to_le_bytes());
+    out.extend(PIXELS_PER_METER.to_le_bytes());
+    out.extend([0u8; 8]);
+    // R, G, B, A のマスク
+    for mask in [0x00FF_0000u32, 0x0000_FF00, 0x0000_00FF, 0xFF00_0000] {
+        out.extend(mask.to_le_bytes());
+    }
+    out.extend(LCS_SRGB.to_le_bytes());
+    // CIEXYZTRIPLE エンドポイントとガンマ（LCS_sRGB では使われない）
+    out.extend([0u8; 36 + 12]);
+
+    for y in (0..height).rev() {
+        push_row8(view, y, &[2, 1, 0, 3], &mut out)?;
+    }
+    Ok(out)
+}
+
+/// マスクの位置の値を 8bit に伸ばす（マスクが 0 なら None）
+///
+/// 24bit を超えるマスクでも `* 255` があふれないよう u64 で計算する。
+fn extract(value: u32, mask: u32) -> Option<u8> {
+    if mask == 0 {
+        return None;
+    }
+    let max = (mask >> mask.trailing_zeros()) as u64;
+    Some((((value & mask) >> mask.trailing_zeros()) as u64 * 255 / max) as u8)
+}
+
+/// BMP ファイルを 8bit RGBA にデコード（RLE・JPEG・PNG 圧縮は未対応）
+///
+/// 32bit の BI_RGB は 4 バイト目が予約領域だが、0 以外の値があればアルファとして使う。
+pub fn decode_bmp(bytes: &[u8]) -> Result<ImageBuf, ImportError> {
+    if slice(bytes, 0, 2)? != b"BM" {
+        return Err(corrupt("not a BMP file"));
+    }
+    let offset = le_u32(bytes, 10)? as usize;
+    let header_size = le_u32(bytes, 14)?;
+    let at = FILE_HEADER_SIZE as usize;
+
+    let (width, height, bit_count, compression) = if header_size == CORE_HEADER_SIZE {
+        (le_u16(bytes, at + 4)? as i32, le_u16(bytes, at + 6)? as i32, le_u16(bytes, at + 10)?, BI_RGB)
+    } else if header_size >= INFO_HEADER_SIZE {
+        (le_u32(bytes, at + 4)? as i32, le_u32(bytes, at + 8)? as i32, le_u16(bytes, at + 14)?, le_u32(bytes, at + 16)?)
+    } else {
+        return Err(corrupt(format!("invalid BMP header size: {}", header_size)));
+    };
+    let top_down = height < 0;
+    let (width, height) = (width.unsigned_abs(), height.unsigned_abs());
+    check_dimensions(width, height)?;
+
+    // R, G, B, A のマスク（BITMAPINFOHEADER ではヘッダーの直後に置かれる）
+    let masks = match (compression, bit_count) {
+        (BI_RGB, 16) => [0x7C00, 0x03E0, 0x001F, 0],
+        (BI_RGB, 32) => [0x00FF_0000, 0x0000_FF00, 0x0000_00FF, 0xFF00_0000],
+        (BI_RGB, _) => [0; 4],
+        (BI_BITFIELDS | BI_ALPHABITFIELDS, 16 | 32) => {
+            let alpha = compression == BI_ALPHABITFIELDS || header_size >= 56;
+            let mask = |i: usize| le_u32(bytes, at + INFO_HEADER_SIZE as usize + i * 4);
+            [mask(0)?, mask(1)?, mask(2)?, if alpha { mask(3)? } else { 0 }]
+        }
+        _ => return Err(ImportError::Unsupported(format!("BMP compression {} with {} bits", compression, bit_count))),
+    };
+
+    let palette: Vec<[u8; 4]> = if bit_count <= 8 {
+        let (entry_size, colors) = if header_size == CORE_HEADER_SIZE {
+            (3, 1usize << bit_count)
+        } else {
+            let used = le_u32(bytes, at + 32)? as usize;
+            (4, if used == 0 { 1 << bit_count } else { used.min(256) })
+        };
+        let table = slice(bytes, at + header_size as usize, colors * entry_size)?;
+        table.chunks_exact(entry_size).map(|c| [c[2], c[1], c[0], 255]).collect()
+    } else {
+        Vec::new()
+    };
+
+    let stride = (width as usize * bit_count as usize).div_ceil(32) * 4;
+    let pixels = slice(bytes, offset, stride * height as usize)?;
+    let mut out = vec![0u8; width as usize * height as usize * 4];
+    let mut has_alpha = false;
+    for y in 0..height as usize {
+        let row = &pixels[if top_down { y } else { height as usize - 1 - y } * stride..][..stride];
+        let dst = &mut out[y * width as usize * 4..][..width as usize * 4];
+        for (x, px) in dst.chunks_exact_mut(4).enumerate() {
+            let rgba = match bit_count {
+                1 | 2 | 4 | 8 => {
+                    let bits = bit_count as usize;
+                    let shift = 8 - bits - (x * bits) % 8;
+                    let index = (row[x * bits / 8] >> shift) as usize & ((1 << bits) - 1);
+                    *palette.get(index).ok_or_else(|| corrupt(format!("palette index {} out of range", index)))?
+                }
+                24 => [row[x * 3 + 2], row[x * 3 + 1], row[x * 3], 255],
+                16 | 32 => {
+                    let value = match bit_count {
+                        16 => u16::from_le_bytes([row[x * 2], row[x * 2 + 1]]) as u32,
+                        _ => u32::from_le_bytes([row[x * 4], row[x * 4 + 1], row[x * 4 + 2], row[x * 4 + 3]]),
+                    };
+                    let alpha = extract(value, masks[3]);
+                    has_alpha |= alpha.is_some_and(|a| a != 0);
+                    [
+                        extract(value, masks[0]).unwrap_or(0),
+                        extract(value, masks[1]).unwrap_or(0),
+                        extract(value, masks[2]).unwrap_or(0),
+                        alpha.unwrap_or(255),
+                    ]
+                }
+                _ => return Err(ImportError::Unsupported(format!("BMP with {} bits per pixel", bit_count))),
+            };
+            px.copy_from_slice(&rgba);
+        }
+    }
+    // 予約領域が全部 0 の BI_RGB は不透明
+    if compression == BI_RGB && bit_count == 32 && !has_alpha {
+        out.chunks_exact_mut(4).for_each(|px| px[3] = 255);
+    }
+    rgba8_image(width, height, out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::image::DxgiFormat;
+
+    /// 1x1 の 32bit BI_BITFIELDS（R, G, B のマスクだけ）
+    fn bitfields_bmp(masks: [u32; 3], pixel: u32) -> Vec<u8> {
+        let offset = FILE_HEADER_SIZE + INFO_HEADER_SIZE + 12;
+        let mut out = Vec::new();
+        out.extend(b"BM");
+        out.extend((offset + 4).to_le_bytes());
+        out.extend([0u8; 4]);
+        out.extend(offset.to_le_bytes());
+        out.extend(INFO_HEADER_SIZE.to_le_bytes());
+        out.extend(1i32.to_le_bytes());
+        out.extend(1i32.to_le_bytes());
+        out.extend(1u16.to_le_bytes());
+        out.extend(32u16.to_le_bytes());
+        out.extend(BI_BITFIELDS.to_le_bytes());
+        out.extend([0u8; 20]);
+        for mask in masks {
+            out.extend(mask.to_le_bytes());
+        }
+        out.extend(pixel.to_le_bytes());
+        out
+    }
+
+    #[test]
+    fn wide_bitfield_masks_do_not_overflow() {
+        for (mask, pixel, expected) in [
+            (0xFFFF_FFFFu32, 0xFFFF_FFFFu32, 255u8),
+            (0xFFFF_FFFF, 0x8000_0000, 127),
+            (0xFFFF_FFFE, 0xFFFF_FFFE, 255),
+            (0x3FFF_FFFF, 0x0000_0000, 0),
+        ] {
+            let image = decode_bmp(&bitfields_bmp([mask, mask, mask], pixel)).unwrap();
+            assert_eq!(image.pixel(0, 0)[..3], [expected; 3], "mask {:#x}", mask);
+        }
+    }
+
+    #[test]
+    fn encode_decode_roundtrip() {
+        let mut image = ImageBuf::new(3, 2, DxgiFormat::R8G8B8A8Unorm).unwrap();
+        for (i, byte) in image.data_mut().iter_mut().enumerate() {
+            *byte = (i * 37) as u8;
+        }
+        let decoded = decode_bmp(&encode_bmp(&image.view()).unwrap()).unwrap();
+        assert_eq!(decoded, image);
+    }
+}
@@ -0,0 +1,361 @@
+// ============================================================
+// 短いクリップのアニメーション書き出し（APNG / GIF）
+// ============================================================
+//
+// 受信したフレームを縮小して溜め、最後にアニメーション PNG か GIF にする。
+// 各フレームの表示時間は受信時刻の差から求める。2 枚目以降は前のフレームから
+// 変わった範囲だけを書き、変化のないフレームは前のフレームの表示時間に足す。
+
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+use serde::Serialize;
+
+use crate::export::{write_file, ExportError};
+use crate::gif::encode_gif;
+use crate::hdr::{to_bgra8, Tonemap};
+use crate::image::{DxgiFormat, ImageBuf, ImageError, ImageView};
+use crate::mock::MockReceiver;
+use crate::pattern::{FrameStamp, Pattern};
+use crate::png::{encode_apng, PngOptions};
+use crate::transform::resize;
+
+/// GIF の表示時間の最小値（1/100 秒単位、これより短いとブラウザーが 0.1 秒にする）
+const GIF_MIN_DELAY: u32 = 2;
+
+/// クリップの形式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ClipFormat {
+    /// アニメーション PNG（フルカラー）
+    #[default]
+    Apng,
+    /// 256 色の GIF
+    Gif,
+}
+
+impl ClipFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Apng => "png",
+            Self::Gif => "gif",
+        }
+    }
+
+    /// 拡張子から選ぶ
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()?.to_ascii_lowercase().as_str() {
+            "png" | "apng" => Some(Self::Apng),
+            "gif" => Some(Self::Gif),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ClipFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Apng => write!(f, "APNG"),
+            Self::Gif => write!(f, "GIF"),
+        }
+    }
+}
+
+/// クリップの設定
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct ClipOptions {
+    pub format: ClipFormat,
+    /// 長辺の上限（これより大きいフレームは縦横比を保って縮小）
+    pub max_size: u32,
+    /// 残すフレームの上限 fps（None なら全フレーム、GIF は 50 fps まで）
+    pub max_fps: Option<f64>,
+    /// 繰り返し回数（0 は無限）
+    pub loops: u16,
+    /// APNG でアルファを残す
+    pub alpha: bool,
+    /// APNG の圧縮レベル 0-9
+    pub compression: u8,
+    /// FLOAT フォーマットを 8bit にするときのトーンマップ
+    #[serde(skip)]
+    pub tonemap: Tonemap,
+}
+
+impl Default for ClipOptions {
+    fn default() -> Self {
+        Self {
+            format: ClipFormat::Apng,
+            max_size: 640,
+            max_fps: Some(30.0),
+            loops: 0,
+            alpha: false,
+            compression: 6,
+            tonemap: Tonemap::Clamp,
+        }
+    }
+}
+
+impl ClipOptions {
+    /// 形式の制限を含めた fps の上限
+    fn effective_fps(&self) -> Option<f64> {
+        match self.format {
+            ClipFormat::Apng => self.max_fps,
+            ClipFormat::Gif => Some(self.max_fps.map_or(50.0, |fps| fps.min(50.0))),
+        }
+    }
+
+    fn png_options(&self) -> PngOptions {
+        PngOptions { alpha: self.alpha, compression: self.compression, ..PngOptions::default() }
+    }
+}
+
+impl fmt::Display for ClipOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}, max {}px", self.format, self.max_size)?;
+        match self.effective_fps() {
+            Some(fps) => write!(f, ", up to {} fps", fps)?,
+            None => write!(f, ", all frames")?,
+        }
+        match self.loops {
+            0 => write!(f, ", loop forever"),
+            loops => write!(f, ", loop {} times", loops),
+        }
+    }
+}
+
+impl FromStr for ClipOptions {
+    type Err = String;
+
+    /// `format=gif,max=480,fps=15,loop=1,alpha=on,level=9`（省略した項目は既定値）
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut options = Self::default();
+        for item in s.split(',').filter(|item| !item.is_empty()) {
+            let (key, value) = item
+                .split_once('=')
+                .ok_or_else(|| format!("invalid clip option: {} (expected key=value)", item))?;
+            let invalid = || format!("invalid clip option value: {}", item);
+            match (key, value.to_ascii_lowercase().as_str()) {
+                ("format", "apng" | "png") => options.format = ClipFormat::Apng,
+                ("format", "gif") => options.format = ClipFormat::Gif,
+                ("max", max) => options.max_size = max.parse().ok().filter(|&max| max > 0).ok_or_else(invalid)?,
+                ("fps", "all") => options.max_fps = None,
+                ("fps", fps) => {
+                    options.max_fps = Some(fps.parse().ok().filter(|&fps: &f64| fps > 0.0).ok_or_else(invalid)?)
+                }
+                ("loop", loops) => options.loops = loops.parse().map_err(|_| invalid())?,
+                ("alpha", "on" | "true" | "1") => options.alpha = true,
+                ("alpha", "off" | "false" | "0") => options.alpha = false,
+                ("level", level) => {
+                    options.compression = level.parse().ok().filter(|&level| level <= 9).ok_or_else(invalid)?
+                }
+                ("format" | "alpha", _) => return Err(invalid()),
+                _ => return Err(format!("unknown clip option: {} (format, max, fps, loop, alpha, level)", key)),
+            }
+        }
+        Ok(options)
+    }
+}
+
+/// 溜めた 1 フレーム（縮小済みの BGRA8）
+#[derive(Debug, Clone)]
+pub struct ClipFrame {
+    pub image: ImageBuf,
+    pub timestamp_us: u64,
+}
+
+/// 書き出す 1 フレーム（キャンバス上の位置に重ねる部分画像）
+#[derive(Debug, Clone, Copy)]
+pub struct AnimationFrame<'a> {
+    pub view: ImageView<'a>,
+    pub x: u32,
+    pub y: u32,
+    pub delay_us: u64,
+}
+
+/// 長辺を `max_size` 以下にしたサイズ（拡大はしない）
+pub fn fit_within(width: u32, height: u32, max_size: u32) -> (u32, u32) {
+    let longest = width.max(height);
+    if longest <= max_size {
+        return (width, height);
+    }
+    let scale = max_size as f64 / longest as f64;
+    let scaled = |v: u32| ((v as f64 * scale).round() as u32).clamp(1, max_size);
+    (scaled(width), scaled(height))
+}
+
+/// 2 枚の BGRA8 画像で違うピクセルを囲む範囲 (x, y, width, height)
+fn changed_rect(previous: &ImageBuf, current: &ImageBuf) -> Option<(u32, u32, u32, u32)> {
+    let (mut left, mut top, mut right, mut bottom) = (u32::MAX, u32::MAX, 0, 0);
+    for y in 0..current.height() {
+        let (a, b) = (previous.row(y), current.row(y));
+        if a == b {
+            continue;
+        }
+        let first = a.chunks_exact(4).zip(b.chunks_exact(4)).position(|(p, q)| p != q).unwrap_or(0) as u32;
+        let last = a.chunks_exact(4).zip(b.chunks_exact(4)).rposition(|(p, q)| p != q).unwrap_or(0) as u32;
+        (left, right) = (left.min(first), right.max(last));
+        (top, bottom) = (top.min(y), y);
+    }
+    (top != u32::MAX).then(|| (left, top, right - left + 1, bottom - top + 1))
+}
+
+/// 表示時間を `unit_us` 単位に丸める（累積で丸めて全体の長さをずらさない）
+pub(crate) fn frame_delays(frames: &[AnimationFrame], unit_us: u64, min: u32) -> Vec<u32> {
+    let mut elapsed_us = 0;
+    let mut shown = 0;
+    frames
+        .iter()
+        .map(|frame| {
+            elapsed_us += frame.delay_us;
+            let end = ((elapsed_us + unit_us / 2) / unit_us) as u32;
+            let delay = end.saturating_sub(shown).max(min);
+            shown += delay;
+            delay
+        })
+        .collect()
+}
+
+/// フレームを溜めて APNG / GIF にする
+#[derive(Debug, Default)]
+pub struct Clip {
+    options: ClipOptions,
+    frames: Vec<ClipFrame>,
+    skipped: u64,
+}
+
+impl Clip {
+    pub fn new(options: ClipOptions) -> Self {
+        Self { options, frames: Vec::new(), skipped: 0 }
+    }
+
+    pub fn options(&self) -> &ClipOptions {
+        &self.options
+    }
+
+    pub fn frames(&self) -> &[ClipFrame] {
+        &self.frames
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// fps の上限で捨てたフレーム数
+    pub fn skipped(&self) -> u64 {
+        self.skipped
+    }
+
+    /// 出力サイズ（最初のフレームまで None）
+    pub fn size(&self) -> Option<(u32, u32)> {
+        self.frames.first().map(|frame| (frame.image.width(), frame.image.height()))
+    }
+
+    /// 1 フレーム追加（fps の上限より早ければ捨てて false）
+    ///
+    /// 出力サイズは最初のフレームで決まり、以降のフレームはそのサイズに合わせる。
+    pub fn push(&mut self, view: &ImageView, timestamp_us: u64) -> Result<bool, ImageError> {
+        if let (Some(last), Some(fps)) = (self.frames.last(), self.options.effective_fps()) {
+            // 受信間隔の揺れで 1 つおきに捨てないよう、少しだけ余裕を持たせる
+            let interval_us = (1_000_000.0 / fps * 0.9) as u64;
+            if timestamp_us.saturating_sub(last.timestamp_us) < interval_us {
+                self.skipped += 1;
+                return Ok(false);
+            }
+        }
+        let converted;
+        let view = if matches!(view.format(), DxgiFormat::B8G8R8A8Unorm | DxgiFormat::B8G8R8A8UnormSrgb) {
+            *view
+        } else {
+            converted = to_bgra8(view, self.options.tonemap)?;
+            converted.view()
+        };
+        let (width, height) =
+            self.size().unwrap_or_else(|| fit_within(view.width(), view.height(), self.options.max_size));
+        let mut image = resize(&view, width, height)?;
+        image.set_format(DxgiFormat::B8G8R8A8Unorm)?;
+        self.frames.push(ClipFrame { image, timestamp_us });
+        Ok(true)
+    }
+
+    /// 最初のフレームから最後のフレームの表示が終わるまでの長さ
+    pub fn duration_us(&self) -> u64 {
+        match (self.frames.first(), self.frames.last()) {
+            (Some(first), Some(last)) => last.timestamp_us - first.timestamp_us + self.last_delay_us(),
+            _ => 0,
+        }
+    }
+
+    /// 最後のフレームの表示時間（それまでの平均間隔、1 枚なら fps の上限か 60 fps）
+    fn last_delay_us(&self) -> u64 {
+        match (self.frames.first(), self.frames.last()) {
+            (Some(first), Some(last)) if self.frames.len() >= 2 => {
+                (last.timestamp_us - first.timestamp_us) / (self.frames.len() as u64 - 1)
+            }
+            _ => (1_000_000.0 / self.options.effective_fps().unwrap_or(60.0)) as u64,
+        }
+    }
+
+    /// 書き出すフレーム（2 枚目以降は変わった範囲だけ、変化がなければ前のフレームに足す）
+    pub fn animation(&self) -> Result<Vec<AnimationFrame<'_>>, ImageError> {
+        let mut out: Vec<AnimationFrame> = Vec::with_capacity(self.frames.len());
+        for (i, frame) in self.frames.iter().enumerate() {
+            let delay_us = match self.frames.get(i + 1) {
+                Some(next) => next.timestamp_us.saturating_sub(frame.timestamp_us),
+                None => self.last_delay_us(),
+            };
+            let rect = match i {
+                0 => Some((0, 0, frame.image.width(), frame.image.height())),
+                _ => changed_rect(&self.frames[i - 1].image, &frame.image),
+            };
+            match (rect, out.last_mut()) {
+                (Some((x, y, width, height)), _) => {
+                    out.push(AnimationFrame { view: frame.image.view().sub_view(x, y, width, height)?, x, y, delay_us });
+                }
+                (None, Some(previous)) => previous.delay_us += delay_us,
+                (None, None) => {}
+            }
+        }
+        Ok(out)
+    }
+
+    /// 設定の形式でエンコード
+    pub fn encode(&self) -> Result<Vec<u8>, ImageError> {
+        let (width, height) = self.size().ok_or(ImageError::InvalidDimensions { width: 0, height: 0, row_pitch: 0 })?;
+        let frames = self.animation()?;
+        match self.options.format {
+            ClipFormat::Apng => encode_apng(width, height, &frames, self.options.loops, &self.options.png_options()),
+            ClipFormat::Gif => encode_gif(width, height, &frames, self.options.loops),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), ExportError> {
+        write_file(path, &self.encode()?)
+    }
+}
+
+/// 表示時間を GIF の単位（1/100 秒）に丸める
+pub(crate) fn gif_delays(frames: &[AnimationFrame]) -> Vec<u32> {
+    frame_delays(frames, 10_000, GIF_MIN_DELAY)
+}
+
+/// フレームカウンターパターンを `interval_us` 間隔で受信したことにしてクリップを作る
+pub fn simulate(options: ClipOptions, frames: u64, interval_us: u64, width: u32, height: u32) -> Result<Clip, ImageError> {
+    let mut clip = Clip::new(options);
+    let mut receiver = MockReceiver::new("clip-mock");
+    for index in 0..frames {
+        let timestamp_us = 1_000_000 + index * interval_us;
+        let stamp = FrameStamp { frame: index, timestamp_us };
+        receiver.push_frame(Pattern::FrameCounter.generate(width, height, DxgiFormat::B8G8R8A8Unorm, stamp)?);
+        if receiver.receive().is_err() || !receiver.is_frame_new() {
+            continue;
+        }
+        if let Ok(image) = receiver.receive_image() {
+            clip.push(&image.view(), timestamp_us)?;
+        }
+    }
+    Ok(clip)
+}
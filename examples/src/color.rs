@@ -0,0 +1,164 @@
+// ============================================================
+// 色空間（sRGB ⇔ リニア）と平均色
+// ============================================================
+//
+// 平均や比較はリニア光で行い、表示用には sRGB へ戻す。
+// - _UNORM_SRGB フォーマット: sRGB エンコード（フォーマットで宣言）
+// - 8bit / 10bit UNORM: sRGB エンコードとみなす（一般的なセンダーの実態）
+// - FLOAT フォーマット: リニア（scene-linear）
+
+use std::fmt;
+use std::sync::OnceLock;
+
+use crate::hdr::decode_pixel;
+use crate::image::{DxgiFormat, ImageError, ImageView};
+
+/// sRGB EOTF（エンコード値 0..1 → リニア）
+pub fn srgb_to_linear(encoded: f32) -> f32 {
+    if encoded <= 0.04045 {
+        encoded / 12.92
+    } else {
+        ((encoded + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// sRGB OETF（リニア → エンコード値 0..1、範囲外はクランプ）
+pub fn linear_to_srgb(linear: f32) -> f32 {
+    let linear = linear.clamp(0.0, 1.0);
+    if linear <= 0.003_130_8 {
+        linear * 12.92
+    } else {
+        1.055 * linear.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// 8bit sRGB 値 → リニアの変換表
+pub fn srgb8_to_linear(value: u8) -> f32 {
+    static TABLE: OnceLock<[f32; 256]> = OnceLock::new();
+    TABLE.get_or_init(|| std::array::from_fn(|i| srgb_to_linear(i as f32 / 255.0)))[value as usize]
+}
+
+/// リニア → 8bit sRGB 値
+pub fn linear_to_srgb8(linear: f32) -> u8 {
+    (linear_to_srgb(linear) * 255.0 + 0.5) as u8
+}
+
+/// ピクセル値がどうエンコードされているか
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransferFunction {
+    Srgb,
+    Linear,
+}
+
+impl TransferFunction {
+    /// フォーマットからの既定値（UNORM は sRGB、FLOAT はリニア）
+    pub fn for_format(format: DxgiFormat) -> Self {
+        match format {
+            DxgiFormat::R16G16B16A16Float | DxgiFormat::R32G32B32A32Float => Self::Linear,
+            _ => Self::Srgb,
+        }
+    }
+
+    /// エンコード値 → リニア
+    pub fn to_linear(self, value: f32) -> f32 {
+        match self {
+            Self::Srgb => srgb_to_linear(value),
+            Self::Linear => value,
+        }
+    }
+}
+
+impl fmt::Display for TransferFunction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Srgb => write!(f, "sRGB"),
+            Self::Linear => write!(f, "linear"),
+        }
+    }
+}
+
+/// 画像の色空間の解釈
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ColorSpace {
+    pub transfer: TransferFunction,
+    /// フォーマット（_SRGB / FLOAT）で宣言されているか、推定か
+    pub declared: bool,
+}
+
+impl ColorSpace {
+    /// フォーマットから決定。`assume_linear` なら UNORM もリニアとして扱う
+    pub fn for_format(format: DxgiFormat, assume_linear: bool) -> Self {
+        if format.is_srgb() {
+            return Self { transfer: TransferFunction::Srgb, declared: true };
+        }
+        match TransferFunction::for_format(format) {
+            TransferFunction::Linear => Self { transfer: TransferFunction::Linear, declared: true },
+            TransferFunction::Srgb if assume_linear => Self { transfer: TransferFunction::Linear, declared: false },
+            TransferFunction::Srgb => Self { transfer: TransferFunction::Srgb, declared: false },
+        }
+    }
+}
+
+impl fmt::Display for ColorSpace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let origin = if self.declared { "declared by format" } else { "assumed" };
+        write!(f, "{} ({})", self.transfer, origin)
+    }
+}
+
+/// 1 ピクセルをリニアの [R, G, B, A] に変換（アルファはそのまま）
+pub fn pixel_to_linear(px: &[u8], format: DxgiFormat, transfer: TransferFunction) -> Result<[f32; 4], ImageError> {
+    // 8bit sRGB は変換表で処理（powf を避ける）
+    if transfer == TransferFunction::Srgb {
+        match format {
+            DxgiFormat::R8G8B8A8Unorm | DxgiFormat::R8G8B8A8UnormSrgb => {
+                return Ok([srgb8_to_linear(px[0]), srgb8_to_linear(px[1]), srgb8_to_linear(px[2]), px[3] as f32 / 255.0]);
+            }
+            DxgiFormat::B8G8R8A8Unorm | DxgiFormat::B8G8R8A8UnormSrgb => {
+                return Ok([srgb8_to_linear(px[2]), srgb8_to_linear(px[1]), srgb8_to_linear(px[0]), px[3] as f32 / 255.0]);
+            }
+            _ => {}
+        }
+    }
+    let [r, g, b, a] = decode_pixel(px, format)?;
+    Ok([transfer.to_linear(r), transfer.to_linear(g), transfer.to_linear(b), a])
+}
+
+/// リニア光での平均色
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AverageColor {
+    /// リニアの [R, G, B, A]（0..1、HDR では 1 を超える）
+    pub linear: [f64; 4],
+    pub color_space: ColorSpace,
+}
+
+impl AverageColor {
+    /// 表示上の見た目に相当する 8bit sRGB 値（0..255）
+    pub fn srgb8(&self) -> [f64; 4] {
+        let [r, g, b, a] = self.linear;
+        let encode = |v: f64| linear_to_srgb(v as f32) as f64 * 255.0;
+        [encode(r), encode(g), encode(b), a.clamp(0.0, 1.0) * 255.0]
+    }
+}
+
+/// 平均色をリニア光で計算
+pub fn average_color(view: &ImageView, color_space: ColorSpace) -> Result<AverageColor, ImageError> {
+    let format = view.format();
+    let bpp = view.bytes_per_pixel();
+    let mut total = [0f64; 4];
+
+    for y in 0..view.height() {
+        for px in view.row(y).chunks_exact(bpp) {
+            let rgba = pixel_to_linear(px, format, color_space.transfer)?;
+            for (t, v) in total.iter_mut().zip(rgba) {
+                *t += v as f64;
+            }
+        }
+    }
+
+    let pixel_count = (view.width() as u64 * view.height() as u64) as f64;
+    Ok(AverageColor {
+        linear: total.map(|t| t / pixel_count),
+        color_space,
+    })
+}
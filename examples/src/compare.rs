@@ -0,0 +1,360 @@
+// ============================================================
+// ゴールデン画像との比較（PSNR / SSIM / 最大誤差・差分ヒートマップ）
+// ============================================================
+//
+// 受信画像と基準画像の格納値（0..1）を比較する。フォーマットは
+// 違ってもよい（BGRA と RGBA など）が、サイズは一致している必要がある。
+// アルファの扱いは AlphaCompare で明示的に選ぶ。
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::Serialize;
+
+use crate::hdr::decode_pixel;
+use crate::image::{DxgiFormat, ImageBuf, ImageError, ImageView};
+
+/// 輝度の重み（BT.709、格納値に対して適用）
+const LUMA_WEIGHTS: [f32; 3] = [0.2126, 0.7152, 0.0722];
+
+/// SSIM の窓サイズと移動量
+const SSIM_WINDOW: u32 = 8;
+const SSIM_STRIDE: u32 = 4;
+
+/// SSIM の安定化定数（ダイナミックレンジ 1.0 に対する K1=0.01, K2=0.03）
+const SSIM_C1: f64 = 0.01 * 0.01;
+const SSIM_C2: f64 = 0.03 * 0.03;
+
+const CHANNEL_NAMES: [&str; 4] = ["R", "G", "B", "A"];
+
+/// アルファチャンネルの扱い
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AlphaCompare {
+    /// RGB だけを比較（アルファは無視）
+    Ignore,
+    /// RGBA を独立したチャンネルとして比較
+    #[default]
+    Straight,
+    /// RGB にアルファを掛けてから比較（透明部分の色の違いを無視）
+    Premultiplied,
+}
+
+impl AlphaCompare {
+    fn channels(self) -> usize {
+        match self {
+            Self::Ignore => 3,
+            Self::Straight | Self::Premultiplied => 4,
+        }
+    }
+
+    /// 比較に使う値へ変換
+    fn prepare(self, [r, g, b, a]: [f32; 4]) -> [f32; 4] {
+        match self {
+            Self::Ignore => [r, g, b, 1.0],
+            Self::Straight => [r, g, b, a],
+            Self::Premultiplied => [r * a, g * a, b * a, a],
+        }
+    }
+}
+
+impl fmt::Display for AlphaCompare {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Ignore => "ignore",
+            Self::Straight => "straight",
+            Self::Premultiplied => "premultiplied",
+        };
+        f.write_str(name)
+    }
+}
+
+impl FromStr for AlphaCompare {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "ignore" | "none" => Ok(Self::Ignore),
+            "straight" => Ok(Self::Straight),
+            "premultiplied" | "premul" => Ok(Self::Premultiplied),
+            _ => Err(format!("unknown alpha compare mode: {} (ignore, straight, premultiplied)", s)),
+        }
+    }
+}
+
+/// 合否の許容値
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct CompareTolerances {
+    /// PSNR の下限（dB）
+    pub min_psnr_db: f64,
+    /// SSIM の下限
+    pub min_ssim: f64,
+    /// 最大誤差の上限（0..1）
+    pub max_abs_error: f32,
+}
+
+impl Default for CompareTolerances {
+    fn default() -> Self {
+        Self { min_psnr_db: 40.0, min_ssim: 0.98, max_abs_error: 8.0 / 255.0 }
+    }
+}
+
+impl FromStr for CompareTolerances {
+    type Err = String;
+
+    /// `psnr=40,ssim=0.98,max=8`（max は 8bit の段階数、省略した項目は既定値）
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut tolerances = Self::default();
+        for item in s.split(',').filter(|item| !item.is_empty()) {
+            let (key, value) = item
+                .split_once('=')
+                .ok_or_else(|| format!("invalid tolerance: {} (expected key=value)", item))?;
+            let value: f64 = value
+                .parse()
+                .map_err(|_| format!("invalid tolerance value: {}", item))?;
+            match key {
+                "psnr" => tolerances.min_psnr_db = value,
+                "ssim" => tolerances.min_ssim = value,
+                "max" => tolerances.max_abs_error = (value / 255.0) as f32,
+                _ => return Err(format!("unknown tolerance: {} (psnr, ssim, max)", key)),
+            }
+        }
+        Ok(tolerances)
+    }
+}
+
+/// 1 チャンネル分の誤差
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ChannelError {
+    pub channel: &'static str,
+    pub mse: f64,
+    /// None なら完全一致
+    pub psnr_db: Option<f64>,
+    pub max_abs_error: f32,
+}
+
+/// 比較結果
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Comparison {
+    pub width: u32,
+    pub height: u32,
+    pub alpha: AlphaCompare,
+    pub channels: Vec<ChannelError>,
+    /// 比較したチャンネル全体の PSNR（None なら完全一致）
+    pub psnr_db: Option<f64>,
+    /// 輝度の平均 SSIM（8x8 窓、4 ピクセル間隔）
+    pub ssim: f64,
+    pub max_abs_error: f32,
+    pub mean_abs_error: f64,
+    /// 1 チャンネルでも値が違うピクセルの割合（%）
+    pub differing_percent: f64,
+    pub tolerances: CompareTolerances,
+}
+
+impl Comparison {
+    pub fn passed(&self) -> bool {
+        self.psnr_db.is_none_or(|psnr| psnr >= self.tolerances.min_psnr_db)
+            && self.ssim >= self.tolerances.min_ssim
+            && self.max_abs_error <= self.tolerances.max_abs_error
+    }
+
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+}
+
+fn psnr(mse: f64) -> Option<f64> {
+    (mse > 0.0).then(|| -10.0 * mse.log10())
+}
+
+fn format_psnr(psnr_db: Option<f64>) -> String {
+    psnr_db.map_or_else(|| "inf".to_string(), |db| format!("{:.2} dB", db))
+}
+
+impl fmt::Display for Comparison {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let verdict = if self.passed() { "✅ PASS" } else { "❌ FAIL" };
+        writeln!(f, "[Compare] {} ({}x{}, alpha: {})", verdict, self.width, self.height, self.alpha)?;
+        writeln!(
+            f,
+            "  PSNR {} (min {:.2}), SSIM {:.4} (min {:.4})",
+            format_psnr(self.psnr_db),
+            self.tolerances.min_psnr_db,
+            self.ssim,
+            self.tolerances.min_ssim
+        )?;
+        writeln!(
+            f,
+            "  max error {:.1}/255 (max {:.1}), mean {:.3}/255, {:.2}% pixels differ",
+            self.max_abs_error * 255.0,
+            self.tolerances.max_abs_error * 255.0,
+            self.mean_abs_error * 255.0,
+            self.differing_percent
+        )?;
+        for channel in &self.channels {
+            writeln!(
+                f,
+                "  {}: PSNR {}, max error {:.1}/255",
+                channel.channel,
+                format_psnr(channel.psnr_db),
+                channel.max_abs_error * 255.0
+            )?;
+        }
+        Ok(())
+    }
+}
+
+fn check_size(reference: &ImageView, actual: &ImageView) -> Result<(), ImageError> {
+    let expected = (reference.width(), reference.height());
+    let found = (actual.width(), actual.height());
+    if expected != found {
+        return Err(ImageError::SizeMismatch { expected, actual: found });
+    }
+    Ok(())
+}
+
+/// 2 枚の画像を 1 行ずつデコードして比較用の値を渡す
+fn for_each_pixel(
+    reference: &ImageView,
+    actual: &ImageView,
+    alpha: AlphaCompare,
+    mut f: impl FnMut(u32, u32, [f32; 4], [f32; 4]),
+) -> Result<(), ImageError> {
+    check_size(reference, actual)?;
+    let (ref_bpp, act_bpp) = (reference.bytes_per_pixel(), actual.bytes_per_pixel());
+    for y in 0..reference.height() {
+        let rows = reference.row(y).chunks_exact(ref_bpp).zip(actual.row(y).chunks_exact(act_bpp));
+        for (x, (a, b)) in rows.take(reference.width() as usize).enumerate() {
+            let a = alpha.prepare(decode_pixel(a, reference.format())?);
+            let b = alpha.prepare(decode_pixel(b, actual.format())?);
+            f(x as u32, y, a, b);
+        }
+    }
+    Ok(())
+}
+
+fn luma(rgba: [f32; 4]) -> f32 {
+    LUMA_WEIGHTS[0] * rgba[0] + LUMA_WEIGHTS[1] * rgba[1] + LUMA_WEIGHTS[2] * rgba[2]
+}
+
+/// 輝度平面の平均 SSIM（画像が窓より小さければ全体を 1 つの窓とする）
+fn mean_ssim(a: &[f32], b: &[f32], width: u32, height: u32) -> f64 {
+    let window_w = SSIM_WINDOW.min(width);
+    let window_h = SSIM_WINDOW.min(height);
+    let starts = |size: u32, window: u32| (0..=size - window).step_by(SSIM_STRIDE as usize);
+
+    let (mut total, mut windows) = (0.0, 0u64);
+    for y0 in starts(height, window_h) {
+        for x0 in starts(width, window_w) {
+            let (mut sa, mut sb, mut saa, mut sbb, mut sab) = (0.0f64, 0.0f64, 0.0f64, 0.0f64, 0.0f64);
+            for y in y0..y0 + window_h {
+                for x in x0..x0 + window_w {
+                    let i = (y * width + x) as usize;
+                    let (va, vb) = (a[i] as f64, b[i] as f64);
+                    sa += va;
+                    sb += vb;
+                    saa += va * va;
+                    sbb += vb * vb;
+                    sab += va * vb;
+                }
+            }
+            let n = (window_w * window_h) as f64;
+            let (mean_a, mean_b) = (sa / n, sb / n);
+            let var_a = (saa / n - mean_a * mean_a).max(0.0);
+            let var_b = (sbb / n - mean_b * mean_b).max(0.0);
+            let covariance = sab / n - mean_a * mean_b;
+            total += ((2.0 * mean_a * mean_b + SSIM_C1) * (2.0 * covariance + SSIM_C2))
+                / ((mean_a * mean_a + mean_b * mean_b + SSIM_C1) * (var_a + var_b + SSIM_C2));
+            windows += 1;
+        }
+    }
+    if windows == 0 { 1.0 } else { total / windows as f64 }
+}
+
+/// 基準画像と受信画像を比較する
+pub fn compare(
+    reference: &ImageView,
+    actual: &ImageView,
+    alpha: AlphaCompare,
+    tolerances: CompareTolerances,
+) -> Result<Comparison, ImageError> {
+    let channels = alpha.channels();
+    let (width, height) = (reference.width(), reference.height());
+    let pixels = width as usize * height as usize;
+
+    let mut sum_sq = [0.0f64; 4];
+    let mut max_abs = [0.0f32; 4];
+    let (mut sum_abs, mut differing) = (0.0f64, 0u64);
+    let (mut luma_ref, mut luma_act) = (Vec::with_capacity(pixels), Vec::with_capacity(pixels));
+
+    for_each_pixel(reference, actual, alpha, |_, _, a, b| {
+        let mut differs = false;
+        for c in 0..channels {
+            let diff = (a[c] - b[c]).abs();
+            sum_sq[c] += diff as f64 * diff as f64;
+            sum_abs += diff as f64;
+            max_abs[c] = max_abs[c].max(diff);
+            differs |= diff > 0.0;
+        }
+        differing += differs as u64;
+        luma_ref.push(luma(a));
+        luma_act.push(luma(b));
+    })?;
+
+    let n = pixels.max(1) as f64;
+    let channel_errors: Vec<ChannelError> = (0..channels)
+        .map(|c| {
+            let mse = sum_sq[c] / n;
+            ChannelError { channel: CHANNEL_NAMES[c], mse, psnr_db: psnr(mse), max_abs_error: max_abs[c] }
+        })
+        .collect();
+    let mse = sum_sq[..channels].iter().sum::<f64>() / (n * channels as f64);
+
+    Ok(Comparison {
+        width,
+        height,
+        alpha,
+        channels: channel_errors,
+        psnr_db: psnr(mse),
+        ssim: if pixels == 0 { 1.0 } else { mean_ssim(&luma_ref, &luma_act, width, height) },
+        max_abs_error: max_abs.iter().copied().fold(0.0, f32::max),
+        mean_abs_error: sum_abs / (n * channels as f64),
+        differing_percent: differing as f64 * 100.0 / n,
+        tolerances,
+    })
+}
+
+/// 誤差 0..1 を紺 → 青 → 緑 → 黄 → 赤のカラーマップで BGRA8 にする
+fn heat_color(t: f32) -> [u8; 4] {
+    const STOPS: [[f32; 3]; 5] = [[0.0, 0.0, 0.4], [0.0, 0.0, 1.0], [0.0, 1.0, 0.0], [1.0, 1.0, 0.0], [1.0, 0.0, 0.0]];
+    let pos = t.clamp(0.0, 1.0) * (STOPS.len() - 1) as f32;
+    let i = (pos.floor() as usize).min(STOPS.len() - 2);
+    let frac = pos - i as f32;
+    let [r, g, b] = [0, 1, 2].map(|c| {
+        let v = STOPS[i][c] + (STOPS[i + 1][c] - STOPS[i][c]) * frac;
+        (v * 255.0).round() as u8
+    });
+    [b, g, r, 255]
+}
+
+/// 差分ヒートマップ（BGRA8）
+///
+/// 各ピクセルの最大チャンネル誤差を `scale` で割ってカラーマップに載せる
+/// （`scale` 以上の誤差は赤）。一致したピクセルは基準画像の輝度を暗く表示する。
+pub fn heatmap(reference: &ImageView, actual: &ImageView, alpha: AlphaCompare, scale: f32) -> Result<ImageBuf, ImageError> {
+    let channels = alpha.channels();
+    let scale = scale.max(f32::EPSILON);
+    let mut image = ImageBuf::new(reference.width(), reference.height(), DxgiFormat::B8G8R8A8Unorm)?;
+
+    for_each_pixel(reference, actual, alpha, |x, y, a, b| {
+        let error = (0..channels).map(|c| (a[c] - b[c]).abs()).fold(0.0, f32::max);
+        let color = if error > 0.0 {
+            heat_color(error / scale)
+        } else {
+            let gray = (luma(a).clamp(0.0, 1.0) * 64.0).round() as u8;
+            [gray, gray, gray, 255]
+        };
+        image.pixel_mut(x, y).copy_from_slice(&color);
+    })?;
+    Ok(image)
+}
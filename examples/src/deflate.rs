@@ -0,0 +1,930 @@
+// ============================================================
+// Deflate / zlib 圧縮・展開（PNG 用、外部クレートなし）
+// ============================================================
+//
+// RFC 1950 / 1951 を実装する。展開は PNG の読み込み用で、速さより簡潔さを優先する。
+// - レベル 0: 無圧縮ブロック
+// - レベル 1-9: ハッシュチェーンの LZ77（レベルで探索の深さを変える、4 以上は遅延評価）
+// ブロックごとに動的ハフマン・固定ハフマン・無圧縮のうち最小のものを選ぶ。
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+const WINDOW_SIZE: usize = 1 << 15;
+const WINDOW_MASK: usize = WINDOW_SIZE - 1;
+const HASH_BITS: u32 = 15;
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 258;
+/// 1 ブロックのトークン数の上限
+const BLOCK_TOKENS: usize = 1 << 14;
+const MAX_CODE_BITS: usize = 15;
+const MAX_CODE_LENGTH_BITS: usize = 7;
+const END_OF_BLOCK: usize = 256;
+
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131, 163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0];
+const DISTANCE_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537, 2049, 3073, 4097, 6145,
+    8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13, 13,
+];
+/// 符号長符号の並び順
+const CODE_LENGTH_ORDER: [usize; 19] = [16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15];
+
+// ============================================================
+// チェックサム
+// ============================================================
+
+const fn crc32_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut n = 0;
+    while n < 256 {
+        let mut c = n as u32;
+        let mut k = 0;
+        while k < 8 {
+            c = if c & 1 != 0 { 0xEDB8_8320 ^ (c >> 1) } else { c >> 1 };
+            k += 1;
+        }
+        table[n] = c;
+        n += 1;
+    }
+    table
+}
+
+static CRC32_TABLE: [u32; 256] = crc32_table();
+
+/// CRC-32（PNG のチャンク・gzip と同じ多項式）を逐次計算
+#[derive(Debug, Clone, Copy)]
+pub struct Crc32(u32);
+
+impl Default for Crc32 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Crc32 {
+    pub fn new() -> Self {
+        Self(0xFFFF_FFFF)
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        for &byte in data {
+            self.0 = CRC32_TABLE[((self.0 ^ byte as u32) & 0xFF) as usize] ^ (self.0 >> 8);
+        }
+    }
+
+    pub fn finish(self) -> u32 {
+        self.0 ^ 0xFFFF_FFFF
+    }
+}
+
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc = Crc32::new();
+    crc.update(data);
+    crc.finish()
+}
+
+/// Adler-32（zlib のトレーラー）
+pub fn adler32(data: &[u8]) -> u32 {
+    const MOD: u32 = 65521;
+    // 5552 バイトまでは u32 であふれない
+    let (mut a, mut b) = (1u32, 0u32);
+    for chunk in data.chunks(5552) {
+        for &byte in chunk {
+            a += byte as u32;
+            b += a;
+        }
+        a %= MOD;
+        b %= MOD;
+    }
+    b << 16 | a
+}
+
+// ============================================================
+// ビット出力
+// ============================================================
+
+/// LSB から詰めるビット列
+#[derive(Debug, Default)]
+struct BitWriter {
+    out: Vec<u8>,
+    buffer: u64,
+    bits: u32,
+}
+
+impl BitWriter {
+    fn write(&mut self, value: u32, bits: u32) {
+        self.buffer |= (value as u64) << self.bits;
+        self.bits += bits;
+        while self.bits >= 8 {
+            self.out.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.bits -= 8;
+        }
+    }
+
+    /// バイト境界まで 0 で埋める
+    fn align(&mut self) {
+        if self.bits > 0 {
+            self.write(0, 8 - self.bits);
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        self.align();
+        self.out
+    }
+}
+
+// ============================================================
+// ハフマン符号
+// ============================================================
+
+/// 頻度から長さ制限付きのハフマン符号長を求める
+///
+/// 通常のハフマン木を作り、`max_bits` を超えた分は Kraft 和が 1 に
+/// なるよう短い符号を分割して調整する（miniz / zlib と同じ方針）。
+fn code_lengths(freqs: &[u32], max_bits: usize) -> Vec<u8> {
+    let mut lengths = vec![0u8; freqs.len()];
+    let mut symbols: Vec<usize> = (0..freqs.len()).filter(|&s| freqs[s] > 0).collect();
+    match symbols.len() {
+        0 => return lengths,
+        1 => {
+            lengths[symbols[0]] = 1;
+            return lengths;
+        }
+        _ => {}
+    }
+
+    // ノード: 0..n が葉、それ以降が内部ノード
+    let n = symbols.len();
+    let mut parent = vec![0usize; 2 * n - 1];
+    let mut heap: BinaryHeap<Reverse<(u64, usize)>> =
+        symbols.iter().enumerate().map(|(i, &s)| Reverse((freqs[s] as u64, i))).collect();
+    let mut next = n;
+    while heap.len() > 1 {
+        let Reverse((wa, a)) = heap.pop().unwrap();
+        let Reverse((wb, b)) = heap.pop().unwrap();
+        parent[a] = next;
+        parent[b] = next;
+        heap.push(Reverse((wa + wb, next)));
+        next += 1;
+    }
+    let root = next - 1;
+
+    // 葉の深さを符号長ごとに数える（長すぎるものは max_bits に寄せる）
+    let mut counts = vec![0u32; max_bits + 1];
+    for leaf in 0..n {
+        let (mut depth, mut node) = (0, leaf);
+        while node != root {
+            node = parent[node];
+            depth += 1;
+        }
+        counts[depth.min(max_bits)] += 1;
+    }
+    let kraft = |counts: &[u32]| -> u64 { (1..=max_bits).map(|l| (counts[l] as u64) << (max_bits - l)).sum() };
+    while kraft(&counts) > 1 << max_bits {
+        counts[max_bits] -= 1;
+        let shorter = (1..max_bits).rev().find(|&l| counts[l] > 0).expect("a shorter code always exists");
+        counts[shorter] -= 1;
+        counts[shorter + 1] += 2;
+    }
+
+    // 頻度の低い記号から長い符号を割り当てる
+    symbols.sort_by_key(|&s| (freqs[s], s));
+    let mut iter = symbols.into_iter();
+    for length in (1..=max_bits).rev() {
+        for _ in 0..counts[length] {
+            lengths[iter.next().unwrap()] = length as u8;
+        }
+    }
+    lengths
+}
+
+/// 符号長から正準ハフマン符号を作る（ビット順は出力用に反転済み）
+fn canonical_codes(lengths: &[u8]) -> Vec<u16> {
+    let mut counts = [0u16; MAX_CODE_BITS + 1];
+    for &length in lengths {
+        counts[length as usize] += 1;
+    }
+    counts[0] = 0;
+    let mut next = [0u16; MAX_CODE_BITS + 1];
+    let mut code = 0u16;
+    for bits in 1..=MAX_CODE_BITS {
+        code = (code + counts[bits - 1]) << 1;
+        next[bits] = code;
+    }
+    lengths
+        .iter()
+        .map(|&length| {
+            if length == 0 {
+                return 0;
+            }
+            let code = next[length as usize];
+            next[length as usize] += 1;
+            code.reverse_bits() >> (16 - length)
+        })
+        .collect()
+}
+
+/// 少なくとも 2 つの記号を持たせる（単一符号の扱いが実装によって違うため）
+fn ensure_two_symbols(freqs: &mut [u32]) {
+    let mut used = freqs.iter().filter(|&&f| f > 0).count();
+    for freq in freqs.iter_mut() {
+        if used >= 2 {
+            break;
+        }
+        if *freq == 0 {
+            *freq = 1;
+            used += 1;
+        }
+    }
+}
+
+// ============================================================
+// LZ77
+// ============================================================
+
+#[derive(Debug, Clone, Copy)]
+enum Token {
+    Literal(u8),
+    Match { length: u16, distance: u16 },
+}
+
+fn length_code(length: u16) -> usize {
+    LENGTH_BASE.iter().rposition(|&base| base <= length).unwrap()
+}
+
+fn distance_code(distance: u16) -> usize {
+    DISTANCE_BASE.iter().rposition(|&base| base <= distance).unwrap()
+}
+
+/// レベルごとの探索設定 (チェーンの長さ, 十分な一致長, 遅延評価)
+fn level_config(level: u8) -> (usize, usize, bool) {
+    match level {
+        1 => (4, 8, false),
+        2 => (8, 16, false),
+        3 => (16, 32, false),
+        4 => (16, 32, true),
+        5 => (32, 64, true),
+        6 => (128, 128, true),
+        7 => (256, MAX_MATCH, true),
+        8 => (1024, MAX_MATCH, true),
+        _ => (4096, MAX_MATCH, true),
+    }
+}
+
+struct Matcher<'a> {
+    data: &'a [u8],
+    /// ハッシュごとの最新位置 + 1（0 は空）
+    head: Vec<u32>,
+    prev: Vec<u32>,
+    max_chain: usize,
+    nice_length: usize,
+}
+
+impl<'a> Matcher<'a> {
+    fn new(data: &'a [u8], max_chain: usize, nice_length: usize) -> Self {
+        Self { data, head: vec![0; 1 << HASH_BITS], prev: vec![0; WINDOW_SIZE], max_chain, nice_length }
+    }
+
+    fn hash(&self, pos: usize) -> usize {
+        let d = self.data;
+        let v = (d[pos] as u32) << 16 | (d[pos + 1] as u32) << 8 | d[pos + 2] as u32;
+        (v.wrapping_mul(0x9E37_79B1) >> (32 - HASH_BITS)) as usize
+    }
+
+    fn insert(&mut self, pos: usize) {
+        if pos + MIN_MATCH > self.data.len() {
+            return;
+        }
+        let h = self.hash(pos);
+        self.prev[pos & WINDOW_MASK] = self.head[h];
+        self.head[h] = pos as u32 + 1;
+    }
+
+    /// pos からの最長一致 (長さ, 距離)。見つからなければ長さ 0
+    fn longest_match(&self, pos: usize) -> (usize, usize) {
+        let data = self.data;
+        let max_length = MAX_MATCH.min(data.len() - pos);
+        if max_length < MIN_MATCH {
+            return (0, 0);
+        }
+        let (mut best_length, mut best_distance) = (0, 0);
+        let mut candidate = self.head[self.hash(pos)];
+        let mut chain = self.max_chain;
+        while candidate != 0 && chain > 0 {
+            let start = candidate as usize - 1;
+            let distance = pos - start;
+            if distance > WINDOW_SIZE {
+                break;
+            }
+            // 現在の最長より先の 1 バイトが違えば比較を省略
+            if data[start + best_length.min(max_length - 1)] == data[pos + best_length.min(max_length - 1)] {
+                let length = data[start..start + max_length]
+                    .iter()
+                    .zip(&data[pos..pos + max_length])
+                    .take_while(|(a, b)| a == b)
+                    .count();
+                if length > best_length {
+                    (best_length, best_distance) = (length, distance);
+                    if length >= self.nice_length {
+                        break;
+                    }
+                }
+            }
+            let next = self.prev[start & WINDOW_MASK];
+            // 窓を一周して上書きされたエントリは新しい位置を指すので打ち切る
+            if next as usize > start {
+                break;
+            }
+            candidate = next;
+            chain -= 1;
+        }
+        if best_length >= MIN_MATCH { (best_length, best_distance) } else { (0, 0) }
+    }
+}
+
+// ============================================================
+// ブロック出力
+// ============================================================
+
+struct Encoder {
+    writer: BitWriter,
+}
+
+impl Encoder {
+    fn write_stored(&mut self, bytes: &[u8], last: bool) {
+        let mut chunks = bytes.chunks(0xFFFF).peekable();
+        if chunks.peek().is_none() {
+            self.writer.write(last as u32, 1);
+            self.writer.write(0, 2);
+            self.writer.align();
+            self.writer.write(0, 16);
+            self.writer.write(0xFFFF, 16);
+            return;
+        }
+        while let Some(chunk) = chunks.next() {
+            let final_chunk = last && chunks.peek().is_none();
+            self.writer.write(final_chunk as u32, 1);
+            self.writer.write(0, 2);
+            self.writer.align();
+            self.writer.write(chunk.len() as u32, 16);
+            self.writer.write(!(chunk.len() as u16) as u32, 16);
+            for &byte in chunk {
+                self.writer.write(byte as u32, 8);
+            }
+        }
+    }
+
+    fn write_tokens(&mut self, tokens: &[Token], lit_lengths: &[u8], dist_lengths: &[u8]) {
+        let lit_codes = canonical_codes(lit_lengths);
+        let dist_codes = canonical_codes(dist_lengths);
+        let w = &mut self.writer;
+        for &token in tokens {
+            match token {
+                Token::Literal(byte) => w.write(lit_codes[byte as usize] as u32, lit_lengths[byte as usize] as u32),
+                Token::Match { length, distance } => {
+                    let lc = length_code(length);
+                    w.write(lit_codes[257 + lc] as u32, lit_lengths[257 + lc] as u32);
+                    w.write((length - LENGTH_BASE[lc]) as u32, LENGTH_EXTRA[lc] as u32);
+                    let dc = distance_code(distance);
+                    w.write(dist_codes[dc] as u32, dist_lengths[dc] as u32);
+                    w.write((distance - DISTANCE_BASE[dc]) as u32, DISTANCE_EXTRA[dc] as u32);
+                }
+            }
+        }
+        w.write(lit_codes[END_OF_BLOCK] as u32, lit_lengths[END_OF_BLOCK] as u32);
+    }
+
+    /// トークン列を 1 ブロックとして出力（`bytes` はブロックが表す元データ）
+    fn write_block(&mut self, tokens: &[Token], bytes: &[u8], last: bool) {
+        let mut lit_freqs = vec![0u32; 286];
+        let mut dist_freqs = vec![0u32; 30];
+        for &token in tokens {
+            match token {
+                Token::Literal(byte) => lit_freqs[byte as usize] += 1,
+                Token::Match { length, distance } => {
+                    lit_freqs[257 + length_code(length)] += 1;
+                    dist_freqs[distance_code(distance)] += 1;
+                }
+            }
+        }
+        lit_freqs[END_OF_BLOCK] = 1;
+
+        // データ部分のビット数（符号 + 拡張ビット）
+        let extra_bits: u64 = tokens
+            .iter()
+            .map(|&token| match token {
+                Token::Literal(_) => 0,
+                Token::Match { length, distance } => {
+                    LENGTH_EXTRA[length_code(length)] as u64 + DISTANCE_EXTRA[distance_code(distance)] as u64
+                }
+            })
+            .sum();
+        let body_bits = |lit: &[u8], dist: &[u8]| -> u64 {
+            lit_freqs.iter().zip(lit).map(|(&f, &l)| f as u64 * l as u64).sum::<u64>()
+                + dist_freqs.iter().zip(dist).map(|(&f, &l)| f as u64 * l as u64).sum::<u64>()
+                + extra_bits
+        };
+
+        // 動的ハフマン
+        let mut lit_used = lit_freqs.clone();
+        let mut dist_used = dist_freqs.clone();
+        ensure_two_symbols(&mut lit_used);
+        ensure_two_symbols(&mut dist_used);
+        let lit_lengths = code_lengths(&lit_used, MAX_CODE_BITS);
+        let dist_lengths = code_lengths(&dist_used, MAX_CODE_BITS);
+        let header = DynamicHeader::new(&lit_lengths, &dist_lengths);
+        let dynamic_bits = 3 + header.bits() + body_bits(&lit_lengths, &dist_lengths);
+
+        // 固定ハフマン
+        let (fixed_lit, fixed_dist) = fixed_lengths();
+        let fixed_bits = 3 + body_bits(&fixed_lit, &fixed_dist);
+
+        // 無圧縮（ヘッダー 5 バイト / 65535 バイト）
+        let stored_bits = (bytes.len() as u64 + 5 * (bytes.len() as u64 / 0xFFFF + 1)) * 8 + 7;
+
+        if stored_bits <= dynamic_bits.min(fixed_bits) {
+            self.write_stored(bytes, last);
+        } else if fixed_bits <= dynamic_bits {
+            self.writer.write(last as u32, 1);
+            self.writer.write(1, 2);
+            self.write_tokens(tokens, &fixed_lit, &fixed_dist);
+        } else {
+            self.writer.write(last as u32, 1);
+            self.writer.write(2, 2);
+            header.write(&mut self.writer);
+            self.write_tokens(tokens, &lit_lengths, &dist_lengths);
+        }
+    }
+}
+
+fn fixed_lengths() -> (Vec<u8>, Vec<u8>) {
+    let lit = (0..288)
+        .map(|s| match s {
+            0..=143 => 8,
+            144..=255 => 9,
+            256..=279 => 7,
+            _ => 8,
+        })
+        .collect();
+    (lit, vec![5; 30])
+}
+
+/// 動的ハフマンブロックのヘッダー（符号長を符号長符号で RLE 圧縮したもの）
+struct DynamicHeader {
+    hlit: usize,
+    hdist: usize,
+    hclen: usize,
+    /// (符号長記号, 拡張ビット値, 拡張ビット数)
+    symbols: Vec<(u8, u8, u8)>,
+    cl_lengths: Vec<u8>,
+}
+
+impl DynamicHeader {
+    fn new(lit_lengths: &[u8], dist_lengths: &[u8]) -> Self {
+        let hlit = lit_lengths.iter().rposition(|&l| l > 0).map_or(257, |i| (i + 1).max(257));
+        let hdist = dist_lengths.iter().rposition(|&l| l > 0).map_or(1, |i| i + 1);
+        let lengths: Vec<u8> = lit_lengths[..hlit].iter().chain(&dist_lengths[..hdist]).copied().collect();
+
+        let mut symbols = Vec::new();
+        let mut i = 0;
+        while i < lengths.len() {
+            let length = lengths[i];
+            let run = lengths[i..].iter().take_while(|&&l| l == length).count();
+            let mut remaining = run;
+            if length == 0 {
+                while remaining >= 11 {
+                    let n = remaining.min(138);
+                    symbols.push((18, (n - 11) as u8, 7));
+                    remaining -= n;
+                }
+                if remaining >= 3 {
+                    symbols.push((17, (remaining - 3) as u8, 3));
+                    remaining = 0;
+                }
+            } else {
+                symbols.push((length, 0, 0));
+                remaining -= 1;
+                while remaining >= 3 {
+                    let n = remaining.min(6);
+                    symbols.push((16, (n - 3) as u8, 2));
+                    remaining -= n;
+                }
+            }
+            symbols.extend(std::iter::repeat_n((length, 0, 0), remaining));
+            i += run;
+        }
+
+        let mut cl_freqs = vec![0u32; 19];
+        for &(symbol, _, _) in &symbols {
+            cl_freqs[symbol as usize] += 1;
+        }
+        ensure_two_symbols(&mut cl_freqs);
+        let cl_lengths = code_lengths(&cl_freqs, MAX_CODE_LENGTH_BITS);
+        let hclen = CODE_LENGTH_ORDER.iter().rposition(|&s| cl_lengths[s] > 0).map_or(4, |i| (i + 1).max(4));
+        Self { hlit, hdist, hclen, symbols, cl_lengths }
+    }
+
+    fn bits(&self) -> u64 {
+        14 + 3 * self.hclen as u64
+            + self
+                .symbols
+                .iter()
+                .map(|&(symbol, _, extra_bits)| self.cl_lengths[symbol as usize] as u64 + extra_bits as u64)
+                .sum::<u64>()
+    }
+
+    fn write(&self, w: &mut BitWriter) {
+        w.write((self.hlit - 257) as u32, 5);
+        w.write((self.hdist - 1) as u32, 5);
+        w.write((self.hclen - 4) as u32, 4);
+        for &symbol in &CODE_LENGTH_ORDER[..self.hclen] {
+            w.write(self.cl_lengths[symbol] as u32, 3);
+        }
+        let codes = canonical_codes(&self.cl_lengths);
+        for &(symbol, extra, extra_bits) in &self.symbols {
+            w.write(codes[symbol as usize] as u32, self.cl_lengths[symbol as usize] as u32);
+            w.write(extra as u32, extra_bits as u32);
+        }
+    }
+}
+
+// ============================================================
+// 展開
+// ============================================================
+
+/// LSB から読むビット列
+struct BitReader<'a> {
+    data: &'a [u8],
+    /// 読んだビット数
+    position: usize,
+}
+
+impl<'a> BitReader<'a> {
+    /// `count` ビット（24 以下）を読む
+    fn bits(&mut self, count: u32) -> Result<u32, String> {
+        let (byte, shift) = (self.position / 8, self.position % 8);
+        if (self.position + count as usize).div_ceil(8) > self.data.len() {
+            return Err("unexpected end of deflate stream".to_string());
+        }
+        let mut word = [0u8; 4];
+        let available = (self.data.len() - byte).min(4);
+        word[..available].copy_from_slice(&self.data[byte..byte + available]);
+        self.position += count as usize;
+        Ok((u32::from_le_bytes(word) >> shift) & ((1 << count) - 1))
+    }
+
+    /// 次のバイト境界まで読み飛ばす
+    fn align(&mut self) {
+        self.position = self.position.div_ceil(8) * 8;
+    }
+}
+
+/// 正準ハフマン符号の復号表（符号長ごとの数と、符号順の記号）
+struct Decoder {
+    counts: [u16; MAX_CODE_BITS + 1],
+    symbols: Vec<u16>,
+}
+
+impl Decoder {
+    fn new(lengths: &[u8]) -> Result<Self, String> {
+        let mut counts = [0u16; MAX_CODE_BITS + 1];
+        for &length in lengths {
+            counts[length as usize] += 1;
+        }
+        counts[0] = 0;
+        // 符号が余るのはよい（記号 1 つだけの距離符号など）が、足りないのは壊れている
+        let mut left = 1i32;
+        for &count in &counts[1..] {
+            left = left * 2 - count as i32;
+            if left < 0 {
+                return Err("over-subscribed Huffman code".to_string());
+            }
+        }
+        let mut offsets = [0u16; MAX_CODE_BITS + 2];
+        for length in 1..=MAX_CODE_BITS {
+            offsets[length + 1] = offsets[length] + counts[length];
+        }
+        let mut symbols = vec![0u16; offsets[MAX_CODE_BITS + 1] as usize];
+        for (symbol, &length) in lengths.iter().enumerate().filter(|(_, length)| **length > 0) {
+            symbols[offsets[length as usize] as usize] = symbol as u16;
+            offsets[length as usize] += 1;
+        }
+        Ok(Self { counts, symbols })
+    }
+
+    /// 1 ビットずつ読んで記号を求める
+    fn decode(&self, reader: &mut BitReader) -> Result<usize, String> {
+        let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+        for &count in &self.counts[1..] {
+            code |= reader.bits(1)? as i32;
+            let count = count as i32;
+            if code - first < count {
+                return Ok(self.symbols[(index + code - first) as usize] as usize);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err("invalid Huffman code".to_string())
+    }
+}
+
+/// 動的ハフマンブロックのヘッダーを読んで (リテラル・長さ, 距離) の復号表を作る
+fn read_dynamic_header(reader: &mut BitReader) -> Result<(Decoder, Decoder), String> {
+    let literals = reader.bits(5)? as usize + 257;
+    let distances = reader.bits(5)? as usize + 1;
+    let code_lengths = reader.bits(4)? as usize + 4;
+    let mut lengths = [0u8; 19];
+    for &symbol in &CODE_LENGTH_ORDER[..code_lengths] {
+        lengths[symbol] = reader.bits(3)? as u8;
+    }
+    let decoder = Decoder::new(&lengths)?;
+
+    let mut lengths = Vec::with_capacity(literals + distances);
+    while lengths.len() < literals + distances {
+        let (value, repeat) = match decoder.decode(reader)? {
+            symbol @ 0..=15 => (symbol as u8, 1),
+            16 => (*lengths.last().ok_or("repeat without a previous length")?, 3 + reader.bits(2)?),
+            17 => (0, 3 + reader.bits(3)?),
+            _ => (0, 11 + reader.bits(7)?),
+        };
+        lengths.extend(std::iter::repeat_n(value, repeat as usize));
+    }
+    if lengths.len() > literals + distances || lengths[END_OF_BLOCK] == 0 {
+        return Err("invalid code lengths".to_string());
+    }
+    Ok((Decoder::new(&lengths[..literals])?, Decoder::new(&lengths[literals..])?))
+}
+
+/// ハフマン符号化されたブロックを 1 つ展開
+fn inflate_block(
+    reader: &mut BitReader,
+    out: &mut Vec<u8>,
+    literals: &Decoder,
+    distances: &Decoder,
+    limit: usize,
+) -> Result<(), String> {
+    loop {
+        let symbol = literals.decode(reader)?;
+        match symbol {
+            0..=255 => out.push(symbol as u8),
+            END_OF_BLOCK => return Ok(()),
+            _ => {
+                let code = symbol - 257;
+                if code >= LENGTH_BASE.len() {
+                    return Err(format!("invalid length symbol: {}", symbol));
+                }
+                let length = LENGTH_BASE[code] as usize + reader.bits(LENGTH_EXTRA[code] as u32)? as usize;
+                let code = distances.decode(reader)?;
+                if code >= DISTANCE_BASE.len() {
+                    return Err(format!("invalid distance symbol: {}", code));
+                }
+                let distance = DISTANCE_BASE[code] as usize + reader.bits(DISTANCE_EXTRA[code] as u32)? as usize;
+                if distance > out.len() {
+                    return Err("distance too far back".to_string());
+                }
+                // 重なりのあるコピー（distance < length）があるので 1 バイトずつ
+                let start = out.len() - distance;
+                for i in 0..length {
+                    out.push(out[start + i]);
+                }
+            }
+        }
+        if out.len() > limit {
+            return Err(format!("inflated data exceeds {} bytes", limit));
+        }
+    }
+}
+
+// ============================================================
+// 公開 API
+// ============================================================
+
+/// raw deflate で圧縮（`level` は 0-9、9 を超える値は 9 として扱う）
+pub fn deflate(data: &[u8], level: u8) -> Vec<u8> {
+    let mut encoder = Encoder { writer: BitWriter::default() };
+    if level == 0 {
+        encoder.write_stored(data, true);
+        return encoder.writer.finish();
+    }
+
+    let (max_chain, nice_length, lazy) = level_config(level);
+    let mut matcher = Matcher::new(data, max_chain, nice_length);
+    let mut tokens = Vec::with_capacity(BLOCK_TOKENS);
+    let mut block_start = 0;
+    let mut pos = 0;
+
+    while pos < data.len() {
+        let (mut length, mut distance) = matcher.longest_match(pos);
+        if length < MIN_MATCH {
+            matcher.insert(pos);
+            tokens.push(Token::Literal(data[pos]));
+            pos += 1;
+        } else {
+            // 挿入済みの位置の次
+            let mut inserted = pos;
+            if lazy && length < nice_length && pos + 1 < data.len() {
+                matcher.insert(pos);
+                inserted = pos + 1;
+                let (next_length, next_distance) = matcher.longest_match(pos + 1);
+                if next_length > length {
+                    // 1 バイト先の方が長く一致するなら、ここはリテラルにする
+                    tokens.push(Token::Literal(data[pos]));
+                    pos += 1;
+                    (length, distance) = (next_length, next_distance);
+                }
+            }
+            tokens.push(Token::Match { length: length as u16, distance: distance as u16 });
+            for p in inserted..pos + length {
+                matcher.insert(p);
+            }
+            pos += length;
+        }
+
+        if tokens.len() >= BLOCK_TOKENS {
+            encoder.write_block(&tokens, &data[block_start..pos], pos == data.len());
+            tokens.clear();
+            block_start = pos;
+        }
+    }
+    if !tokens.is_empty() || block_start == 0 {
+        encoder.write_block(&tokens, &data[block_start..pos], true);
+    }
+    encoder.writer.finish()
+}
+
+/// zlib 形式（ヘッダー + deflate + Adler-32）で圧縮
+pub fn zlib_compress(data: &[u8], level: u8) -> Vec<u8> {
+    // CMF: deflate, 32K 窓。FLG の FLEVEL は目安、FCHECK で 31 の倍数にする
+    let cmf = 0x78u8;
+    let flevel = match level {
+        0..=1 => 0u8,
+        2..=5 => 1,
+        6 => 2,
+        _ => 3,
+    };
+    let mut flg = flevel << 6;
+    flg += 31 - ((cmf as u16 * 256 + flg as u16) % 31) as u8;
+
+    let mut out = vec![cmf, flg];
+    out.extend(deflate(data, level));
+    out.extend(adler32(data).to_be_bytes());
+    out
+}
+
+/// raw deflate を展開（`limit` バイトを超えたらエラー）
+pub fn inflate(data: &[u8], limit: usize) -> Result<Vec<u8>, String> {
+    let mut reader = BitReader { data, position: 0 };
+    let mut out = Vec::new();
+    loop {
+        let last = reader.bits(1)? == 1;
+        match reader.bits(2)? {
+            0 => {
+                reader.align();
+                let length = reader.bits(16)? as usize;
+                if reader.bits(16)? as usize != !length & 0xFFFF {
+                    return Err("stored block length mismatch".to_string());
+                }
+                let start = reader.position / 8;
+                let block = data.get(start..start + length).ok_or("unexpected end of stored block")?;
+                out.extend_from_slice(block);
+                reader.position += length * 8;
+                if out.len() > limit {
+                    return Err(format!("inflated data exceeds {} bytes", limit));
+                }
+            }
+            1 => {
+                let (literals, distances) = fixed_lengths();
+                inflate_block(&mut reader, &mut out, &Decoder::new(&literals)?, &Decoder::new(&distances)?, limit)?;
+            }
+            2 => {
+                let (literals, distances) = read_dynamic_header(&mut reader)?;
+                inflate_block(&mut reader, &mut out, &literals, &distances, limit)?;
+            }
+            _ => return Err("invalid deflate block type".to_string()),
+        }
+        if last {
+            return Ok(out);
+        }
+    }
+}
+
+/// zlib 形式を展開して Adler-32 を確かめる（`limit` バイトを超えたらエラー）
+pub fn zlib_decompress(data: &[u8], limit: usize) -> Result<Vec<u8>, String> {
+    let [cmf, flg, ..] = *data else {
+        return Err("zlib stream too short".to_string());
+    };
+    if cmf & 0x0F != 8 || !(cmf as u16 * 256 + flg as u16).is_multiple_of(31) {
+        return Err("invalid zlib header".to_string());
+    }
+    if flg & 0x20 != 0 {
+        return Err("zlib preset dictionary is not supported".to_string());
+    }
+    if data.len() < 6 {
+        return Err("zlib stream too short".to_string());
+    }
+    let (body, trailer) = data[2..].split_at(data.len() - 6);
+    let out = inflate(body, limit)?;
+    let expected = u32::from_be_bytes([trailer[0], trailer[1], trailer[2], trailer[3]]);
+    if adler32(&out) != expected {
+        return Err("zlib Adler-32 mismatch".to_string());
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use miniz_oxide::inflate::{decompress_to_vec, decompress_to_vec_zlib};
+
+    /// 先頭ブロックの種別（0: 無圧縮、1: 固定ハフマン、2: 動的ハフマン）
+    fn first_block_type(stream: &[u8]) -> u8 {
+        (stream[0] >> 1) & 3
+    }
+
+    fn noise(len: usize) -> Vec<u8> {
+        let mut state = 0x9E37_79B9u32;
+        (0..len)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                state as u8
+            })
+            .collect()
+    }
+
+    fn text(len: usize) -> Vec<u8> {
+        let words = ["spout", "sender", "receiver", "texture", "frame", "latency", "tearing", "pattern"];
+        let noise = noise(len);
+        let mut out = Vec::with_capacity(len + 16);
+        let mut i = 0;
+        while out.len() < len {
+            out.extend_from_slice(words[noise[i] as usize % words.len()].as_bytes());
+            out.push(if noise[i] & 0x80 != 0 { b'\n' } else { b' ' });
+            i += 1;
+        }
+        out.truncate(len);
+        out
+    }
+
+    /// 参照実装と自前の展開の両方で元に戻ることを確かめる
+    fn assert_roundtrip(data: &[u8], level: u8) -> Vec<u8> {
+        let compressed = deflate(data, level);
+        assert_eq!(decompress_to_vec(&compressed).unwrap(), data, "reference inflate, level {}", level);
+        assert_eq!(inflate(&compressed, data.len()).unwrap(), data, "inflate, level {}", level);
+        let zlib = zlib_compress(data, level);
+        assert_eq!(decompress_to_vec_zlib(&zlib).unwrap(), data, "reference zlib, level {}", level);
+        assert_eq!(zlib_decompress(&zlib, data.len()).unwrap(), data, "zlib_decompress, level {}", level);
+        compressed
+    }
+
+    #[test]
+    fn stored_blocks_match_reference() {
+        // レベル 0 は常に無圧縮、65535 バイトを超えると複数ブロックに分かれる
+        for len in [0, 1, 65535, 65536, 200_000] {
+            assert_eq!(first_block_type(&assert_roundtrip(&text(len), 0)), 0, "len {}", len);
+        }
+        // ノイズは圧縮しても小さくならないので無圧縮を選ぶ
+        for level in 1..=9 {
+            assert_eq!(first_block_type(&assert_roundtrip(&noise(50_000), level)), 0, "level {}", level);
+        }
+    }
+
+    #[test]
+    fn fixed_blocks_match_reference() {
+        // 短いデータは符号表を送らない固定ハフマンが小さい
+        for level in 1..=9 {
+            let compressed = assert_roundtrip(b"spout spout spout sender", level);
+            assert_eq!(first_block_type(&compressed), 1, "level {}", level);
+        }
+    }
+
+    #[test]
+    fn dynamic_blocks_match_reference() {
+        // 長いテキストは動的ハフマン（トークン数の上限で複数ブロックに分かれる）
+        for level in 1..=9 {
+            let data = text(120_000);
+            let compressed = assert_roundtrip(&data, level);
+            assert_eq!(first_block_type(&compressed), 2, "level {}", level);
+            assert!(compressed.len() < data.len() / 2, "level {}: {} bytes", level, compressed.len());
+        }
+    }
+
+    #[test]
+    fn inflate_matches_reference_encoder() {
+        let data = [text(100_000), noise(10_000), vec![0; 70_000]].concat();
+        for level in 0..=10 {
+            let compressed = miniz_oxide::deflate::compress_to_vec(&data, level);
+            assert_eq!(inflate(&compressed, data.len()).unwrap(), data, "level {}", level);
+        }
+    }
+}
@@ -0,0 +1,399 @@
+// ============================================================
+// 画像診断レポート
+// ============================================================
+//
+// 受信画像の平均色・サンプルピクセル・警告をまとめた構造体。
+// 人が読む用に Display、機械が読む用に JSON を出力できる。
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Serialize, Serializer};
+
+use crate::alpha::{detect_premultiplied, AlphaAnalysis};
+use crate::color::{average_color, ColorSpace};
+use crate::hdr::decode_pixel;
+use crate::image::{DxgiFormat, ImageError, ImageView};
+use crate::letterbox::{find_active_area, ActiveArea, AreaLayout, BorderOptions};
+use crate::layout::{detect_layout, ChannelOrder, LayoutDetection, LayoutOptions, Orientation};
+use crate::stats::compute_stats;
+
+/// Display 文字列としてシリアライズ（フォーマット名・色空間など）
+fn serialize_display<T: fmt::Display, S: Serializer>(value: &T, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(value)
+}
+
+/// 項目ごとに重要度とメッセージを付けてシリアライズ
+fn serialize_findings<S: Serializer>(findings: &[Finding], serializer: S) -> Result<S::Ok, S::Error> {
+    #[derive(Serialize)]
+    struct Entry<'a> {
+        severity: Severity,
+        message: String,
+        #[serde(flatten)]
+        finding: &'a Finding,
+    }
+    serializer.collect_seq(findings.iter().map(|finding| Entry {
+        severity: finding.severity(),
+        message: finding.to_string(),
+        finding,
+    }))
+}
+
+/// 判定しきい値（いずれも sRGB 0-255 の平均値に対して適用）
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct DiagnosticThresholds {
+    /// RGB 平均がすべてこれ未満なら全黒、アルファ平均がこれ未満なら透明
+    pub black: f64,
+    /// RGB 平均がすべてこれを超えれば全白、アルファ平均がこれを超えれば不透明
+    pub white: f64,
+    /// アルファ平均がこれ未満なら警告
+    pub alpha_low: f64,
+    /// チャンネル順・上下の推定をこの確信度（0..1）以上で報告する
+    pub layout_confidence: f64,
+    /// 枠（黒・透明）とみなす格納値の上限（0-255）
+    pub border: f64,
+}
+
+impl Default for DiagnosticThresholds {
+    fn default() -> Self {
+        Self {
+            black: 1.0,
+            white: 254.0,
+            alpha_low: 128.0,
+            layout_confidence: 0.8,
+            border: 8.0,
+        }
+    }
+}
+
+impl FromStr for DiagnosticThresholds {
+    type Err = String;
+
+    /// `black=1,white=254,alpha-low=128,layout=0.8,border=8`（省略した項目は既定値）
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut thresholds = Self::default();
+        for item in s.split(',').filter(|item| !item.is_empty()) {
+            let (key, value) = item
+                .split_once('=')
+                .ok_or_else(|| format!("invalid threshold: {} (expected key=value)", item))?;
+            let value: f64 = value
+                .parse()
+                .map_err(|_| format!("invalid threshold value: {}", item))?;
+            match key {
+                "black" => thresholds.black = value,
+                "white" => thresholds.white = value,
+                "alpha-low" => thresholds.alpha_low = value,
+                "layout" => thresholds.layout_confidence = value,
+                "border" => thresholds.border = value,
+                _ => return Err(format!("unknown threshold: {} (black, white, alpha-low, layout, border)", key)),
+            }
+        }
+        Ok(thresholds)
+    }
+}
+
+/// 重要度
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,
+    Warning,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Info => write!(f, "info"),
+            Self::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+/// 診断結果の項目
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Finding {
+    AllBlack,
+    AllWhite,
+    /// アルファがほぼ 0（完全に透明）
+    AlphaNearZero { average: f64 },
+    /// アルファ平均が低い
+    AlphaLow { average: f64 },
+    /// アルファが完全に不透明（想定どおり）
+    AlphaOpaque,
+    /// メモリ上のチャンネル順がフォーマットの宣言と違う
+    ChannelOrderMismatch { declared: ChannelOrder, detected: ChannelOrder, confidence: f32 },
+    /// 上下が反転している
+    Flipped { confidence: f32 },
+    /// 内容の周りに黒・透明の枠がある
+    Borders { layout: AreaLayout, top: u32, bottom: u32, left: u32, right: u32 },
+}
+
+impl Finding {
+    pub fn severity(&self) -> Severity {
+        match self {
+            Self::AlphaOpaque | Self::Borders { .. } => Severity::Info,
+            _ => Severity::Warning,
+        }
+    }
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::AllBlack => write!(f, "Image appears to be ALL BLACK!"),
+            Self::AllWhite => write!(f, "Image appears to be ALL WHITE!"),
+            Self::AlphaNearZero { .. } => {
+                write!(f, "Alpha channel is near ZERO - image may be fully transparent!")
+            }
+            Self::AlphaLow { average } => {
+                write!(f, "Alpha channel average is low ({:.1}) - possible alpha issue", average)
+            }
+            Self::AlphaOpaque => write!(f, "Alpha channel is fully opaque (expected)"),
+            Self::ChannelOrderMismatch { declared, detected, confidence } => write!(
+                f,
+                "Channel order looks like {} but the format declares {} ({:.0}% confidence)",
+                detected,
+                declared,
+                confidence * 100.0
+            ),
+            Self::Flipped { confidence } => {
+                write!(f, "Image appears vertically flipped ({:.0}% confidence)", confidence * 100.0)
+            }
+            Self::Borders { layout, top, bottom, left, right } => write!(
+                f,
+                "Content is padded ({}): top {}, bottom {}, left {}, right {} pixels",
+                layout, top, bottom, left, right
+            ),
+        }
+    }
+}
+
+/// チャンネルごとの統計
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct ChannelStats {
+    /// 表示上の平均（sRGB 0-255、アルファは 0-255）
+    pub mean_srgb8: f64,
+    /// リニア光での平均（0..1、HDR では 1 を超える）
+    pub mean_linear: f64,
+    /// 以下は格納値（0..1）
+    pub min: f32,
+    pub max: f32,
+    pub stddev: f64,
+}
+
+/// サンプルピクセル（格納値、0..1）
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SamplePixel {
+    pub label: &'static str,
+    pub x: u32,
+    pub y: u32,
+    pub rgba: [f32; 4],
+}
+
+/// 画像診断レポート
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DiagnosticReport {
+    pub width: u32,
+    pub height: u32,
+    #[serde(serialize_with = "serialize_display")]
+    pub format: DxgiFormat,
+    #[serde(serialize_with = "serialize_display")]
+    pub color_space: ColorSpace,
+    /// R, G, B, A の順
+    pub channels: [ChannelStats; 4],
+    pub clipped_black_percent: f64,
+    pub clipped_white_percent: f64,
+    pub alpha: Option<AlphaAnalysis>,
+    /// チャンネル順・上下・乗算済みの推定（8bit 以外は None）
+    pub layout: Option<LayoutDetection>,
+    /// 黒・透明の枠を除いた有効領域
+    pub active_area: Option<ActiveArea>,
+    #[serde(serialize_with = "serialize_findings")]
+    pub findings: Vec<Finding>,
+    /// 四隅と中心
+    pub samples: Vec<SamplePixel>,
+    pub thresholds: DiagnosticThresholds,
+}
+
+impl DiagnosticReport {
+    /// 画像を診断（平均はリニア光で計算し、しきい値は sRGB に戻した値で判定）
+    pub fn analyze(
+        view: &ImageView,
+        color_space: ColorSpace,
+        thresholds: DiagnosticThresholds,
+    ) -> Result<Self, ImageError> {
+        let average = average_color(view, color_space)?;
+        let stats = compute_stats(view, 1)?;
+        let srgb8 = average.srgb8();
+        let channels = std::array::from_fn(|i| ChannelStats {
+            mean_srgb8: srgb8[i],
+            mean_linear: average.linear[i],
+            min: stats.channels[i].min,
+            max: stats.channels[i].max,
+            stddev: stats.channels[i].stddev,
+        });
+
+        let (width, height) = (view.width(), view.height());
+        let corners = [
+            ("Top-Left", 0, 0),
+            ("Top-Right", width - 1, 0),
+            ("Center", width / 2, height / 2),
+            ("Bottom-Left", 0, height - 1),
+            ("Bottom-Right", width - 1, height - 1),
+        ];
+        let mut samples = Vec::with_capacity(corners.len());
+        for (label, x, y) in corners {
+            let rgba = decode_pixel(view.pixel(x, y), view.format())?;
+            samples.push(SamplePixel { label, x, y, rgba });
+        }
+
+        let layout = detect_layout(view, &LayoutOptions::default()).ok();
+        let border_options = BorderOptions { tolerance: (thresholds.border / 255.0) as f32, ..BorderOptions::default() };
+        let active_area = find_active_area(view, &border_options).ok();
+
+        Ok(Self {
+            width,
+            height,
+            format: view.format(),
+            color_space,
+            channels,
+            clipped_black_percent: stats.clipped_black_percent,
+            clipped_white_percent: stats.clipped_white_percent,
+            alpha: detect_premultiplied(view, 1.0 / 255.0).ok(),
+            findings: evaluate(srgb8, layout.as_ref(), active_area.as_ref(), &thresholds),
+            layout,
+            active_area,
+            samples,
+            thresholds,
+        })
+    }
+
+    /// 最も高い重要度（項目がなければ None）
+    pub fn max_severity(&self) -> Option<Severity> {
+        self.findings.iter().map(Finding::severity).max()
+    }
+
+    pub fn has_warnings(&self) -> bool {
+        self.max_severity() == Some(Severity::Warning)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("DiagnosticReport is always serializable")
+    }
+}
+
+/// sRGB 0-255 の平均値からしきい値判定
+fn evaluate(
+    [r, g, b, a]: [f64; 4],
+    layout: Option<&LayoutDetection>,
+    active_area: Option<&ActiveArea>,
+    t: &DiagnosticThresholds,
+) -> Vec<Finding> {
+    let mut findings = Vec::new();
+
+    if r < t.black && g < t.black && b < t.black {
+        findings.push(Finding::AllBlack);
+    }
+    if r > t.white && g > t.white && b > t.white {
+        findings.push(Finding::AllWhite);
+    }
+
+    if a < t.black {
+        findings.push(Finding::AlphaNearZero { average: a });
+    } else if a < t.alpha_low {
+        findings.push(Finding::AlphaLow { average: a });
+    } else if a > t.white {
+        findings.push(Finding::AlphaOpaque);
+    }
+
+    // 平均色の偏りは普通の絵柄でも起きるため、確信度付きの推定だけで判定する
+    if let Some(layout) = layout {
+        let order = layout.channel_order;
+        if order.value != layout.declared && order.confidence as f64 >= t.layout_confidence {
+            findings.push(Finding::ChannelOrderMismatch {
+                declared: layout.declared,
+                detected: order.value,
+                confidence: order.confidence,
+            });
+        }
+        let orientation = layout.orientation;
+        if orientation.value == Orientation::Flipped && orientation.confidence as f64 >= t.layout_confidence {
+            findings.push(Finding::Flipped { confidence: orientation.confidence });
+        }
+    }
+
+    // 全面が枠の場合は全黒・透明の警告に任せる
+    if let Some(area) = active_area.filter(|area| !area.is_full() && area.layout != AreaLayout::Empty) {
+        let b = area.borders;
+        findings.push(Finding::Borders { layout: area.layout, top: b.top, bottom: b.bottom, left: b.left, right: b.right });
+    }
+    findings
+}
+
+impl fmt::Display for DiagnosticReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let [r, g, b, a] = self.channels;
+        writeln!(f, "[Image Diagnostics] {}x{} {}", self.width, self.height, self.format)?;
+        writeln!(f, "  Color space: {} -> averaged in linear light", self.color_space)?;
+        writeln!(
+            f,
+            "  Average color (RGBA, sRGB 0-255): ({:.1}, {:.1}, {:.1}, {:.1})",
+            r.mean_srgb8, g.mean_srgb8, b.mean_srgb8, a.mean_srgb8
+        )?;
+        writeln!(
+            f,
+            "  Average color (RGBA, linear):     ({:.4}, {:.4}, {:.4}, {:.4})",
+            r.mean_linear, g.mean_linear, b.mean_linear, a.mean_linear
+        )?;
+        for (name, channel) in ["R", "G", "B", "A"].iter().zip(&self.channels) {
+            writeln!(
+                f,
+                "  {}: min={:.3} max={:.3} stddev={:.3}",
+                name, channel.min, channel.max, channel.stddev
+            )?;
+        }
+        writeln!(
+            f,
+            "  Clipped: black {:.2}%, white {:.2}%",
+            self.clipped_black_percent, self.clipped_white_percent
+        )?;
+
+        for finding in &self.findings {
+            match finding.severity() {
+                Severity::Warning => writeln!(f, "  ⚠️  WARNING: {}", finding)?,
+                Severity::Info => writeln!(f, "  ✓ {}", finding)?,
+            }
+        }
+
+        if let Some(alpha) = &self.alpha {
+            writeln!(
+                f,
+                "  Alpha mode: {} ({} translucent, {} color > alpha of {} pixels)",
+                alpha.mode, alpha.translucent_pixels, alpha.violations, alpha.total_pixels
+            )?;
+        }
+
+        if let Some(layout) = &self.layout {
+            writeln!(
+                f,
+                "  Layout: channels {}, orientation {}, alpha {}",
+                layout.channel_order, layout.orientation, layout.alpha
+            )?;
+        }
+        if let Some(area) = &self.active_area {
+            writeln!(f, "  Active area: {}", area)?;
+        }
+
+        writeln!(f, "  Sample pixels ({} in memory, stored values x255):", self.format.name())?;
+        for sample in &self.samples {
+            let [r, g, b, a] = sample.rgba.map(|v| v * 255.0);
+            writeln!(
+                f,
+                "    {:<12} ({:>5}, {:>5}): R={:5.1} G={:5.1} B={:5.1} A={:5.1}",
+                sample.label, sample.x, sample.y, r, g, b, a
+            )?;
+        }
+        Ok(())
+    }
+}
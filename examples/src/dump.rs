@@ -0,0 +1,263 @@
+// ============================================================
+// 生フレームのダンプ（row_pitch 付きのバイト列 + JSON サイドカー）
+// ============================================================
+//
+// バグ報告用に、Map した staging テクスチャのバイト列をそのまま保存する。
+// サイドカーにはサイズ・row_pitch・DXGI_FORMAT・センダー情報と CRC-32 を書き、
+// ローダーは ImageBuf に戻す。センダーのない環境（Linux など）でも
+// 診断・比較・書き出しをそのまま再現できる。
+
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::deflate::crc32;
+use crate::image::{DxgiFormat, ImageBuf, ImageError, ImageView};
+use crate::import::{load_image, ImportError};
+
+/// サイドカーの形式のバージョン
+const DUMP_VERSION: u32 = 1;
+
+/// ダンプの読み書きの失敗
+#[derive(Debug)]
+pub enum DumpError {
+    Io(io::Error),
+    Json(serde_json::Error),
+    Image(ImageError),
+    /// 未対応のサイドカーのバージョン
+    UnsupportedVersion(u32),
+    /// バイト列の長さ・CRC がサイドカーと一致しない
+    Corrupt(String),
+    /// 比較の基準の画像ファイルを読めない
+    Import(ImportError),
+}
+
+impl fmt::Display for DumpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "I/O error: {}", e),
+            Self::Json(e) => write!(f, "invalid sidecar: {}", e),
+            Self::Image(e) => write!(f, "{}", e),
+            Self::UnsupportedVersion(version) => write!(f, "unsupported dump version: {}", version),
+            Self::Corrupt(message) => write!(f, "corrupt dump: {}", message),
+            Self::Import(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for DumpError {}
+
+impl From<io::Error> for DumpError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for DumpError {
+    fn from(e: serde_json::Error) -> Self {
+        Self::Json(e)
+    }
+}
+
+impl From<ImageError> for DumpError {
+    fn from(e: ImageError) -> Self {
+        Self::Image(e)
+    }
+}
+
+impl From<ImportError> for DumpError {
+    fn from(e: ImportError) -> Self {
+        Self::Import(e)
+    }
+}
+
+/// 受信時の情報（わからない項目は None）
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DumpMeta {
+    pub sender: Option<String>,
+    /// センダーのフレーム番号
+    pub frame: Option<u64>,
+    pub fps: Option<f64>,
+    /// UNIX 時刻（ミリ秒）、None なら保存時の時刻
+    pub timestamp_ms: Option<u64>,
+}
+
+/// サイドカー（JSON）の内容
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DumpInfo {
+    pub version: u32,
+    pub width: u32,
+    pub height: u32,
+    pub row_pitch: usize,
+    /// DXGI_FORMAT の数値
+    pub format: u32,
+    /// 読む人向けのフォーマット名（ローダーは `format` だけを見る）
+    pub format_name: String,
+    pub sender: Option<String>,
+    pub frame: Option<u64>,
+    pub fps: Option<f64>,
+    pub timestamp_ms: u64,
+    /// バイト列のファイル名（サイドカーからの相対パス）
+    pub data: String,
+    pub data_len: usize,
+    pub crc32: u32,
+}
+
+impl DumpInfo {
+    pub fn dxgi_format(&self) -> DxgiFormat {
+        DxgiFormat::from_raw(self.format)
+    }
+}
+
+impl fmt::Display for DumpInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}x{} {} (row_pitch {}), sender {}, frame {}, fps {}, timestamp {} ms",
+            self.width,
+            self.height,
+            self.dxgi_format(),
+            self.row_pitch,
+            self.sender.as_deref().unwrap_or("?"),
+            self.frame.map_or_else(|| "?".to_string(), |frame| frame.to_string()),
+            self.fps.map_or_else(|| "?".to_string(), |fps| format!("{:.2}", fps)),
+            self.timestamp_ms
+        )
+    }
+}
+
+/// 読み込んだダンプ
+#[derive(Debug)]
+pub struct Dump {
+    pub image: ImageBuf,
+    pub info: DumpInfo,
+}
+
+/// `path` の拡張子を替えたサイドカーとバイト列のパス
+pub fn dump_paths(path: &Path) -> (PathBuf, PathBuf) {
+    (path.with_extension("json"), path.with_extension("raw"))
+}
+
+/// パスがダンプ（.json / .raw）を指しているか
+pub fn is_dump_path(path: &Path) -> bool {
+    matches!(path.extension().and_then(|e| e.to_str()), Some("json" | "raw"))
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_millis() as u64)
+}
+
+/// ビューを row_pitch ごと保存（`path` の拡張子は .json / .raw に置き換える）。サイドカーのパスを返す
+pub fn save_dump(path: &Path, view: &ImageView, meta: &DumpMeta) -> Result<PathBuf, DumpError> {
+    let (json_path, raw_path) = dump_paths(path);
+
+    // 最終行も row_pitch 分そろえる（Map したときと同じ長さ）
+    let data_len = view.row_pitch() * view.height() as usize;
+    let mut data = view.data()[..view.data().len().min(data_len)].to_vec();
+    data.resize(data_len, 0);
+
+    let info = DumpInfo {
+        version: DUMP_VERSION,
+        width: view.width(),
+        height: view.height(),
+        row_pitch: view.row_pitch(),
+        format: view.format().raw(),
+        format_name: view.format().name().to_string(),
+        sender: meta.sender.clone(),
+        frame: meta.frame,
+        fps: meta.fps,
+        timestamp_ms: meta.timestamp_ms.unwrap_or_else(now_ms),
+        data: raw_path.file_name().map_or_else(String::new, |name| name.to_string_lossy().into_owned()),
+        data_len,
+        crc32: crc32(&data),
+    };
+
+    std::fs::write(&raw_path, &data)?;
+    std::fs::write(&json_path, serde_json::to_string_pretty(&info)?)?;
+    Ok(json_path)
+}
+
+/// ダンプを読み込む（.json / .raw のどちらを指定してもよい）
+pub fn load_dump(path: &Path) -> Result<Dump, DumpError> {
+    let (json_path, _) = dump_paths(path);
+    let info: DumpInfo = serde_json::from_str(&std::fs::read_to_string(&json_path)?)?;
+    if info.version != DUMP_VERSION {
+        return Err(DumpError::UnsupportedVersion(info.version));
+    }
+
+    let raw_path = json_path.parent().unwrap_or(Path::new("")).join(&info.data);
+    let data = std::fs::read(&raw_path)?;
+    if data.len() != info.data_len {
+        return Err(DumpError::Corrupt(format!("expected {} bytes, got {}", info.data_len, data.len())));
+    }
+    let actual = crc32(&data);
+    if actual != info.crc32 {
+        return Err(DumpError::Corrupt(format!("CRC-32 mismatch: expected {:08x}, got {:08x}", info.crc32, actual)));
+    }
+
+    let image = ImageBuf::from_vec(data, info.width, info.height, info.row_pitch, info.dxgi_format())?;
+    Ok(Dump { image, info })
+}
+
+/// 比較の基準を読み込む（ダンプは元のフォーマットのまま、画像ファイルは `format` に変換）
+///
+/// Windows では自前で読めない形式を WIC に任せる。
+pub fn load_reference(path: &Path, format: DxgiFormat) -> Result<ImageBuf, DumpError> {
+    if is_dump_path(path) {
+        return load_dump(path).map(|dump| dump.image);
+    }
+    match load_image(path, format) {
+        #[cfg(windows)]
+        Err(ImportError::Unsupported(message)) => {
+            let image = crate::wic::load_bgra8(path)
+                .map_err(|e| ImportError::Unsupported(format!("{} (WIC: {})", message, e)))?;
+            Ok(crate::import::convert(&image.view(), format)?)
+        }
+        result => Ok(result?),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::png::save_png;
+
+    fn temp_dir(tag: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("ping-dump-{}-{}", tag, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn image() -> ImageBuf {
+        let data = (0..4 * 3).flat_map(|i| [i as u8 * 20, 0x40, 0x80, 0xFF]).collect();
+        ImageBuf::from_vec(data, 4, 3, 16, DxgiFormat::B8G8R8A8Unorm).unwrap()
+    }
+
+    #[test]
+    fn reference_from_dump_keeps_its_format() {
+        let dir = temp_dir("dump");
+        let json = save_dump(&dir.join("frame.json"), &image().view(), &DumpMeta::default()).unwrap();
+        // ダンプは `format` を無視して元のフォーマットのまま
+        let reference = load_reference(&json, DxgiFormat::R8G8B8A8Unorm).unwrap();
+        assert_eq!(reference, image());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn reference_image_is_converted() {
+        let dir = temp_dir("png");
+        let path = dir.join("golden.png");
+        save_png(&path, &image().view()).unwrap();
+        let reference = load_reference(&path, DxgiFormat::B8G8R8A8Unorm).unwrap();
+        assert_eq!(reference.format(), DxgiFormat::B8G8R8A8Unorm);
+        assert_eq!(reference.data(), image().data());
+
+        let missing = load_reference(&dir.join("missing.png"), DxgiFormat::B8G8R8A8Unorm);
+        assert!(matches!(missing, Err(DumpError::Import(ImportError::Io(_)))));
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
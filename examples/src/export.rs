@@ -0,0 +1,203 @@
+// ============================================================
+// 画像ファイルの書き出し（フォーマットの選択と共通処理）
+// ============================================================
+//
+// フォーマットごとのエンコーダーは png / bmp / pnm / tga / qoi / exr の各モジュール。
+// 高レートのダンプには圧縮しない BMP / PPM / PAM / TGA か、軽い QOI を使う。
+// JPEG は非可逆（アルファなし）で、プレビューや MJPEG 録画向け。
+// HDR のリニア値を残すなら EXR（それ以外は 8bit に切り詰める）。
+
+use std::fmt;
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+
+use serde::Serialize;
+
+use crate::exr::{encode_exr, ExrOptions};
+use crate::hdr::decode_pixel;
+use crate::image::{DxgiFormat, ImageError, ImageView};
+use crate::jpeg::{encode_jpeg, JpegOptions};
+use crate::png::{encode_png, PngOptions};
+use crate::{bmp, pnm, qoi, tga};
+
+/// 書き出しの失敗
+#[derive(Debug)]
+pub enum ExportError {
+    /// 画像が変換できない
+    Image(ImageError),
+    /// ファイルの書き込みに失敗
+    Io(io::Error),
+    /// 選んだバックエンドでは扱えない指定
+    Unsupported(String),
+    /// バックエンド（WIC など）のエラー
+    Backend(String),
+}
+
+impl fmt::Display for ExportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Image(e) => write!(f, "{}", e),
+            Self::Io(e) => write!(f, "I/O error: {}", e),
+            Self::Unsupported(message) => write!(f, "unsupported: {}", message),
+            Self::Backend(message) => write!(f, "backend error: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for ExportError {}
+
+impl From<ImageError> for ExportError {
+    fn from(e: ImageError) -> Self {
+        Self::Image(e)
+    }
+}
+
+impl From<io::Error> for ExportError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+/// 書き出せる画像ファイルの形式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageFormat {
+    #[default]
+    Png,
+    /// 32bit BGRA（アルファ付き、BITMAPV4HEADER）
+    Bmp,
+    /// バイナリ PPM（RGB、アルファなし）
+    Ppm,
+    /// PAM（RGB_ALPHA）
+    Pam,
+    /// 非圧縮 32bit TGA
+    Tga,
+    /// Quite OK Image（RGBA）
+    Qoi,
+    /// OpenEXR（half / float のリニア RGBA）
+    Exr,
+    /// ベースライン JPEG（非可逆、アルファなし）
+    Jpeg,
+}
+
+impl ImageFormat {
+    pub const ALL: [Self; 8] = [Self::Png, Self::Bmp, Self::Ppm, Self::Pam, Self::Tga, Self::Qoi, Self::Exr, Self::Jpeg];
+
+    /// 既定の拡張子
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Png => "png",
+            Self::Bmp => "bmp",
+            Self::Ppm => "ppm",
+            Self::Pam => "pam",
+            Self::Tga => "tga",
+            Self::Qoi => "qoi",
+            Self::Exr => "exr",
+            Self::Jpeg => "jpg",
+        }
+    }
+
+    /// 拡張子から判定（大文字小文字は区別しない）
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension.to_ascii_lowercase().as_str() {
+            "png" => Some(Self::Png),
+            "bmp" | "dib" => Some(Self::Bmp),
+            "ppm" | "pnm" => Some(Self::Ppm),
+            "pam" => Some(Self::Pam),
+            "tga" => Some(Self::Tga),
+            "qoi" => Some(Self::Qoi),
+            "exr" => Some(Self::Exr),
+            "jpg" | "jpeg" => Some(Self::Jpeg),
+            _ => None,
+        }
+    }
+
+    pub fn from_path(path: &Path) -> Option<Self> {
+        path.extension().and_then(|e| e.to_str()).and_then(Self::from_extension)
+    }
+
+    /// アルファを保存できるか
+    pub fn has_alpha(self) -> bool {
+        !matches!(self, Self::Ppm | Self::Jpeg)
+    }
+
+    /// 8bit を超える精度・1.0 を超える値を保存できるか
+    pub fn is_high_precision(self) -> bool {
+        self == Self::Exr
+    }
+}
+
+impl fmt::Display for ImageFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.extension())
+    }
+}
+
+impl FromStr for ImageFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_extension(s).ok_or_else(|| format!("unknown image format: {} (png, bmp, ppm, pam, tga, qoi, exr, jpg)", s))
+    }
+}
+
+/// 指定の形式でエンコード（PNG・EXR・JPEG は既定の設定）
+pub fn encode_image(view: &ImageView, format: ImageFormat) -> Result<Vec<u8>, ImageError> {
+    match format {
+        ImageFormat::Png => encode_png(view, &PngOptions::default()),
+        ImageFormat::Bmp => bmp::encode_bmp(view),
+        ImageFormat::Ppm => pnm::encode_ppm(view),
+        ImageFormat::Pam => pnm::encode_pam(view),
+        ImageFormat::Tga => tga::encode_tga(view),
+        ImageFormat::Qoi => qoi::encode_qoi(view),
+        ImageFormat::Exr => encode_exr(view, &ExrOptions::default()),
+        ImageFormat::Jpeg => encode_jpeg(view, &JpegOptions::default()),
+    }
+}
+
+/// 拡張子から形式を選んで保存
+pub fn save_image(path: &Path, view: &ImageView) -> Result<(), ExportError> {
+    let format = ImageFormat::from_path(path)
+        .ok_or_else(|| ExportError::Unsupported(format!("unknown image extension: {}", path.display())))?;
+    save_image_as(path, view, format)
+}
+
+/// 形式を指定して保存（拡張子は見ない）
+pub fn save_image_as(path: &Path, view: &ImageView, format: ImageFormat) -> Result<(), ExportError> {
+    write_file(path, &encode_image(view, format)?)
+}
+
+/// エンコード済みのバイト列をファイルに書き込む
+pub fn write_file(path: &Path, bytes: &[u8]) -> Result<(), ExportError> {
+    std::fs::write(path, bytes)?;
+    Ok(())
+}
+
+/// 0..1 に切り詰めて `max` 段階に量子化（NaN は 0）
+pub(crate) fn quantize(v: f32, max: f32) -> u32 {
+    let v = if v.is_nan() { 0.0 } else { v.clamp(0.0, 1.0) };
+    (v * max + 0.5) as u32
+}
+
+/// 1 行分を 8bit のサンプルで追加（`order` は出力するチャンネルの並び、R=0 G=1 B=2 A=3）
+pub(crate) fn push_row8(view: &ImageView, y: u32, order: &[usize], out: &mut Vec<u8>) -> Result<(), ImageError> {
+    let row = view.row(y);
+    // 8bit のフォーマットは格納値をそのまま使う（往復で完全に一致させる）
+    let positions = match view.format() {
+        DxgiFormat::R8G8B8A8Unorm | DxgiFormat::R8G8B8A8UnormSrgb => Some([0, 1, 2, 3]),
+        DxgiFormat::B8G8R8A8Unorm | DxgiFormat::B8G8R8A8UnormSrgb => Some([2, 1, 0, 3]),
+        _ => None,
+    };
+    if let Some(positions) = positions {
+        for px in row.chunks_exact(4) {
+            out.extend(order.iter().map(|&c| px[positions[c]]));
+        }
+        return Ok(());
+    }
+    for px in row.chunks_exact(view.bytes_per_pixel()) {
+        let rgba = decode_pixel(px, view.format())?;
+        out.extend(order.iter().map(|&c| quantize(rgba[c], 255.0) as u8));
+    }
+    Ok(())
+}
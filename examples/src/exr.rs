@@ -0,0 +1,252 @@
+// ============================================================
+// OpenEXR 書き出し（HDR キャプチャをリニアのまま保存）
+// ============================================================
+//
+// スキャンライン形式・単一パートの RGBA。half / float を選べ、圧縮は
+// なしか ZIP（16 行ごと、deflate モジュールの zlib）。値はリニア光で書く:
+// FLOAT のフォーマットは格納値そのまま、UNORM は sRGB として展開する。
+
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+use serde::Serialize;
+
+use crate::color::{pixel_to_linear, ColorSpace};
+use crate::deflate::zlib_compress;
+use crate::export::{write_file, ExportError};
+use crate::hdr::f32_to_f16;
+use crate::image::{DxgiFormat, ImageError, ImageView};
+
+const MAGIC: [u8; 4] = [0x76, 0x2F, 0x31, 0x01];
+/// バージョン 2、シングルパートのスキャンライン
+const VERSION: u32 = 2;
+/// ZIP 圧縮の 1 ブロックの行数
+const ZIP_LINES: u32 = 16;
+/// ZIP のレベル（速度と圧縮率の中間）
+const ZIP_LEVEL: u8 = 6;
+/// チャンネルはアルファベット順に並べる決まり（データ上は [R, G, B, A] のインデックス）
+const CHANNELS: [(&str, usize); 4] = [("A", 3), ("B", 2), ("G", 1), ("R", 0)];
+
+/// サンプルの型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExrPixelType {
+    Half,
+    Float,
+}
+
+impl ExrPixelType {
+    /// フォーマットの精度に合わせた型（R32G32B32A32_FLOAT だけ float）
+    pub fn for_format(format: DxgiFormat) -> Self {
+        match format {
+            DxgiFormat::R32G32B32A32Float => Self::Float,
+            _ => Self::Half,
+        }
+    }
+
+    fn code(self) -> i32 {
+        match self {
+            Self::Half => 1,
+            Self::Float => 2,
+        }
+    }
+
+    fn size(self) -> usize {
+        match self {
+            Self::Half => 2,
+            Self::Float => 4,
+        }
+    }
+}
+
+/// 圧縮方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExrCompression {
+    None,
+    /// 16 行ごとの zlib（可逆）
+    #[default]
+    Zip,
+}
+
+impl ExrCompression {
+    fn code(self) -> u8 {
+        match self {
+            Self::None => 0,
+            Self::Zip => 3,
+        }
+    }
+
+    fn lines_per_block(self) -> u32 {
+        match self {
+            Self::None => 1,
+            Self::Zip => ZIP_LINES,
+        }
+    }
+}
+
+/// EXR の書き出し設定
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+pub struct ExrOptions {
+    /// None ならフォーマットの精度に合わせる
+    pub pixel_type: Option<ExrPixelType>,
+    pub compression: ExrCompression,
+    /// UNORM のフォーマットをリニアとして扱う（sRGB の展開をしない）
+    pub assume_linear: bool,
+}
+
+impl fmt::Display for ExrOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let pixel_type = match self.pixel_type {
+            Some(ExrPixelType::Half) => "half",
+            Some(ExrPixelType::Float) => "float",
+            None => "auto",
+        };
+        let compression = match self.compression {
+            ExrCompression::None => "none",
+            ExrCompression::Zip => "zip",
+        };
+        write!(f, "{} RGBA, {} compression", pixel_type, compression)
+    }
+}
+
+impl FromStr for ExrOptions {
+    type Err = String;
+
+    /// `type=float,compression=none`（省略した項目は既定値）
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut options = Self::default();
+        for item in s.split(',').filter(|item| !item.is_empty()) {
+            let (key, value) = item
+                .split_once('=')
+                .ok_or_else(|| format!("invalid EXR option: {} (expected key=value)", item))?;
+            match (key, value.to_ascii_lowercase().as_str()) {
+                ("type", "half") => options.pixel_type = Some(ExrPixelType::Half),
+                ("type", "float") => options.pixel_type = Some(ExrPixelType::Float),
+                ("type", "auto") => options.pixel_type = None,
+                ("compression", "none") => options.compression = ExrCompression::None,
+                ("compression", "zip") => options.compression = ExrCompression::Zip,
+                ("type" | "compression", _) => return Err(format!("invalid EXR option value: {}", item)),
+                _ => return Err(format!("unknown EXR option: {} (type, compression)", key)),
+            }
+        }
+        Ok(options)
+    }
+}
+
+/// ヘッダーの属性を 1 つ追加
+fn push_attribute(out: &mut Vec<u8>, name: &str, kind: &str, value: &[u8]) {
+    out.extend(name.as_bytes());
+    out.push(0);
+    out.extend(kind.as_bytes());
+    out.push(0);
+    out.extend((value.len() as i32).to_le_bytes());
+    out.extend(value);
+}
+
+fn box2i(width: u32, height: u32) -> Vec<u8> {
+    [0, 0, width as i32 - 1, height as i32 - 1].iter().flat_map(|v| v.to_le_bytes()).collect()
+}
+
+/// ZIP 圧縮の前処理（偶数・奇数バイトの分離と差分）をして zlib で圧縮
+fn zip_block(raw: &[u8]) -> Vec<u8> {
+    let half = raw.len().div_ceil(2);
+    let mut reordered = vec![0u8; raw.len()];
+    for (i, &byte) in raw.iter().enumerate() {
+        let target = if i % 2 == 0 { i / 2 } else { half + i / 2 };
+        reordered[target] = byte;
+    }
+    for i in (1..reordered.len()).rev() {
+        reordered[i] = reordered[i].wrapping_sub(reordered[i - 1]).wrapping_add(128);
+    }
+    zlib_compress(&reordered, ZIP_LEVEL)
+}
+
+/// EXR ファイルのバイト列にエンコード
+pub fn encode_exr(view: &ImageView, options: &ExrOptions) -> Result<Vec<u8>, ImageError> {
+    let (width, height) = (view.width(), view.height());
+    if width > i32::MAX as u32 || height > i32::MAX as u32 {
+        return Err(ImageError::InvalidDimensions { width, height, row_pitch: view.row_pitch() });
+    }
+    let format = view.format();
+    let pixel_type = options.pixel_type.unwrap_or(ExrPixelType::for_format(format));
+    let transfer = ColorSpace::for_format(format, options.assume_linear).transfer;
+
+    let mut header = Vec::new();
+    header.extend(MAGIC);
+    header.extend(VERSION.to_le_bytes());
+
+    let mut channels = Vec::new();
+    for (name, _) in CHANNELS {
+        channels.extend(name.as_bytes());
+        channels.push(0);
+        channels.extend(pixel_type.code().to_le_bytes());
+        // pLinear, 予約 3 バイト, x / y のサンプリング
+        channels.extend([0, 0, 0, 0]);
+        channels.extend(1i32.to_le_bytes());
+        channels.extend(1i32.to_le_bytes());
+    }
+    channels.push(0);
+    push_attribute(&mut header, "channels", "chlist", &channels);
+    push_attribute(&mut header, "compression", "compression", &[options.compression.code()]);
+    push_attribute(&mut header, "dataWindow", "box2i", &box2i(width, height));
+    push_attribute(&mut header, "displayWindow", "box2i", &box2i(width, height));
+    push_attribute(&mut header, "lineOrder", "lineOrder", &[0]);
+    push_attribute(&mut header, "pixelAspectRatio", "float", &1.0f32.to_le_bytes());
+    push_attribute(&mut header, "screenWindowCenter", "v2f", &[0u8; 8]);
+    push_attribute(&mut header, "screenWindowWidth", "float", &1.0f32.to_le_bytes());
+    header.push(0);
+
+    // 行ブロックごとに、行 → チャンネル → ピクセルの順で並べる
+    let lines = options.compression.lines_per_block();
+    let blocks = height.div_ceil(lines);
+    let mut chunks = Vec::with_capacity(blocks as usize);
+    let mut raw = Vec::with_capacity(lines as usize * width as usize * CHANNELS.len() * pixel_type.size());
+    let mut pixels = vec![[0f32; 4]; width as usize];
+    for block in 0..blocks {
+        let first = block * lines;
+        raw.clear();
+        for y in first..(first + lines).min(height) {
+            for (pixel, px) in pixels.iter_mut().zip(view.row(y).chunks_exact(view.bytes_per_pixel())) {
+                *pixel = pixel_to_linear(px, format, transfer)?;
+            }
+            for (_, index) in CHANNELS {
+                for pixel in &pixels {
+                    match pixel_type {
+                        ExrPixelType::Half => raw.extend(f32_to_f16(pixel[index]).to_le_bytes()),
+                        ExrPixelType::Float => raw.extend(pixel[index].to_le_bytes()),
+                    }
+                }
+            }
+        }
+        // 圧縮して大きくなるブロックは無圧縮のまま格納する（読み手はサイズで判別）
+        let data = match options.compression {
+            ExrCompression::Zip => Some(zip_block(&raw)).filter(|zipped| zipped.len() < raw.len()),
+            ExrCompression::None => None,
+        };
+        chunks.push((first, data.unwrap_or_else(|| raw.clone())));
+    }
+
+    // オフセット表（ファイル先頭からの位置）
+    let table_size = blocks as usize * 8;
+    let data_size: usize = chunks.iter().map(|(_, data)| 8 + data.len()).sum();
+    let mut out = Vec::with_capacity(header.len() + table_size + data_size);
+    out.extend(&header);
+    let mut offset = (header.len() + table_size) as u64;
+    for (_, data) in &chunks {
+        out.extend(offset.to_le_bytes());
+        offset += 8 + data.len() as u64;
+    }
+    for (y, data) in &chunks {
+        out.extend((*y as i32).to_le_bytes());
+        out.extend((data.len() as i32).to_le_bytes());
+        out.extend(data);
+    }
+    Ok(out)
+}
+
+/// 設定を指定して保存
+pub fn save_exr(path: &Path, view: &ImageView, options: &ExrOptions) -> Result<(), ExportError> {
+    write_file(path, &encode_exr(view, options)?)
+}
@@ -0,0 +1,48 @@
+// ============================================================
+// spoutdx_ffi.h と共有する型定義
+// ============================================================
+
+use std::ffi::{c_char, c_uint};
+
+/// spoutdx_ffi.h の `SpoutDxResult`
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpoutDxResult {
+    Ok = 0,
+    ErrorNullHandle = -1,
+    ErrorNullDevice = -2,
+    ErrorNotConnected = -3,
+    ErrorInitFailed = -4,
+    ErrorReceiveFailed = -5,
+    ErrorSendFailed = -6,
+    /// receive_image がセンダーの変更を検出した（画素は未書き込み、サイズを取り直して再呼び出し）
+    ErrorSenderUpdated = -7,
+    ErrorInternal = -99,
+}
+
+impl SpoutDxResult {
+    /// C ABI の戻り値から変換（未知の値は ErrorInternal）
+    pub fn from_code(code: i32) -> Self {
+        match code {
+            0 => Self::Ok,
+            -1 => Self::ErrorNullHandle,
+            -2 => Self::ErrorNullDevice,
+            -3 => Self::ErrorNotConnected,
+            -4 => Self::ErrorInitFailed,
+            -5 => Self::ErrorReceiveFailed,
+            -6 => Self::ErrorSendFailed,
+            -7 => Self::ErrorSenderUpdated,
+            _ => Self::ErrorInternal,
+        }
+    }
+}
+
+/// spoutdx_ffi.h の `SpoutDxSenderInfo`
+#[repr(C)]
+#[derive(Debug)]
+pub struct SpoutDxSenderInfo {
+    pub name: [c_char; 256],
+    pub width: c_uint,
+    pub height: c_uint,
+    pub format: c_uint,
+}
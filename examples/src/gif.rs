@@ -0,0 +1,276 @@
+// ============================================================
+// GIF 書き出し（アニメーション、256 色のパレット）
+// ============================================================
+//
+// 全フレームの色をまとめてメディアンカットで最大 256 色に減らし、
+// 1 つのグローバルパレットで LZW 圧縮する（ディザリングなし）。
+// フレームの並びと表示時間は clip モジュールが決める。
+
+use std::collections::HashMap;
+
+use crate::clip::{gif_delays, AnimationFrame};
+use crate::image::{DxgiFormat, ImageError, ImageView};
+
+/// パレットの最大色数
+const MAX_COLORS: usize = 256;
+/// LZW の符号長の上限
+const MAX_CODE_BITS: u32 = 12;
+
+/// 1 ピクセルの RGB（BGRA / RGBA 8bit のみ）
+fn pixels<'a>(view: &ImageView<'a>) -> Result<impl Iterator<Item = [u8; 3]> + 'a, ImageError> {
+    let swap = match view.format() {
+        DxgiFormat::B8G8R8A8Unorm | DxgiFormat::B8G8R8A8UnormSrgb => true,
+        DxgiFormat::R8G8B8A8Unorm | DxgiFormat::R8G8B8A8UnormSrgb => false,
+        format => return Err(ImageError::UnsupportedFormat(format)),
+    };
+    let view = *view;
+    Ok((0..view.height()).flat_map(move |y| {
+        view.row(y)
+            .chunks_exact(4)
+            .map(move |px| if swap { [px[2], px[1], px[0]] } else { [px[0], px[1], px[2]] })
+    }))
+}
+
+/// 各チャンネル 5bit に丸めた色の番号
+fn bin(rgb: [u8; 3]) -> usize {
+    ((rgb[0] as usize >> 3) << 10) | ((rgb[1] as usize >> 3) << 5) | (rgb[2] as usize >> 3)
+}
+
+/// 5bit の色ごとの出現数と合計
+#[derive(Debug, Clone, Copy, Default)]
+struct Bin {
+    count: u64,
+    sum: [u64; 3],
+}
+
+impl Bin {
+    fn mean(&self) -> [u8; 3] {
+        self.sum.map(|s| ((s + self.count / 2) / self.count.max(1)) as u8)
+    }
+}
+
+/// メディアンカットで作ったパレットと、5bit の色からパレット番号への表
+struct Palette {
+    colors: Vec<[u8; 3]>,
+    lookup: Vec<u8>,
+}
+
+impl Palette {
+    fn build(frames: &[AnimationFrame]) -> Result<Self, ImageError> {
+        let mut bins = vec![Bin::default(); 1 << 15];
+        for frame in frames {
+            for rgb in pixels(&frame.view)? {
+                let bin = &mut bins[bin(rgb)];
+                bin.count += 1;
+                for (sum, c) in bin.sum.iter_mut().zip(rgb) {
+                    *sum += c as u64;
+                }
+            }
+        }
+        let used: Vec<usize> = (0..bins.len()).filter(|&i| bins[i].count > 0).collect();
+        let means: Vec<[u8; 3]> = bins.iter().map(Bin::mean).collect();
+
+        // 最も広がった箱を、その軸の出現数の中央で分ける
+        let mut boxes = vec![used];
+        while boxes.len() < MAX_COLORS {
+            let widest = boxes
+                .iter()
+                .enumerate()
+                .filter(|(_, entries)| entries.len() >= 2)
+                .map(|(i, entries)| {
+                    let (axis, range) = (0..3)
+                        .map(|axis| {
+                            let values = entries.iter().map(|&e| means[e][axis]);
+                            (axis, values.clone().max().unwrap_or(0) - values.min().unwrap_or(0))
+                        })
+                        .max_by_key(|&(_, range)| range)
+                        .unwrap_or((0, 0));
+                    (i, axis, range)
+                })
+                .max_by_key(|&(_, _, range)| range);
+            let Some((index, axis, _)) = widest else {
+                break;
+            };
+            let mut entries = boxes.swap_remove(index);
+            entries.sort_by_key(|&e| means[e][axis]);
+            let total: u64 = entries.iter().map(|&e| bins[e].count).sum();
+            let mut seen = 0;
+            let split = entries
+                .iter()
+                .position(|&e| {
+                    seen += bins[e].count;
+                    seen * 2 >= total
+                })
+                .map_or(1, |i| i + 1)
+                .clamp(1, entries.len() - 1);
+            let upper = entries.split_off(split);
+            boxes.push(entries);
+            boxes.push(upper);
+        }
+
+        let colors: Vec<[u8; 3]> = boxes
+            .iter()
+            .map(|entries| {
+                let mut merged = Bin::default();
+                for &e in entries {
+                    merged.count += bins[e].count;
+                    for (sum, s) in merged.sum.iter_mut().zip(bins[e].sum) {
+                        *sum += s;
+                    }
+                }
+                merged.mean()
+            })
+            .collect();
+
+        // 使われている色だけ、最も近いパレットの色を引いておく
+        let mut lookup = vec![0u8; bins.len()];
+        for &i in boxes.iter().flatten() {
+            let mean = means[i];
+            let distance = |color: &[u8; 3]| -> u32 {
+                color.iter().zip(mean).map(|(&a, b)| (a as i32 - b as i32).pow(2) as u32).sum()
+            };
+            lookup[i] = (0..colors.len()).min_by_key(|&c| distance(&colors[c])).unwrap_or(0) as u8;
+        }
+        Ok(Self { colors, lookup })
+    }
+
+    /// パレットの大きさを表すビット数（2 の累乗に切り上げ、最小 1）
+    fn bits(&self) -> u32 {
+        (self.colors.len().max(2) as u32).next_power_of_two().trailing_zeros()
+    }
+
+    fn indices(&self, view: &ImageView) -> Result<Vec<u8>, ImageError> {
+        Ok(pixels(view)?.map(|rgb| self.lookup[bin(rgb)]).collect())
+    }
+}
+
+/// LSB から詰めて 255 バイトずつのサブブロックに分けて書く
+struct SubBlocks<'a> {
+    out: &'a mut Vec<u8>,
+    block: Vec<u8>,
+    bits: u32,
+    count: u32,
+}
+
+impl<'a> SubBlocks<'a> {
+    fn new(out: &'a mut Vec<u8>) -> Self {
+        Self { out, block: Vec::with_capacity(255), bits: 0, count: 0 }
+    }
+
+    fn write(&mut self, code: u16, size: u32) {
+        self.bits |= (code as u32) << self.count;
+        self.count += size;
+        while self.count >= 8 {
+            self.push(self.bits as u8);
+            self.bits >>= 8;
+            self.count -= 8;
+        }
+    }
+
+    fn push(&mut self, byte: u8) {
+        self.block.push(byte);
+        if self.block.len() == 255 {
+            self.out.push(255);
+            self.out.append(&mut self.block);
+        }
+    }
+
+    fn finish(mut self) {
+        if self.count > 0 {
+            self.push(self.bits as u8);
+        }
+        if !self.block.is_empty() {
+            self.out.push(self.block.len() as u8);
+            self.out.append(&mut self.block);
+        }
+        self.out.push(0);
+    }
+}
+
+/// パレット番号の並びを LZW で圧縮して書く（最小符号長のバイトから終端ブロックまで）
+fn write_lzw(out: &mut Vec<u8>, indices: &[u8], min_code_size: u32) {
+    out.push(min_code_size as u8);
+    let clear = 1u16 << min_code_size;
+    let end = clear + 1;
+    let mut writer = SubBlocks::new(out);
+    let mut dictionary: HashMap<(u16, u8), u16> = HashMap::new();
+    let mut next = end + 1;
+    let mut size = min_code_size + 1;
+
+    writer.write(clear, size);
+    let Some((&first, rest)) = indices.split_first() else {
+        writer.write(end, size);
+        writer.finish();
+        return;
+    };
+    let mut prefix = first as u16;
+    for &index in rest {
+        if let Some(&code) = dictionary.get(&(prefix, index)) {
+            prefix = code;
+            continue;
+        }
+        writer.write(prefix, size);
+        if next < 1 << MAX_CODE_BITS {
+            dictionary.insert((prefix, index), next);
+            next += 1;
+            // 読む側は 1 つ遅れて表に足すので、次の次の符号から長くする
+            if next > 1 << size && size < MAX_CODE_BITS {
+                size += 1;
+            }
+        } else {
+            writer.write(clear, size);
+            dictionary.clear();
+            next = end + 1;
+            size = min_code_size + 1;
+        }
+        prefix = index as u16;
+    }
+    writer.write(prefix, size);
+    writer.write(end, size);
+    writer.finish();
+}
+
+/// アニメーション GIF にエンコード
+///
+/// 1 枚目はキャンバス全体、2 枚目以降は前のフレームに上書きする部分画像
+/// （disposal = 残す）。`loops` は繰り返し回数（0 は無限）。
+pub fn encode_gif(width: u32, height: u32, frames: &[AnimationFrame], loops: u16) -> Result<Vec<u8>, ImageError> {
+    if width > u16::MAX as u32 || height > u16::MAX as u32 || frames.is_empty() {
+        return Err(ImageError::InvalidDimensions { width, height, row_pitch: 0 });
+    }
+    let palette = Palette::build(frames)?;
+    let bits = palette.bits();
+    let delays = gif_delays(frames);
+
+    let mut out = Vec::new();
+    out.extend(b"GIF89a");
+    out.extend((width as u16).to_le_bytes());
+    out.extend((height as u16).to_le_bytes());
+    // グローバルパレットあり、色の解像度 8bit、パレットの大きさ
+    out.extend([0x80 | 0x70 | (bits - 1) as u8, 0, 0]);
+    for i in 0..1usize << bits {
+        out.extend(palette.colors.get(i).copied().unwrap_or_default());
+    }
+    // NETSCAPE2.0 拡張（繰り返し回数）
+    out.extend([0x21, 0xFF, 0x0B]);
+    out.extend(b"NETSCAPE2.0");
+    out.extend([0x03, 0x01]);
+    out.extend(loops.to_le_bytes());
+    out.push(0);
+
+    for (frame, &delay) in frames.iter().zip(&delays) {
+        // グラフィック制御拡張（disposal = 1: 残す、透明色なし）
+        out.extend([0x21, 0xF9, 0x04, 0x04]);
+        out.extend((delay.min(u16::MAX as u32) as u16).to_le_bytes());
+        out.extend([0, 0]);
+        // イメージ記述子（ローカルパレットなし、インターレースなし）
+        out.push(0x2C);
+        for v in [frame.x, frame.y, frame.view.width(), frame.view.height()] {
+            out.extend((v as u16).to_le_bytes());
+        }
+        out.push(0);
+        write_lzw(&mut out, &palette.indices(&frame.view)?, bits.max(2));
+    }
+    out.push(0x3B);
+    Ok(out)
+}
@@ -0,0 +1,259 @@
+// ============================================================
+// HDR / 高ビット深度フォーマットのデコードとトーンマップ
+// ============================================================
+//
+// R16G16B16A16_FLOAT / R10G10B10A2_UNORM / R32G32B32A32_FLOAT を
+// f32 の RGBA に展開し、8bit BGRA へトーンマップする（GPU 不要）。
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::color::{linear_to_srgb8, TransferFunction};
+use crate::image::{DxgiFormat, ImageBuf, ImageError, ImageView};
+
+/// IEEE 754 半精度（binary16）を f32 へ変換
+pub fn f16_to_f32(bits: u16) -> f32 {
+    let sign = ((bits >> 15) as u32) << 31;
+    let exponent = ((bits >> 10) & 0x1F) as u32;
+    let mantissa = (bits & 0x3FF) as u32;
+
+    let value = match (exponent, mantissa) {
+        (0, 0) => sign,
+        // 非正規化数: mantissa * 2^-24
+        (0, _) => {
+            let magnitude = mantissa as f32 * f32::powi(2.0, -24);
+            return if sign != 0 { -magnitude } else { magnitude };
+        }
+        (0x1F, 0) => sign | 0x7F80_0000,
+        (0x1F, _) => sign | 0x7FC0_0000 | (mantissa << 13),
+        _ => sign | ((exponent + 127 - 15) << 23) | (mantissa << 13),
+    };
+    f32::from_bits(value)
+}
+
+/// f32 を IEEE 754 半精度へ変換（最近接偶数丸め、範囲外は ±inf）
+pub fn f32_to_f16(value: f32) -> u16 {
+    let bits = value.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exponent = ((bits >> 23) & 0xFF) as i32;
+    let mantissa = bits & 0x7F_FFFF;
+
+    if exponent == 0xFF {
+        let nan = if mantissa != 0 { 0x200 } else { 0 };
+        return sign | 0x7C00 | nan;
+    }
+
+    let half_exponent = exponent - 127 + 15;
+    if half_exponent >= 0x1F {
+        return sign | 0x7C00;
+    }
+
+    // 切り捨てたビットで丸める（ちょうど半分なら偶数へ）
+    let round = |value: u32, shift: u32| {
+        let truncated = value >> shift;
+        let rest = value & ((1 << shift) - 1);
+        let halfway = 1 << (shift - 1);
+        if rest > halfway || (rest == halfway && truncated & 1 == 1) { truncated + 1 } else { truncated }
+    };
+
+    if half_exponent <= 0 {
+        // 非正規化数（または 0）
+        if half_exponent < -10 {
+            return sign;
+        }
+        let shift = (14 - half_exponent) as u32;
+        return sign | round(mantissa | 0x80_0000, shift) as u16;
+    }
+
+    // 繰り上がりで指数部へ溢れても正しい値（最大で inf）になる
+    sign | round(((half_exponent as u32) << 23) | mantissa, 13) as u16
+}
+
+/// f32 の RGBA 画像（リニア値、1.0 を超えてもよい）
+#[derive(Debug, Clone, PartialEq)]
+pub struct LinearImage {
+    pub width: u32,
+    pub height: u32,
+    /// 行優先・パディングなしの [R, G, B, A]
+    pub pixels: Vec<[f32; 4]>,
+}
+
+impl LinearImage {
+    pub fn pixel(&self, x: u32, y: u32) -> [f32; 4] {
+        self.pixels[(y * self.width + x) as usize]
+    }
+}
+
+/// 1 ピクセルを [R, G, B, A] の f32 に展開（格納値のまま、伝達関数は適用しない）
+pub fn decode_pixel(px: &[u8], format: DxgiFormat) -> Result<[f32; 4], ImageError> {
+    let unorm8 = |v: u8| v as f32 / 255.0;
+    let rgba = match format {
+        DxgiFormat::R8G8B8A8Unorm | DxgiFormat::R8G8B8A8UnormSrgb => {
+            [unorm8(px[0]), unorm8(px[1]), unorm8(px[2]), unorm8(px[3])]
+        }
+        DxgiFormat::B8G8R8A8Unorm | DxgiFormat::B8G8R8A8UnormSrgb => {
+            [unorm8(px[2]), unorm8(px[1]), unorm8(px[0]), unorm8(px[3])]
+        }
+        DxgiFormat::R10G10B10A2Unorm => {
+            let v = u32::from_le_bytes([px[0], px[1], px[2], px[3]]);
+            [
+                (v & 0x3FF) as f32 / 1023.0,
+                ((v >> 10) & 0x3FF) as f32 / 1023.0,
+                ((v >> 20) & 0x3FF) as f32 / 1023.0,
+                (v >> 30) as f32 / 3.0,
+            ]
+        }
+        DxgiFormat::R16G16B16A16Float => {
+            let half = |i: usize| f16_to_f32(u16::from_le_bytes([px[i], px[i + 1]]));
+            [half(0), half(2), half(4), half(6)]
+        }
+        DxgiFormat::R32G32B32A32Float => {
+            let float = |i: usize| f32::from_le_bytes([px[i], px[i + 1], px[i + 2], px[i + 3]]);
+            [float(0), float(4), float(8), float(12)]
+        }
+        DxgiFormat::Unknown(_) => return Err(ImageError::UnsupportedFormat(format)),
+    };
+    Ok(rgba)
+}
+
+/// [R, G, B, A] の f32 を 1 ピクセル分のバイト列へ書き込む（decode_pixel の逆）
+pub fn encode_pixel(rgba: [f32; 4], format: DxgiFormat, px: &mut [u8]) -> Result<(), ImageError> {
+    let [r, g, b, a] = rgba;
+    match format {
+        DxgiFormat::R8G8B8A8Unorm | DxgiFormat::R8G8B8A8UnormSrgb => {
+            px.copy_from_slice(&[to_unorm8(r), to_unorm8(g), to_unorm8(b), to_unorm8(a)]);
+        }
+        DxgiFormat::B8G8R8A8Unorm | DxgiFormat::B8G8R8A8UnormSrgb => {
+            px.copy_from_slice(&[to_unorm8(b), to_unorm8(g), to_unorm8(r), to_unorm8(a)]);
+        }
+        DxgiFormat::R10G10B10A2Unorm => {
+            let unorm = |v: f32, max: f32| (v.clamp(0.0, 1.0) * max + 0.5) as u32;
+            let v = unorm(r, 1023.0) | (unorm(g, 1023.0) << 10) | (unorm(b, 1023.0) << 20) | (unorm(a, 3.0) << 30);
+            px.copy_from_slice(&v.to_le_bytes());
+        }
+        DxgiFormat::R16G16B16A16Float => {
+            for (chunk, v) in px.chunks_exact_mut(2).zip(rgba) {
+                chunk.copy_from_slice(&f32_to_f16(v).to_le_bytes());
+            }
+        }
+        DxgiFormat::R32G32B32A32Float => {
+            for (chunk, v) in px.chunks_exact_mut(4).zip(rgba) {
+                chunk.copy_from_slice(&v.to_le_bytes());
+            }
+        }
+        DxgiFormat::Unknown(_) => return Err(ImageError::UnsupportedFormat(format)),
+    }
+    Ok(())
+}
+
+/// 画像全体をリニアの f32 RGBA に展開（UNORM は sRGB エンコードとみなす）
+pub fn decode_linear(view: &ImageView) -> Result<LinearImage, ImageError> {
+    let bpp = view.bytes_per_pixel();
+    let transfer = TransferFunction::for_format(view.format());
+    let mut pixels = Vec::with_capacity(view.width() as usize * view.height() as usize);
+    for y in 0..view.height() {
+        for px in view.row(y).chunks_exact(bpp) {
+            let [r, g, b, a] = decode_pixel(px, view.format())?;
+            pixels.push([transfer.to_linear(r), transfer.to_linear(g), transfer.to_linear(b), a]);
+        }
+    }
+    Ok(LinearImage { width: view.width(), height: view.height(), pixels })
+}
+
+/// 8bit へ落とすときのトーンマップ
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Tonemap {
+    /// 0.0..=1.0 にクランプ
+    #[default]
+    Clamp,
+    /// x / (1 + x)
+    Reinhard,
+    /// 2^stops 倍してからクランプ
+    Exposure(f32),
+}
+
+impl Tonemap {
+    /// カラーチャンネル 1 つに適用（アルファには使わない）
+    pub fn apply(self, value: f32) -> f32 {
+        let value = if value.is_nan() { 0.0 } else { value.max(0.0) };
+        let mapped = match self {
+            Self::Clamp => value,
+            Self::Reinhard => value / (1.0 + value),
+            Self::Exposure(stops) => value * stops.exp2(),
+        };
+        mapped.clamp(0.0, 1.0)
+    }
+}
+
+impl fmt::Display for Tonemap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Clamp => write!(f, "clamp"),
+            Self::Reinhard => write!(f, "reinhard"),
+            Self::Exposure(stops) => write!(f, "exposure={}", stops),
+        }
+    }
+}
+
+impl FromStr for Tonemap {
+    type Err = String;
+
+    /// `clamp` / `reinhard` / `exposure=<stops>`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "clamp" => Ok(Self::Clamp),
+            "reinhard" => Ok(Self::Reinhard),
+            _ => match s.strip_prefix("exposure=") {
+                Some(stops) => stops
+                    .parse()
+                    .map(Self::Exposure)
+                    .map_err(|_| format!("invalid exposure: {}", stops)),
+                None => Err(format!("unknown tonemap: {} (clamp, reinhard, exposure=<stops>)", s)),
+            },
+        }
+    }
+}
+
+fn to_unorm8(value: f32) -> u8 {
+    (value.clamp(0.0, 1.0) * 255.0 + 0.5) as u8
+}
+
+/// 任意のフォーマットを 8bit BGRA（DXGI_FORMAT_B8G8R8A8_UNORM、sRGB エンコード）へ変換
+///
+/// UNORM フォーマットは再量子化のみ。FLOAT フォーマットはトーンマップしてから sRGB へエンコードする。
+pub fn to_bgra8(view: &ImageView, tonemap: Tonemap) -> Result<ImageBuf, ImageError> {
+    let format = view.format();
+    let bpp = view.bytes_per_pixel();
+    let mut dst = ImageBuf::new(view.width(), view.height(), DxgiFormat::B8G8R8A8Unorm)?;
+
+    for y in 0..view.height() {
+        let src_row = view.row(y);
+        let dst_row = dst.row_mut(y);
+        match format {
+            DxgiFormat::B8G8R8A8Unorm | DxgiFormat::B8G8R8A8UnormSrgb => dst_row.copy_from_slice(src_row),
+            DxgiFormat::R8G8B8A8Unorm | DxgiFormat::R8G8B8A8UnormSrgb => {
+                for (d, s) in dst_row.chunks_exact_mut(4).zip(src_row.chunks_exact(4)) {
+                    d.copy_from_slice(&[s[2], s[1], s[0], s[3]]);
+                }
+            }
+            DxgiFormat::R10G10B10A2Unorm => {
+                for (d, s) in dst_row.chunks_exact_mut(4).zip(src_row.chunks_exact(bpp)) {
+                    let [r, g, b, a] = decode_pixel(s, format)?;
+                    d.copy_from_slice(&[to_unorm8(b), to_unorm8(g), to_unorm8(r), to_unorm8(a)]);
+                }
+            }
+            _ => {
+                for (d, s) in dst_row.chunks_exact_mut(4).zip(src_row.chunks_exact(bpp)) {
+                    let [r, g, b, a] = decode_pixel(s, format)?;
+                    d.copy_from_slice(&[
+                        linear_to_srgb8(tonemap.apply(b)),
+                        linear_to_srgb8(tonemap.apply(g)),
+                        linear_to_srgb8(tonemap.apply(r)),
+                        to_unorm8(a),
+                    ]);
+                }
+            }
+        }
+    }
+    Ok(dst)
+}
@@ -0,0 +1,319 @@
+// ============================================================
+// 画像バッファ型（row_pitch 対応）
+// ============================================================
+
+use std::fmt;
+
+/// 本ツールが扱う DXGI_FORMAT
+///
+/// 値は `SpoutDxSenderInfo.format` / `D3D11_TEXTURE2D_DESC.Format` と同じ数値。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DxgiFormat {
+    R32G32B32A32Float,
+    R16G16B16A16Float,
+    R10G10B10A2Unorm,
+    R8G8B8A8Unorm,
+    R8G8B8A8UnormSrgb,
+    B8G8R8A8Unorm,
+    B8G8R8A8UnormSrgb,
+    Unknown(u32),
+}
+
+impl DxgiFormat {
+    /// DXGI_FORMAT の数値から変換
+    pub fn from_raw(value: u32) -> Self {
+        match value {
+            2 => Self::R32G32B32A32Float,
+            10 => Self::R16G16B16A16Float,
+            24 => Self::R10G10B10A2Unorm,
+            28 => Self::R8G8B8A8Unorm,
+            29 => Self::R8G8B8A8UnormSrgb,
+            87 => Self::B8G8R8A8Unorm,
+            91 => Self::B8G8R8A8UnormSrgb,
+            other => Self::Unknown(other),
+        }
+    }
+
+    /// DXGI_FORMAT の数値
+    pub fn raw(self) -> u32 {
+        match self {
+            Self::R32G32B32A32Float => 2,
+            Self::R16G16B16A16Float => 10,
+            Self::R10G10B10A2Unorm => 24,
+            Self::R8G8B8A8Unorm => 28,
+            Self::R8G8B8A8UnormSrgb => 29,
+            Self::B8G8R8A8Unorm => 87,
+            Self::B8G8R8A8UnormSrgb => 91,
+            Self::Unknown(value) => value,
+        }
+    }
+
+    /// 1 ピクセルあたりのバイト数（未知のフォーマットは None）
+    pub fn bytes_per_pixel(self) -> Option<usize> {
+        match self {
+            Self::R32G32B32A32Float => Some(16),
+            Self::R16G16B16A16Float => Some(8),
+            Self::R10G10B10A2Unorm
+            | Self::R8G8B8A8Unorm
+            | Self::R8G8B8A8UnormSrgb
+            | Self::B8G8R8A8Unorm
+            | Self::B8G8R8A8UnormSrgb => Some(4),
+            Self::Unknown(_) => None,
+        }
+    }
+
+    /// _UNORM_SRGB フォーマットか
+    pub fn is_srgb(self) -> bool {
+        matches!(self, Self::R8G8B8A8UnormSrgb | Self::B8G8R8A8UnormSrgb)
+    }
+
+    /// 表示用の名前
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::R32G32B32A32Float => "DXGI_FORMAT_R32G32B32A32_FLOAT",
+            Self::R16G16B16A16Float => "DXGI_FORMAT_R16G16B16A16_FLOAT",
+            Self::R10G10B10A2Unorm => "DXGI_FORMAT_R10G10B10A2_UNORM",
+            Self::R8G8B8A8Unorm => "DXGI_FORMAT_R8G8B8A8_UNORM (RGBA)",
+            Self::R8G8B8A8UnormSrgb => "DXGI_FORMAT_R8G8B8A8_UNORM_SRGB (RGBA)",
+            Self::B8G8R8A8Unorm => "DXGI_FORMAT_B8G8R8A8_UNORM (BGRA)",
+            Self::B8G8R8A8UnormSrgb => "DXGI_FORMAT_B8G8R8A8_UNORM_SRGB (BGRA)",
+            Self::Unknown(_) => "Unknown",
+        }
+    }
+}
+
+impl fmt::Display for DxgiFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.name(), self.raw())
+    }
+}
+
+/// 画像操作のエラー
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ImageError {
+    /// 扱えないフォーマット
+    UnsupportedFormat(DxgiFormat),
+    /// 幅・高さ・row_pitch の組み合わせが不正
+    InvalidDimensions { width: u32, height: u32, row_pitch: usize },
+    /// バッファがサイズに対して短い
+    BufferTooSmall { expected: usize, actual: usize },
+    /// 2 枚の画像のサイズが一致しない
+    SizeMismatch { expected: (u32, u32), actual: (u32, u32) },
+}
+
+impl fmt::Display for ImageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnsupportedFormat(format) => write!(f, "unsupported format: {}", format),
+            Self::InvalidDimensions { width, height, row_pitch } => {
+                write!(f, "invalid dimensions: {}x{} (row_pitch: {})", width, height, row_pitch)
+            }
+            Self::BufferTooSmall { expected, actual } => {
+                write!(f, "buffer too small: expected {} bytes, got {}", expected, actual)
+            }
+            Self::SizeMismatch { expected, actual } => {
+                write!(f, "size mismatch: expected {}x{}, got {}x{}", expected.0, expected.1, actual.0, actual.1)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ImageError {}
+
+/// 必要なバイト数を検証して返す（最終行は row_pitch 分なくてもよい）
+fn required_len(width: u32, height: u32, row_pitch: usize, format: DxgiFormat) -> Result<usize, ImageError> {
+    let bpp = format.bytes_per_pixel().ok_or(ImageError::UnsupportedFormat(format))?;
+    let row_bytes = width as usize * bpp;
+    if width == 0 || height == 0 || row_pitch < row_bytes {
+        return Err(ImageError::InvalidDimensions { width, height, row_pitch });
+    }
+    Ok(row_pitch * (height as usize - 1) + row_bytes)
+}
+
+/// 借用した画像データ（Map した staging テクスチャなど）
+///
+/// 各行は `row_pitch` バイト間隔で並び、行末のパディングは無視する。
+#[derive(Debug, Clone, Copy)]
+pub struct ImageView<'a> {
+    data: &'a [u8],
+    width: u32,
+    height: u32,
+    row_pitch: usize,
+    format: DxgiFormat,
+}
+
+impl<'a> ImageView<'a> {
+    pub fn new(
+        data: &'a [u8],
+        width: u32,
+        height: u32,
+        row_pitch: usize,
+        format: DxgiFormat,
+    ) -> Result<Self, ImageError> {
+        let expected = required_len(width, height, row_pitch, format)?;
+        if data.len() < expected {
+            return Err(ImageError::BufferTooSmall { expected, actual: data.len() });
+        }
+        Ok(Self { data, width, height, row_pitch, format })
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn row_pitch(&self) -> usize {
+        self.row_pitch
+    }
+
+    pub fn format(&self) -> DxgiFormat {
+        self.format
+    }
+
+    pub fn data(&self) -> &'a [u8] {
+        self.data
+    }
+
+    /// 1 ピクセルあたりのバイト数（生成時に検証済み）
+    pub fn bytes_per_pixel(&self) -> usize {
+        self.format.bytes_per_pixel().unwrap_or(0)
+    }
+
+    /// y 行目のピクセル（パディングを除く）
+    pub fn row(&self, y: u32) -> &'a [u8] {
+        let start = y as usize * self.row_pitch;
+        &self.data[start..start + self.width as usize * self.bytes_per_pixel()]
+    }
+
+    /// (x, y) のピクセルのバイト列
+    pub fn pixel(&self, x: u32, y: u32) -> &'a [u8] {
+        let bpp = self.bytes_per_pixel();
+        let offset = x as usize * bpp;
+        &self.row(y)[offset..offset + bpp]
+    }
+
+    /// 矩形部分を指すビュー（コピーしない、row_pitch は元のまま）
+    pub fn sub_view(&self, x: u32, y: u32, width: u32, height: u32) -> Result<ImageView<'a>, ImageError> {
+        if width == 0
+            || height == 0
+            || x.checked_add(width).is_none_or(|right| right > self.width)
+            || y.checked_add(height).is_none_or(|bottom| bottom > self.height)
+        {
+            return Err(ImageError::InvalidDimensions { width, height, row_pitch: self.row_pitch });
+        }
+        let start = y as usize * self.row_pitch + x as usize * self.bytes_per_pixel();
+        ImageView::new(&self.data[start..], width, height, self.row_pitch, self.format)
+    }
+
+    /// パディングなしの所有バッファへコピー
+    pub fn to_image_buf(&self) -> ImageBuf {
+        let mut buf = ImageBuf::new(self.width, self.height, self.format)
+            .expect("view dimensions are already validated");
+        for y in 0..self.height {
+            buf.row_mut(y).copy_from_slice(self.row(y));
+        }
+        buf
+    }
+}
+
+/// 所有する画像データ
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImageBuf {
+    data: Vec<u8>,
+    width: u32,
+    height: u32,
+    row_pitch: usize,
+    format: DxgiFormat,
+}
+
+impl ImageBuf {
+    /// ゼロ埋めのバッファを作成（row_pitch = width * bpp）
+    pub fn new(width: u32, height: u32, format: DxgiFormat) -> Result<Self, ImageError> {
+        let bpp = format.bytes_per_pixel().ok_or(ImageError::UnsupportedFormat(format))?;
+        let row_pitch = width as usize * bpp;
+        let len = required_len(width, height, row_pitch, format)?;
+        Ok(Self { data: vec![0u8; len], width, height, row_pitch, format })
+    }
+
+    /// 既存のバイト列から作成（`read_texture_to_cpu` の戻り値など）
+    pub fn from_vec(
+        data: Vec<u8>,
+        width: u32,
+        height: u32,
+        row_pitch: usize,
+        format: DxgiFormat,
+    ) -> Result<Self, ImageError> {
+        ImageView::new(&data, width, height, row_pitch, format)?;
+        Ok(Self { data, width, height, row_pitch, format })
+    }
+
+    pub fn view(&self) -> ImageView<'_> {
+        ImageView {
+            data: &self.data,
+            width: self.width,
+            height: self.height,
+            row_pitch: self.row_pitch,
+            format: self.format,
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn row_pitch(&self) -> usize {
+        self.row_pitch
+    }
+
+    pub fn format(&self) -> DxgiFormat {
+        self.format
+    }
+
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    pub fn data_mut(&mut self) -> &mut [u8] {
+        &mut self.data
+    }
+
+    pub fn into_vec(self) -> Vec<u8> {
+        self.data
+    }
+
+    /// フォーマットのラベルだけを差し替える（バイト列はそのまま）
+    pub fn set_format(&mut self, format: DxgiFormat) -> Result<(), ImageError> {
+        if format.bytes_per_pixel() != self.format.bytes_per_pixel() {
+            return Err(ImageError::UnsupportedFormat(format));
+        }
+        self.format = format;
+        Ok(())
+    }
+
+    pub fn row(&self, y: u32) -> &[u8] {
+        self.view().row(y)
+    }
+
+    pub fn row_mut(&mut self, y: u32) -> &mut [u8] {
+        let bpp = self.view().bytes_per_pixel();
+        let start = y as usize * self.row_pitch;
+        &mut self.data[start..start + self.width as usize * bpp]
+    }
+
+    pub fn pixel(&self, x: u32, y: u32) -> &[u8] {
+        self.view().pixel(x, y)
+    }
+
+    pub fn pixel_mut(&mut self, x: u32, y: u32) -> &mut [u8] {
+        let bpp = self.view().bytes_per_pixel();
+        let offset = x as usize * bpp;
+        &mut self.row_mut(y)[offset..offset + bpp]
+    }
+}
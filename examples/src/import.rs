@@ -0,0 +1,189 @@
+// ============================================================
+// 画像ファイルの読み込み（形式の判定と DxgiFormat への変換）
+// ============================================================
+//
+// テスト用のセンダーやモックに既知の画像を流すため、書き出せる形式のうち
+// PNG / BMP / PPM / PAM / TGA / QOI を読み込む（デコーダーは各モジュール）。
+// 8bit の形式は sRGB エンコード値のまま、16bit の PNG / PAM はリニアの
+// float にしてから、指定の DxgiFormat に変換する。EXR と JPEG は読まない。
+// 16bit の値も sRGB エンコードとみなす（PNG の書き出しは float のビューを
+// sRGB へエンコードしてから 16bit にするので、往復で元のリニア値に戻る）。
+
+use std::fmt;
+use std::io;
+use std::path::Path;
+
+use crate::color::{linear_to_srgb, srgb_to_linear, TransferFunction};
+use crate::export::ImageFormat;
+use crate::hdr::{decode_pixel, encode_pixel};
+use crate::image::{DxgiFormat, ImageBuf, ImageError, ImageView};
+use crate::{bmp, png, pnm, qoi, tga};
+
+/// 読み込める画素数の上限（壊れたヘッダーで巨大な確保をしないため）
+const MAX_PIXELS: u64 = 1 << 28;
+
+/// 読み込みの失敗
+#[derive(Debug)]
+pub enum ImportError {
+    Io(io::Error),
+    Image(ImageError),
+    /// 読めない形式・機能
+    Unsupported(String),
+    /// ファイルが壊れている
+    Corrupt(String),
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "I/O error: {}", e),
+            Self::Image(e) => write!(f, "{}", e),
+            Self::Unsupported(message) => write!(f, "unsupported: {}", message),
+            Self::Corrupt(message) => write!(f, "corrupt image: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for ImportError {}
+
+impl From<io::Error> for ImportError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<ImageError> for ImportError {
+    fn from(e: ImageError) -> Self {
+        Self::Image(e)
+    }
+}
+
+pub(crate) fn corrupt(message: impl Into<String>) -> ImportError {
+    ImportError::Corrupt(message.into())
+}
+
+/// `start` から `len` バイト（足りなければ Corrupt）
+pub(crate) fn slice(bytes: &[u8], start: usize, len: usize) -> Result<&[u8], ImportError> {
+    start
+        .checked_add(len)
+        .and_then(|end| bytes.get(start..end))
+        .ok_or_else(|| corrupt(format!("truncated at offset {}", start)))
+}
+
+pub(crate) fn le_u16(bytes: &[u8], at: usize) -> Result<u16, ImportError> {
+    slice(bytes, at, 2).map(|b| u16::from_le_bytes([b[0], b[1]]))
+}
+
+pub(crate) fn le_u32(bytes: &[u8], at: usize) -> Result<u32, ImportError> {
+    slice(bytes, at, 4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+pub(crate) fn be_u32(bytes: &[u8], at: usize) -> Result<u32, ImportError> {
+    slice(bytes, at, 4).map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+/// 幅・高さが 0 でなく、上限に収まること
+pub(crate) fn check_dimensions(width: u32, height: u32) -> Result<(), ImportError> {
+    if width == 0 || height == 0 || width as u64 * height as u64 > MAX_PIXELS {
+        return Err(corrupt(format!("invalid dimensions: {}x{}", width, height)));
+    }
+    Ok(())
+}
+
+/// 8bit RGBA を詰めて並べたバイト列から ImageBuf を作る
+pub(crate) fn rgba8_image(width: u32, height: u32, data: Vec<u8>) -> Result<ImageBuf, ImportError> {
+    Ok(ImageBuf::from_vec(data, width, height, width as usize * 4, DxgiFormat::R8G8B8A8Unorm)?)
+}
+
+/// RGBA の並びのサンプル（0..=max）から ImageBuf を作る
+///
+/// `max` が 255 以下なら 8bit RGBA（エンコード値のまま）、それより大きければ
+/// リニアの f32 RGBA（RGB は sRGB からリニアへ、アルファはそのまま）。
+pub(crate) fn rgba_image(width: u32, height: u32, samples: &[u16], max: u32) -> Result<ImageBuf, ImportError> {
+    let expected = width as usize * height as usize * 4;
+    if samples.len() != expected {
+        return Err(corrupt(format!("expected {} samples, got {}", expected, samples.len())));
+    }
+    if max <= 255 {
+        return rgba8_image(width, height, samples.iter().map(|&v| ((v as u32 * 255 + max / 2) / max) as u8).collect());
+    }
+    let mut data = Vec::with_capacity(samples.len() * 4);
+    for px in samples.chunks_exact(4) {
+        for (c, &v) in px.iter().enumerate() {
+            let v = v as f32 / max as f32;
+            data.extend(if c < 3 { srgb_to_linear(v) } else { v }.to_le_bytes());
+        }
+    }
+    Ok(ImageBuf::from_vec(data, width, height, width as usize * 16, DxgiFormat::R32G32B32A32Float)?)
+}
+
+/// 別の DxgiFormat に変換（UNORM と FLOAT の間では sRGB とリニアを変換する）
+pub fn convert(view: &ImageView, target: DxgiFormat) -> Result<ImageBuf, ImageError> {
+    let source = view.format();
+    if source == target {
+        return Ok(view.to_image_buf());
+    }
+    let mut out = ImageBuf::new(view.width(), view.height(), target)?;
+    let target_bpp = target.bytes_per_pixel().ok_or(ImageError::UnsupportedFormat(target))?;
+    let transfer = (TransferFunction::for_format(source), TransferFunction::for_format(target));
+    for y in 0..view.height() {
+        let row = out.row_mut(y);
+        for (src, dst) in view.row(y).chunks_exact(view.bytes_per_pixel()).zip(row.chunks_exact_mut(target_bpp)) {
+            let mut rgba = decode_pixel(src, source)?;
+            for c in &mut rgba[..3] {
+                *c = match transfer {
+                    (TransferFunction::Srgb, TransferFunction::Linear) => srgb_to_linear(*c),
+                    (TransferFunction::Linear, TransferFunction::Srgb) => linear_to_srgb(*c),
+                    _ => *c,
+                };
+            }
+            encode_pixel(rgba, target, dst)?;
+        }
+    }
+    Ok(out)
+}
+
+/// 先頭のバイト列から形式を判定（TGA はフッターがあるときだけ）
+pub fn detect_format(bytes: &[u8]) -> Option<ImageFormat> {
+    match bytes {
+        [0x89, b'P', b'N', b'G', ..] => Some(ImageFormat::Png),
+        [b'B', b'M', ..] => Some(ImageFormat::Bmp),
+        [b'q', b'o', b'i', b'f', ..] => Some(ImageFormat::Qoi),
+        [b'P', b'5' | b'6', ..] => Some(ImageFormat::Ppm),
+        [b'P', b'7', ..] => Some(ImageFormat::Pam),
+        [0x76, 0x2F, 0x31, 0x01, ..] => Some(ImageFormat::Exr),
+        [0xFF, 0xD8, 0xFF, ..] => Some(ImageFormat::Jpeg),
+        _ if bytes.ends_with(b"TRUEVISION-XFILE.\0") => Some(ImageFormat::Tga),
+        _ => None,
+    }
+}
+
+/// 形式を指定してデコードし、`target` に変換
+pub fn decode_image_as(bytes: &[u8], format: ImageFormat, target: DxgiFormat) -> Result<ImageBuf, ImportError> {
+    let image = match format {
+        ImageFormat::Png => png::decode_png(bytes)?,
+        ImageFormat::Bmp => bmp::decode_bmp(bytes)?,
+        ImageFormat::Ppm | ImageFormat::Pam => pnm::decode_pnm(bytes)?,
+        ImageFormat::Tga => tga::decode_tga(bytes)?,
+        ImageFormat::Qoi => qoi::decode_qoi(bytes)?,
+        ImageFormat::Exr | ImageFormat::Jpeg => {
+            return Err(ImportError::Unsupported(format!("reading {} is not supported", format)));
+        }
+    };
+    Ok(convert(&image.view(), target)?)
+}
+
+/// 先頭のバイト列から形式を判定してデコード
+pub fn decode_image(bytes: &[u8], target: DxgiFormat) -> Result<ImageBuf, ImportError> {
+    let format = detect_format(bytes).ok_or_else(|| ImportError::Unsupported("unknown image format".to_string()))?;
+    decode_image_as(bytes, format, target)
+}
+
+/// ファイルを読み込む（中身で判定できなければ拡張子で選ぶ）
+pub fn load_image(path: &Path, target: DxgiFormat) -> Result<ImageBuf, ImportError> {
+    let bytes = std::fs::read(path)?;
+    let format = detect_format(&bytes)
+        .or_else(|| ImageFormat::from_path(path))
+        .ok_or_else(|| ImportError::Unsupported(format!("unknown image format: {}", path.display())))?;
+    decode_image_as(&bytes, format, target)
+}
@@ -0,0 +1,396 @@
+// ============================================================
+// ベースライン JPEG 書き出し（MJPEG 録画・プレビュー用）
+// ============================================================
+//
+// JFIF 形式の YCbCr（4:2:0 / 4:4:4）、8bit、ハフマン表は規格の標準表。
+// アルファは捨てる。量子化表は IJG と同じ品質係数でスケールする。
+
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+use serde::Serialize;
+
+use crate::export::{push_row8, write_file, ExportError};
+use crate::image::{ImageError, ImageView};
+
+/// 係数のジグザグ順（i 番目に書く係数の 8x8 内の位置）
+const ZIGZAG: [usize; 64] = [
+    0, 1, 8, 16, 9, 2, 3, 10, 17, 24, 32, 25, 18, 11, 4, 5, 12, 19, 26, 33, 40, 48, 41, 34, 27, 20, 13, 6, 7, 14, 21, 28,
+    35, 42, 49, 56, 57, 50, 43, 36, 29, 22, 15, 23, 30, 37, 44, 51, 58, 59, 52, 45, 38, 31, 39, 46, 53, 60, 61, 54, 47,
+    55, 62, 63,
+];
+
+/// 規格 Annex K の輝度の量子化表（品質 50）
+const LUMA_QUANT: [u8; 64] = [
+    16, 11, 10, 16, 24, 40, 51, 61, 12, 12, 14, 19, 26, 58, 60, 55, 14, 13, 16, 24, 40, 57, 69, 56, 14, 17, 22, 29, 51,
+    87, 80, 62, 18, 22, 37, 56, 68, 109, 103, 77, 24, 35, 55, 64, 81, 104, 113, 92, 49, 64, 78, 87, 103, 121, 120, 101,
+    72, 92, 95, 98, 112, 100, 103, 99,
+];
+
+/// 規格 Annex K の色差の量子化表（品質 50）
+const CHROMA_QUANT: [u8; 64] = [
+    17, 18, 24, 47, 99, 99, 99, 99, 18, 21, 26, 66, 99, 99, 99, 99, 24, 26, 56, 99, 99, 99, 99, 99, 47, 66, 99, 99, 99,
+    99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99,
+    99, 99, 99, 99, 99, 99,
+];
+
+// 規格 Annex K.3 の標準ハフマン表（符号長ごとの数, シンボル）
+const DC_LUMA_BITS: [u8; 16] = [0, 1, 5, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0];
+const DC_CHROMA_BITS: [u8; 16] = [0, 3, 1, 1, 1, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0];
+const DC_VALUES: [u8; 12] = [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11];
+const AC_LUMA_BITS: [u8; 16] = [0, 2, 1, 3, 3, 2, 4, 3, 5, 5, 4, 4, 0, 0, 1, 0x7D];
+const AC_LUMA_VALUES: [u8; 162] = [
+    0x01, 0x02, 0x03, 0x00, 0x04, 0x11, 0x05, 0x12, 0x21, 0x31, 0x41, 0x06, 0x13, 0x51, 0x61, 0x07, 0x22, 0x71, 0x14,
+    0x32, 0x81, 0x91, 0xA1, 0x08, 0x23, 0x42, 0xB1, 0xC1, 0x15, 0x52, 0xD1, 0xF0, 0x24, 0x33, 0x62, 0x72, 0x82, 0x09,
+    0x0A, 0x16, 0x17, 0x18, 0x19, 0x1A, 0x25, 0x26, 0x27, 0x28, 0x29, 0x2A, 0x34, 0x35, 0x36, 0x37, 0x38, 0x39, 0x3A,
+    0x43, 0x44, 0x45, 0x46, 0x47, 0x48, 0x49, 0x4A, 0x53, 0x54, 0x55, 0x56, 0x57, 0x58, 0x59, 0x5A, 0x63, 0x64, 0x65,
+    0x66, 0x67, 0x68, 0x69, 0x6A, 0x73, 0x74, 0x75, 0x76, 0x77, 0x78, 0x79, 0x7A, 0x83, 0x84, 0x85, 0x86, 0x87, 0x88,
+    0x89, 0x8A, 0x92, 0x93, 0x94, 0x95, 0x96, 0x97, 0x98, 0x99, 0x9A, 0xA2, 0xA3, 0xA4, 0xA5, 0xA6, 0xA7, 0xA8, 0xA9,
+    0xAA, 0xB2, 0xB3, 0xB4, 0xB5, 0xB6, 0xB7, 0xB8, 0xB9, 0xBA, 0xC2, 0xC3, 0xC4, 0xC5, 0xC6, 0xC7, 0xC8, 0xC9, 0xCA,
+    0xD2, 0xD3, 0xD4, 0xD5, 0xD6, 0xD7, 0xD8, 0xD9, 0xDA, 0xE1, 0xE2, 0xE3, 0xE4, 0xE5, 0xE6, 0xE7, 0xE8, 0xE9, 0xEA,
+    0xF1, 0xF2, 0xF3, 0xF4, 0xF5, 0xF6, 0xF7, 0xF8, 0xF9, 0xFA,
+];
+const AC_CHROMA_BITS: [u8; 16] = [0, 2, 1, 2, 4, 4, 3, 4, 7, 5, 4, 4, 0, 1, 2, 0x77];
+const AC_CHROMA_VALUES: [u8; 162] = [
+    0x00, 0x01, 0x02, 0x03, 0x11, 0x04, 0x05, 0x21, 0x31, 0x06, 0x12, 0x41, 0x51, 0x07, 0x61, 0x71, 0x13, 0x22, 0x32,
+    0x81, 0x08, 0x14, 0x42, 0x91, 0xA1, 0xB1, 0xC1, 0x09, 0x23, 0x33, 0x52, 0xF0, 0x15, 0x62, 0x72, 0xD1, 0x0A, 0x16,
+    0x24, 0x34, 0xE1, 0x25, 0xF1, 0x17, 0x18, 0x19, 0x1A, 0x26, 0x27, 0x28, 0x29, 0x2A, 0x35, 0x36, 0x37, 0x38, 0x39,
+    0x3A, 0x43, 0x44, 0x45, 0x46, 0x47, 0x48, 0x49, 0x4A, 0x53, 0x54, 0x55, 0x56, 0x57, 0x58, 0x59, 0x5A, 0x63, 0x64,
+    0x65, 0x66, 0x67, 0x68, 0x69, 0x6A, 0x73, 0x74, 0x75, 0x76, 0x77, 0x78, 0x79, 0x7A, 0x82, 0x83, 0x84, 0x85, 0x86,
+    0x87, 0x88, 0x89, 0x8A, 0x92, 0x93, 0x94, 0x95, 0x96, 0x97, 0x98, 0x99, 0x9A, 0xA2, 0xA3, 0xA4, 0xA5, 0xA6, 0xA7,
+    0xA8, 0xA9, 0xAA, 0xB2, 0xB3, 0xB4, 0xB5, 0xB6, 0xB7, 0xB8, 0xB9, 0xBA, 0xC2, 0xC3, 0xC4, 0xC5, 0xC6, 0xC7, 0xC8,
+    0xC9, 0xCA, 0xD2, 0xD3, 0xD4, 0xD5, 0xD6, 0xD7, 0xD8, 0xD9, 0xDA, 0xE2, 0xE3, 0xE4, 0xE5, 0xE6, 0xE7, 0xE8, 0xE9,
+    0xEA, 0xF2, 0xF3, 0xF4, 0xF5, 0xF6, 0xF7, 0xF8, 0xF9, 0xFA,
+];
+
+/// 色差の間引き
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+pub enum ChromaSubsampling {
+    /// 縦横とも 1/2（一般的な JPEG / MJPEG）
+    #[default]
+    #[serde(rename = "420")]
+    Yuv420,
+    /// 間引きなし（細い色の線が残る）
+    #[serde(rename = "444")]
+    Yuv444,
+}
+
+impl ChromaSubsampling {
+    /// MCU の 1 辺のピクセル数
+    fn mcu_size(self) -> u32 {
+        match self {
+            Self::Yuv420 => 16,
+            Self::Yuv444 => 8,
+        }
+    }
+}
+
+/// JPEG の書き出し設定
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct JpegOptions {
+    /// 品質 1-100（IJG と同じ尺度）
+    pub quality: u8,
+    pub subsampling: ChromaSubsampling,
+}
+
+impl Default for JpegOptions {
+    fn default() -> Self {
+        Self { quality: 85, subsampling: ChromaSubsampling::Yuv420 }
+    }
+}
+
+impl fmt::Display for JpegOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let subsampling = match self.subsampling {
+            ChromaSubsampling::Yuv420 => "4:2:0",
+            ChromaSubsampling::Yuv444 => "4:4:4",
+        };
+        write!(f, "quality {}, {}", self.quality, subsampling)
+    }
+}
+
+impl FromStr for JpegOptions {
+    type Err = String;
+
+    /// `quality=90,subsampling=444`（省略した項目は既定値）
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut options = Self::default();
+        for item in s.split(',').filter(|item| !item.is_empty()) {
+            let (key, value) = item
+                .split_once('=')
+                .ok_or_else(|| format!("invalid JPEG option: {} (expected key=value)", item))?;
+            match (key, value) {
+                ("quality", quality) => {
+                    options.quality = quality
+                        .parse()
+                        .ok()
+                        .filter(|quality| (1..=100).contains(quality))
+                        .ok_or_else(|| format!("invalid JPEG quality: {} (1-100)", value))?;
+                }
+                ("subsampling", "420") => options.subsampling = ChromaSubsampling::Yuv420,
+                ("subsampling", "444") => options.subsampling = ChromaSubsampling::Yuv444,
+                ("subsampling", _) => return Err(format!("invalid JPEG option value: {}", item)),
+                _ => return Err(format!("unknown JPEG option: {} (quality, subsampling)", key)),
+            }
+        }
+        Ok(options)
+    }
+}
+
+/// 品質に合わせてスケールした量子化表（自然順）
+fn scaled_quant(base: &[u8; 64], quality: u8) -> [u8; 64] {
+    let quality = quality.clamp(1, 100) as u32;
+    let scale = if quality < 50 { 5000 / quality } else { 200 - quality * 2 };
+    base.map(|q| ((q as u32 * scale + 50) / 100).clamp(1, 255) as u8)
+}
+
+/// 標準表から (符号, 符号長) をシンボルごとに引ける表を作る
+struct HuffmanTable {
+    codes: [(u16, u8); 256],
+}
+
+impl HuffmanTable {
+    fn new(bits: &[u8; 16], values: &[u8]) -> Self {
+        let mut codes = [(0, 0); 256];
+        let mut code = 0u16;
+        let mut values = values.iter();
+        for (length, &count) in bits.iter().enumerate() {
+            for _ in 0..count {
+                if let Some(&value) = values.next() {
+                    codes[value as usize] = (code, length as u8 + 1);
+                }
+                code += 1;
+            }
+            code <<= 1;
+        }
+        Self { codes }
+    }
+}
+
+/// エントロピー符号化のビット列（0xFF の後に 0x00 を挟む）
+struct BitWriter {
+    out: Vec<u8>,
+    buffer: u32,
+    bits: u32,
+}
+
+impl BitWriter {
+    fn write(&mut self, value: u16, length: u8) {
+        if length == 0 {
+            return;
+        }
+        self.buffer = (self.buffer << length) | (value as u32 & ((1 << length) - 1));
+        self.bits += length as u32;
+        while self.bits >= 8 {
+            let byte = (self.buffer >> (self.bits - 8)) as u8;
+            self.out.push(byte);
+            if byte == 0xFF {
+                self.out.push(0);
+            }
+            self.bits -= 8;
+        }
+    }
+
+    /// 残りのビットを 1 で埋めて書き出す
+    fn flush(&mut self) {
+        if self.bits > 0 {
+            let padding = 8 - self.bits as u8;
+            self.write((1 << padding) - 1, padding);
+        }
+    }
+}
+
+/// 8x8 の順方向 DCT（行・列の分離形）
+fn fdct(block: &[f32; 64], cosines: &[[f32; 8]; 8]) -> [f32; 64] {
+    let mut rows = [0f32; 64];
+    for y in 0..8 {
+        for u in 0..8 {
+            rows[y * 8 + u] = (0..8).map(|x| cosines[u][x] * block[y * 8 + x]).sum();
+        }
+    }
+    let mut out = [0f32; 64];
+    for u in 0..8 {
+        for v in 0..8 {
+            out[v * 8 + u] = (0..8).map(|y| cosines[v][y] * rows[y * 8 + u]).sum();
+        }
+    }
+    out
+}
+
+/// DC の差分・AC の値の (カテゴリ, 追加ビット)
+fn magnitude(value: i32) -> (u8, u16) {
+    let category = (32 - value.unsigned_abs().leading_zeros()) as u8;
+    let bits = if value < 0 { value - 1 } else { value };
+    (category, bits as u16 & ((1u32 << category) - 1) as u16)
+}
+
+/// 1 成分の符号化の状態
+struct Component<'a> {
+    quant: [f32; 64],
+    dc: &'a HuffmanTable,
+    ac: &'a HuffmanTable,
+    previous_dc: i32,
+}
+
+impl Component<'_> {
+    fn encode_block(&mut self, block: &[f32; 64], cosines: &[[f32; 8]; 8], writer: &mut BitWriter) {
+        let coefficients = fdct(block, cosines);
+        let mut quantized = [0i32; 64];
+        for (i, &position) in ZIGZAG.iter().enumerate() {
+            quantized[i] = (coefficients[position] / self.quant[position]).round() as i32;
+        }
+
+        let (category, bits) = magnitude(quantized[0] - self.previous_dc);
+        self.previous_dc = quantized[0];
+        let (code, length) = self.dc.codes[category as usize];
+        writer.write(code, length);
+        writer.write(bits, category);
+
+        let mut zeros = 0;
+        for &value in &quantized[1..] {
+            if value == 0 {
+                zeros += 1;
+                continue;
+            }
+            while zeros >= 16 {
+                let (code, length) = self.ac.codes[0xF0];
+                writer.write(code, length);
+                zeros -= 16;
+            }
+            let (category, bits) = magnitude(value);
+            let (code, length) = self.ac.codes[(zeros << 4) | category as usize];
+            writer.write(code, length);
+            writer.write(bits, category);
+            zeros = 0;
+        }
+        if zeros > 0 {
+            let (code, length) = self.ac.codes[0x00];
+            writer.write(code, length);
+        }
+    }
+}
+
+fn push_segment(out: &mut Vec<u8>, marker: u8, data: &[u8]) {
+    out.extend([0xFF, marker]);
+    out.extend((data.len() as u16 + 2).to_be_bytes());
+    out.extend(data);
+}
+
+fn push_huffman(data: &mut Vec<u8>, class_id: u8, bits: &[u8; 16], values: &[u8]) {
+    data.push(class_id);
+    data.extend(bits);
+    data.extend(values);
+}
+
+/// JPEG ファイルのバイト列にエンコード（アルファは捨てる）
+pub fn encode_jpeg(view: &ImageView, options: &JpegOptions) -> Result<Vec<u8>, ImageError> {
+    let (width, height) = (view.width(), view.height());
+    if width > u16::MAX as u32 || height > u16::MAX as u32 {
+        return Err(ImageError::InvalidDimensions { width, height, row_pitch: view.row_pitch() });
+    }
+
+    // JFIF の YCbCr（BT.601、フルレンジ）へ変換して平面に分ける
+    let pixels = width as usize * height as usize;
+    let mut rgb = Vec::with_capacity(pixels * 3);
+    for y in 0..height {
+        push_row8(view, y, &[0, 1, 2], &mut rgb)?;
+    }
+    let mut planes = [vec![0f32; pixels], vec![0f32; pixels], vec![0f32; pixels]];
+    for (i, px) in rgb.chunks_exact(3).enumerate() {
+        let [r, g, b] = [px[0], px[1], px[2]].map(|v| v as f32);
+        planes[0][i] = 0.299 * r + 0.587 * g + 0.114 * b - 128.0;
+        planes[1][i] = -0.168_736 * r - 0.331_264 * g + 0.5 * b;
+        planes[2][i] = 0.5 * r - 0.418_688 * g - 0.081_312 * b;
+    }
+
+    let luma_quant = scaled_quant(&LUMA_QUANT, options.quality);
+    let chroma_quant = scaled_quant(&CHROMA_QUANT, options.quality);
+
+    let mut out = Vec::with_capacity(pixels / 4 + 1024);
+    out.extend([0xFF, 0xD8]);
+    // JFIF 1.01, アスペクト比 1:1, サムネイルなし
+    push_segment(&mut out, 0xE0, &[b'J', b'F', b'I', b'F', 0, 1, 1, 0, 0, 1, 0, 1, 0, 0]);
+    let mut dqt = Vec::with_capacity(130);
+    for (id, table) in [(0u8, &luma_quant), (1, &chroma_quant)] {
+        dqt.push(id);
+        dqt.extend(ZIGZAG.map(|position| table[position]));
+    }
+    push_segment(&mut out, 0xDB, &dqt);
+
+    let luma_sampling = match options.subsampling {
+        ChromaSubsampling::Yuv420 => 0x22,
+        ChromaSubsampling::Yuv444 => 0x11,
+    };
+    let mut sof = vec![8];
+    sof.extend((height as u16).to_be_bytes());
+    sof.extend((width as u16).to_be_bytes());
+    sof.extend([3, 1, luma_sampling, 0, 2, 0x11, 1, 3, 0x11, 1]);
+    push_segment(&mut out, 0xC0, &sof);
+
+    let mut dht = Vec::with_capacity(420);
+    push_huffman(&mut dht, 0x00, &DC_LUMA_BITS, &DC_VALUES);
+    push_huffman(&mut dht, 0x10, &AC_LUMA_BITS, &AC_LUMA_VALUES);
+    push_huffman(&mut dht, 0x01, &DC_CHROMA_BITS, &DC_VALUES);
+    push_huffman(&mut dht, 0x11, &AC_CHROMA_BITS, &AC_CHROMA_VALUES);
+    push_segment(&mut out, 0xC4, &dht);
+    push_segment(&mut out, 0xDA, &[3, 1, 0x00, 2, 0x11, 3, 0x11, 0, 63, 0]);
+
+    // 係数 F(u,v) = C(u)C(v)/4 ΣΣ f(x,y) cos…（C(0) = 1/√2）を 1 次元の係数に分ける
+    let cosines: [[f32; 8]; 8] = std::array::from_fn(|u| {
+        let scale = if u == 0 { std::f32::consts::FRAC_1_SQRT_2 / 2.0 } else { 0.5 };
+        std::array::from_fn(|x| scale * ((2 * x + 1) as f32 * u as f32 * std::f32::consts::PI / 16.0).cos())
+    });
+    let (dc_luma, ac_luma) = (HuffmanTable::new(&DC_LUMA_BITS, &DC_VALUES), HuffmanTable::new(&AC_LUMA_BITS, &AC_LUMA_VALUES));
+    let (dc_chroma, ac_chroma) =
+        (HuffmanTable::new(&DC_CHROMA_BITS, &DC_VALUES), HuffmanTable::new(&AC_CHROMA_BITS, &AC_CHROMA_VALUES));
+    let mut components = [
+        Component { quant: luma_quant.map(|q| q as f32), dc: &dc_luma, ac: &ac_luma, previous_dc: 0 },
+        Component { quant: chroma_quant.map(|q| q as f32), dc: &dc_chroma, ac: &ac_chroma, previous_dc: 0 },
+        Component { quant: chroma_quant.map(|q| q as f32), dc: &dc_chroma, ac: &ac_chroma, previous_dc: 0 },
+    ];
+
+    // 画像の外は端のピクセルを繰り返す
+    let sample = |plane: &[f32], x: u32, y: u32| plane[(y.min(height - 1) * width + x.min(width - 1)) as usize];
+    let mut writer = BitWriter { out, buffer: 0, bits: 0 };
+    let mut block = [0f32; 64];
+    let mcu = options.subsampling.mcu_size();
+    for mcu_y in (0..height).step_by(mcu as usize) {
+        for mcu_x in (0..width).step_by(mcu as usize) {
+            // 輝度は MCU 内の 8x8 ブロックを左上から
+            for (by, bx) in [(0, 0), (0, 8), (8, 0), (8, 8)].into_iter().filter(|&(by, bx)| by < mcu && bx < mcu) {
+                for (i, value) in block.iter_mut().enumerate() {
+                    *value = sample(&planes[0], mcu_x + bx + i as u32 % 8, mcu_y + by + i as u32 / 8);
+                }
+                components[0].encode_block(&block, &cosines, &mut writer);
+            }
+            for c in 1..3 {
+                for (i, value) in block.iter_mut().enumerate() {
+                    let (x, y) = (i as u32 % 8, i as u32 / 8);
+                    *value = match options.subsampling {
+                        ChromaSubsampling::Yuv444 => sample(&planes[c], mcu_x + x, mcu_y + y),
+                        ChromaSubsampling::Yuv420 => {
+                            let (sx, sy) = (mcu_x + x * 2, mcu_y + y * 2);
+                            (sample(&planes[c], sx, sy)
+                                + sample(&planes[c], sx + 1, sy)
+                                + sample(&planes[c], sx, sy + 1)
+                                + sample(&planes[c], sx + 1, sy + 1))
+                                / 4.0
+                        }
+                    };
+                }
+                components[c].encode_block(&block, &cosines, &mut writer);
+            }
+        }
+    }
+    writer.flush();
+
+    let mut out = writer.out;
+    out.extend([0xFF, 0xD9]);
+    Ok(out)
+}
+
+/// 設定を指定して保存
+pub fn save_jpeg(path: &Path, view: &ImageView, options: &JpegOptions) -> Result<(), ExportError> {
+    write_file(path, &encode_jpeg(view, options)?)
+}
@@ -0,0 +1,347 @@
+// ============================================================
+// エンドツーエンド遅延の計測
+// ============================================================
+//
+// 送信側がフレームカウンターパターン（pattern.rs）にフレーム番号と
+// 送信時刻を埋め込み、受信側が読み取って遅延を集計する。
+// 送受信で同じクロック（同一マシンなら MonotonicClock）を使うこと。
+
+use std::cell::Cell;
+use std::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+
+use crate::image::{DxgiFormat, ImageError};
+use crate::mock::MockReceiver;
+use crate::pattern::{FrameStamp, Pattern};
+
+/// マイクロ秒のクロック
+pub trait Clock {
+    fn now_us(&self) -> u64;
+}
+
+/// UNIX 時刻（NTP 同期した別マシン間で使う、巻き戻りうる）
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now_us(&self) -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_micros() as u64)
+    }
+}
+
+/// プロセス間で共有できる単調クロック
+///
+/// Windows では QueryPerformanceCounter、Linux / macOS では CLOCK_MONOTONIC（どちらもシステム全体で共通）。
+/// それ以外ではプロセスで最初に読んだ時刻からの経過時間（別のプロセスの値とは比べられない）。
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MonotonicClock;
+
+#[cfg(windows)]
+impl Clock for MonotonicClock {
+    fn now_us(&self) -> u64 {
+        use windows::Win32::System::Performance::{QueryPerformanceCounter, QueryPerformanceFrequency};
+        let (mut counter, mut frequency) = (0i64, 0i64);
+        unsafe {
+            if QueryPerformanceCounter(&mut counter).is_err() || QueryPerformanceFrequency(&mut frequency).is_err() {
+                return SystemClock.now_us();
+            }
+        }
+        (counter as u128 * 1_000_000 / frequency.max(1) as u128) as u64
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android", target_os = "macos"))]
+impl Clock for MonotonicClock {
+    fn now_us(&self) -> u64 {
+        use std::ffi::{c_int, c_long};
+
+        #[repr(C)]
+        struct Timespec {
+            tv_sec: c_long,
+            tv_nsec: c_long,
+        }
+        unsafe extern "C" {
+            fn clock_gettime(clock: c_int, time: *mut Timespec) -> c_int;
+        }
+        #[cfg(target_os = "macos")]
+        const CLOCK_MONOTONIC: c_int = 6;
+        #[cfg(not(target_os = "macos"))]
+        const CLOCK_MONOTONIC: c_int = 1;
+
+        let mut time = Timespec { tv_sec: 0, tv_nsec: 0 };
+        if unsafe { clock_gettime(CLOCK_MONOTONIC, &mut time) } != 0 {
+            return 0;
+        }
+        time.tv_sec as u64 * 1_000_000 + time.tv_nsec as u64 / 1_000
+    }
+}
+
+#[cfg(not(any(windows, target_os = "linux", target_os = "android", target_os = "macos")))]
+impl Clock for MonotonicClock {
+    fn now_us(&self) -> u64 {
+        use std::sync::OnceLock;
+        use std::time::Instant;
+
+        static START: OnceLock<Instant> = OnceLock::new();
+        START.get_or_init(Instant::now).elapsed().as_micros() as u64
+    }
+}
+
+/// 手動で進めるクロック（モック用）
+#[derive(Debug, Default)]
+pub struct ManualClock {
+    now_us: Cell<u64>,
+}
+
+impl ManualClock {
+    pub fn new(start_us: u64) -> Self {
+        Self { now_us: Cell::new(start_us) }
+    }
+
+    pub fn set(&self, now_us: u64) {
+        self.now_us.set(now_us);
+    }
+
+    pub fn advance(&self, delta_us: u64) {
+        self.now_us.set(self.now_us.get() + delta_us);
+    }
+}
+
+impl Clock for ManualClock {
+    fn now_us(&self) -> u64 {
+        self.now_us.get()
+    }
+}
+
+/// 受信フレームを順に記録して遅延とフレーム欠落を集計する
+#[derive(Debug, Clone, Default)]
+pub struct LatencyTracker {
+    /// 受信した順の遅延（マイクロ秒、クロックずれで負になりうる）
+    latencies_us: Vec<i64>,
+    last_frame: Option<u64>,
+    received: u64,
+    dropped: u64,
+    duplicated: u64,
+    out_of_order: u64,
+    unreadable: u64,
+}
+
+impl LatencyTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// スタンプを読めたフレームを記録
+    pub fn record(&mut self, stamp: FrameStamp, received_us: u64) {
+        self.received += 1;
+        match self.last_frame {
+            Some(last) if stamp.frame == last => {
+                // 同じフレームを再度受信（遅延には数えない）
+                self.duplicated += 1;
+                return;
+            }
+            Some(last) if stamp.frame < last => self.out_of_order += 1,
+            Some(last) => self.dropped += stamp.frame - last - 1,
+            None => {}
+        }
+        self.last_frame = Some(self.last_frame.map_or(stamp.frame, |last| last.max(stamp.frame)));
+        self.latencies_us.push(received_us as i64 - stamp.timestamp_us as i64);
+    }
+
+    /// スタンプを読めなかったフレームを記録
+    pub fn record_unreadable(&mut self) {
+        self.received += 1;
+        self.unreadable += 1;
+    }
+
+    pub fn summary(&self) -> LatencySummary {
+        let mut sorted = self.latencies_us.clone();
+        sorted.sort_unstable();
+        let ms = |us: i64| us as f64 / 1000.0;
+        let percentile = |p: f64| {
+            if sorted.is_empty() {
+                return 0.0;
+            }
+            // nearest-rank
+            let rank = ((p / 100.0) * sorted.len() as f64).ceil().max(1.0) as usize;
+            ms(sorted[rank.min(sorted.len()) - 1])
+        };
+
+        let count = sorted.len().max(1) as f64;
+        let mean = self.latencies_us.iter().map(|&v| v as f64).sum::<f64>() / count;
+        let variance = self.latencies_us.iter().map(|&v| (v as f64 - mean).powi(2)).sum::<f64>() / count;
+        // 連続するフレーム間の遅延差の平均（RFC 3550 の平滑化なし版）
+        let jitter = self
+            .latencies_us
+            .windows(2)
+            .map(|w| (w[1] - w[0]).unsigned_abs() as f64)
+            .sum::<f64>()
+            / (self.latencies_us.len().saturating_sub(1)).max(1) as f64;
+
+        LatencySummary {
+            received: self.received,
+            measured: self.latencies_us.len() as u64,
+            dropped: self.dropped,
+            duplicated: self.duplicated,
+            out_of_order: self.out_of_order,
+            unreadable: self.unreadable,
+            min_ms: sorted.first().copied().map_or(0.0, ms),
+            max_ms: sorted.last().copied().map_or(0.0, ms),
+            mean_ms: mean / 1000.0,
+            stddev_ms: variance.sqrt() / 1000.0,
+            jitter_ms: jitter / 1000.0,
+            p50_ms: percentile(50.0),
+            p90_ms: percentile(90.0),
+            p95_ms: percentile(95.0),
+            p99_ms: percentile(99.0),
+        }
+    }
+}
+
+/// 遅延の集計結果
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LatencySummary {
+    /// 受信したフレーム数（重複・読めなかったものを含む）
+    pub received: u64,
+    /// 遅延を計測できたフレーム数
+    pub measured: u64,
+    /// フレーム番号の欠け
+    pub dropped: u64,
+    pub duplicated: u64,
+    pub out_of_order: u64,
+    pub unreadable: u64,
+    pub min_ms: f64,
+    pub max_ms: f64,
+    pub mean_ms: f64,
+    pub stddev_ms: f64,
+    pub jitter_ms: f64,
+    pub p50_ms: f64,
+    pub p90_ms: f64,
+    pub p95_ms: f64,
+    pub p99_ms: f64,
+}
+
+impl fmt::Display for LatencySummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "[Latency] {} frames received, {} measured", self.received, self.measured)?;
+        writeln!(
+            f,
+            "  min {:.2} / mean {:.2} / max {:.2} ms (stddev {:.2}, jitter {:.2})",
+            self.min_ms, self.mean_ms, self.max_ms, self.stddev_ms, self.jitter_ms
+        )?;
+        writeln!(
+            f,
+            "  p50 {:.2} / p90 {:.2} / p95 {:.2} / p99 {:.2} ms",
+            self.p50_ms, self.p90_ms, self.p95_ms, self.p99_ms
+        )?;
+        writeln!(
+            f,
+            "  dropped {}, duplicated {}, out of order {}, unreadable {}",
+            self.dropped, self.duplicated, self.out_of_order, self.unreadable
+        )
+    }
+}
+
+// ============================================================
+// モック（合成した遅延で送受信を再現）
+// ============================================================
+
+/// 合成フレーム 1 枚分の振る舞い
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SyntheticFrame {
+    /// 送信から受信までの遅延
+    pub delay_us: u64,
+    /// 受信側に届かない（センダー側で落ちた）
+    pub dropped: bool,
+    /// 受信側が同じフレームを読む回数（2 以上で重複）
+    pub reads: u32,
+}
+
+impl SyntheticFrame {
+    pub fn delivered(delay_us: u64) -> Self {
+        Self { delay_us, dropped: false, reads: 1 }
+    }
+}
+
+/// モック受信で遅延計測を再現する
+///
+/// `interval_us` ごとにフレームカウンターパターンを送信し、各フレームの
+/// `delay_us` 後に受信してスタンプを読む。集計コードを実機なしで確認できる。
+pub fn simulate(frames: &[SyntheticFrame], interval_us: u64, width: u32, height: u32) -> Result<LatencyTracker, ImageError> {
+    let clock = ManualClock::new(1_000_000);
+    let mut receiver = MockReceiver::new("latency-mock");
+    let mut tracker = LatencyTracker::new();
+
+    for (index, frame) in frames.iter().enumerate() {
+        let sent_us = 1_000_000 + index as u64 * interval_us;
+        clock.set(sent_us);
+        if frame.dropped {
+            continue;
+        }
+        let stamp = FrameStamp { frame: index as u64, timestamp_us: clock.now_us() };
+        receiver.push_frame(Pattern::FrameCounter.generate(width, height, DxgiFormat::B8G8R8A8Unorm, stamp)?);
+
+        clock.advance(frame.delay_us);
+        if receiver.receive().is_err() {
+            continue;
+        }
+        for _ in 0..frame.reads {
+            let stamp = receiver.receive_image().ok().and_then(|image| Pattern::read_stamp(&image.view()));
+            match stamp {
+                Some(stamp) => tracker.record(stamp, clock.now_us()),
+                None => tracker.record_unreadable(),
+            }
+        }
+    }
+    Ok(tracker)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn monotonic_clock_does_not_go_back() {
+        let mut last = MonotonicClock.now_us();
+        assert!(last > 0);
+        for _ in 0..1000 {
+            let now = MonotonicClock.now_us();
+            assert!(now >= last);
+            last = now;
+        }
+        let start = MonotonicClock.now_us();
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        let elapsed = MonotonicClock.now_us() - start;
+        assert!((20_000..1_000_000).contains(&elapsed), "{} us", elapsed);
+    }
+
+    #[test]
+    fn simulate_measures_injected_latency() {
+        // 9 フレームは 5 ms、1 フレームだけ 40 ms の遅延を入れる
+        let mut frames = vec![SyntheticFrame::delivered(5_000); 10];
+        frames[6].delay_us = 40_000;
+        let summary = simulate(&frames, 16_667, 256, 144).unwrap().summary();
+        assert_eq!((summary.received, summary.measured, summary.unreadable), (10, 10, 0));
+        assert_eq!((summary.min_ms, summary.p50_ms, summary.p90_ms), (5.0, 5.0, 5.0));
+        assert_eq!(summary.max_ms, 40.0);
+        assert!((summary.mean_ms - 8.5).abs() < 1e-9);
+        // 5 → 40 → 5 ms の 2 回の変化
+        assert!((summary.jitter_ms - 70.0 / 9.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn simulate_counts_drops_and_duplicates() {
+        let mut frames = vec![SyntheticFrame::delivered(12_000); 8];
+        frames[2].dropped = true;
+        frames[3].dropped = true;
+        frames[5].reads = 3;
+        let summary = simulate(&frames, 16_667, 256, 144).unwrap().summary();
+        assert_eq!((summary.received, summary.measured), (8, 6));
+        assert_eq!((summary.dropped, summary.duplicated, summary.out_of_order), (2, 2, 0));
+        assert_eq!((summary.min_ms, summary.max_ms), (12.0, 12.0));
+    }
+}
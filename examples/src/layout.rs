@@ -0,0 +1,376 @@
+// ============================================================
+// 受信データのレイアウト推定（チャンネル順・上下・乗算済み）
+// ============================================================
+//
+// テストパターン（pattern.rs）が写っていればそれを目印に判定し、
+// なければ統計的な手がかりで推定する。各項目に 0..1 の確信度を付ける。
+//
+// 統計だけでは R/B の入れ替わりは判別できない（どちらの並びも
+// ありうる色になる）ため、目印がない場合の確信度は 0.5 に留める。
+
+use std::fmt;
+
+use serde::{Serialize, Serializer};
+
+use crate::alpha::{AlphaMode, AlphaOp};
+use crate::hdr::decode_pixel;
+use crate::image::{DxgiFormat, ImageBuf, ImageError, ImageView};
+use crate::pattern::{Pattern, VerifyOptions};
+use crate::transform::{Rotation, Transform};
+
+/// 目印として採用する一致率（%）
+const MARKER_MIN_MATCH: f32 = 90.0;
+
+/// 上下の明るさの差による確信度の上乗せ上限（空が上にある、程度の弱い手がかり）
+const BRIGHTNESS_PRIOR_WEIGHT: f32 = 0.1;
+
+/// メモリ上のチャンネル順（8bit x 4）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChannelOrder {
+    Bgra,
+    Rgba,
+    Argb,
+    Abgr,
+}
+
+impl ChannelOrder {
+    /// フォーマットが宣言しているチャンネル順（8bit 以外は None）
+    pub fn for_format(format: DxgiFormat) -> Option<Self> {
+        match format {
+            DxgiFormat::B8G8R8A8Unorm | DxgiFormat::B8G8R8A8UnormSrgb => Some(Self::Bgra),
+            DxgiFormat::R8G8B8A8Unorm | DxgiFormat::R8G8B8A8UnormSrgb => Some(Self::Rgba),
+            _ => None,
+        }
+    }
+
+    /// R, G, B, A のバイト位置
+    fn positions(self) -> [usize; 4] {
+        match self {
+            Self::Bgra => [2, 1, 0, 3],
+            Self::Rgba => [0, 1, 2, 3],
+            Self::Argb => [1, 2, 3, 0],
+            Self::Abgr => [3, 2, 1, 0],
+        }
+    }
+
+    /// 宣言された順から、アルファ位置と R/B の入れ替わりで実際の順を求める
+    fn from_parts(declared: Self, alpha_first: bool, swap_rb: bool) -> Self {
+        let rgb_first = matches!(declared, Self::Rgba | Self::Argb) != swap_rb;
+        match (alpha_first, rgb_first) {
+            (false, true) => Self::Rgba,
+            (false, false) => Self::Bgra,
+            (true, true) => Self::Argb,
+            (true, false) => Self::Abgr,
+        }
+    }
+}
+
+impl fmt::Display for ChannelOrder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Bgra => "BGRA",
+            Self::Rgba => "RGBA",
+            Self::Argb => "ARGB",
+            Self::Abgr => "ABGR",
+        };
+        f.write_str(name)
+    }
+}
+
+/// 上下の向き
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Orientation {
+    Upright,
+    Flipped,
+}
+
+impl fmt::Display for Orientation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Upright => write!(f, "upright"),
+            Self::Flipped => write!(f, "flipped"),
+        }
+    }
+}
+
+/// 判定の根拠
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Evidence {
+    Marker,
+    Statistics,
+}
+
+/// 確信度付きの判定結果
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Detection<T> {
+    pub value: T,
+    /// 0..1（0.5 は判別できなかったことを表す）
+    pub confidence: f32,
+    pub evidence: Evidence,
+}
+
+impl<T: fmt::Display> fmt::Display for Detection<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({:.0}%, {})", self.value, self.confidence * 100.0, match self.evidence {
+            Evidence::Marker => "marker",
+            Evidence::Statistics => "statistics",
+        })
+    }
+}
+
+/// 推定の設定
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct LayoutOptions {
+    /// 写っているはずのテストパターン（None ならフレームカウンターのスタンプだけ探す）
+    pub marker: Option<Pattern>,
+    /// 送信時のサイズ（None なら受信サイズと同じとみなす）
+    pub sent_size: Option<(u32, u32)>,
+}
+
+fn serialize_marker<S: Serializer>(marker: &Option<Pattern>, serializer: S) -> Result<S::Ok, S::Error> {
+    match marker {
+        Some(pattern) => serializer.collect_str(pattern),
+        None => serializer.serialize_none(),
+    }
+}
+
+/// 推定結果
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct LayoutDetection {
+    /// フォーマットが宣言しているチャンネル順
+    pub declared: ChannelOrder,
+    pub channel_order: Detection<ChannelOrder>,
+    pub orientation: Detection<Orientation>,
+    pub alpha: Detection<AlphaMode>,
+    /// 目印として使えたパターン
+    #[serde(serialize_with = "serialize_marker")]
+    pub marker: Option<Pattern>,
+}
+
+impl LayoutDetection {
+    /// 確信度が `min_confidence` 以上の項目だけを直す補正
+    pub fn correction(&self, min_confidence: f32) -> LayoutCorrection {
+        let confident = |confidence: f32| confidence >= min_confidence;
+        LayoutCorrection {
+            from: if confident(self.channel_order.confidence) { self.channel_order.value } else { self.declared },
+            to: self.declared,
+            flip_vertical: confident(self.orientation.confidence) && self.orientation.value == Orientation::Flipped,
+            alpha: if confident(self.alpha.confidence) && self.alpha.value == AlphaMode::Premultiplied {
+                AlphaOp::Unpremultiply
+            } else {
+                AlphaOp::None
+            },
+        }
+    }
+}
+
+impl fmt::Display for LayoutDetection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "[Layout] declared {}", self.declared)?;
+        writeln!(f, "  Channel order: {}", self.channel_order)?;
+        writeln!(f, "  Orientation: {}", self.orientation)?;
+        writeln!(f, "  Alpha: {}", self.alpha)?;
+        if let Some(marker) = self.marker {
+            writeln!(f, "  Marker: {}", marker)?;
+        }
+        Ok(())
+    }
+}
+
+/// 受信画像を宣言どおりのレイアウトへ戻す補正
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LayoutCorrection {
+    /// 実際のチャンネル順
+    pub from: ChannelOrder,
+    /// 並べ替え先（フォーマットの宣言）
+    pub to: ChannelOrder,
+    pub flip_vertical: bool,
+    pub alpha: AlphaOp,
+}
+
+impl LayoutCorrection {
+    pub fn is_identity(&self) -> bool {
+        self.from == self.to && !self.flip_vertical && self.alpha == AlphaOp::None
+    }
+
+    pub fn apply(&self, buf: &mut ImageBuf) -> Result<(), ImageError> {
+        if self.from != self.to {
+            let (from, to) = (self.from.positions(), self.to.positions());
+            if ChannelOrder::for_format(buf.format()).is_none() {
+                return Err(ImageError::UnsupportedFormat(buf.format()));
+            }
+            let width = buf.width() as usize;
+            for y in 0..buf.height() {
+                for px in buf.row_mut(y).chunks_exact_mut(4).take(width) {
+                    let old = [px[0], px[1], px[2], px[3]];
+                    for c in 0..4 {
+                        px[to[c]] = old[from[c]];
+                    }
+                }
+            }
+        }
+        if self.flip_vertical {
+            Transform { flip_vertical: true, ..Transform::default() }.apply_in_place(buf)?;
+        }
+        self.alpha.apply(buf)
+    }
+}
+
+/// チャンネル順・上下・乗算済みを推定（8bit BGRA / RGBA のみ）
+pub fn detect_layout(view: &ImageView, options: &LayoutOptions) -> Result<LayoutDetection, ImageError> {
+    let declared = ChannelOrder::for_format(view.format()).ok_or(ImageError::UnsupportedFormat(view.format()))?;
+    let (alpha_first, alpha_confidence) = detect_alpha_position(view);
+
+    // アルファを末尾へ移した画像で残りを判定
+    let mut normalized = view.to_image_buf();
+    if alpha_first {
+        for y in 0..normalized.height() {
+            for px in normalized.row_mut(y).chunks_exact_mut(4) {
+                px.rotate_left(1);
+            }
+        }
+    }
+    let normalized_view = normalized.view();
+
+    let marker = verify_marker(&normalized_view, options);
+    let (swap_rb, orientation) = match &marker {
+        Some((pattern_confidence, swap_rb, flipped)) => (
+            Detection { value: *swap_rb, confidence: *pattern_confidence, evidence: Evidence::Marker },
+            Detection {
+                value: if *flipped { Orientation::Flipped } else { Orientation::Upright },
+                confidence: *pattern_confidence,
+                evidence: Evidence::Marker,
+            },
+        ),
+        None => (
+            Detection { value: false, confidence: 0.5, evidence: Evidence::Statistics },
+            orientation_from_brightness(&normalized_view)?,
+        ),
+    };
+
+    Ok(LayoutDetection {
+        declared,
+        channel_order: Detection {
+            value: ChannelOrder::from_parts(declared, alpha_first, swap_rb.value),
+            confidence: alpha_confidence.min(swap_rb.confidence),
+            evidence: swap_rb.evidence,
+        },
+        orientation,
+        alpha: detect_alpha_mode(&normalized_view)?,
+        marker: marker.and(options.marker.or(Some(Pattern::FrameCounter))),
+    })
+}
+
+/// アルファが先頭（ARGB / ABGR）か末尾かを判定
+///
+/// アルファは乗算済みでも不透明でも他の 3 チャンネル以上になるため、
+/// 「そのバイトが他より大きいか等しい」ピクセルの割合を先頭と末尾で比べる。
+fn detect_alpha_position(view: &ImageView) -> (bool, f32) {
+    let (mut first, mut last) = (0u64, 0u64);
+    for y in 0..view.height() {
+        for px in view.row(y).chunks_exact(4).take(view.width() as usize) {
+            first += (px[0] >= px[1] && px[0] >= px[2] && px[0] >= px[3]) as u64;
+            last += (px[3] >= px[0] && px[3] >= px[1] && px[3] >= px[2]) as u64;
+        }
+    }
+    let (best, other) = (first.max(last), first.min(last));
+    if best == 0 {
+        return (false, 0.5);
+    }
+    let confidence = 0.5 + 0.5 * (best - other) as f32 / best as f32;
+    (first > last, confidence)
+}
+
+/// 目印のパターンを照合して (確信度, R/B 入れ替わり, 上下反転) を返す
+fn verify_marker(view: &ImageView, options: &LayoutOptions) -> Option<(f32, bool, bool)> {
+    let (width, height) = options.sent_size.unwrap_or((view.width(), view.height()));
+    let pattern = match options.marker {
+        Some(pattern) => pattern,
+        None => {
+            // スタンプは白黒なので、上下どちらかで読めればフレームカウンターとみなす
+            let flipped = Transform { flip_vertical: true, ..Transform::default() }.apply(view).ok()?;
+            Pattern::read_stamp(view).or_else(|| Pattern::read_stamp(&flipped.view()))?;
+            Pattern::FrameCounter
+        }
+    };
+
+    let verification = pattern.verify(view, width, height, &VerifyOptions::default()).ok()?;
+    if verification.matched_percent < MARKER_MIN_MATCH {
+        return None;
+    }
+    let swap_rb = match verification.channel_order {
+        [0, 1, 2] => false,
+        [2, 1, 0] => true,
+        // BGRA / RGBA / ARGB では表せない並び
+        _ => return None,
+    };
+    // 上下の成分だけを見る（180 度回転は上下反転 + 左右反転）
+    let t = verification.transform;
+    let flipped = match t.rotation {
+        Rotation::None => t.flip_vertical,
+        Rotation::Cw180 => !t.flip_vertical,
+        Rotation::Cw90 | Rotation::Cw270 => return None,
+    };
+    Some((verification.matched_percent / 100.0, swap_rb, flipped))
+}
+
+/// 上が明るいことが多い（空・照明）という弱い手がかりで上下を推定
+fn orientation_from_brightness(view: &ImageView) -> Result<Detection<Orientation>, ImageError> {
+    let band = (view.height() / 4).max(1);
+    let mean_luma = |rows: std::ops::Range<u32>| -> Result<f32, ImageError> {
+        let (mut sum, mut count) = (0.0f32, 0u32);
+        for y in rows {
+            for px in view.row(y).chunks_exact(4).take(view.width() as usize) {
+                let [r, g, b, _] = decode_pixel(px, view.format())?;
+                sum += 0.2126 * r + 0.7152 * g + 0.0722 * b;
+                count += 1;
+            }
+        }
+        Ok(sum / count.max(1) as f32)
+    };
+    let top = mean_luma(0..band)?;
+    let bottom = mean_luma(view.height() - band..view.height())?;
+    let difference = top - bottom;
+
+    Ok(Detection {
+        value: if difference >= 0.0 { Orientation::Upright } else { Orientation::Flipped },
+        confidence: 0.5 + BRIGHTNESS_PRIOR_WEIGHT * (difference.abs() * 4.0).min(1.0),
+        evidence: Evidence::Statistics,
+    })
+}
+
+/// 乗算済みかどうかを確信度付きで推定
+///
+/// カラー > アルファのピクセルがあればストレート。なければ乗算済みの可能性が
+/// 高いが、低いアルファで色を持つピクセルが多いほど確信できる。
+fn detect_alpha_mode(view: &ImageView) -> Result<Detection<AlphaMode>, ImageError> {
+    const TOLERANCE: f32 = 1.0 / 255.0;
+    let (mut translucent, mut violations, mut informative) = (0u64, 0u64, 0u64);
+    for y in 0..view.height() {
+        for px in view.row(y).chunks_exact(4).take(view.width() as usize) {
+            let [r, g, b, a] = decode_pixel(px, view.format())?;
+            let color = r.max(g).max(b);
+            if a < 1.0 {
+                translucent += 1;
+            }
+            if color > a + TOLERANCE {
+                violations += 1;
+            } else if a < 0.5 && color > 0.0 {
+                informative += 1;
+            }
+        }
+    }
+
+    let saturate = |n: u64, scale: f32| 0.5 + 0.5 * (1.0 - (-(n as f32) / scale).exp());
+    let (value, confidence) = if translucent == 0 {
+        (AlphaMode::Opaque, 1.0)
+    } else if violations > 0 {
+        (AlphaMode::Straight, saturate(violations, 10.0))
+    } else {
+        (AlphaMode::Premultiplied, saturate(informative, 100.0))
+    };
+    Ok(Detection { value, confidence, evidence: Evidence::Statistics })
+}
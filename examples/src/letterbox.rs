@@ -0,0 +1,255 @@
+// ============================================================
+// レターボックス・黒枠・有効領域の検出
+// ============================================================
+//
+// 固定サイズのキャンバスに余白を付けて送るセンダー向け。黒（または透明）
+// でない画素を含む行・列から有効領域の矩形を求め、枠の太さと
+// アスペクト比を報告する。出力時に有効領域だけ切り出すこともできる。
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::Serialize;
+
+use crate::hdr::decode_pixel;
+use crate::image::{ImageError, ImageView};
+
+/// よく使われるアスペクト比（比の許容誤差 1%）
+const STANDARD_RATIOS: [(&str, f64); 11] = [
+    ("9:16", 9.0 / 16.0),
+    ("1:1", 1.0),
+    ("5:4", 1.25),
+    ("4:3", 4.0 / 3.0),
+    ("3:2", 1.5),
+    ("16:10", 1.6),
+    ("16:9", 16.0 / 9.0),
+    ("1.85:1", 1.85),
+    ("2:1", 2.0),
+    ("21:9", 21.0 / 9.0),
+    ("2.39:1", 2.39),
+];
+
+/// 枠とみなす画素
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BorderKind {
+    /// RGB がすべて許容値以下
+    Black,
+    /// アルファが許容値以下
+    Transparent,
+    /// 黒または透明（全面が透明なら黒だけを見る）
+    #[default]
+    Any,
+}
+
+impl fmt::Display for BorderKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Black => write!(f, "black"),
+            Self::Transparent => write!(f, "transparent"),
+            Self::Any => write!(f, "any"),
+        }
+    }
+}
+
+impl FromStr for BorderKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "black" => Ok(Self::Black),
+            "transparent" => Ok(Self::Transparent),
+            "any" => Ok(Self::Any),
+            _ => Err(format!("unknown border kind: {} (black, transparent, any)", s)),
+        }
+    }
+}
+
+/// 検出の設定
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct BorderOptions {
+    pub kind: BorderKind,
+    /// 枠とみなす値の上限（格納値 0..1）
+    pub tolerance: f32,
+    /// 行・列のうちこの割合（%）以下しか内容がなければ枠とみなす（ノイズ対策）
+    pub max_content_percent: f32,
+}
+
+impl Default for BorderOptions {
+    fn default() -> Self {
+        Self { kind: BorderKind::Any, tolerance: 8.0 / 255.0, max_content_percent: 0.5 }
+    }
+}
+
+
+/// 上下左右の枠の太さ（ピクセル）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+pub struct Borders {
+    pub top: u32,
+    pub bottom: u32,
+    pub left: u32,
+    pub right: u32,
+}
+
+/// 枠の付き方
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AreaLayout {
+    /// 枠なし
+    Full,
+    /// 上下に枠
+    Letterbox,
+    /// 左右に枠
+    Pillarbox,
+    /// 上下左右に枠
+    Windowbox,
+    /// 内容がない（全面が枠）
+    Empty,
+}
+
+impl fmt::Display for AreaLayout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Full => write!(f, "full frame"),
+            Self::Letterbox => write!(f, "letterbox"),
+            Self::Pillarbox => write!(f, "pillarbox"),
+            Self::Windowbox => write!(f, "windowbox"),
+            Self::Empty => write!(f, "empty"),
+        }
+    }
+}
+
+/// 有効領域
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct ActiveArea {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+    pub full_width: u32,
+    pub full_height: u32,
+    pub borders: Borders,
+    pub layout: AreaLayout,
+    /// 有効領域の幅 / 高さ（空なら 0）
+    pub aspect_ratio: f64,
+    /// 近い標準のアスペクト比
+    pub standard_ratio: Option<&'static str>,
+}
+
+impl ActiveArea {
+    pub fn is_full(&self) -> bool {
+        self.layout == AreaLayout::Full
+    }
+
+    /// 有効領域を指すビュー（コピーしない）
+    pub fn crop<'a>(&self, view: &ImageView<'a>) -> Result<ImageView<'a>, ImageError> {
+        view.sub_view(self.x, self.y, self.width, self.height)
+    }
+}
+
+impl fmt::Display for ActiveArea {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.layout == AreaLayout::Empty {
+            return write!(f, "no active content in {}x{}", self.full_width, self.full_height);
+        }
+        write!(
+            f,
+            "{} {}x{} at ({}, {}) in {}x{}, aspect {:.3}",
+            self.layout, self.width, self.height, self.x, self.y, self.full_width, self.full_height, self.aspect_ratio
+        )?;
+        if let Some(ratio) = self.standard_ratio {
+            write!(f, " ({})", ratio)?;
+        }
+        let b = self.borders;
+        if !self.is_full() {
+            write!(f, ", borders top {} bottom {} left {} right {}", b.top, b.bottom, b.left, b.right)?;
+        }
+        Ok(())
+    }
+}
+
+fn standard_ratio(aspect: f64) -> Option<&'static str> {
+    STANDARD_RATIOS
+        .iter()
+        .find(|(_, ratio)| (aspect / ratio - 1.0).abs() <= 0.01)
+        .map(|(name, _)| *name)
+}
+
+/// 有効領域を検出（行・列ごとに内容の画素数を数えて端から枠を削る）
+pub fn find_active_area(view: &ImageView, options: &BorderOptions) -> Result<ActiveArea, ImageError> {
+    let (width, height) = (view.width(), view.height());
+    let format = view.format();
+    let bpp = view.bytes_per_pixel();
+
+    // 行・列ごとの内容の画素数（[0] は黒だけを枠とした場合、[1] は指定どおり）
+    let mut rows = [vec![0u32; height as usize], vec![0u32; height as usize]];
+    let mut columns = [vec![0u32; width as usize], vec![0u32; width as usize]];
+    let mut opaque = false;
+    for y in 0..height {
+        for (x, px) in view.row(y).chunks_exact(bpp).enumerate() {
+            let [r, g, b, a] = decode_pixel(px, format)?;
+            let black = r.max(g).max(b) <= options.tolerance;
+            let transparent = a <= options.tolerance;
+            opaque |= !transparent;
+            let content = match options.kind {
+                BorderKind::Black => !black,
+                BorderKind::Transparent => !transparent,
+                BorderKind::Any => !black && !transparent,
+            };
+            for (i, is_content) in [!black, content].into_iter().enumerate() {
+                rows[i][y as usize] += is_content as u32;
+                columns[i][x] += is_content as u32;
+            }
+        }
+    }
+    // Any でもアルファが全面 0（アルファを使わないセンダー）なら黒だけで判定
+    let pick = if options.kind == BorderKind::Any && !opaque { 0 } else { 1 };
+    let (rows, columns) = (&rows[pick], &columns[pick]);
+
+    let is_content = |count: u32, length: u32| count as f32 * 100.0 > length as f32 * options.max_content_percent;
+    let span = |counts: &[u32], length: u32| -> Option<(u32, u32)> {
+        let first = counts.iter().position(|&c| is_content(c, length))?;
+        let last = counts.iter().rposition(|&c| is_content(c, length))?;
+        Some((first as u32, last as u32))
+    };
+
+    let (Some((top, bottom)), Some((left, right))) = (span(rows, width), span(columns, height)) else {
+        return Ok(ActiveArea {
+            x: 0,
+            y: 0,
+            width: 0,
+            height: 0,
+            full_width: width,
+            full_height: height,
+            borders: Borders::default(),
+            layout: AreaLayout::Empty,
+            aspect_ratio: 0.0,
+            standard_ratio: None,
+        });
+    };
+
+    let borders = Borders { top, bottom: height - 1 - bottom, left, right: width - 1 - right };
+    let vertical = borders.top > 0 || borders.bottom > 0;
+    let horizontal = borders.left > 0 || borders.right > 0;
+    let layout = match (vertical, horizontal) {
+        (false, false) => AreaLayout::Full,
+        (true, false) => AreaLayout::Letterbox,
+        (false, true) => AreaLayout::Pillarbox,
+        (true, true) => AreaLayout::Windowbox,
+    };
+    let (area_width, area_height) = (right - left + 1, bottom - top + 1);
+    let aspect_ratio = area_width as f64 / area_height as f64;
+
+    Ok(ActiveArea {
+        x: left,
+        y: top,
+        width: area_width,
+        height: area_height,
+        full_width: width,
+        full_height: height,
+        borders,
+        layout,
+        aspect_ratio,
+        standard_ratio: standard_ratio(aspect_ratio),
+    })
+}
@@ -0,0 +1,45 @@
+// ============================================================
+// 受信画像の CPU 側処理（プラットフォーム非依存）
+// ============================================================
+//
+// DirectX / DLL に依存する部分は main.rs に置き、
+// ここには Linux でも動く画像処理とモックをまとめる（WIC だけは cfg(windows)）。
+
+pub mod alpha;
+pub mod avi;
+pub mod bmp;
+pub mod clip;
+pub mod color;
+pub mod compare;
+pub mod deflate;
+pub mod diagnostics;
+pub mod dump;
+pub mod export;
+pub mod exr;
+pub mod gif;
+pub mod ffi;
+pub mod hdr;
+pub mod image;
+pub mod import;
+pub mod jpeg;
+pub mod latency;
+pub mod layout;
+pub mod letterbox;
+pub mod mock;
+pub mod pattern;
+pub mod png;
+pub mod pnm;
+pub mod qoi;
+pub mod receiver;
+pub mod recorder;
+pub mod sender;
+pub mod session;
+pub mod shm;
+pub mod stats;
+pub mod stream;
+pub mod tearing;
+pub mod tga;
+pub mod transform;
+#[cfg(windows)]
+pub mod wic;
+pub mod yuv;
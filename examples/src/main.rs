@@ -1,14 +1,41 @@
-use std::ffi::{c_char, c_int, c_uint, c_void, CStr};
-use std::path::Path;
+use std::ffi::{c_char, c_int, c_long, c_uint, c_void, CStr, CString};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use ping::ffi::{SpoutDxResult, SpoutDxSenderInfo};
+use ping::alpha::AlphaOp;
+use ping::avi::AviWriter;
+use ping::clip::{simulate as simulate_clip, Clip, ClipFormat, ClipOptions};
+use ping::color::ColorSpace;
+use ping::compare::{compare, heatmap, AlphaCompare, CompareTolerances};
+use ping::diagnostics::{DiagnosticReport, DiagnosticThresholds};
+use ping::dump::{load_reference, save_dump, DumpMeta};
+use ping::export::{save_image_as, ImageFormat};
+use ping::exr::{save_exr, ExrOptions};
+use ping::hdr::{to_bgra8, Tonemap};
+use ping::letterbox::{find_active_area, BorderOptions};
+use ping::layout::{detect_layout, LayoutOptions};
+use ping::jpeg::{save_jpeg, JpegOptions};
+use ping::latency::{simulate, Clock, LatencyTracker, MonotonicClock, SyntheticFrame};
+use ping::mock::{MockReceiver, MockSender};
+use ping::pattern::{FrameStamp, Pattern, VerifyOptions};
+use ping::receiver::SenderInfo;
+use ping::recorder::{simulate as simulate_recording, FrameMeta, Recorder, RecorderOptions};
+use ping::sender::{executable_name, Sender, SenderBackend};
+use ping::png::{save_png, save_png_with, PngBitDepth, PngOptions};
+use ping::session::{ContentHasher, SessionAnalyzer, SessionOptions};
+use ping::stream::{FrameRate, StreamOptions, VideoStreamWriter};
+use ping::tearing::{simulate as simulate_tearing, RowBands, SendPattern, TearingTracker};
+use ping::stats::{compute_stats, render_ascii, render_image};
+use ping::image::{DxgiFormat, ImageBuf, ImageView};
+use ping::transform::{Rotation, Transform};
 use windows::{
-    core::PCWSTR,
+    core::Interface,
     Win32::Graphics::{
         Direct3D::*,
         Direct3D11::*,
         Dxgi::Common::*,
-        Imaging::*,
     },
-    Win32::System::Com::*,
 };
 
 // FFI declarations matching spoutdx_ffi.h
@@ -23,28 +50,6 @@ unsafe extern "C" {
 // Receiver API types
 type SpoutDxReceiverHandle = *mut c_void;
 
-#[repr(C)]
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-#[allow(dead_code)]
-enum SpoutDxResult {
-    Ok = 0,
-    ErrorNullHandle = -1,
-    ErrorNullDevice = -2,
-    ErrorNotConnected = -3,
-    ErrorInitFailed = -4,
-    ErrorReceiveFailed = -5,
-    ErrorInternal = -99,
-}
-
-#[repr(C)]
-#[derive(Debug)]
-struct SpoutDxSenderInfo {
-    name: [c_char; 256],
-    width: c_uint,
-    height: c_uint,
-    format: c_uint,
-}
-
 // Receiver API functions
 #[allow(dead_code)]
 unsafe extern "C" {
@@ -59,197 +64,1219 @@ unsafe extern "C" {
     fn spoutdx_receiver_get_dx11_context(handle: SpoutDxReceiverHandle) -> *mut c_void;
     fn spoutdx_receiver_release(handle: SpoutDxReceiverHandle) -> c_int;
     fn spoutdx_receiver_get_sender_info(handle: SpoutDxReceiverHandle, out_info: *mut SpoutDxSenderInfo) -> c_int;
+    fn spoutdx_receiver_get_sender_frame(handle: SpoutDxReceiverHandle, out_frame: *mut c_long) -> c_int;
+    fn spoutdx_receiver_get_sender_fps(handle: SpoutDxReceiverHandle, out_fps: *mut f64) -> c_int;
     fn spoutdx_receiver_is_updated(handle: SpoutDxReceiverHandle) -> c_int;
     fn spoutdx_receiver_is_connected(handle: SpoutDxReceiverHandle) -> c_int;
     fn spoutdx_receiver_is_frame_new(handle: SpoutDxReceiverHandle) -> c_int;
 }
 
+// Receiver transform API（受信ループで使うので dead_code を許可しない）
+unsafe extern "C" {
+    fn spoutdx_receiver_set_transform(handle: SpoutDxReceiverHandle, flags: c_uint) -> c_int;
+    fn spoutdx_receiver_get_transform(handle: SpoutDxReceiverHandle, out_flags: *mut c_uint) -> c_int;
+    fn spoutdx_receiver_receive_image(handle: SpoutDxReceiverHandle, pixels: *mut u8, width: c_uint, height: c_uint) -> c_int;
+}
+
+// Sender API types
+type SpoutDxSenderHandle = *mut c_void;
+
+// Sender API functions
+#[allow(dead_code)]
+unsafe extern "C" {
+    fn spoutdx_sender_create() -> SpoutDxSenderHandle;
+    fn spoutdx_sender_destroy(handle: SpoutDxSenderHandle) -> c_int;
+    fn spoutdx_sender_open_dx11(handle: SpoutDxSenderHandle, device: *mut c_void) -> c_int;
+    fn spoutdx_sender_close_dx11(handle: SpoutDxSenderHandle) -> c_int;
+    fn spoutdx_sender_set_name(handle: SpoutDxSenderHandle, sender_name: *const c_char) -> c_int;
+    fn spoutdx_sender_set_format(handle: SpoutDxSenderHandle, format: c_uint) -> c_int;
+    fn spoutdx_sender_send_texture(handle: SpoutDxSenderHandle, texture: *mut c_void) -> c_int;
+    fn spoutdx_sender_send_image(
+        handle: SpoutDxSenderHandle,
+        pixels: *const u8,
+        width: c_uint,
+        height: c_uint,
+        pitch: c_uint,
+    ) -> c_int;
+    fn spoutdx_sender_send_back_buffer(handle: SpoutDxSenderHandle) -> c_int;
+    fn spoutdx_sender_release(handle: SpoutDxSenderHandle) -> c_int;
+    fn spoutdx_sender_get_info(handle: SpoutDxSenderHandle, out_info: *mut SpoutDxSenderInfo) -> c_int;
+    fn spoutdx_sender_get_frame(handle: SpoutDxSenderHandle, out_frame: *mut c_long) -> c_int;
+    fn spoutdx_sender_get_fps(handle: SpoutDxSenderHandle, out_fps: *mut f64) -> c_int;
+    fn spoutdx_sender_is_initialized(handle: SpoutDxSenderHandle) -> c_int;
+}
+
+// 標準出力をストリームに使うとき、ログを標準エラーへ回すため
+#[link(name = "kernel32")]
+unsafe extern "system" {
+    fn SetStdHandle(std_handle: u32, handle: *mut c_void) -> c_int;
+}
+const STD_OUTPUT_HANDLE: u32 = -11i32 as u32;
+
+// ============================================================
+// コマンドライン引数
+// ============================================================
+
+/// `--mock` だけを指定したときの遅延計測のフレーム数（`--latency 300` と同じ）
+const DEFAULT_MOCK_LATENCY_FRAMES: u32 = 300;
+
+/// 受信画像の処理オプション
+#[derive(Debug, Default)]
+struct CliOptions {
+    transform: Transform,
+    tonemap: Tonemap,
+    assume_linear: bool,
+    alpha: AlphaOp,
+    export_alpha: AlphaOp,
+    thresholds: DiagnosticThresholds,
+    report_json: Option<PathBuf>,
+    histogram: bool,
+    stats_step: u32,
+    verify_pattern: Option<Pattern>,
+    latency_frames: u32,
+    mock: bool,
+    session_seconds: u32,
+    hash_grid: Option<u32>,
+    tearing_seconds: u32,
+    bands: RowBands,
+    compare: Option<PathBuf>,
+    compare_tolerances: CompareTolerances,
+    compare_alpha: AlphaCompare,
+    auto_correct: bool,
+    crop_active: bool,
+    png: PngOptions,
+    exr: ExrOptions,
+    output: Option<PathBuf>,
+    format: Option<ImageFormat>,
+    dump: Option<PathBuf>,
+    record_seconds: u32,
+    record: RecorderOptions,
+    stream_seconds: u32,
+    stream_output: Option<PathBuf>,
+    stream: StreamOptions,
+    jpeg: JpegOptions,
+    avi_seconds: u32,
+    avi_output: Option<PathBuf>,
+    clip_seconds: u32,
+    clip_output: Option<PathBuf>,
+    clip: ClipOptions,
+    send_seconds: u32,
+    send_name: Option<String>,
+    send_image: bool,
+    send_format: Option<DxgiFormat>,
+    send_pattern: SendPattern,
+}
+
+/// 引数を解析
+/// 例: `--flip --mirror --swap-rb --rotate 90 --tonemap reinhard --assume-linear --alpha opaque`
+///     `--thresholds black=1,white=254,alpha-low=128,layout=0.8,border=8 --report-json report.json`
+///     `--histogram --stats-step 4 --verify-pattern counter --auto-correct --crop-active`
+///     `--latency 300`（実機）/ `--latency 300 --mock`（合成した遅延、`--mock` だけなら 300 フレーム）
+///     `--session 10 --hash-grid 64`
+///     `--tearing 10 --bands 16`（実機）/ `--tearing 10 --mock`（合成したティアリング）
+///     `--compare golden.png --compare-tolerance psnr=40,ssim=0.98,max=8 --compare-alpha premultiplied`
+///     `--png depth=16,alpha=off,level=9`（既定は 8bit RGBA、レベル 6）/ `--png backend=wic`
+///     `--output capture.qoi`（拡張子で形式を選ぶ）/ `--format bmp`（png, bmp, ppm, pam, tga, qoi, exr, jpg）
+///     `--output capture.exr --exr type=float,compression=none`（既定は元の精度、ZIP 圧縮）
+///     `--record 10 --record-options dir=captures,every=2,policy=block,max=500M`（既定は QOI、キューが満杯なら捨てる）
+///     `--record 30 --record-options pre=5`（終了時に直前の 5 秒だけ保存）/ `--record 10 --mock`
+///     `--stream 60 --stream-output - --stream-options fps=60,resize=fit | ffmpeg -f yuv4mpegpipe -i - out.mp4`
+///     `--stream 10 --stream-output capture.bgra --stream-options format=raw,size=1280x720,resize=stretch`
+///     `--output capture.jpg --jpeg quality=90,subsampling=444`（既定は品質 85、4:2:0）
+///     `--avi 30 --avi-output session.avi --jpeg quality=75`（MJPEG、フレームレートは受信間隔から推定）
+///     `--clip 3 --clip-output bug.gif --clip-options max=480,fps=15`（拡張子で APNG / GIF、既定は長辺 640px・30 fps）
+///     `--clip 3 --mock`
+///     `--dump frame.json`（生のバイト列 frame.raw + サイドカー、`--compare` の基準にも使える）
+///     `--send 10 --send-name "ping pattern"`（受信の代わりにフレームカウンターを 60 fps で送信）/ `--send 3 --mock`
+///     `--send 10 --send-image --send-format 10`（テクスチャを使わず CPU の画素を送る、フォーマットは DXGI_FORMAT の数値）
+///     `--send 10 --send-pattern bands --bands 16`（別のプロセスの `--tearing 10 --bands 16` の送信側）
+fn parse_args(args: &[String]) -> CliOptions {
+    let mut options = CliOptions::default();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--flip" => options.transform.flip_vertical = true,
+            "--mirror" => options.transform.mirror_horizontal = true,
+            "--swap-rb" => options.transform.swap_rb = true,
+            "--rotate" => {
+                let degrees = iter.next().and_then(|v| v.parse().ok());
+                match degrees.and_then(Rotation::from_degrees) {
+                    Some(rotation) => options.transform.rotation = rotation,
+                    None => println!("Ignoring --rotate: expected 90, 180 or 270"),
+                }
+            }
+            "--assume-linear" => options.assume_linear = true,
+            "--tonemap" => match iter.next().map(|v| v.parse::<Tonemap>()) {
+                Some(Ok(tonemap)) => options.tonemap = tonemap,
+                Some(Err(e)) => println!("Ignoring --tonemap: {}", e),
+                None => println!("Ignoring --tonemap: missing value"),
+            },
+            "--alpha" | "--export-alpha" => match iter.next().map(|v| v.parse::<AlphaOp>()) {
+                Some(Ok(op)) if arg == "--alpha" => options.alpha = op,
+                Some(Ok(op)) => options.export_alpha = op,
+                Some(Err(e)) => println!("Ignoring {}: {}", arg, e),
+                None => println!("Ignoring {}: missing value", arg),
+            },
+            "--thresholds" => match iter.next().map(|v| v.parse::<DiagnosticThresholds>()) {
+                Some(Ok(thresholds)) => options.thresholds = thresholds,
+                Some(Err(e)) => println!("Ignoring --thresholds: {}", e),
+                None => println!("Ignoring --thresholds: missing value"),
+            },
+            "--histogram" => options.histogram = true,
+            "--auto-correct" => options.auto_correct = true,
+            "--crop-active" => options.crop_active = true,
+            "--latency" => match iter.next().and_then(|v| v.parse::<u32>().ok()) {
+                Some(frames) if frames > 0 => options.latency_frames = frames,
+                _ => println!("Ignoring --latency: expected a positive frame count"),
+            },
+            "--mock" => options.mock = true,
+            "--tearing" => match iter.next().and_then(|v| v.parse::<u32>().ok()) {
+                Some(seconds) if seconds > 0 => options.tearing_seconds = seconds,
+                _ => println!("Ignoring --tearing: expected a positive number of seconds"),
+            },
+            "--bands" => match iter.next().and_then(|v| v.parse::<u32>().ok()) {
+                Some(bands) if bands > 0 => options.bands = RowBands::new(bands),
+                _ => println!("Ignoring --bands: expected a positive integer"),
+            },
+            "--session" => match iter.next().and_then(|v| v.parse::<u32>().ok()) {
+                Some(seconds) if seconds > 0 => options.session_seconds = seconds,
+                _ => println!("Ignoring --session: expected a positive number of seconds"),
+            },
+            "--record" => match iter.next().and_then(|v| v.parse::<u32>().ok()) {
+                Some(seconds) if seconds > 0 => options.record_seconds = seconds,
+                _ => println!("Ignoring --record: expected a positive number of seconds"),
+            },
+            "--record-options" => match iter.next().map(|v| v.parse::<RecorderOptions>()) {
+                Some(Ok(record)) => options.record = record,
+                Some(Err(e)) => println!("Ignoring --record-options: {}", e),
+                None => println!("Ignoring --record-options: missing value"),
+            },
+            "--stream" => match iter.next().and_then(|v| v.parse::<u32>().ok()) {
+                Some(seconds) if seconds > 0 => options.stream_seconds = seconds,
+                _ => println!("Ignoring --stream: expected a positive number of seconds"),
+            },
+            "--stream-output" => match iter.next() {
+                Some(path) => options.stream_output = Some(PathBuf::from(path)),
+                None => println!("Ignoring --stream-output: missing value"),
+            },
+            "--stream-options" => match iter.next().map(|v| v.parse::<StreamOptions>()) {
+                Some(Ok(stream)) => options.stream = stream,
+                Some(Err(e)) => println!("Ignoring --stream-options: {}", e),
+                None => println!("Ignoring --stream-options: missing value"),
+            },
+            "--jpeg" => match iter.next().map(|v| v.parse::<JpegOptions>()) {
+                Some(Ok(jpeg)) => options.jpeg = jpeg,
+                Some(Err(e)) => println!("Ignoring --jpeg: {}", e),
+                None => println!("Ignoring --jpeg: missing value"),
+            },
+            "--avi" => match iter.next().and_then(|v| v.parse::<u32>().ok()) {
+                Some(seconds) if seconds > 0 => options.avi_seconds = seconds,
+                _ => println!("Ignoring --avi: expected a positive number of seconds"),
+            },
+            "--avi-output" => match iter.next() {
+                Some(path) => options.avi_output = Some(PathBuf::from(path)),
+                None => println!("Ignoring --avi-output: missing value"),
+            },
+            "--clip" => match iter.next().and_then(|v| v.parse::<u32>().ok()) {
+                Some(seconds) if seconds > 0 => options.clip_seconds = seconds,
+                _ => println!("Ignoring --clip: expected a positive number of seconds"),
+            },
+            "--clip-output" => match iter.next() {
+                Some(path) => options.clip_output = Some(PathBuf::from(path)),
+                None => println!("Ignoring --clip-output: missing value"),
+            },
+            "--clip-options" => match iter.next().map(|v| v.parse::<ClipOptions>()) {
+                Some(Ok(clip)) => options.clip = clip,
+                Some(Err(e)) => println!("Ignoring --clip-options: {}", e),
+                None => println!("Ignoring --clip-options: missing value"),
+            },
+            "--send" => match iter.next().and_then(|v| v.parse::<u32>().ok()) {
+                Some(seconds) if seconds > 0 => options.send_seconds = seconds,
+                _ => println!("Ignoring --send: expected a positive number of seconds"),
+            },
+            "--send-name" => match iter.next() {
+                Some(name) => options.send_name = Some(name.clone()),
+                None => println!("Ignoring --send-name: missing value"),
+            },
+            "--send-image" => options.send_image = true,
+            "--send-pattern" => match iter.next().map(|v| v.parse::<SendPattern>()) {
+                Some(Ok(pattern)) => options.send_pattern = pattern,
+                Some(Err(e)) => println!("Ignoring --send-pattern: {}", e),
+                None => println!("Ignoring --send-pattern: missing value"),
+            },
+            "--send-format" => {
+                let format = iter.next().and_then(|v| v.parse::<u32>().ok()).map(DxgiFormat::from_raw);
+                match format.filter(|format| format.bytes_per_pixel().is_some()) {
+                    Some(format) => options.send_format = Some(format),
+                    None => println!("Ignoring --send-format: expected a supported DXGI_FORMAT number"),
+                }
+            }
+            "--hash-grid" => match iter.next().and_then(|v| v.parse::<u32>().ok()) {
+                Some(grid) if grid > 0 => options.hash_grid = Some(grid),
+                _ => println!("Ignoring --hash-grid: expected a positive integer"),
+            },
+            "--verify-pattern" => match iter.next().map(|v| v.parse::<Pattern>()) {
+                Some(Ok(pattern)) => options.verify_pattern = Some(pattern),
+                Some(Err(e)) => println!("Ignoring --verify-pattern: {}", e),
+                None => println!("Ignoring --verify-pattern: missing value"),
+            },
+            "--stats-step" => match iter.next().and_then(|v| v.parse::<u32>().ok()) {
+                Some(step) if step > 0 => options.stats_step = step,
+                _ => println!("Ignoring --stats-step: expected a positive integer"),
+            },
+            "--compare" => match iter.next() {
+                Some(path) => options.compare = Some(PathBuf::from(path)),
+                None => println!("Ignoring --compare: missing value"),
+            },
+            "--compare-tolerance" => match iter.next().map(|v| v.parse::<CompareTolerances>()) {
+                Some(Ok(tolerances)) => options.compare_tolerances = tolerances,
+                Some(Err(e)) => println!("Ignoring --compare-tolerance: {}", e),
+                None => println!("Ignoring --compare-tolerance: missing value"),
+            },
+            "--compare-alpha" => match iter.next().map(|v| v.parse::<AlphaCompare>()) {
+                Some(Ok(alpha)) => options.compare_alpha = alpha,
+                Some(Err(e)) => println!("Ignoring --compare-alpha: {}", e),
+                None => println!("Ignoring --compare-alpha: missing value"),
+            },
+            "--png" => match iter.next().map(|v| v.parse::<PngOptions>()) {
+                Some(Ok(png)) => options.png = png,
+                Some(Err(e)) => println!("Ignoring --png: {}", e),
+                None => println!("Ignoring --png: missing value"),
+            },
+            "--exr" => match iter.next().map(|v| v.parse::<ExrOptions>()) {
+                Some(Ok(exr)) => options.exr = exr,
+                Some(Err(e)) => println!("Ignoring --exr: {}", e),
+                None => println!("Ignoring --exr: missing value"),
+            },
+            "--output" => match iter.next() {
+                Some(path) => options.output = Some(PathBuf::from(path)),
+                None => println!("Ignoring --output: missing value"),
+            },
+            "--format" => match iter.next().map(|v| v.parse::<ImageFormat>()) {
+                Some(Ok(format)) => options.format = Some(format),
+                Some(Err(e)) => println!("Ignoring --format: {}", e),
+                None => println!("Ignoring --format: missing value"),
+            },
+            "--dump" => match iter.next() {
+                Some(path) => options.dump = Some(PathBuf::from(path)),
+                None => println!("Ignoring --dump: missing value"),
+            },
+            "--report-json" => match iter.next() {
+                Some(path) => options.report_json = Some(PathBuf::from(path)),
+                None => println!("Ignoring --report-json: missing value"),
+            },
+            _ => {}
+        }
+    }
+    options
+}
+
 // ============================================================
 // 画像診断・ダンプ機能
 // ============================================================
 
-/// 平均色を計算（BGRA フォーマット）
-fn calculate_average_color(data: &[u8], width: u32, height: u32, row_pitch: u32) -> (f64, f64, f64, f64) {
-    let mut total_b: u64 = 0;
-    let mut total_g: u64 = 0;
-    let mut total_r: u64 = 0;
-    let mut total_a: u64 = 0;
-    let pixel_count = (width * height) as u64;
+/// キャプチャの保存先と形式（`--format` > `--output` の拡張子 > PNG）
+fn capture_target(options: &CliOptions) -> (PathBuf, ImageFormat) {
+    let format = options
+        .format
+        .or_else(|| options.output.as_deref().and_then(ImageFormat::from_path))
+        .unwrap_or_default();
+    let path = options
+        .output
+        .clone()
+        .unwrap_or_else(|| PathBuf::from(format!("spout_capture.{}", format.extension())));
+    (path, format)
+}
+
+/// 画像診断を実行してレポートを表示（`--report-json` 指定時は JSON も保存）
+fn diagnose_image(view: &ImageView, color_space: ColorSpace, options: &CliOptions) {
+    let report = match DiagnosticReport::analyze(view, color_space, options.thresholds) {
+        Ok(report) => report,
+        Err(e) => {
+            println!("\n  [Image Diagnostics] skipped: {}", e);
+            return;
+        }
+    };
+
+    println!();
+    for line in report.to_string().lines() {
+        println!("  {}", line);
+    }
 
-    for y in 0..height {
-        let row_start = (y * row_pitch) as usize;
-        for x in 0..width {
-            let pixel_offset = row_start + (x * 4) as usize;
-            total_b += data[pixel_offset] as u64;
-            total_g += data[pixel_offset + 1] as u64;
-            total_r += data[pixel_offset + 2] as u64;
-            total_a += data[pixel_offset + 3] as u64;
+    if let Some(path) = &options.report_json {
+        match std::fs::write(path, report.to_json()) {
+            Ok(()) => println!("  ✓ Saved diagnostic report: {}", path.display()),
+            Err(e) => println!("  ✗ Failed to save diagnostic report: {}", e),
         }
     }
+}
 
-    let avg_b = total_b as f64 / pixel_count as f64;
-    let avg_g = total_g as f64 / pixel_count as f64;
-    let avg_r = total_r as f64 / pixel_count as f64;
-    let avg_a = total_a as f64 / pixel_count as f64;
+/// レイアウトを推定し、確信度の高い項目だけ宣言どおりに直す
+fn auto_correct_layout(image: &mut ImageBuf, options: &CliOptions, sent_size: (u32, u32)) {
+    let layout_options = LayoutOptions { marker: options.verify_pattern, sent_size: Some(sent_size) };
+    let detection = match detect_layout(&image.view(), &layout_options) {
+        Ok(detection) => detection,
+        Err(e) => {
+            println!("  [Layout] skipped: {}", e);
+            return;
+        }
+    };
+    for line in detection.to_string().lines() {
+        println!("  {}", line);
+    }
 
-    (avg_r, avg_g, avg_b, avg_a)
+    let correction = detection.correction(options.thresholds.layout_confidence as f32);
+    if correction.is_identity() {
+        println!("    No correction needed");
+        return;
+    }
+    match correction.apply(image) {
+        Ok(()) => println!(
+            "    ✓ Corrected: {} -> {}, flip={}, alpha op: {}",
+            correction.from, correction.to, correction.flip_vertical, correction.alpha
+        ),
+        Err(e) => println!("    ✗ Failed to correct layout: {}", e),
+    }
 }
 
-/// 画像診断を実行
-fn diagnose_image(data: &[u8], width: u32, height: u32, row_pitch: u32) {
-    let (avg_r, avg_g, avg_b, avg_a) = calculate_average_color(data, width, height, row_pitch);
+/// 有効領域（黒・透明の枠を除いた矩形）だけを残す
+fn crop_to_active_area(image: &mut ImageBuf, options: &CliOptions) {
+    let border_options = BorderOptions { tolerance: (options.thresholds.border / 255.0) as f32, ..BorderOptions::default() };
+    let area = match find_active_area(&image.view(), &border_options) {
+        Ok(area) => area,
+        Err(e) => {
+            println!("  Failed to find active area: {}", e);
+            return;
+        }
+    };
+    if area.is_full() {
+        return;
+    }
+    match area.crop(&image.view()) {
+        Ok(view) => {
+            let cropped = view.to_image_buf();
+            println!("  Cropped to active area: {}", area);
+            *image = cropped;
+        }
+        Err(e) => println!("  Not cropping ({}): {}", area, e),
+    }
+}
 
-    println!("\n  [Image Diagnostics]");
-    println!("    Average color (RGBA): ({:.1}, {:.1}, {:.1}, {:.1})", avg_r, avg_g, avg_b, avg_a);
+/// ヒストグラムを表示し、RGB を重ねた画像を PNG で保存
+fn dump_histogram(view: &ImageView, step: u32, path: &Path) {
+    let stats = match compute_stats(view, step) {
+        Ok(stats) => stats,
+        Err(e) => {
+            println!("\n  [Histogram] skipped: {}", e);
+            return;
+        }
+    };
 
-    // 全黒チェック
-    if avg_r < 1.0 && avg_g < 1.0 && avg_b < 1.0 {
-        println!("    ⚠️  WARNING: Image appears to be ALL BLACK!");
+    println!("\n  [Histogram] {} of {} pixels (step {})",
+             stats.sampled_pixels, stats.width as u64 * stats.height as u64, stats.step);
+    for (name, channel) in ["R", "G", "B", "A", "Luma"].iter().zip(stats.channels.iter().chain([&stats.luminance])) {
+        println!("    {:<4} min={:.3} max={:.3} mean={:.3} stddev={:.3}",
+                 name, channel.min, channel.max, channel.mean, channel.stddev);
+    }
+    println!("    Clipped: black {:.2}%, white {:.2}%", stats.clipped_black_percent, stats.clipped_white_percent);
+    println!("    Luminance:");
+    for line in render_ascii(&stats.luminance, 64, 8).lines() {
+        println!("      |{}", line);
     }
 
-    // 全白チェック
-    if avg_r > 254.0 && avg_g > 254.0 && avg_b > 254.0 {
-        println!("    ⚠️  WARNING: Image appears to be ALL WHITE!");
+    match render_image(&stats, 128) {
+        Ok(image) => match save_png(path, &image.view()) {
+            Ok(()) => println!("    ✓ Saved histogram: {}", path.display()),
+            Err(e) => println!("    ✗ Failed to save histogram: {}", e),
+        },
+        Err(e) => println!("    ✗ Failed to render histogram: {}", e),
     }
+}
 
-    // アルファ誤りチェック（アルファが0または非常に低い）
-    if avg_a < 1.0 {
-        println!("    ⚠️  WARNING: Alpha channel is near ZERO - image may be fully transparent!");
-    } else if avg_a < 128.0 {
-        println!("    ⚠️  WARNING: Alpha channel average is low ({:.1}) - possible alpha issue", avg_a);
-    } else if avg_a > 254.0 {
-        println!("    ✓ Alpha channel is fully opaque (expected)");
+/// 基準画像（PNG・ダンプ）と比較して結果を表示し、差分ヒートマップを PNG で保存
+///
+/// 伝達関数とビット深度をそろえるため、どちらも 8bit sRGB の BGRA に変換してから比べる
+/// （リニアの FLOAT・16bit のまま保存するキャプチャや FLOAT のダンプもトーンマップして 8bit にする）。
+fn compare_with_reference(view: &ImageView, reference_path: &Path, options: &CliOptions, diff_path: &Path) {
+    fn as_bgra8(view: &ImageView, tonemap: Tonemap) -> Result<ImageBuf, ping::image::ImageError> {
+        match view.format() {
+            DxgiFormat::B8G8R8A8Unorm => Ok(view.to_image_buf()),
+            _ => to_bgra8(view, tonemap),
+        }
     }
 
-    // ガンマ/並べ替えミスチェック（極端な色偏り）
-    let color_diff_rg = (avg_r - avg_g).abs();
-    let color_diff_rb = (avg_r - avg_b).abs();
-    let color_diff_gb = (avg_g - avg_b).abs();
+    let reference = match load_reference(reference_path, DxgiFormat::B8G8R8A8Unorm) {
+        Ok(reference) => reference,
+        Err(e) => {
+            println!("\n  [Compare] Failed to load {}: {}", reference_path.display(), e);
+            return;
+        }
+    };
+    let (reference, image) = match (as_bgra8(&reference.view(), options.tonemap), as_bgra8(view, options.tonemap)) {
+        (Ok(reference), Ok(image)) => (reference, image),
+        (Err(e), _) | (_, Err(e)) => {
+            println!("\n  [Compare] Failed to convert to BGRA8: {}", e);
+            return;
+        }
+    };
+    let view = &image.view();
+    let comparison = match compare(&reference.view(), view, options.compare_alpha, options.compare_tolerances) {
+        Ok(comparison) => comparison,
+        Err(e) => {
+            println!("\n  [Compare] skipped: {}", e);
+            return;
+        }
+    };
+
+    println!();
+    for line in comparison.to_string().lines() {
+        println!("  {}", line);
+    }
 
-    // 極端に一色だけ違う場合は RGB/BGR 入れ替わりの可能性
-    if color_diff_rg > 100.0 || color_diff_rb > 100.0 || color_diff_gb > 100.0 {
-        println!("    ⚠️  NOTE: Large color channel imbalance detected");
-        println!("       This could indicate RGB/BGR swap or gamma issues");
-        println!("       R-G diff: {:.1}, R-B diff: {:.1}, G-B diff: {:.1}",
-                 color_diff_rg, color_diff_rb, color_diff_gb);
+    // 許容値の 2 倍で赤になるスケール
+    let scale = options.compare_tolerances.max_abs_error * 2.0;
+    match heatmap(&reference.view(), view, options.compare_alpha, scale) {
+        Ok(diff) => match save_png(diff_path, &diff.view()) {
+            Ok(()) => println!("    ✓ Saved diff heatmap: {}", diff_path.display()),
+            Err(e) => println!("    ✗ Failed to save diff heatmap: {}", e),
+        },
+        Err(e) => println!("    ✗ Failed to render diff heatmap: {}", e),
     }
+}
 
-    // サンプルピクセル表示（四隅と中心）
-    println!("\n    Sample pixels (BGRA format in memory):");
-    let show_pixel = |name: &str, x: u32, y: u32| {
-        let offset = (y * row_pitch + x * 4) as usize;
-        let b = data[offset];
-        let g = data[offset + 1];
-        let r = data[offset + 2];
-        let a = data[offset + 3];
-        println!("      {}: R={:3} G={:3} B={:3} A={:3}", name, r, g, b, a);
+/// ダンプに書くセンダー情報（フレーム数えをしていないセンダーは None）
+unsafe fn sender_meta(receiver: SpoutDxReceiverHandle, sender: &str) -> DumpMeta {
+    let mut frame: c_long = 0;
+    let mut fps = 0.0f64;
+    let (frame_ok, fps_ok) = unsafe {
+        (
+            spoutdx_receiver_get_sender_frame(receiver, &mut frame) == 0,
+            spoutdx_receiver_get_sender_fps(receiver, &mut fps) == 0,
+        )
     };
+    let frame = (frame_ok && frame > 0).then_some(frame as u64);
+    let fps = (fps_ok && fps > 0.0).then_some(fps);
+    DumpMeta { sender: Some(sender.to_string()).filter(|s| !s.is_empty()), frame, fps, timestamp_ms: None }
+}
+
+// ============================================================
+// 遅延計測
+// ============================================================
+
+/// フレームカウンターパターンを送るセンダーから `frames` 枚受信して遅延を集計
+///
+/// センダーは同じマシンで MonotonicClock の時刻を埋め込んでいること。
+/// 受信時刻は CPU へ読み出した直後（receive_image では受信と読み出しが 1 回の呼び出しのため）。
+unsafe fn measure_latency(receiver: SpoutDxReceiverHandle, device: &ID3D11Device, frames: u32, transform: Transform) {
+    let clock = MonotonicClock;
+    let mut tracker = LatencyTracker::new();
+    let mut reader = FrameReader::new(transform);
+    let mut received = 0;
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(frames as u64 / 10 + 10);
+
+    println!("\n  [Latency] Receiving {} frames...", frames);
+    unsafe {
+        while received < frames && std::time::Instant::now() < deadline {
+            let Some(image) = reader.next_frame(receiver, device) else {
+                std::thread::sleep(std::time::Duration::from_micros(500));
+                continue;
+            };
+            let received_us = clock.now_us();
+            match Pattern::read_stamp(&image.view()) {
+                Some(stamp) => tracker.record(stamp, received_us),
+                None => tracker.record_unreadable(),
+            }
+            received += 1;
+        }
+    }
 
-    show_pixel("Top-Left    ", 0, 0);
-    show_pixel("Top-Right   ", width - 1, 0);
-    show_pixel("Center      ", width / 2, height / 2);
-    show_pixel("Bottom-Left ", 0, height - 1);
-    show_pixel("Bottom-Right", width - 1, height - 1);
+    for line in tracker.summary().to_string().lines() {
+        println!("  {}", line);
+    }
 }
 
-/// WIC を使って PNG 形式で保存（BGRA -> RGBA 変換）
-fn save_as_png_wic(path: &Path, data: &[u8], width: u32, height: u32, row_pitch: u32) -> windows::core::Result<()> {
+/// `seconds` 秒間受信を繰り返し、内容のハッシュとカウンターの食い違いを解析
+///
+/// receive_image は新しいフレームでしか書き込まないため、内部テクスチャを毎回読み出す。
+unsafe fn analyse_session(
+    receiver: SpoutDxReceiverHandle,
+    device: &ID3D11Device,
+    seconds: u32,
+    hasher: ContentHasher,
+    transform: Transform,
+) {
+    let clock = MonotonicClock;
+    let mut analyzer = SessionAnalyzer::new(SessionOptions::default());
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(seconds as u64);
+
+    println!("\n  [Session] Observing for {} seconds...", seconds);
     unsafe {
-        // COM 初期化（既に初期化されている場合は S_FALSE が返るが、ok() で無視）
-        let _ = CoInitializeEx(None, COINIT_MULTITHREADED);
+        while std::time::Instant::now() < deadline {
+            std::thread::sleep(std::time::Duration::from_millis(1));
+            if spoutdx_receiver_receive(receiver) != 0 {
+                continue;
+            }
+            let counter_advanced = spoutdx_receiver_is_frame_new(receiver) != 0;
+            let timestamp_us = clock.now_us();
+            if let Some(image) = copy_received_image(receiver, device, transform) {
+                analyzer.observe(timestamp_us, counter_advanced, hasher.hash(&image.view()));
+            }
+        }
+    }
 
-        // WIC Factory 作成
-        let factory: IWICImagingFactory = CoCreateInstance(
-            &CLSID_WICImagingFactory,
-            None,
-            CLSCTX_INPROC_SERVER,
-        )?;
-
-        // ストリーム作成
-        let stream = factory.CreateStream()?;
-
-        // パスを PCWSTR に変換
-        let path_str = path.to_string_lossy().to_string();
-        let wide_path: Vec<u16> = path_str.encode_utf16().chain(std::iter::once(0)).collect();
-        const GENERIC_WRITE: u32 = 0x40000000;
-        stream.InitializeFromFilename(PCWSTR(wide_path.as_ptr()), GENERIC_WRITE)?;
-
-        // PNG エンコーダー作成
-        let encoder = factory.CreateEncoder(&GUID_ContainerFormatPng, std::ptr::null())?;
-        encoder.Initialize(&stream, WICBitmapEncoderNoCache)?;
-
-        // フレーム作成
-        let mut frame: Option<IWICBitmapFrameEncode> = None;
-        let mut property_bag: Option<windows::Win32::System::Com::StructuredStorage::IPropertyBag2> = None;
-        encoder.CreateNewFrame(&mut frame, &mut property_bag)?;
-        let frame = frame.unwrap();
-        frame.Initialize(property_bag.as_ref())?;
-        frame.SetSize(width, height)?;
-
-        // ピクセルフォーマット設定（WIC は要求を変更する可能性がある）
-        let requested_format = GUID_WICPixelFormat32bppRGBA;
-        let mut pixel_format = requested_format;
-        frame.SetPixelFormat(&mut pixel_format)?;
-
-        // WIC が実際に採用したフォーマットを確認
-        let format_name = if pixel_format == GUID_WICPixelFormat32bppRGBA {
-            "RGBA"
-        } else if pixel_format == GUID_WICPixelFormat32bppBGRA {
-            "BGRA"
-        } else {
-            "Unknown"
-        };
-        println!("  [WIC] Requested: RGBA, Actual: {}", format_name);
-
-        // 実際のフォーマットに応じてデータを準備
-        let write_data: Vec<u8> = if pixel_format == GUID_WICPixelFormat32bppBGRA {
-            // WIC が BGRA を要求 → そのまま渡す（変換不要）
-            println!("  [WIC] Using BGRA directly (no conversion)");
-            let mut bgra_data = vec![0u8; (width * height * 4) as usize];
-            for y in 0..height {
-                let src_row_start = (y * row_pitch) as usize;
-                let dst_row_start = (y * width * 4) as usize;
-                for x in 0..width {
-                    let src_offset = src_row_start + (x * 4) as usize;
-                    let dst_offset = dst_row_start + (x * 4) as usize;
-                    if src_offset + 3 < data.len() && dst_offset + 3 < bgra_data.len() {
-                        bgra_data[dst_offset..dst_offset + 4].copy_from_slice(&data[src_offset..src_offset + 4]);
+    for line in analyzer.finish().to_string().lines() {
+        println!("  {}", line);
+    }
+}
+
+/// `seconds` 秒間、新しいフレームを連番画像で保存（pre-trigger なら終了時に直前の分だけ書き出す）
+unsafe fn record_frames(
+    receiver: SpoutDxReceiverHandle,
+    device: &ID3D11Device,
+    seconds: u32,
+    options: &RecorderOptions,
+    transform: Transform,
+) {
+    let clock = MonotonicClock;
+    let mut reader = FrameReader::new(transform);
+    let mut recorder = match Recorder::start(options.clone()) {
+        Ok(recorder) => recorder,
+        Err(e) => {
+            println!("\n  [Recorder] Failed to start: {}", e);
+            return;
+        }
+    };
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(seconds as u64);
+
+    println!("\n  [Recorder] Recording for {} seconds: {}", seconds, options);
+    let mut frames = 0u64;
+    unsafe {
+        while std::time::Instant::now() < deadline {
+            std::thread::sleep(std::time::Duration::from_millis(1));
+            let Some(image) = reader.next_frame(receiver, device) else {
+                continue;
+            };
+            let timestamp_us = clock.now_us();
+            let mut info = std::mem::zeroed::<SpoutDxSenderInfo>();
+            spoutdx_receiver_get_sender_info(receiver, &mut info);
+            let sender = CStr::from_ptr(info.name.as_ptr()).to_string_lossy().into_owned();
+            // フレーム数えをしていないセンダーは受信した順の番号
+            frames += 1;
+            let frame = sender_meta(receiver, &sender).frame.unwrap_or(frames);
+            recorder.offer(image, FrameMeta { sender, frame, timestamp_us });
+        }
+    }
+    if !recorder.is_triggered() {
+        println!("  [Recorder] Writing pre-trigger buffer...");
+        recorder.trigger();
+    }
+
+    for line in recorder.finish().to_string().lines() {
+        println!("  {}", line);
+    }
+}
+
+/// ストリームの出力先（None / `-` は標準出力）
+fn open_stream_output(path: Option<&Path>) -> io::Result<Box<dyn Write>> {
+    match path {
+        Some(path) if path != Path::new("-") => Ok(Box::new(File::create(path)?)),
+        _ => Ok(Box::new(take_stdout()?)),
+    }
+}
+
+/// 元の標準出力を取り出し、以後の println! は標準エラーへ出す
+fn take_stdout() -> io::Result<File> {
+    use std::os::windows::io::{AsRawHandle, FromRawHandle};
+    io::stdout().flush()?;
+    let stdout = io::stdout().as_raw_handle();
+    let stderr = io::stderr().as_raw_handle();
+    // std の Stdout は書き込みのたびに GetStdHandle で取り直すので、差し替えが効く
+    if unsafe { SetStdHandle(STD_OUTPUT_HANDLE, stderr) } == 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(unsafe { File::from_raw_handle(stdout) })
+}
+
+/// `seconds` 秒間、新しいフレームを Y4M / 生の BGRA で書き出す（サイズ変更でエラーなら打ち切り）
+unsafe fn stream_frames(
+    receiver: SpoutDxReceiverHandle,
+    device: &ID3D11Device,
+    seconds: u32,
+    out: Box<dyn Write>,
+    options: &StreamOptions,
+    transform: Transform,
+) {
+    let mut options = *options;
+    let mut reader = FrameReader::new(transform);
+    if options.rate.is_none() {
+        options.rate = unsafe { sender_meta(receiver, "") }.fps.and_then(FrameRate::from_fps);
+    }
+    let mut writer = VideoStreamWriter::new(BufWriter::new(out), options);
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(seconds as u64);
+
+    println!("\n  [Stream] Streaming for {} seconds: {}", seconds, options);
+    unsafe {
+        while std::time::Instant::now() < deadline {
+            std::thread::sleep(std::time::Duration::from_millis(1));
+            let Some(image) = reader.next_frame(receiver, device) else {
+                continue;
+            };
+            if let Err(e) = writer.write_frame(&image.view()) {
+                println!("  [Stream] ✗ {}", e);
+                break;
+            }
+            if writer.frames() == 1
+                && let Some(args) = writer.ffmpeg_input_args()
+            {
+                println!("  [Stream] Reader input: ffmpeg {}", args);
+            }
+        }
+    }
+
+    let frames = writer.frames();
+    match writer.finish() {
+        Ok(_) => println!("  [Stream] {} frames written", frames),
+        Err(e) => println!("  [Stream] ✗ Failed to flush: {}", e),
+    }
+}
+
+/// `seconds` 秒間、新しいフレームを MJPEG の AVI に書き出す（フレームレートは受信時刻から推定）
+unsafe fn record_avi(receiver: SpoutDxReceiverHandle, device: &ID3D11Device, seconds: u32, path: &Path, options: &CliOptions) {
+    let clock = MonotonicClock;
+    let mut reader = FrameReader::new(options.transform);
+    let file = match File::create(path) {
+        Ok(file) => file,
+        Err(e) => {
+            println!("\n  [AVI] Failed to create {}: {}", path.display(), e);
+            return;
+        }
+    };
+    let mut writer = match AviWriter::new(BufWriter::new(file), options.jpeg) {
+        Ok(writer) => writer,
+        Err(e) => {
+            println!("\n  [AVI] Failed to write header: {}", e);
+            return;
+        }
+    };
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(seconds as u64);
+
+    println!("\n  [AVI] Recording for {} seconds to {} ({})", seconds, path.display(), options.jpeg);
+    unsafe {
+        while std::time::Instant::now() < deadline {
+            std::thread::sleep(std::time::Duration::from_millis(1));
+            let Some(mut image) = reader.next_frame(receiver, device) else {
+                continue;
+            };
+            let timestamp_us = clock.now_us();
+            if image.format() != DxgiFormat::B8G8R8A8Unorm {
+                match to_bgra8(&image.view(), options.tonemap) {
+                    Ok(bgra) => image = bgra,
+                    Err(e) => {
+                        println!("  [AVI] ✗ {}", e);
+                        break;
                     }
                 }
             }
-            bgra_data
-        } else {
-            // WIC が RGBA を要求 → BGRA から変換
-            println!("  [WIC] Converting BGRA -> RGBA");
-            let mut rgba_data = vec![0u8; (width * height * 4) as usize];
-        for y in 0..height {
-            let src_row_start = (y * row_pitch) as usize;
-            let dst_row_start = (y * width * 4) as usize;
-            for x in 0..width {
-                let src_offset = src_row_start + (x * 4) as usize;
-                let dst_offset = dst_row_start + (x * 4) as usize;
-
-                    if src_offset + 3 < data.len() && dst_offset + 3 < rgba_data.len() {
-                        // BGRA (memory) -> RGBA (PNG)
-                        rgba_data[dst_offset] = data[src_offset + 2];     // R
-                        rgba_data[dst_offset + 1] = data[src_offset + 1]; // G
-                        rgba_data[dst_offset + 2] = data[src_offset];     // B
-                        rgba_data[dst_offset + 3] = data[src_offset + 3]; // A
+            if let Err(e) = writer.write_image(&image.view(), timestamp_us) {
+                println!("  [AVI] ✗ {}", e);
+                break;
+            }
+        }
+    }
+
+    let frames = writer.frames();
+    let rate = writer.estimated_rate();
+    match writer.finish(None) {
+        Ok(_) => match rate {
+            Some(rate) => println!("  [AVI] {} frames written at {} ({:.3} fps)", frames, rate, rate.fps()),
+            None => println!("  [AVI] {} frames written (rate unknown, using 60 fps)", frames),
+        },
+        Err(e) => println!("  [AVI] ✗ Failed to finalize: {}", e),
+    }
+}
+
+/// クリップの保存先（`--clip-output` の拡張子が形式より優先）
+fn clip_target(options: &CliOptions) -> (PathBuf, ClipOptions) {
+    let mut clip = options.clip;
+    clip.tonemap = options.tonemap;
+    match &options.clip_output {
+        Some(path) => {
+            clip.format = ClipFormat::from_path(path).unwrap_or(clip.format);
+            (path.clone(), clip)
+        }
+        None => (PathBuf::from(format!("spout_clip.{}", clip.format.extension())), clip),
+    }
+}
+
+/// クリップを保存して結果を表示
+fn save_clip(clip: &Clip, path: &Path) {
+    let (width, height) = clip.size().unwrap_or((0, 0));
+    println!(
+        "  [Clip] {} frames ({} skipped), {}x{}, {:.2} s",
+        clip.len(),
+        clip.skipped(),
+        width,
+        height,
+        clip.duration_us() as f64 / 1_000_000.0
+    );
+    if clip.is_empty() {
+        println!("  [Clip] ✗ No frames captured");
+        return;
+    }
+    match clip.save(path) {
+        Ok(()) => println!("  [Clip] ✓ Saved {}: {}", clip.options().format, path.display()),
+        Err(e) => println!("  [Clip] ✗ Failed to save {}: {}", path.display(), e),
+    }
+}
+
+/// `seconds` 秒間、新しいフレームを溜めて APNG / GIF に保存
+unsafe fn capture_clip(receiver: SpoutDxReceiverHandle, device: &ID3D11Device, seconds: u32, options: &CliOptions) {
+    let clock = MonotonicClock;
+    let mut reader = FrameReader::new(options.transform);
+    let (path, clip_options) = clip_target(options);
+    let mut clip = Clip::new(clip_options);
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(seconds as u64);
+
+    println!("\n  [Clip] Capturing {} seconds: {}", seconds, clip_options);
+    unsafe {
+        while std::time::Instant::now() < deadline {
+            std::thread::sleep(std::time::Duration::from_millis(1));
+            let Some(image) = reader.next_frame(receiver, device) else {
+                continue;
+            };
+            let timestamp_us = clock.now_us();
+            if let Err(e) = clip.push(&image.view(), timestamp_us) {
+                println!("  [Clip] ✗ {}", e);
+                break;
+            }
+        }
+    }
+    save_clip(&clip, &path);
+}
+
+/// 受信済みの内部テクスチャを CPU へ読み出し、受信オプションの変換を適用する（読めなければ None）
+unsafe fn copy_received_image(
+    receiver: SpoutDxReceiverHandle,
+    device: &ID3D11Device,
+    transform: Transform,
+) -> Option<ImageBuf> {
+    unsafe {
+        let mut info = std::mem::zeroed::<SpoutDxSenderInfo>();
+        let texture_ptr = spoutdx_receiver_get_received_texture(receiver);
+        let context_ptr = spoutdx_receiver_get_dx11_context(receiver);
+        if spoutdx_receiver_get_sender_info(receiver, &mut info) != 0 || texture_ptr.is_null() || context_ptr.is_null() {
+            return None;
+        }
+        // 借用したポインタなので Release しない
+        let texture = std::mem::ManuallyDrop::new(std::mem::transmute::<_, ID3D11Texture2D>(texture_ptr));
+        let context = std::mem::ManuallyDrop::new(std::mem::transmute::<_, ID3D11DeviceContext>(context_ptr));
+
+        let (data, row_pitch) = copy_texture_to_cpu(device, &context, &texture, info.width, info.height).ok()?;
+        let format = DxgiFormat::from_raw(info.format);
+        let mut image = ImageBuf::from_vec(data, info.width, info.height, row_pitch as usize, format).ok()?;
+        transform.apply_in_place(&mut image).ok()?;
+        Some(image)
+    }
+}
+
+/// 新しいフレームだけを受け取るループの受信
+///
+/// 8bit の RGBA / BGRA は receive_image で受け、変換は DLL 側（set_transform で設定済み）で行う。
+/// それ以外の形式は ReceiveImage が扱えないので、内部テクスチャを読み出して同じ変換を Rust 側で適用する。
+struct FrameReader {
+    transform: Transform,
+    pixels: Vec<u8>,
+}
+
+impl FrameReader {
+    fn new(transform: Transform) -> Self {
+        Self { transform, pixels: Vec::new() }
+    }
+
+    /// 新しいフレームがあれば返す（未接続・センダー更新・新しいフレームなしは None）
+    unsafe fn next_frame(&mut self, receiver: SpoutDxReceiverHandle, device: &ID3D11Device) -> Option<ImageBuf> {
+        unsafe {
+            let mut info = std::mem::zeroed::<SpoutDxSenderInfo>();
+            // ReleaseReceiver の後はサイズが 0 に戻る
+            let connected =
+                spoutdx_receiver_get_sender_info(receiver, &mut info) == 0 && info.width > 0 && info.height > 0;
+            let format = DxgiFormat::from_raw(info.format);
+            if !connected || !receives_as_image(format) {
+                // 未接続のときもこちらで接続し、次の呼び出しで形式を見て経路を選ぶ
+                if spoutdx_receiver_receive(receiver) != 0 || spoutdx_receiver_is_frame_new(receiver) == 0 {
+                    return None;
+                }
+                return copy_received_image(receiver, device, self.transform);
+            }
+
+            // センダー更新（SENDER_UPDATED）なら次の呼び出しで新しいサイズを使う
+            let (width, height) = (info.width, info.height);
+            self.pixels.resize(width as usize * height as usize * 4, 0);
+            if spoutdx_receiver_receive_image(receiver, self.pixels.as_mut_ptr(), width, height) != 0
+                || spoutdx_receiver_is_frame_new(receiver) == 0
+            {
+                return None;
+            }
+            let (width, height) = self.transform.output_size(width, height);
+            ImageBuf::from_vec(self.pixels.clone(), width, height, width as usize * 4, format).ok()
+        }
+    }
+}
+
+/// receive_image（ReceiveImage、4 バイトのまま読み出す）で受け取れる形式か
+fn receives_as_image(format: DxgiFormat) -> bool {
+    matches!(
+        format,
+        DxgiFormat::R8G8B8A8Unorm | DxgiFormat::R8G8B8A8UnormSrgb | DxgiFormat::B8G8R8A8Unorm | DxgiFormat::B8G8R8A8UnormSrgb
+    )
+}
+
+/// `seconds` 秒間、行帯パターンを送るセンダーから受信してティアリングを数える
+///
+/// 書き込み途中のテクスチャを捕まえるため、新しいフレームかどうかに関係なく毎回読み出す。
+unsafe fn inspect_tearing(
+    receiver: SpoutDxReceiverHandle,
+    device: &ID3D11Device,
+    seconds: u32,
+    bands: RowBands,
+    transform: Transform,
+) {
+    let mut tracker = TearingTracker::new(bands);
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(seconds as u64);
+
+    println!("\n  [Tearing] Observing {} bands for {} seconds...", bands.bands(), seconds);
+    unsafe {
+        while std::time::Instant::now() < deadline {
+            if spoutdx_receiver_receive(receiver) != 0 {
+                std::thread::sleep(std::time::Duration::from_millis(1));
+                continue;
+            }
+            let Some(image) = copy_received_image(receiver, device, transform) else {
+                continue;
+            };
+            match bands.inspect(&image.view()) {
+                Ok(report) => {
+                    if report.is_torn() {
+                        for line in report.to_string().lines() {
+                            println!("  {}", line);
+                        }
                     }
+                    tracker.record(&report, image.height());
+                }
+                Err(e) => {
+                    println!("  [Tearing] skipped: {}", e);
+                    return;
                 }
             }
-            rgba_data
-        };
+        }
+    }
+
+    for line in tracker.summary().to_string().lines() {
+        println!("  {}", line);
+    }
+}
 
-        // 書き込み
-        frame.WritePixels(height, width * 4, &write_data)?;
-        frame.Commit()?;
-        encoder.Commit()?;
+/// 合成したティアリング（60 fps で 7 フレームに 1 回、位置は毎回変える）でモック検査
+fn inspect_tearing_mock(seconds: u32, bands: RowBands) {
+    let (width, height) = (256, 144);
+    let tears: Vec<Option<u32>> = (0..seconds * 60)
+        .map(|i| (i % 7 == 6).then_some(1 + (i * 37) % (height - 1)))
+        .collect();
+
+    println!("[Tearing] Mock mode: {} synthetic frames, {} bands", tears.len(), bands.bands());
+    match simulate_tearing(&tears, bands, width, height) {
+        Ok(tracker) => print!("{}", tracker.summary()),
+        Err(e) => println!("  Simulation failed: {}", e),
+    }
+}
+
+/// 60 fps のフレームカウンターパターンをモックで録画（pre-trigger なら最後のフレームでトリガー）
+fn record_frames_mock(seconds: u32, options: &RecorderOptions) {
+    let frames = seconds as u64 * 60;
+    let trigger_at = options.pre_trigger_us.map(|_| frames - 1);
+    println!("[Recorder] Mock mode: {} synthetic frames at 60 fps: {}", frames, options);
+    let recorder = match Recorder::start(options.clone()) {
+        Ok(recorder) => recorder,
+        Err(e) => {
+            println!("  Failed to start: {}", e);
+            return;
+        }
+    };
+    match simulate_recording(recorder, frames, 16_667, 256, 144, trigger_at) {
+        Ok(report) => print!("{}", report),
+        Err(e) => println!("  Simulation failed: {}", e),
+    }
+}
+
+/// 60 fps のフレームカウンターパターンをモックでクリップに保存
+fn capture_clip_mock(seconds: u32, options: &CliOptions) {
+    let (path, clip_options) = clip_target(options);
+    println!("[Clip] Mock mode: {} synthetic frames at 60 fps: {}", seconds * 60, clip_options);
+    match simulate_clip(clip_options, seconds as u64 * 60, 16_667, 256, 144) {
+        Ok(clip) => save_clip(&clip, &path),
+        Err(e) => println!("  Simulation failed: {}", e),
+    }
+}
 
-        // COM は自動的に解放される（IUnknown の Drop）
+/// 合成した遅延（10-20ms、5% の欠落、2% の重複）でモック計測
+fn measure_latency_mock(frames: u32) {
+    let synthetic: Vec<SyntheticFrame> = (0..frames as u64)
+        .map(|i| SyntheticFrame {
+            delay_us: 10_000 + (i * 7919) % 10_000,
+            dropped: i % 20 == 19,
+            reads: if i % 50 == 49 { 2 } else { 1 },
+        })
+        .collect();
+
+    println!("[Latency] Mock mode: {} synthetic frames at 60 fps", frames);
+    match simulate(&synthetic, 16_667, 256, 144) {
+        Ok(tracker) => print!("{}", tracker.summary()),
+        Err(e) => println!("  Simulation failed: {}", e),
+    }
+}
+
+// ============================================================
+// センダー
+// ============================================================
 
+fn check(code: c_int) -> Result<(), SpoutDxResult> {
+    match SpoutDxResult::from_code(code) {
+        SpoutDxResult::Ok => Ok(()),
+        error => Err(error),
+    }
+}
+
+/// DLL のセンダー（`Sender` のバックエンド）
+struct DllSender {
+    handle: SpoutDxSenderHandle,
+    format: DxgiFormat,
+}
+
+impl DllSender {
+    /// センダーを作成し、`device` で初期化
+    fn open(device: &ID3D11Device) -> Result<Self, SpoutDxResult> {
+        let handle = unsafe { spoutdx_sender_create() };
+        if handle.is_null() {
+            return Err(SpoutDxResult::ErrorInternal);
+        }
+        let sender = Self { handle, format: DxgiFormat::B8G8R8A8Unorm };
+        check(unsafe { spoutdx_sender_open_dx11(handle, device.as_raw()) })?;
+        Ok(sender)
+    }
+}
+
+impl SenderBackend for DllSender {
+    type Texture = ID3D11Texture2D;
+
+    fn set_name(&mut self, name: Option<&str>) -> Result<(), SpoutDxResult> {
+        let name = name.map(CString::new).transpose().map_err(|_| SpoutDxResult::ErrorInternal)?;
+        let name_ptr = name.as_ref().map_or(std::ptr::null(), |name| name.as_ptr());
+        check(unsafe { spoutdx_sender_set_name(self.handle, name_ptr) })
+    }
+
+    fn set_format(&mut self, format: DxgiFormat) -> Result<(), SpoutDxResult> {
+        check(unsafe { spoutdx_sender_set_format(self.handle, format.raw()) })?;
+        self.format = format;
         Ok(())
     }
+
+    /// 初期化後は DLL の値（`send_texture` でテクスチャのフォーマットに変わる）
+    fn format(&self) -> DxgiFormat {
+        self.info().map_or(self.format, |info| info.format)
+    }
+
+    fn send_texture(&mut self, texture: &ID3D11Texture2D) -> Result<(), SpoutDxResult> {
+        check(unsafe { spoutdx_sender_send_texture(self.handle, texture.as_raw()) })
+    }
+
+    fn send_image(&mut self, image: &ImageView) -> Result<(), SpoutDxResult> {
+        let pitch = u32::try_from(image.row_pitch()).map_err(|_| SpoutDxResult::ErrorInternal)?;
+        check(unsafe {
+            spoutdx_sender_send_image(self.handle, image.data().as_ptr(), image.width(), image.height(), pitch)
+        })
+    }
+
+    fn release(&mut self) -> Result<(), SpoutDxResult> {
+        check(unsafe { spoutdx_sender_release(self.handle) })
+    }
+
+    fn info(&self) -> Option<SenderInfo> {
+        unsafe {
+            let mut info = std::mem::zeroed::<SpoutDxSenderInfo>();
+            check(spoutdx_sender_get_info(self.handle, &mut info)).ok()?;
+            Some(SenderInfo {
+                name: CStr::from_ptr(info.name.as_ptr()).to_string_lossy().into_owned(),
+                width: info.width,
+                height: info.height,
+                format: DxgiFormat::from_raw(info.format),
+            })
+        }
+    }
+
+    fn frame(&self) -> u64 {
+        let mut frame: c_long = 0;
+        unsafe { spoutdx_sender_get_frame(self.handle, &mut frame) };
+        frame.max(0) as u64
+    }
+
+    fn fps(&self) -> f64 {
+        let mut fps = 0.0;
+        unsafe { spoutdx_sender_get_fps(self.handle, &mut fps) };
+        fps
+    }
+}
+
+impl Drop for DllSender {
+    fn drop(&mut self) {
+        unsafe { spoutdx_sender_destroy(self.handle) };
+    }
+}
+
+/// 名前とフォーマットを設定（名前が None なら実行ファイル名）
+fn configure_sender<B: SenderBackend>(sender: &mut Sender<B>, options: &CliOptions) -> Result<(), SpoutDxResult> {
+    match options.send_name.as_deref() {
+        Some(name) => sender.set_name(name)?,
+        None => sender.set_default_name()?,
+    }
+    match options.send_format {
+        Some(format) => sender.set_format(format),
+        None => Ok(()),
+    }
+}
+
+/// `--send-pattern` のパターンを 60 fps で送信（フレームカウンターには MonotonicClock の時刻を埋め込む）
+///
+/// `--send-image` なら CPU の画素をそのまま送り、なければテクスチャにアップロードして送る。
+fn send_pattern(device: &ID3D11Device, context: &ID3D11DeviceContext, options: &CliOptions) {
+    let (width, height) = (640, 360);
+    let frames = options.send_seconds as u64 * 60;
+    let path = if options.send_image { "CPU image" } else { "texture" };
+    println!("\n  [Send] Sending {} frames of the {} pattern at 60 fps ({})...", frames, options.send_pattern, path);
+
+    let mut sender = match DllSender::open(device) {
+        Ok(backend) => Sender::new(backend),
+        Err(e) => {
+            println!("  Failed to open sender: {:?}", e);
+            return;
+        }
+    };
+    if let Err(e) = configure_sender(&mut sender, options) {
+        println!("  Failed to configure sender: {:?}", e);
+        return;
+    }
+    let texture = if options.send_image {
+        None
+    } else {
+        match create_render_texture(device, width, height) {
+            Some(texture) => Some(texture),
+            None => {
+                println!("  Failed to create texture");
+                return;
+            }
+        }
+    };
+
+    let clock = MonotonicClock;
+    let start = std::time::Instant::now();
+    for frame in 0..frames {
+        let stamp = FrameStamp { frame, timestamp_us: clock.now_us() };
+        let generated = options.send_pattern.generate(options.bands, width, height, DxgiFormat::B8G8R8A8Unorm, stamp);
+        let image = match generated {
+            Ok(image) => image,
+            Err(e) => {
+                println!("  Failed to generate pattern: {}", e);
+                return;
+            }
+        };
+        let sent = match &texture {
+            Some(texture) => {
+                unsafe {
+                    let pixels = image.data().as_ptr() as *const c_void;
+                    context.UpdateSubresource(texture, 0, None, pixels, image.row_pitch() as u32, 0);
+                }
+                sender.send_texture(texture)
+            }
+            // センダーのフォーマットと違えば Sender が変換する
+            None => sender.send_image(&image.view()),
+        };
+        if let Err(e) = sent {
+            println!("  Frame {}: send failed: {:?}", frame, e);
+            return;
+        }
+        if frame == 0
+            && let Some(info) = sender.info()
+        {
+            println!("  Sending as \"{}\" ({}x{} {})", info.name, info.width, info.height, info.format);
+        }
+        // 次のフレームの予定時刻まで待つ
+        let next = std::time::Duration::from_micros((frame + 1) * 16_667);
+        if let Some(wait) = next.checked_sub(start.elapsed()) {
+            std::thread::sleep(wait);
+        }
+    }
+    println!("  Sent {} frames ({:.2} fps)", sender.frame(), sender.fps());
+}
+
+/// `--send-pattern` のパターンを MockSender から MockReceiver へ送り、受け取った番号を確かめる
+fn send_pattern_mock(options: &CliOptions) {
+    let (width, height) = (256, 144);
+    let frames = options.send_seconds as u64 * 60;
+    let path = if options.send_image { "CPU image" } else { "texture" };
+    println!("[Send] Mock mode: {} frames of the {} pattern at 60 fps ({})", frames, options.send_pattern, path);
+
+    let mut sender = Sender::new(MockSender::new());
+    if let Err(e) = configure_sender(&mut sender, options) {
+        println!("  Failed to configure sender: {:?}", e);
+        return;
+    }
+    let mut receiver = MockReceiver::new(&options.send_name.clone().unwrap_or_else(executable_name));
+    let mut mismatches = 0;
+    for frame in 0..frames {
+        let stamp = FrameStamp { frame, timestamp_us: frame * 16_667 };
+        let sent = options
+            .send_pattern
+            .generate(options.bands, width, height, DxgiFormat::B8G8R8A8Unorm, stamp)
+            .map_err(|e| e.to_string())
+            .and_then(|image| {
+                let sent = if options.send_image {
+                    sender.send_image(&image.view())
+                } else {
+                    sender.send_texture(&image)
+                };
+                sent.map_err(|e| format!("{:?}", e))
+            });
+        if let Err(e) = sent {
+            println!("  Frame {}: send failed: {}", frame, e);
+            return;
+        }
+        sender.backend_mut().deliver(&mut receiver);
+        let Ok(image) = receiver.receive().and_then(|()| receiver.receive_image()) else {
+            mismatches += 1;
+            continue;
+        };
+        let matched = match options.send_pattern {
+            SendPattern::FrameCounter => Pattern::read_stamp(&image.view()) == Some(stamp),
+            SendPattern::RowBands => options
+                .bands
+                .inspect(&image.view())
+                .is_ok_and(|report| !report.is_torn() && report.frame() == Some(frame)),
+        };
+        if !matched {
+            mismatches += 1;
+        }
+    }
+    if let Some(info) = sender.info() {
+        println!("  Sender \"{}\": {}x{} {}", info.name, info.width, info.height, info.format);
+    }
+    println!("  Sent {} frames ({:.2} fps), {} mismatched on the receiver", sender.frame(), sender.fps(), mismatches);
+
+    // release で受信側は未接続になる
+    let _ = sender.release();
+    sender.backend_mut().deliver(&mut receiver);
+    match receiver.receive() {
+        Err(SpoutDxResult::ErrorNotConnected) => println!("  ✓ Receiver disconnected after release"),
+        other => println!("  ✗ Receiver still connected after release: {:?}", other),
+    }
 }
 
 /// Staging テクスチャを作成
@@ -294,12 +1321,24 @@ fn read_texture_to_cpu(
         source_texture.GetDesc(&mut src_desc);
         let format = src_desc.Format;
 
-        let format_name = match format.0 {
-            87 => "DXGI_FORMAT_B8G8R8A8_UNORM (BGRA)",
-            28 => "DXGI_FORMAT_R8G8B8A8_UNORM (RGBA)",
-            _ => "Unknown",
-        };
+        let format_name = DxgiFormat::from_raw(format.0 as u32).name();
         println!("  [D3D11] Source texture format: {} ({})", format.0, format_name);
+    }
+    copy_texture_to_cpu(device, context, source_texture, width, height)
+}
+
+/// テクスチャから CPU メモリへコピー（ログなし、毎フレーム呼ぶ用）
+fn copy_texture_to_cpu(
+    device: &ID3D11Device,
+    context: &ID3D11DeviceContext,
+    source_texture: &ID3D11Texture2D,
+    width: u32,
+    height: u32,
+) -> windows::core::Result<(Vec<u8>, u32)> {
+    unsafe {
+        let mut src_desc = std::mem::zeroed::<D3D11_TEXTURE2D_DESC>();
+        source_texture.GetDesc(&mut src_desc);
+        let format = src_desc.Format;
 
         // Staging テクスチャ作成（同じフォーマットで）
         let staging = create_staging_texture(device, width, height, format)?;
@@ -330,6 +1369,49 @@ fn read_texture_to_cpu(
 }
 
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let mut options = parse_args(&args);
+    let transform = options.transform;
+    options.stream.tonemap = options.tonemap;
+
+    if options.mock {
+        if options.tearing_seconds > 0 {
+            inspect_tearing_mock(options.tearing_seconds, options.bands);
+        }
+        if options.record_seconds > 0 {
+            record_frames_mock(options.record_seconds, &options.record);
+        }
+        if options.clip_seconds > 0 {
+            capture_clip_mock(options.clip_seconds, &options);
+        }
+        if options.send_seconds > 0 {
+            send_pattern_mock(&options);
+        }
+        let ran_mock = options.tearing_seconds > 0
+            || options.record_seconds > 0
+            || options.clip_seconds > 0
+            || options.send_seconds > 0;
+        if options.latency_frames > 0 {
+            measure_latency_mock(options.latency_frames);
+        } else if !ran_mock {
+            // モードの指定がなければ既定のフレーム数で遅延計測のモックを動かす
+            measure_latency_mock(DEFAULT_MOCK_LATENCY_FRAMES);
+        }
+        return;
+    }
+
+    // 受信してストリームを書くときだけ、ログより先に出力先を開く（標準出力ならログは以後すべて標準エラーへ）
+    let mut stream_out = None;
+    if options.stream_seconds > 0 && options.send_seconds == 0 {
+        match open_stream_output(options.stream_output.as_deref()) {
+            Ok(out) => stream_out = Some(out),
+            Err(e) => {
+                eprintln!("Failed to open stream output: {}", e);
+                return;
+            }
+        }
+    }
+
     unsafe {
         // ============================================================
         // Existing API tests
@@ -391,9 +1473,16 @@ fn main() {
         }
 
         let device = device.unwrap();
-        let _context = context.unwrap(); // Rust側のコンテキストは使わない（SpoutDXのコンテキストを使う）
+        let context = context.unwrap(); // 受信では使わない（SpoutDXのコンテキストを使う）、送信のアップロード用
         println!("  D3D11 device created successfully");
 
+        if options.send_seconds > 0 {
+            send_pattern(&device, &context, &options);
+            println!("\n========================================");
+            println!("Sender API test completed.");
+            return;
+        }
+
         // Create receiver
         println!("  Creating receiver...");
         let receiver = spoutdx_receiver_create();
@@ -414,6 +1503,20 @@ fn main() {
         }
         println!("  Receiver initialized successfully");
 
+        // 受信ループの receive_image に使う変換（8bit 以外と単発のキャプチャは Rust 側で同じ変換を適用）
+        if !transform.is_identity() {
+            let mut flags = 0;
+            let applied = spoutdx_receiver_set_transform(receiver, transform.to_flags()) == 0
+                && spoutdx_receiver_get_transform(receiver, &mut flags) == 0
+                && Transform::from_flags(flags) == Some(transform);
+            if !applied {
+                println!("  Failed to set receiver transform: {:?}", transform);
+                spoutdx_receiver_destroy(receiver);
+                return;
+            }
+            println!("  Receiver transform: {:?} (flags 0x{:02X})", transform, flags);
+        }
+
         // ============================================================
         // Spout 受信ループ（新方式：内部テクスチャ使用）
         // SpoutDX の受信フロー:
@@ -428,6 +1531,8 @@ fn main() {
 
         let mut current_width = 0u32;
         let mut current_height = 0u32;
+        let mut current_format = DxgiFormat::B8G8R8A8Unorm;
+        let mut current_sender = String::new();
 
         // 受信ループ（最大10回試行）
         let mut frame_received = false;
@@ -460,6 +1565,8 @@ fn main() {
                     println!("    Format: {} (DXGI_FORMAT)", info.format);
                     current_width = info.width;
                     current_height = info.height;
+                    current_format = DxgiFormat::from_raw(info.format);
+                    current_sender = sender_name.into_owned();
                 }
             }
 
@@ -522,15 +1629,121 @@ fn main() {
             Ok((data, row_pitch)) => {
                 println!("  CPU copy successful (row_pitch: {})", row_pitch);
 
-                // 画像診断
-                diagnose_image(&data, current_width, current_height, row_pitch);
+                let mut image = match ImageBuf::from_vec(data, current_width, current_height, row_pitch as usize, current_format) {
+                    Ok(image) => image,
+                    Err(e) => {
+                        println!("  Invalid image data: {}", e);
+                        spoutdx_receiver_destroy(receiver);
+                        return;
+                    }
+                };
+
+                // 変換前の生のバイト列をそのまま保存（センダーなしで再現するため）
+                if let Some(path) = &options.dump {
+                    let meta = sender_meta(receiver, &current_sender);
+                    match save_dump(path, &image.view(), &meta) {
+                        Ok(sidecar) => println!("  ✓ Saved raw dump: {}", sidecar.display()),
+                        Err(e) => println!("  ✗ Failed to save raw dump: {}", e),
+                    }
+                }
+
+                // チャンネル順・上下・乗算済みの自動補正（--verify-pattern のパターンを目印に使う）
+                if options.auto_correct {
+                    auto_correct_layout(&mut image, &options, (current_width, current_height));
+                }
+
+                // 受信オプションの変換（上下反転・左右反転・R/B 入れ替え・回転）
+                if !transform.is_identity() {
+                    match transform.apply_in_place(&mut image) {
+                        Ok(()) => println!("  Applied transform: {:?} -> {}x{}", transform, image.width(), image.height()),
+                        Err(e) => println!("  Failed to apply transform: {}", e),
+                    }
+                }
+
+                // 受信オプションのアルファ操作
+                if options.alpha != AlphaOp::None {
+                    match options.alpha.apply(&mut image) {
+                        Ok(()) => println!("  Applied alpha op: {}", options.alpha),
+                        Err(e) => println!("  Failed to apply alpha op: {}", e),
+                    }
+                }
 
-                // PNG 保存
-                let output_dir = Path::new(".");
-                let png_path = output_dir.join("spout_capture.png");
-                match save_as_png_wic(&png_path.as_path(), &data, current_width, current_height, row_pitch) {
-                    Ok(()) => println!("\n  ✓ Saved PNG: {} (RGBA with alpha)", png_path.display()),
-                    Err(e) => println!("\n  ✗ Failed to save PNG: {:?}", e),
+                // 画像診断（元のフォーマットのまま、リニア光で平均）
+                let color_space = ColorSpace::for_format(image.format(), options.assume_linear);
+                diagnose_image(&image.view(), color_space, &options);
+                // テストパターンとの比較（送信サイズはセンダー情報のもの）
+                if let Some(pattern) = options.verify_pattern {
+                    match pattern.verify(&image.view(), current_width, current_height, &VerifyOptions::default()) {
+                        Ok(verification) => {
+                            println!();
+                            for line in verification.to_string().lines() {
+                                println!("  {}", line);
+                            }
+                        }
+                        Err(e) => println!("\n  [Pattern Verification] skipped: {}", e),
+                    }
+                }
+                if options.histogram {
+                    dump_histogram(&image.view(), options.stats_step, Path::new("spout_histogram.png"));
+                }
+
+                // 8bit の形式はトーンマップしてから BGRA8 で渡す
+                // （EXR はリニアのまま、16bit PNG は元の精度のまま 1.0 超えを切り詰め）
+                let (capture_path, capture_format) = capture_target(&options);
+                let sixteen_bit = capture_format == ImageFormat::Png && options.png.bit_depth == PngBitDepth::Sixteen;
+                let keep_precision = sixteen_bit || capture_format.is_high_precision();
+                if !keep_precision && image.format() != DxgiFormat::B8G8R8A8Unorm {
+                    match to_bgra8(&image.view(), options.tonemap) {
+                        Ok(bgra) => {
+                            println!("  Converted {} -> BGRA8 (tonemap: {})", image.format(), options.tonemap);
+                            image = bgra;
+                        }
+                        Err(e) => {
+                            println!("  Failed to convert to BGRA8: {}", e);
+                            spoutdx_receiver_destroy(receiver);
+                            return;
+                        }
+                    }
+                }
+
+                // 出力時のアルファ操作
+                if options.export_alpha != AlphaOp::None {
+                    match options.export_alpha.apply(&mut image) {
+                        Ok(()) => println!("  Applied export alpha op: {}", options.export_alpha),
+                        Err(e) => println!("  Failed to apply export alpha op: {}", e),
+                    }
+                }
+
+                // 黒・透明の枠を除いて出力
+                if options.crop_active {
+                    crop_to_active_area(&mut image, &options);
+                }
+
+                // 基準画像との比較（保存する画像を 8bit の BGRA にそろえて比較）
+                if let Some(reference_path) = &options.compare {
+                    compare_with_reference(&image.view(), reference_path, &options, Path::new("spout_diff.png"));
+                }
+
+                // 保存（PNG / EXR はそれぞれの設定、それ以外は 8bit）
+                let exr_options = ExrOptions { assume_linear: options.assume_linear, ..options.exr };
+                let saved = match capture_format {
+                    ImageFormat::Png => save_png_with(&capture_path, &image.view(), &options.png),
+                    ImageFormat::Exr => save_exr(&capture_path, &image.view(), &exr_options),
+                    ImageFormat::Jpeg => save_jpeg(&capture_path, &image.view(), &options.jpeg),
+                    format => save_image_as(&capture_path, &image.view(), format),
+                };
+                match saved {
+                    Ok(()) if capture_format == ImageFormat::Png => {
+                        println!("\n  ✓ Saved PNG: {} ({})", capture_path.display(), options.png)
+                    }
+                    Ok(()) if capture_format == ImageFormat::Exr => {
+                        println!("\n  ✓ Saved EXR: {} ({})", capture_path.display(), exr_options)
+                    }
+                    Ok(()) if capture_format == ImageFormat::Jpeg => {
+                        println!("\n  ✓ Saved JPEG: {} ({})", capture_path.display(), options.jpeg)
+                    }
+                    Ok(()) => println!("\n  ✓ Saved {}: {}", capture_format.extension().to_uppercase(), capture_path.display()),
+                    Err(e) => println!("\n  ✗ Failed to save {}: {}", capture_path.display(), e),
                 }
             }
             Err(e) => {
@@ -547,6 +1760,30 @@ fn main() {
         let is_frame_new = spoutdx_receiver_is_frame_new(receiver);
         println!("    New frame: {}", is_frame_new != 0);
 
+        if options.latency_frames > 0 {
+            measure_latency(receiver, &device, options.latency_frames, transform);
+        }
+        if options.session_seconds > 0 {
+            let hasher = options.hash_grid.map_or(ContentHasher::full(), ContentHasher::sampled);
+            analyse_session(receiver, &device, options.session_seconds, hasher, transform);
+        }
+        if options.record_seconds > 0 {
+            record_frames(receiver, &device, options.record_seconds, &options.record, transform);
+        }
+        if options.clip_seconds > 0 {
+            capture_clip(receiver, &device, options.clip_seconds, &options);
+        }
+        if options.avi_seconds > 0 {
+            let path = options.avi_output.clone().unwrap_or_else(|| PathBuf::from("spout_capture.avi"));
+            record_avi(receiver, &device, options.avi_seconds, &path, &options);
+        }
+        if let Some(out) = stream_out {
+            stream_frames(receiver, &device, options.stream_seconds, out, &options.stream, transform);
+        }
+        if options.tearing_seconds > 0 {
+            inspect_tearing(receiver, &device, options.tearing_seconds, options.bands, transform);
+        }
+
         // Cleanup
         println!("\n  Cleaning up...");
         spoutdx_receiver_close_dx11(receiver);
@@ -555,13 +1792,12 @@ fn main() {
 
         println!("\n========================================");
         println!("Receiver API test completed.");
-        println!("Check spout_capture.png for visual verification.");
+        println!("Check {} for visual verification.", capture_target(&options).0.display());
         println!("========================================");
     }
 }
 
-/// レンダーターゲット用テクスチャを作成（将来の receive_texture API 用）
-#[allow(dead_code)]
+/// レンダーターゲット用テクスチャを作成（送信と将来の receive_texture API 用）
 fn create_render_texture(device: &ID3D11Device, width: u32, height: u32) -> Option<ID3D11Texture2D> {
     let desc = D3D11_TEXTURE2D_DESC {
         Width: width,
@@ -0,0 +1,230 @@
+// ============================================================
+// モック受信・送信（実機 Spout / DirectX なしで受信・送信フローを再現）
+// ============================================================
+//
+// SpoutDX の受信フローと同じ振る舞いをする:
+// - センダーがなければ receive() は ErrorNotConnected
+// - サイズ/フォーマットが変わった直後は is_updated() が 1 回だけ true
+// - 新しいフレームを取り出したときだけ is_frame_new() が true
+// MockSender は送ったフレームを deliver() で MockReceiver に渡す。
+
+use std::collections::VecDeque;
+
+use crate::ffi::SpoutDxResult;
+use crate::image::{DxgiFormat, ImageBuf, ImageView};
+use crate::receiver::{ReceiverOptions, SenderInfo};
+use crate::sender::{executable_name, FrameCounter, SenderBackend};
+
+/// モック送信のフレーム間隔（60 fps）
+const MOCK_FRAME_INTERVAL_US: u64 = 16_667;
+
+/// スクリプトしたフレームを順に返すモック受信
+#[derive(Debug, Default)]
+pub struct MockReceiver {
+    sender_name: String,
+    queued: VecDeque<ImageBuf>,
+    current: Option<ImageBuf>,
+    frame_count: u64,
+    connected: bool,
+    updated: bool,
+    frame_new: bool,
+    sender_closed: bool,
+    options: ReceiverOptions,
+}
+
+impl MockReceiver {
+    pub fn new(sender_name: &str) -> Self {
+        Self {
+            sender_name: sender_name.to_string(),
+            ..Self::default()
+        }
+    }
+
+    pub fn set_options(&mut self, options: ReceiverOptions) {
+        self.options = options;
+    }
+
+    pub fn options(&self) -> ReceiverOptions {
+        self.options
+    }
+
+    /// センダーが次に送るフレームを追加
+    pub fn push_frame(&mut self, frame: ImageBuf) {
+        self.sender_closed = false;
+        self.queued.push_back(frame);
+    }
+
+    /// センダー終了を再現
+    pub fn close_sender(&mut self) {
+        self.sender_closed = true;
+        self.queued.clear();
+    }
+
+    /// `spoutdx_receiver_receive` 相当
+    pub fn receive(&mut self) -> Result<(), SpoutDxResult> {
+        if self.sender_closed || (self.current.is_none() && self.queued.is_empty()) {
+            self.current = None;
+            self.connected = false;
+            self.frame_new = false;
+            return Err(SpoutDxResult::ErrorNotConnected);
+        }
+
+        self.connected = true;
+        match self.queued.pop_front() {
+            Some(frame) => {
+                let changed = self.current.as_ref().is_none_or(|current| {
+                    (current.width(), current.height(), current.format())
+                        != (frame.width(), frame.height(), frame.format())
+                });
+                if changed {
+                    self.updated = true;
+                }
+                self.current = Some(frame);
+                self.frame_count += 1;
+                self.frame_new = true;
+            }
+            None => self.frame_new = false,
+        }
+        Ok(())
+    }
+
+    /// `spoutdx_receiver_receive_image` 相当（受信オプションの変換・アルファ操作を適用）
+    pub fn receive_image(&self) -> Result<ImageBuf, SpoutDxResult> {
+        let current = self.current.as_ref().ok_or(SpoutDxResult::ErrorNotConnected)?;
+        let mut image = self
+            .options
+            .transform
+            .apply(&current.view())
+            .map_err(|_| SpoutDxResult::ErrorInternal)?;
+        self.options
+            .alpha
+            .apply(&mut image)
+            .map_err(|_| SpoutDxResult::ErrorInternal)?;
+        Ok(image)
+    }
+
+    /// 更新フラグを返してリセットする（SpoutDX::IsUpdated と同じ）
+    pub fn is_updated(&mut self) -> bool {
+        std::mem::take(&mut self.updated)
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.connected
+    }
+
+    pub fn is_frame_new(&self) -> bool {
+        self.frame_new
+    }
+
+    /// センダー側のフレーム番号（受信したフレーム数）
+    pub fn frame_count(&self) -> u64 {
+        self.frame_count
+    }
+
+    pub fn sender_info(&self) -> Option<SenderInfo> {
+        self.current.as_ref().map(|frame| SenderInfo {
+            name: self.sender_name.clone(),
+            width: frame.width(),
+            height: frame.height(),
+            format: frame.format(),
+        })
+    }
+}
+
+/// 送ったフレームを溜めておくモック送信（60 fps で送ったとみなす）
+#[derive(Debug)]
+pub struct MockSender {
+    name: Option<String>,
+    format: DxgiFormat,
+    info: Option<SenderInfo>,
+    pending: VecDeque<ImageBuf>,
+    released: bool,
+    counter: FrameCounter,
+}
+
+impl Default for MockSender {
+    fn default() -> Self {
+        Self {
+            name: None,
+            format: DxgiFormat::B8G8R8A8Unorm,
+            info: None,
+            pending: VecDeque::new(),
+            released: false,
+            counter: FrameCounter::default(),
+        }
+    }
+}
+
+impl MockSender {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// まだ受信側に渡していないフレーム数
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// 送ったフレームを受信側に渡す（release の後はセンダー終了を伝える）
+    pub fn deliver(&mut self, receiver: &mut MockReceiver) {
+        for frame in self.pending.drain(..) {
+            receiver.push_frame(frame);
+        }
+        if std::mem::take(&mut self.released) {
+            receiver.close_sender();
+        }
+    }
+}
+
+impl SenderBackend for MockSender {
+    type Texture = ImageBuf;
+
+    fn set_name(&mut self, name: Option<&str>) -> Result<(), SpoutDxResult> {
+        self.name = Some(name.map_or_else(executable_name, str::to_string));
+        Ok(())
+    }
+
+    fn set_format(&mut self, format: DxgiFormat) -> Result<(), SpoutDxResult> {
+        self.format = format;
+        Ok(())
+    }
+
+    fn format(&self) -> DxgiFormat {
+        self.format
+    }
+
+    fn send_texture(&mut self, texture: &ImageBuf) -> Result<(), SpoutDxResult> {
+        self.format = texture.format();
+        self.send_image(&texture.view())
+    }
+
+    fn send_image(&mut self, image: &ImageView) -> Result<(), SpoutDxResult> {
+        let name = self.name.get_or_insert_with(executable_name).clone();
+        self.info = Some(SenderInfo { name, width: image.width(), height: image.height(), format: image.format() });
+        self.released = false;
+        self.pending.push_back(image.to_image_buf());
+        self.counter.record(self.counter.frame() * MOCK_FRAME_INTERVAL_US);
+        Ok(())
+    }
+
+    fn release(&mut self) -> Result<(), SpoutDxResult> {
+        if self.info.take().is_some() {
+            self.counter = FrameCounter::default();
+            self.pending.clear();
+            self.released = true;
+        }
+        Ok(())
+    }
+
+    fn info(&self) -> Option<SenderInfo> {
+        self.info.clone()
+    }
+
+    fn frame(&self) -> u64 {
+        self.counter.frame()
+    }
+
+    fn fps(&self) -> f64 {
+        self.counter.fps()
+    }
+}
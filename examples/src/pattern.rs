@@ -0,0 +1,669 @@
+// ============================================================
+// テストパターン（生成と検証）
+// ============================================================
+//
+// 送信側で既知のパターンを生成し、受信したフレームと比較して
+// チャンネル入れ替わり・反転/回転・位置ずれ・拡大縮小・ガンマ誤りを
+// 具体的な値で報告する。
+//
+// - カラーバー: 上下対称なので上下反転は検出できない
+// - グラデーション: R = x、G = y、B = (1 - x) * y（どの向きでも区別できる）
+// - チェッカー: 白黒のみなので向き・チャンネル・ガンマは検出できない
+// - フレームカウンター: 上端のブロック列にフレーム番号とタイムスタンプ、残りはグラデーション
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::hdr::{decode_pixel, encode_pixel};
+use crate::image::{DxgiFormat, ImageBuf, ImageError, ImageView};
+use crate::transform::{Rotation, Transform};
+
+/// フレームカウンターのビット数（フレーム番号・タイムスタンプそれぞれ）
+pub const STAMP_BITS: u32 = 64;
+
+/// カラーバー（白・黄・シアン・緑・マゼンタ・赤・青・黒）
+const COLOR_BARS: [[f32; 3]; 8] = [
+    [1.0, 1.0, 1.0],
+    [1.0, 1.0, 0.0],
+    [0.0, 1.0, 1.0],
+    [0.0, 1.0, 0.0],
+    [1.0, 0.0, 1.0],
+    [1.0, 0.0, 0.0],
+    [0.0, 0.0, 1.0],
+    [0.0, 0.0, 0.0],
+];
+
+/// 位置ずれを採用するのに必要な誤差の減少率
+const OFFSET_MARGIN: f32 = 0.8;
+
+/// 検証の標本数（縦横それぞれの上限）
+const SAMPLE_GRID: u32 = 96;
+
+/// パターンの種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pattern {
+    ColorBars,
+    Gradient,
+    /// `cell` ピクセル四方の白黒市松模様（0 は 1 とみなす）
+    Checkerboard { cell: u32 },
+    /// フレーム番号とタイムスタンプを埋め込んだグラデーション
+    FrameCounter,
+}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ColorBars => write!(f, "bars"),
+            Self::Gradient => write!(f, "gradient"),
+            Self::Checkerboard { cell } => write!(f, "checker={}", cell),
+            Self::FrameCounter => write!(f, "counter"),
+        }
+    }
+}
+
+impl FromStr for Pattern {
+    type Err = String;
+
+    /// `bars` / `gradient` / `checker[=<cell>]` / `counter`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bars" => Ok(Self::ColorBars),
+            "gradient" => Ok(Self::Gradient),
+            "checker" => Ok(Self::Checkerboard { cell: 32 }),
+            "counter" => Ok(Self::FrameCounter),
+            _ => match s.strip_prefix("checker=").map(str::parse::<u32>) {
+                Some(Ok(cell)) if cell > 0 => Ok(Self::Checkerboard { cell }),
+                _ => Err(format!("unknown pattern: {} (bars, gradient, checker[=<cell>], counter)", s)),
+            },
+        }
+    }
+}
+
+/// フレームカウンターに埋め込む値
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FrameStamp {
+    pub frame: u64,
+    /// 送信時刻（マイクロ秒、latency::Clock の値）
+    pub timestamp_us: u64,
+}
+
+impl fmt::Display for FrameStamp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "frame {} @ {} us", self.frame, self.timestamp_us)
+    }
+}
+
+/// スタンプのブロック配置（上端 2 段: フレーム番号、タイムスタンプ）
+#[derive(Debug, Clone, Copy)]
+struct StampLayout {
+    block_width: u32,
+    block_height: u32,
+}
+
+impl StampLayout {
+    fn new(width: u32, height: u32) -> Option<Self> {
+        let block_width = width / STAMP_BITS;
+        let block_height = (height / 16).max(1);
+        (block_width > 0 && height >= block_height * 3).then_some(Self { block_width, block_height })
+    }
+
+    fn band_height(self) -> u32 {
+        self.block_height * 2
+    }
+
+    /// ブロックの中心（ピクセル座標）
+    fn block_center(self, row: u32, bit: u32) -> (f32, f32) {
+        (
+            (bit as f32 + 0.5) * self.block_width as f32,
+            (row as f32 + 0.5) * self.block_height as f32,
+        )
+    }
+}
+
+impl Pattern {
+    /// パターンを生成（`stamp` はフレームカウンターのみ使用）
+    pub fn generate(&self, width: u32, height: u32, format: DxgiFormat, stamp: FrameStamp) -> Result<ImageBuf, ImageError> {
+        let mut image = ImageBuf::new(width, height, format)?;
+        let layout = self.stamp_layout(width, height)?;
+
+        for y in 0..height {
+            for x in 0..width {
+                let [r, g, b] = match layout.filter(|l| y < l.band_height()) {
+                    Some(layout) => stamp_color(layout, x, y, stamp),
+                    None => self.color_at(x as f32 + 0.5, y as f32 + 0.5, width, height),
+                };
+                encode_pixel([r, g, b, 1.0], format, image.pixel_mut(x, y))?;
+            }
+        }
+        Ok(image)
+    }
+
+    /// フレームカウンターのみブロック配置を返す（小さすぎる場合はエラー）
+    fn stamp_layout(&self, width: u32, height: u32) -> Result<Option<StampLayout>, ImageError> {
+        if *self != Self::FrameCounter {
+            return Ok(None);
+        }
+        StampLayout::new(width, height)
+            .map(Some)
+            .ok_or(ImageError::InvalidDimensions { width, height, row_pitch: 0 })
+    }
+
+    /// 期待値（ピクセル座標、格納値の RGB）
+    fn color_at(&self, x: f32, y: f32, width: u32, height: u32) -> [f32; 3] {
+        let (u, v) = (x / width as f32, y / height as f32);
+        match self {
+            Self::ColorBars => COLOR_BARS[((u * 8.0) as usize).min(7)],
+            Self::Gradient | Self::FrameCounter => [u, v, (1.0 - u) * v],
+            Self::Checkerboard { cell } => {
+                // 0 は 1 ピクセル四方として扱う
+                let cell = (*cell).max(1);
+                let parity = (x as u32 / cell + y as u32 / cell) % 2;
+                [parity as f32; 3]
+            }
+        }
+    }
+
+    /// 受信フレームを検証（`width` x `height` は送信時のサイズ）
+    pub fn verify(
+        &self,
+        received: &ImageView,
+        width: u32,
+        height: u32,
+        options: &VerifyOptions,
+    ) -> Result<Verification, ImageError> {
+        let layout = self.stamp_layout(width, height)?;
+        let band = layout.map_or(0, StampLayout::band_height);
+        let samples = sample_points(width, height, band);
+        let reader = Reader { view: received, format: received.format() };
+        let expected: Vec<[f32; 3]> = samples.iter().map(|&(x, y)| self.color_at(x, y, width, height)).collect();
+
+        // 向き x チャンネル順を総当たり（同点なら候補の先頭 = 無変換を優先）
+        let mut best = Candidate::default();
+        let mut best_error = f32::INFINITY;
+        for transform in orientations() {
+            let geometry = Geometry::new(transform, width, height, received, (0, 0));
+            let pixels = reader.read_all(&geometry, &samples)?;
+            for order in CHANNEL_ORDERS {
+                let error = mean_error(&expected, &pixels, order, 1.0);
+                if error < best_error {
+                    best_error = error;
+                    best = Candidate { transform, order, offset: (0, 0) };
+                }
+            }
+        }
+
+        // 位置ずれ（ガンマ誤りで誤差の谷がずれないよう、先に推定した指数で補正して比較）
+        let pixels = reader.read_all(&Geometry::new(best.transform, width, height, received, (0, 0)), &samples)?;
+        let rough_gamma = estimate_gamma(&expected, &pixels, best.order).unwrap_or(1.0);
+        best_error = mean_error(&expected, &pixels, best.order, rough_gamma);
+        let mut offsets: Vec<(i32, i32)> = Vec::new();
+        let max = options.max_offset as i32;
+        for dy in -max..=max {
+            for dx in -max..=max {
+                offsets.push((dx, dy));
+            }
+        }
+        // 近いものを優先し、ガンマ近似の残差程度の差では動かさない
+        offsets.sort_by_key(|&(dx, dy)| dx * dx + dy * dy);
+        for offset in offsets.into_iter().skip(1) {
+            let geometry = Geometry::new(best.transform, width, height, received, offset);
+            let pixels = reader.read_all(&geometry, &samples)?;
+            let error = mean_error(&expected, &pixels, best.order, rough_gamma);
+            if error < best_error * OFFSET_MARGIN {
+                best_error = error;
+                best.offset = offset;
+            }
+        }
+
+        let geometry = Geometry::new(best.transform, width, height, received, best.offset);
+        let pixels = reader.read_all(&geometry, &samples)?;
+        let gamma = estimate_gamma(&expected, &pixels, best.order);
+        let exponent = gamma.unwrap_or(1.0);
+        let (mean_error, max_error, matched_percent) =
+            match_statistics(&expected, &pixels, best.order, exponent, options.tolerance);
+
+        let stamp = match layout {
+            Some(layout) => reader.read_stamp(&geometry, layout)?,
+            None => None,
+        };
+
+        let mut verification = Verification {
+            pattern: *self,
+            transform: best.transform,
+            channel_order: best.order,
+            offset: best.offset,
+            expected_size: best.transform.output_size(width, height),
+            actual_size: (received.width(), received.height()),
+            gamma,
+            stamp,
+            mean_error,
+            max_error,
+            matched_percent,
+            issues: Vec::new(),
+        };
+        verification.issues = verification.collect_issues(options, layout.is_some());
+        Ok(verification)
+    }
+}
+
+impl Pattern {
+    /// フレームカウンターのスタンプだけを読む（向き・サイズは送信時のままとみなす）
+    ///
+    /// 毎フレーム呼ぶ遅延計測向け。読めなければ None。
+    pub fn read_stamp(received: &ImageView) -> Option<FrameStamp> {
+        let (width, height) = (received.width(), received.height());
+        let layout = StampLayout::new(width, height)?;
+        let geometry = Geometry::new(Transform::default(), width, height, received, (0, 0));
+        let reader = Reader { view: received, format: received.format() };
+        reader.read_stamp(&geometry, layout).ok().flatten()
+    }
+}
+
+/// スタンプ帯の色（値のビットを MSB から白黒で並べる、余りは灰色）
+fn stamp_color(layout: StampLayout, x: u32, y: u32, stamp: FrameStamp) -> [f32; 3] {
+    let bit = x / layout.block_width;
+    if bit >= STAMP_BITS {
+        return [0.5; 3];
+    }
+    let value = if y < layout.block_height { stamp.frame } else { stamp.timestamp_us };
+    [((value >> (STAMP_BITS - 1 - bit)) & 1) as f32; 3]
+}
+
+/// 標本点（スタンプ帯は除く、ピクセル中心）
+fn sample_points(width: u32, height: u32, band: u32) -> Vec<(f32, f32)> {
+    let rows = height - band;
+    let (nx, ny) = (width.min(SAMPLE_GRID), rows.min(SAMPLE_GRID));
+    let mut points = Vec::with_capacity((nx * ny) as usize);
+    for j in 0..ny {
+        let y = band + (j * rows + rows / 2) / ny;
+        for i in 0..nx {
+            let x = (i * width + width / 2) / nx;
+            points.push((x as f32 + 0.5, y as f32 + 0.5));
+        }
+    }
+    points
+}
+
+/// 候補の 8 方向（反転は上下反転として表す）
+fn orientations() -> [Transform; 8] {
+    let t = |flip_vertical, mirror_horizontal, rotation| Transform {
+        flip_vertical,
+        mirror_horizontal,
+        swap_rb: false,
+        rotation,
+    };
+    [
+        t(false, false, Rotation::None),
+        t(false, true, Rotation::None),
+        t(true, false, Rotation::None),
+        t(false, false, Rotation::Cw180),
+        t(false, false, Rotation::Cw90),
+        t(false, false, Rotation::Cw270),
+        t(false, true, Rotation::Cw90),
+        t(false, true, Rotation::Cw270),
+    ]
+}
+
+/// 受信チャンネル c に入っている送信チャンネル（RGB = 0, 1, 2）
+const CHANNEL_ORDERS: [[usize; 3]; 6] = [[0, 1, 2], [2, 1, 0], [0, 2, 1], [1, 0, 2], [1, 2, 0], [2, 0, 1]];
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Candidate {
+    transform: Transform,
+    order: [usize; 3],
+    offset: (i32, i32),
+}
+
+/// 送信座標 → 受信座標の対応（Transform::apply と同じ向き、サイズ差は比率で吸収）
+struct Geometry {
+    transform: Transform,
+    src_size: (f32, f32),
+    dst_size: (u32, u32),
+    offset: (i32, i32),
+}
+
+impl Geometry {
+    fn new(transform: Transform, width: u32, height: u32, received: &ImageView, offset: (i32, i32)) -> Self {
+        Self {
+            transform,
+            src_size: (width as f32, height as f32),
+            dst_size: (received.width(), received.height()),
+            offset,
+        }
+    }
+
+    fn map(&self, x: f32, y: f32) -> Option<(u32, u32)> {
+        let mut u = x / self.src_size.0;
+        let mut v = y / self.src_size.1;
+        if self.transform.flip_vertical {
+            v = 1.0 - v;
+        }
+        if self.transform.mirror_horizontal {
+            u = 1.0 - u;
+        }
+        let (u, v) = match self.transform.rotation {
+            Rotation::None => (u, v),
+            Rotation::Cw90 => (1.0 - v, u),
+            Rotation::Cw180 => (1.0 - u, 1.0 - v),
+            Rotation::Cw270 => (v, 1.0 - u),
+        };
+        let (w, h) = self.dst_size;
+        let rx = (u * w as f32).floor() as i64 + self.offset.0 as i64;
+        let ry = (v * h as f32).floor() as i64 + self.offset.1 as i64;
+        (rx >= 0 && ry >= 0 && rx < w as i64 && ry < h as i64).then_some((rx as u32, ry as u32))
+    }
+}
+
+struct Reader<'a, 'b> {
+    view: &'b ImageView<'a>,
+    format: DxgiFormat,
+}
+
+impl Reader<'_, '_> {
+    /// 標本点の受信値（範囲外は None）
+    fn read_all(&self, geometry: &Geometry, samples: &[(f32, f32)]) -> Result<Vec<Option<[f32; 3]>>, ImageError> {
+        samples
+            .iter()
+            .map(|&(x, y)| match geometry.map(x, y) {
+                Some((rx, ry)) => {
+                    let [r, g, b, _] = decode_pixel(self.view.pixel(rx, ry), self.format)?;
+                    Ok(Some([r, g, b]))
+                }
+                None => Ok(None),
+            })
+            .collect()
+    }
+
+    /// スタンプを読む（白黒がはっきりしないブロックがあれば None）
+    fn read_stamp(&self, geometry: &Geometry, layout: StampLayout) -> Result<Option<FrameStamp>, ImageError> {
+        let mut values = [0u64; 2];
+        for (row, value) in values.iter_mut().enumerate() {
+            for bit in 0..STAMP_BITS {
+                let (x, y) = layout.block_center(row as u32, bit);
+                let Some((rx, ry)) = geometry.map(x, y) else {
+                    return Ok(None);
+                };
+                let [r, g, b, _] = decode_pixel(self.view.pixel(rx, ry), self.format)?;
+                let level = (r + g + b) / 3.0;
+                if (level - 0.5).abs() < 0.3 {
+                    return Ok(None);
+                }
+                *value = (*value << 1) | (level > 0.5) as u64;
+            }
+        }
+        Ok(Some(FrameStamp { frame: values[0], timestamp_us: values[1] }))
+    }
+}
+
+/// 平均誤差（位置ずれで範囲外になった標本は除く）
+fn mean_error(expected: &[[f32; 3]], received: &[Option<[f32; 3]>], order: [usize; 3], gamma: f32) -> f32 {
+    let (total, count) = expected
+        .iter()
+        .zip(received)
+        .filter_map(|(e, r)| r.map(|r| (0..3).map(|c| (r[c] - e[order[c]].powf(gamma)).abs()).sum::<f32>() / 3.0))
+        .fold((0.0, 0usize), |(total, count), error| (total + error, count + 1));
+    // 半分以上はみ出す位置ずれは候補にしない
+    if count * 2 < expected.len() {
+        return f32::INFINITY;
+    }
+    total / count as f32
+}
+
+/// (平均誤差, 最大誤差, 許容誤差内の標本の割合 %)、範囲外の標本は除く
+fn match_statistics(
+    expected: &[[f32; 3]],
+    received: &[Option<[f32; 3]>],
+    order: [usize; 3],
+    gamma: f32,
+    tolerance: f32,
+) -> (f32, f32, f32) {
+    let mut max_error = 0f32;
+    let (mut matched, mut count) = (0usize, 0usize);
+    for (e, r) in expected.iter().zip(received) {
+        let Some(r) = r else { continue };
+        let error = (0..3).map(|c| (r[c] - e[order[c]].powf(gamma)).abs()).fold(0.0, f32::max);
+        max_error = max_error.max(error);
+        count += 1;
+        if error <= tolerance {
+            matched += 1;
+        }
+    }
+    let matched_percent = matched as f32 * 100.0 / count.max(1) as f32;
+    (mean_error(expected, received, order, gamma), max_error, matched_percent)
+}
+
+/// 受信 = 送信^gamma とみなして指数を推定（中間調の中央値、標本が少なければ None）
+fn estimate_gamma(expected: &[[f32; 3]], received: &[Option<[f32; 3]>], order: [usize; 3]) -> Option<f32> {
+    let mut ratios: Vec<f32> = Vec::new();
+    for (e, r) in expected.iter().zip(received) {
+        let Some(r) = r else { continue };
+        for c in 0..3 {
+            let (e, r) = (e[order[c]], r[c]);
+            if (0.1..=0.9).contains(&e) && r > 0.002 && r < 0.998 {
+                ratios.push(r.ln() / e.ln());
+            }
+        }
+    }
+    if ratios.len() < 16 {
+        return None;
+    }
+    ratios.sort_by(f32::total_cmp);
+    Some(ratios[ratios.len() / 2])
+}
+
+/// 検証の設定
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VerifyOptions {
+    /// 探索する位置ずれ（ピクセル）
+    pub max_offset: u32,
+    /// 1 標本の許容誤差（格納値 0..1）
+    pub tolerance: f32,
+    /// 一致とみなす標本の割合（%）
+    pub min_match_percent: f32,
+    /// ガンマ指数の許容幅（|gamma - 1| がこれを超えれば報告）
+    pub gamma_tolerance: f32,
+    /// フレームカウンターの期待値
+    pub expected_stamp: Option<FrameStamp>,
+}
+
+impl Default for VerifyOptions {
+    fn default() -> Self {
+        Self {
+            max_offset: 8,
+            tolerance: 4.0 / 255.0,
+            min_match_percent: 99.0,
+            gamma_tolerance: 0.1,
+            expected_stamp: None,
+        }
+    }
+}
+
+/// 検出した問題
+#[derive(Debug, Clone, PartialEq)]
+pub enum PatternIssue {
+    /// 反転・回転（`correction` を受信オプションに設定すると元に戻る）
+    Orientation { detected: Transform, correction: Transform },
+    /// 受信チャンネル c に送信チャンネル order[c] が入っている
+    ChannelSwap { order: [usize; 3] },
+    Offset { dx: i32, dy: i32 },
+    Scaling { expected: (u32, u32), actual: (u32, u32) },
+    /// 受信 = 送信^exponent
+    Gamma { exponent: f32 },
+    StampUnreadable,
+    StampMismatch { expected: FrameStamp, actual: FrameStamp },
+    /// 補正後も一致しない
+    Mismatch { matched_percent: f32, mean_error: f32 },
+}
+
+fn channel_names(order: [usize; 3]) -> String {
+    order.iter().map(|&c| ['R', 'G', 'B'][c]).collect()
+}
+
+impl fmt::Display for PatternIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Orientation { detected, correction } => write!(
+                f,
+                "orientation changed (flip={}, mirror={}, rotation={:?}); correct with flip={}, mirror={}, rotation={:?}",
+                detected.flip_vertical,
+                detected.mirror_horizontal,
+                detected.rotation,
+                correction.flip_vertical,
+                correction.mirror_horizontal,
+                correction.rotation
+            ),
+            Self::ChannelSwap { order } => {
+                write!(f, "channel swap: received RGB carries source {}", channel_names(*order))
+            }
+            Self::Offset { dx, dy } => write!(f, "offset by ({}, {}) pixels", dx, dy),
+            Self::Scaling { expected, actual } => write!(
+                f,
+                "scaled from {}x{} to {}x{} ({:.3} x {:.3})",
+                expected.0,
+                expected.1,
+                actual.0,
+                actual.1,
+                actual.0 as f32 / expected.0 as f32,
+                actual.1 as f32 / expected.1 as f32
+            ),
+            Self::Gamma { exponent } => write!(
+                f,
+                "gamma error: received = source^{:.3} ({})",
+                exponent,
+                if *exponent < 1.0 {
+                    "brighter: an extra linear -> sRGB encode was applied"
+                } else {
+                    "darker: an extra sRGB -> linear decode was applied"
+                }
+            ),
+            Self::StampUnreadable => write!(f, "frame stamp could not be read"),
+            Self::StampMismatch { expected, actual } => {
+                write!(f, "frame stamp mismatch: expected {}, got {}", expected, actual)
+            }
+            Self::Mismatch { matched_percent, mean_error } => write!(
+                f,
+                "content mismatch: {:.1}% of samples within tolerance, mean error {:.4}",
+                matched_percent, mean_error
+            ),
+        }
+    }
+}
+
+/// 検証結果
+#[derive(Debug, Clone, PartialEq)]
+pub struct Verification {
+    pub pattern: Pattern,
+    /// 送信フレームに適用されていた向きの変化
+    pub transform: Transform,
+    pub channel_order: [usize; 3],
+    pub offset: (i32, i32),
+    /// 向きを考慮した送信サイズ
+    pub expected_size: (u32, u32),
+    pub actual_size: (u32, u32),
+    pub gamma: Option<f32>,
+    pub stamp: Option<FrameStamp>,
+    pub mean_error: f32,
+    pub max_error: f32,
+    pub matched_percent: f32,
+    pub issues: Vec<PatternIssue>,
+}
+
+impl Verification {
+    pub fn passed(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// 検出した向きを元に戻す受信オプション（R/B 入れ替わりも含む）
+    pub fn correction(&self) -> Transform {
+        let t = self.transform;
+        // 回転のみなら逆回転、反転を含む組み合わせは自身が逆変換
+        let rotation = match (t.mirror_horizontal || t.flip_vertical, t.rotation) {
+            (false, Rotation::Cw90) => Rotation::Cw270,
+            (false, Rotation::Cw270) => Rotation::Cw90,
+            (_, rotation) => rotation,
+        };
+        Transform {
+            rotation,
+            swap_rb: self.channel_order == [2, 1, 0],
+            ..t
+        }
+    }
+
+    fn collect_issues(&self, options: &VerifyOptions, has_stamp: bool) -> Vec<PatternIssue> {
+        let mut issues = Vec::new();
+        if !self.transform.is_identity() {
+            let correction = Transform { swap_rb: false, ..self.correction() };
+            issues.push(PatternIssue::Orientation { detected: self.transform, correction });
+        }
+        if self.channel_order != [0, 1, 2] {
+            issues.push(PatternIssue::ChannelSwap { order: self.channel_order });
+        }
+        if self.offset != (0, 0) {
+            issues.push(PatternIssue::Offset { dx: self.offset.0, dy: self.offset.1 });
+        }
+        if self.expected_size != self.actual_size {
+            issues.push(PatternIssue::Scaling { expected: self.expected_size, actual: self.actual_size });
+        }
+        if let Some(exponent) = self.gamma.filter(|g| (g - 1.0).abs() > options.gamma_tolerance) {
+            issues.push(PatternIssue::Gamma { exponent });
+        }
+        if has_stamp {
+            match (self.stamp, options.expected_stamp) {
+                (None, _) => issues.push(PatternIssue::StampUnreadable),
+                (Some(actual), Some(expected)) if actual != expected => {
+                    issues.push(PatternIssue::StampMismatch { expected, actual });
+                }
+                _ => {}
+            }
+        }
+        if self.matched_percent < options.min_match_percent {
+            issues.push(PatternIssue::Mismatch {
+                matched_percent: self.matched_percent,
+                mean_error: self.mean_error,
+            });
+        }
+        issues
+    }
+}
+
+impl fmt::Display for Verification {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "[Pattern Verification] {}: {}",
+            self.pattern,
+            if self.passed() { "PASS" } else { "FAIL" }
+        )?;
+        writeln!(
+            f,
+            "  Matched: {:.1}% (mean error {:.4}, max error {:.4})",
+            self.matched_percent, self.mean_error, self.max_error
+        )?;
+        if let Some(gamma) = self.gamma {
+            writeln!(f, "  Estimated gamma exponent: {:.3}", gamma)?;
+        }
+        if let Some(stamp) = self.stamp {
+            writeln!(f, "  Stamp: {}", stamp)?;
+        }
+        for issue in &self.issues {
+            writeln!(f, "  ✗ {}", issue)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zero_cell_checkerboard_is_one_pixel() {
+        let stamp = FrameStamp::default();
+        let zero = Pattern::Checkerboard { cell: 0 };
+        let image = zero.generate(64, 32, DxgiFormat::B8G8R8A8Unorm, stamp).unwrap();
+        let one = Pattern::Checkerboard { cell: 1 }.generate(64, 32, DxgiFormat::B8G8R8A8Unorm, stamp).unwrap();
+        assert_eq!(image.data(), one.data());
+        assert_ne!(image.pixel(0, 0), image.pixel(1, 0));
+        assert!(zero.verify(&image.view(), 64, 32, &VerifyOptions::default()).is_ok());
+    }
+}
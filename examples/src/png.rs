@@ -0,0 +1,688 @@
+// ============================================================
+// PNG 書き出し・読み込み（Rust 実装、WIC はオプション）
+// ============================================================
+//
+// どの DxgiFormat のビューからでも 8bit / 16bit、アルファあり / なしの
+// PNG を書き出す。圧縮は deflate モジュール、行フィルターは行ごとに
+// 5 種類から選ぶ。Windows では WIC で保存することもできる。
+// 短いクリップ用に APNG（アニメーション PNG）も書き出せる。
+// 読み込みはすべてのカラータイプ・ビット深度・インターレースに対応する（APNG は 1 枚目だけ）。
+
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+use serde::Serialize;
+
+use crate::clip::{frame_delays, AnimationFrame};
+use crate::color::{linear_to_srgb, TransferFunction};
+use crate::deflate::{zlib_compress, zlib_decompress, Crc32};
+use crate::export::{push_row8, quantize, write_file, ExportError};
+use crate::hdr::decode_pixel;
+use crate::image::{ImageBuf, ImageError, ImageView};
+use crate::import::{be_u32, check_dimensions, corrupt, rgba_image, slice, ImportError};
+
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1A, b'\n'];
+/// IDAT チャンク 1 つあたりの上限
+const IDAT_CHUNK: usize = 1 << 20;
+/// Adam7 の各パスの (開始 x, 開始 y, x の間隔, y の間隔)
+const ADAM7: [(u32, u32, u32, u32); 7] =
+    [(0, 0, 8, 8), (4, 0, 8, 8), (0, 4, 4, 8), (2, 0, 4, 4), (0, 2, 2, 4), (1, 0, 2, 2), (0, 1, 1, 2)];
+
+/// サンプルのビット深度
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+pub enum PngBitDepth {
+    #[default]
+    Eight,
+    Sixteen,
+}
+
+impl PngBitDepth {
+    pub fn bits(self) -> u8 {
+        match self {
+            Self::Eight => 8,
+            Self::Sixteen => 16,
+        }
+    }
+}
+
+/// 保存に使う実装
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PngBackend {
+    /// このモジュールのエンコーダー（全プラットフォーム）
+    #[default]
+    Rust,
+    /// Windows Imaging Component（Windows のみ、8bit RGBA だけ）
+    Wic,
+}
+
+impl fmt::Display for PngBackend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Rust => write!(f, "rust"),
+            Self::Wic => write!(f, "wic"),
+        }
+    }
+}
+
+/// PNG の書き出し設定
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct PngOptions {
+    pub bit_depth: PngBitDepth,
+    /// false ならアルファを捨てて RGB で保存
+    pub alpha: bool,
+    /// 圧縮レベル 0-9（0 は無圧縮）
+    pub compression: u8,
+    pub backend: PngBackend,
+}
+
+impl Default for PngOptions {
+    fn default() -> Self {
+        Self { bit_depth: PngBitDepth::Eight, alpha: true, compression: 6, backend: PngBackend::Rust }
+    }
+}
+
+impl PngOptions {
+    fn channels(&self) -> usize {
+        if self.alpha { 4 } else { 3 }
+    }
+
+    /// 1 ピクセルのバイト数（フィルターの単位）
+    fn bytes_per_pixel(&self) -> usize {
+        self.channels() * self.bit_depth.bits() as usize / 8
+    }
+}
+
+impl fmt::Display for PngOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}-bit {}, level {}, {}",
+            self.bit_depth.bits(),
+            if self.alpha { "RGBA" } else { "RGB" },
+            self.compression,
+            self.backend
+        )
+    }
+}
+
+impl FromStr for PngOptions {
+    type Err = String;
+
+    /// `depth=16,alpha=off,level=9,backend=wic`（省略した項目は既定値）
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut options = Self::default();
+        for item in s.split(',').filter(|item| !item.is_empty()) {
+            let (key, value) = item
+                .split_once('=')
+                .ok_or_else(|| format!("invalid PNG option: {} (expected key=value)", item))?;
+            match (key, value.to_ascii_lowercase().as_str()) {
+                ("depth", "8") => options.bit_depth = PngBitDepth::Eight,
+                ("depth", "16") => options.bit_depth = PngBitDepth::Sixteen,
+                ("alpha", "on" | "true" | "1") => options.alpha = true,
+                ("alpha", "off" | "false" | "0") => options.alpha = false,
+                ("level", level) => {
+                    options.compression = level
+                        .parse()
+                        .ok()
+                        .filter(|&level| level <= 9)
+                        .ok_or_else(|| format!("invalid PNG level: {} (0-9)", value))?;
+                }
+                ("backend", "rust") => options.backend = PngBackend::Rust,
+                ("backend", "wic") => options.backend = PngBackend::Wic,
+                ("depth" | "alpha" | "backend", _) => return Err(format!("invalid PNG option value: {}", item)),
+                _ => return Err(format!("unknown PNG option: {} (depth, alpha, level, backend)", key)),
+            }
+        }
+        Ok(options)
+    }
+}
+
+/// 1 行分のサンプルを PNG の並び（RGB(A)、16bit はビッグエンディアン）で追加
+fn push_row(view: &ImageView, y: u32, options: &PngOptions, out: &mut Vec<u8>) -> Result<(), ImageError> {
+    let order = &[0, 1, 2, 3][..options.channels()];
+    if options.bit_depth == PngBitDepth::Eight {
+        return push_row8(view, y, order, out);
+    }
+
+    // 範囲外（HDR の 1.0 超えなど）は切り詰める。8bit は v / 255 * 65535 で 257 倍と一致する。
+    // PNG の値は sRGB エンコードとして読まれるので、FLOAT（リニア）のカラーは sRGB へエンコードする
+    let format = view.format();
+    let linear = TransferFunction::for_format(format) == TransferFunction::Linear;
+    for px in view.row(y).chunks_exact(view.bytes_per_pixel()) {
+        let rgba = decode_pixel(px, format)?;
+        for &c in order {
+            let v = if linear && c < 3 { linear_to_srgb(rgba[c]) } else { rgba[c] };
+            out.extend((quantize(v, 65535.0) as u16).to_be_bytes());
+        }
+    }
+    Ok(())
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let (pa, pb, pc) = ((p - a as i16).abs(), (p - b as i16).abs(), (p - c as i16).abs());
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+/// フィルター `filter` を掛けた行を `out` に書く（先頭のフィルター種別は含まない）
+fn apply_filter(filter: u8, row: &[u8], previous: &[u8], bpp: usize, out: &mut [u8]) {
+    for i in 0..row.len() {
+        let left = if i >= bpp { row[i - bpp] } else { 0 };
+        let up = previous[i];
+        let up_left = if i >= bpp { previous[i - bpp] } else { 0 };
+        let predictor = match filter {
+            0 => 0,
+            1 => left,
+            2 => up,
+            3 => ((left as u16 + up as u16) / 2) as u8,
+            _ => paeth(left, up, up_left),
+        };
+        out[i] = row[i].wrapping_sub(predictor);
+    }
+}
+
+/// 行ごとにフィルターを掛けた画像データ（無圧縮なら None、それ以外は絶対値和が最小のもの）
+fn filter_rows(samples: &[u8], stride: usize, bpp: usize, compression: u8) -> Vec<u8> {
+    let mut out = Vec::with_capacity(samples.len() + samples.len() / stride.max(1));
+    let zero = vec![0u8; stride];
+    let mut candidate = vec![0u8; stride];
+    let mut best = vec![0u8; stride];
+
+    for (y, row) in samples.chunks_exact(stride).enumerate() {
+        let previous = if y == 0 { &zero[..] } else { &samples[(y - 1) * stride..y * stride] };
+        if compression == 0 {
+            out.push(0);
+            out.extend_from_slice(row);
+            continue;
+        }
+        let mut best_filter = 0;
+        let mut best_score = u64::MAX;
+        for filter in 0..5 {
+            apply_filter(filter, row, previous, bpp, &mut candidate);
+            let score: u64 = candidate.iter().map(|&v| (v as i8).unsigned_abs() as u64).sum();
+            if score < best_score {
+                (best_filter, best_score) = (filter, score);
+                std::mem::swap(&mut best, &mut candidate);
+            }
+        }
+        out.push(best_filter);
+        out.extend_from_slice(&best);
+    }
+    out
+}
+
+fn push_chunk(out: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    out.extend((data.len() as u32).to_be_bytes());
+    out.extend(kind);
+    out.extend(data);
+    let mut crc = Crc32::new();
+    crc.update(kind);
+    crc.update(data);
+    out.extend(crc.finish().to_be_bytes());
+}
+
+/// 8bit RGBA の行を詰めて並べたバイト列（WIC に渡す用）
+#[cfg(windows)]
+fn rgba8_samples(view: &ImageView) -> Result<Vec<u8>, ImageError> {
+    let mut samples = Vec::with_capacity(view.width() as usize * view.height() as usize * 4);
+    for y in 0..view.height() {
+        push_row8(view, y, &[0, 1, 2, 3], &mut samples)?;
+    }
+    Ok(samples)
+}
+
+/// フィルターを掛けて zlib で圧縮した画像データ（IDAT / fdAT の中身）
+fn compress_image(view: &ImageView, options: &PngOptions) -> Result<Vec<u8>, ImageError> {
+    let bpp = options.bytes_per_pixel();
+    let stride = view.width() as usize * bpp;
+
+    let mut samples = Vec::with_capacity(stride * view.height() as usize);
+    for y in 0..view.height() {
+        push_row(view, y, options, &mut samples)?;
+    }
+    let filtered = filter_rows(&samples, stride, bpp, options.compression);
+    Ok(zlib_compress(&filtered, options.compression))
+}
+
+fn ihdr(width: u32, height: u32, options: &PngOptions) -> Vec<u8> {
+    let mut ihdr = Vec::with_capacity(13);
+    ihdr.extend(width.to_be_bytes());
+    ihdr.extend(height.to_be_bytes());
+    // ビット深度, カラータイプ (2: RGB, 6: RGBA), 圧縮, フィルター, インターレースなし
+    ihdr.extend([options.bit_depth.bits(), if options.alpha { 6 } else { 2 }, 0, 0, 0]);
+    ihdr
+}
+
+/// PNG ファイルのバイト列にエンコード（`backend` は無視する）
+pub fn encode_png(view: &ImageView, options: &PngOptions) -> Result<Vec<u8>, ImageError> {
+    let compressed = compress_image(view, options)?;
+
+    let mut out = Vec::with_capacity(compressed.len() + 64);
+    out.extend(SIGNATURE);
+    push_chunk(&mut out, b"IHDR", &ihdr(view.width(), view.height(), options));
+    for chunk in compressed.chunks(IDAT_CHUNK) {
+        push_chunk(&mut out, b"IDAT", chunk);
+    }
+    push_chunk(&mut out, b"IEND", &[]);
+    Ok(out)
+}
+
+/// APNG（アニメーション PNG）にエンコード
+///
+/// 1 枚目はキャンバス全体（IDAT、アニメーションの最初のフレームを兼ねる）、
+/// 2 枚目以降は前のフレームに上書きする部分画像（dispose なし、blend なし）。
+/// `loops` は繰り返し回数（0 は無限）、表示時間は 1/1000 秒単位。
+pub fn encode_apng(
+    width: u32,
+    height: u32,
+    frames: &[AnimationFrame],
+    loops: u16,
+    options: &PngOptions,
+) -> Result<Vec<u8>, ImageError> {
+    match frames.first() {
+        Some(first) if (first.x, first.y, first.view.width(), first.view.height()) == (0, 0, width, height) => {}
+        _ => return Err(ImageError::InvalidDimensions { width, height, row_pitch: 0 }),
+    }
+    let delays = frame_delays(frames, 1_000, 1);
+
+    let mut out = Vec::new();
+    out.extend(SIGNATURE);
+    push_chunk(&mut out, b"IHDR", &ihdr(width, height, options));
+    let mut actl = (frames.len() as u32).to_be_bytes().to_vec();
+    actl.extend((loops as u32).to_be_bytes());
+    push_chunk(&mut out, b"acTL", &actl);
+
+    // fcTL と fdAT で共通の連番
+    let mut sequence = 0u32;
+    for (i, (frame, &delay)) in frames.iter().zip(&delays).enumerate() {
+        let mut fctl = Vec::with_capacity(26);
+        for v in [sequence, frame.view.width(), frame.view.height(), frame.x, frame.y] {
+            fctl.extend(v.to_be_bytes());
+        }
+        fctl.extend((delay.min(u16::MAX as u32) as u16).to_be_bytes());
+        fctl.extend(1000u16.to_be_bytes());
+        // dispose_op = NONE, blend_op = SOURCE
+        fctl.extend([0, 0]);
+        push_chunk(&mut out, b"fcTL", &fctl);
+        sequence += 1;
+
+        let compressed = compress_image(&frame.view, options)?;
+        for chunk in compressed.chunks(IDAT_CHUNK) {
+            if i == 0 {
+                push_chunk(&mut out, b"IDAT", chunk);
+            } else {
+                let mut fdat = Vec::with_capacity(chunk.len() + 4);
+                fdat.extend(sequence.to_be_bytes());
+                fdat.extend(chunk);
+                push_chunk(&mut out, b"fdAT", &fdat);
+                sequence += 1;
+            }
+        }
+    }
+    push_chunk(&mut out, b"IEND", &[]);
+    Ok(out)
+}
+
+/// フィルターを外す（`row` は先頭のフィルター種別を除いた行、その場で書き換える）
+fn unfilter(filter: u8, row: &mut [u8], previous: &[u8], bpp: usize) -> Result<(), ImportError> {
+    for i in 0..row.len() {
+        let left = if i >= bpp { row[i - bpp] } else { 0 };
+        let up = previous[i];
+        let up_left = if i >= bpp { previous[i - bpp] } else { 0 };
+        let predictor = match filter {
+            0 => 0,
+            1 => left,
+            2 => up,
+            3 => ((left as u16 + up as u16) / 2) as u8,
+            4 => paeth(left, up, up_left),
+            _ => return Err(corrupt(format!("invalid PNG filter type: {}", filter))),
+        };
+        row[i] = row[i].wrapping_add(predictor);
+    }
+    Ok(())
+}
+
+/// PNG の画像情報（IHDR と色に関わる補助チャンク）
+struct PngHeader {
+    width: u32,
+    height: u32,
+    bit_depth: u8,
+    color_type: u8,
+    interlaced: bool,
+    palette: Vec<[u8; 3]>,
+    transparency: Vec<u8>,
+}
+
+impl PngHeader {
+    fn channels(&self) -> usize {
+        match self.color_type {
+            2 => 3,
+            4 => 2,
+            6 => 4,
+            _ => 1,
+        }
+    }
+
+    fn bits_per_pixel(&self) -> usize {
+        self.channels() * self.bit_depth as usize
+    }
+
+    /// 1 行のバイト数（フィルター種別を除く）
+    fn stride(&self, width: u32) -> usize {
+        (width as usize * self.bits_per_pixel()).div_ceil(8)
+    }
+
+    /// 各パスの (開始 x, 開始 y, x の間隔, y の間隔, 幅, 高さ)
+    fn passes(&self) -> Vec<(u32, u32, u32, u32, u32, u32)> {
+        let passes: &[(u32, u32, u32, u32)] = if self.interlaced { &ADAM7 } else { &[(0, 0, 1, 1)] };
+        passes
+            .iter()
+            .map(|&(x0, y0, dx, dy)| {
+                let count = |size: u32, start: u32, step: u32| size.saturating_sub(start).div_ceil(step);
+                (x0, y0, dx, dy, count(self.width, x0, dx), count(self.height, y0, dy))
+            })
+            .collect()
+    }
+
+    /// 1 ピクセルの RGBA（8bit 以下は 0..=255、16bit は 0..=65535）
+    fn rgba(&self, row: &[u8], x: usize) -> Result<[u16; 4], ImportError> {
+        let depth = self.bit_depth as usize;
+        let sample = |i: usize| -> u16 {
+            let bit = (x * self.channels() + i) * depth;
+            match depth {
+                16 => u16::from_be_bytes([row[bit / 8], row[bit / 8 + 1]]),
+                8 => row[bit / 8] as u16,
+                _ => (row[bit / 8] >> (8 - depth - bit % 8)) as u16 & ((1 << depth) - 1),
+            }
+        };
+        // 8bit 未満のグレーは 0..=255 に伸ばす
+        let scale = |v: u16| if depth < 8 { v * 255 / ((1 << depth) - 1) } else { v };
+        let max = if depth == 16 { 65535 } else { 255 };
+        let transparent = |values: &[u16]| {
+            let key = self.transparency.chunks_exact(2).map(|c| u16::from_be_bytes([c[0], c[1]]));
+            self.transparency.len() == values.len() * 2 && key.eq(values.iter().copied())
+        };
+        Ok(match self.color_type {
+            0 => {
+                let v = sample(0);
+                [scale(v), scale(v), scale(v), if transparent(&[v]) { 0 } else { max }]
+            }
+            2 => {
+                let rgb = [sample(0), sample(1), sample(2)];
+                [rgb[0], rgb[1], rgb[2], if transparent(&rgb) { 0 } else { max }]
+            }
+            3 => {
+                let index = sample(0) as usize;
+                let [r, g, b] = *self
+                    .palette
+                    .get(index)
+                    .ok_or_else(|| corrupt(format!("palette index {} out of range", index)))?;
+                let alpha = self.transparency.get(index).copied().unwrap_or(255);
+                [r as u16, g as u16, b as u16, alpha as u16]
+            }
+            4 => [sample(0), sample(0), sample(0), sample(1)],
+            _ => [sample(0), sample(1), sample(2), sample(3)],
+        })
+    }
+}
+
+/// PNG ファイルをデコード（8bit 以下は 8bit RGBA、16bit はリニアの f32 RGBA）
+pub fn decode_png(bytes: &[u8]) -> Result<ImageBuf, ImportError> {
+    if slice(bytes, 0, 8)? != SIGNATURE {
+        return Err(corrupt("not a PNG file"));
+    }
+    let mut header = None;
+    let mut palette = Vec::new();
+    let mut transparency = Vec::new();
+    let mut idat = Vec::new();
+    let mut at = SIGNATURE.len();
+    loop {
+        let length = be_u32(bytes, at)? as usize;
+        let kind = slice(bytes, at + 4, 4)?;
+        let data = slice(bytes, at + 8, length)?;
+        let mut crc = Crc32::new();
+        crc.update(kind);
+        crc.update(data);
+        if crc.finish() != be_u32(bytes, at + 8 + length)? {
+            return Err(corrupt(format!("CRC mismatch in {} chunk", String::from_utf8_lossy(kind))));
+        }
+        at += 12 + length;
+        match kind {
+            b"IHDR" => header = Some(slice(data, 0, 13)?),
+            b"PLTE" => palette = data.chunks_exact(3).map(|c| [c[0], c[1], c[2]]).collect(),
+            b"tRNS" => transparency = data.to_vec(),
+            b"IDAT" => idat.extend_from_slice(data),
+            b"IEND" => break,
+            // 補助チャンク（先頭が小文字）は読み飛ばす
+            _ if kind[0].is_ascii_lowercase() => {}
+            _ => {
+                return Err(ImportError::Unsupported(format!(
+                    "critical PNG chunk {}",
+                    String::from_utf8_lossy(kind)
+                )));
+            }
+        }
+    }
+
+    let ihdr = header.ok_or_else(|| corrupt("missing IHDR"))?;
+    let header = PngHeader {
+        width: be_u32(ihdr, 0)?,
+        height: be_u32(ihdr, 4)?,
+        bit_depth: ihdr[8],
+        color_type: ihdr[9],
+        interlaced: match ihdr[12] {
+            0 => false,
+            1 => true,
+            method => return Err(corrupt(format!("invalid interlace method: {}", method))),
+        },
+        palette,
+        transparency,
+    };
+    check_dimensions(header.width, header.height)?;
+    let valid = match header.color_type {
+        0 => matches!(header.bit_depth, 1 | 2 | 4 | 8 | 16),
+        3 => matches!(header.bit_depth, 1 | 2 | 4 | 8) && !header.palette.is_empty(),
+        2 | 4 | 6 => matches!(header.bit_depth, 8 | 16),
+        _ => false,
+    };
+    if !valid || ihdr[10] != 0 || ihdr[11] != 0 {
+        return Err(corrupt(format!("invalid IHDR: color type {}, bit depth {}", header.color_type, header.bit_depth)));
+    }
+
+    let passes = header.passes();
+    let expected: usize = passes
+        .iter()
+        .filter(|pass| pass.4 > 0)
+        .map(|pass| pass.5 as usize * (1 + header.stride(pass.4)))
+        .sum();
+    let raw = zlib_decompress(&idat, expected).map_err(corrupt)?;
+    if raw.len() < expected {
+        return Err(corrupt(format!("image data too short: {} of {} bytes", raw.len(), expected)));
+    }
+
+    let bpp = header.bits_per_pixel().div_ceil(8);
+    let width = header.width as usize;
+    let mut samples = vec![0u16; width * header.height as usize * 4];
+    let mut at = 0;
+    for (x0, y0, dx, dy, pass_width, pass_height) in passes {
+        if pass_width == 0 {
+            continue;
+        }
+        let stride = header.stride(pass_width);
+        let mut previous = vec![0u8; stride];
+        let mut row = vec![0u8; stride];
+        for py in 0..pass_height {
+            row.copy_from_slice(&raw[at + 1..at + 1 + stride]);
+            unfilter(raw[at], &mut row, &previous, bpp)?;
+            at += 1 + stride;
+            let y = (y0 + py * dy) as usize;
+            for px in 0..pass_width as usize {
+                let x = x0 as usize + px * dx as usize;
+                samples[(y * width + x) * 4..][..4].copy_from_slice(&header.rgba(&row, px)?);
+            }
+            std::mem::swap(&mut previous, &mut row);
+        }
+    }
+    rgba_image(header.width, header.height, &samples, if header.bit_depth == 16 { 65535 } else { 255 })
+}
+
+/// 既定の設定（8bit RGBA、レベル 6、Rust 実装）で保存
+pub fn save_png(path: &Path, view: &ImageView) -> Result<(), ExportError> {
+    save_png_with(path, view, &PngOptions::default())
+}
+
+/// 設定を指定して保存
+pub fn save_png_with(path: &Path, view: &ImageView, options: &PngOptions) -> Result<(), ExportError> {
+    match options.backend {
+        PngBackend::Rust => write_file(path, &encode_png(view, options)?),
+        PngBackend::Wic => save_png_wic(path, view, options),
+    }
+}
+
+#[cfg(windows)]
+fn save_png_wic(path: &Path, view: &ImageView, options: &PngOptions) -> Result<(), ExportError> {
+    if options.bit_depth != PngBitDepth::Eight || !options.alpha {
+        return Err(ExportError::Unsupported(format!("WIC backend only writes 8-bit RGBA (requested {})", options)));
+    }
+    let samples = rgba8_samples(view)?;
+    crate::wic::save_rgba8(path, view.width(), view.height(), &samples).map_err(|e| ExportError::Backend(e.to_string()))
+}
+
+#[cfg(not(windows))]
+fn save_png_wic(_path: &Path, _view: &ImageView, _options: &PngOptions) -> Result<(), ExportError> {
+    Err(ExportError::Unsupported("WIC backend is only available on Windows".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::srgb_to_linear;
+    use crate::image::DxgiFormat;
+
+    const WIDTH: u32 = 23;
+    const HEIGHT: u32 = 7;
+
+    /// 行ごとに傾向の違う 16bit のサンプル（グラデーション・ノイズ・平坦）
+    fn samples16() -> Vec<u16> {
+        let mut state = 0x1234_5678u32;
+        let mut samples = Vec::new();
+        for y in 0..HEIGHT {
+            for x in 0..WIDTH {
+                for c in 0..4 {
+                    state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                    samples.push(match y % 3 {
+                        0 => (x * 2800 + c * 9000) as u16,
+                        1 => (state >> 16) as u16,
+                        _ => 0x8000 + c as u16,
+                    });
+                }
+            }
+        }
+        samples
+    }
+
+    /// 8bit RGBA のビュー
+    fn image8(alpha: bool) -> ImageBuf {
+        let data = samples16()
+            .iter()
+            .enumerate()
+            .map(|(i, &v)| if !alpha && i % 4 == 3 { 255 } else { (v >> 8) as u8 })
+            .collect();
+        ImageBuf::from_vec(data, WIDTH, HEIGHT, WIDTH as usize * 4, DxgiFormat::R8G8B8A8Unorm).unwrap()
+    }
+
+    /// 16bit の値を読み込みと同じ式でリニアにした f32 RGBA のビュー
+    fn image16(alpha: bool) -> ImageBuf {
+        let mut data = Vec::new();
+        for (i, &v) in samples16().iter().enumerate() {
+            let v = v as f32 / 65535.0;
+            let v = match i % 4 {
+                3 if alpha => v,
+                3 => 1.0,
+                _ => srgb_to_linear(v),
+            };
+            data.extend(v.to_le_bytes());
+        }
+        ImageBuf::from_vec(data, WIDTH, HEIGHT, WIDTH as usize * 16, DxgiFormat::R32G32B32A32Float).unwrap()
+    }
+
+    fn all_options() -> impl Iterator<Item = PngOptions> {
+        [PngBitDepth::Eight, PngBitDepth::Sixteen].into_iter().flat_map(|bit_depth| {
+            [true, false].into_iter().flat_map(move |alpha| {
+                (0..=9).map(move |compression| PngOptions { bit_depth, alpha, compression, ..PngOptions::default() })
+            })
+        })
+    }
+
+    fn source(options: &PngOptions) -> ImageBuf {
+        match options.bit_depth {
+            PngBitDepth::Eight => image8(options.alpha),
+            PngBitDepth::Sixteen => image16(options.alpha),
+        }
+    }
+
+    /// すべての行に同じフィルターを掛けた PNG（デコーダーの各フィルターを通すため）
+    fn encode_with_filter(view: &ImageView, options: &PngOptions, filter: u8) -> Vec<u8> {
+        let bpp = options.bytes_per_pixel();
+        let stride = view.width() as usize * bpp;
+        let mut samples = Vec::new();
+        for y in 0..view.height() {
+            push_row(view, y, options, &mut samples).unwrap();
+        }
+        let mut filtered = Vec::new();
+        let mut row = vec![0u8; stride];
+        let zero = vec![0u8; stride];
+        for (y, line) in samples.chunks_exact(stride).enumerate() {
+            let previous = if y == 0 { &zero[..] } else { &samples[(y - 1) * stride..y * stride] };
+            apply_filter(filter, line, previous, bpp, &mut row);
+            filtered.push(filter);
+            filtered.extend_from_slice(&row);
+        }
+        let mut out = SIGNATURE.to_vec();
+        push_chunk(&mut out, b"IHDR", &ihdr(view.width(), view.height(), options));
+        push_chunk(&mut out, b"IDAT", &zlib_compress(&filtered, options.compression));
+        push_chunk(&mut out, b"IEND", &[]);
+        out
+    }
+
+    #[test]
+    fn encode_decode_is_exact_at_every_level() {
+        for options in all_options() {
+            let image = source(&options);
+            let decoded = decode_png(&encode_png(&image.view(), &options).unwrap()).unwrap();
+            assert_eq!(decoded.format(), image.format(), "{}", options);
+            assert_eq!(decoded.data(), image.data(), "{}", options);
+        }
+    }
+
+    #[test]
+    fn encode_decode_is_exact_with_every_filter() {
+        for options in all_options() {
+            let image = source(&options);
+            for filter in 0..5 {
+                let decoded = decode_png(&encode_with_filter(&image.view(), &options, filter)).unwrap();
+                assert_eq!(decoded.data(), image.data(), "{} filter {}", options, filter);
+            }
+        }
+    }
+
+    #[test]
+    fn float_views_are_srgb_encoded_in_16bit() {
+        // リニアの 0.5 は sRGB で約 0.7354
+        let data: Vec<u8> = [0.5f32, 0.0, 1.0, 0.5].iter().flat_map(|v| v.to_le_bytes()).collect();
+        let image = ImageBuf::from_vec(data, 1, 1, 16, DxgiFormat::R32G32B32A32Float).unwrap();
+        let options = PngOptions { bit_depth: PngBitDepth::Sixteen, ..PngOptions::default() };
+        let mut row = Vec::new();
+        push_row(&image.view(), 0, &options, &mut row).unwrap();
+        let values: Vec<u16> = row.chunks_exact(2).map(|c| u16::from_be_bytes([c[0], c[1]])).collect();
+        assert_eq!(values, [quantize(linear_to_srgb(0.5), 65535.0) as u16, 0, 65535, 32768]);
+    }
+}
@@ -0,0 +1,133 @@
+// ============================================================
+// Netpbm 書き出し（PPM / PAM）・読み込み
+// ============================================================
+//
+// ヘッダーがテキストで本体は生のサンプルだけなので、最も軽い形式。
+// PPM はアルファを持てないため RGB だけ、PAM は RGBA をそのまま書く。
+// 読み込みはバイナリの PGM（P5）・PPM（P6）・PAM（P7）、MAXVAL は 65535 まで。
+
+use crate::export::push_row8;
+use crate::image::{ImageBuf, ImageError, ImageView};
+use crate::import::{check_dimensions, corrupt, rgba_image, slice, ImportError};
+
+fn encode(view: &ImageView, header: String, order: &[usize]) -> Result<Vec<u8>, ImageError> {
+    let mut out = Vec::with_capacity(header.len() + view.width() as usize * view.height() as usize * order.len());
+    out.extend(header.as_bytes());
+    for y in 0..view.height() {
+        push_row8(view, y, order, &mut out)?;
+    }
+    Ok(out)
+}
+
+/// バイナリ PPM（P6、RGB 8bit）にエンコード
+pub fn encode_ppm(view: &ImageView) -> Result<Vec<u8>, ImageError> {
+    let header = format!("P6\n{} {}\n255\n", view.width(), view.height());
+    encode(view, header, &[0, 1, 2])
+}
+
+/// PAM（P7、RGB_ALPHA 8bit）にエンコード
+pub fn encode_pam(view: &ImageView) -> Result<Vec<u8>, ImageError> {
+    let header = format!(
+        "P7\nWIDTH {}\nHEIGHT {}\nDEPTH 4\nMAXVAL 255\nTUPLTYPE RGB_ALPHA\nENDHDR\n",
+        view.width(),
+        view.height()
+    );
+    encode(view, header, &[0, 1, 2, 3])
+}
+
+/// ヘッダーのトークンを順に読む（`#` から行末まではコメント）
+struct Header<'a> {
+    bytes: &'a [u8],
+    at: usize,
+}
+
+impl<'a> Header<'a> {
+    fn token(&mut self) -> Result<&'a str, ImportError> {
+        loop {
+            match self.bytes.get(self.at) {
+                Some(b'#') => {
+                    while self.bytes.get(self.at).is_some_and(|&b| b != b'\n') {
+                        self.at += 1;
+                    }
+                }
+                Some(b) if b.is_ascii_whitespace() => self.at += 1,
+                Some(_) => break,
+                None => return Err(corrupt("unexpected end of Netpbm header")),
+            }
+        }
+        let start = self.at;
+        while self.bytes.get(self.at).is_some_and(|b| !b.is_ascii_whitespace()) {
+            self.at += 1;
+        }
+        std::str::from_utf8(&self.bytes[start..self.at]).map_err(|_| corrupt("invalid Netpbm header"))
+    }
+
+    fn number(&mut self) -> Result<u32, ImportError> {
+        let token = self.token()?;
+        token.parse().map_err(|_| corrupt(format!("invalid Netpbm header value: {}", token)))
+    }
+
+    /// P7 の `KEY value` 行（ENDHDR まで）
+    fn line(&mut self) -> Result<(&'a str, &'a str), ImportError> {
+        let key = self.token()?;
+        if key == "ENDHDR" {
+            return Ok((key, ""));
+        }
+        Ok((key, self.token()?))
+    }
+}
+
+/// バイナリの PGM / PPM / PAM をデコード（MAXVAL が 255 を超えればリニアの float）
+pub fn decode_pnm(bytes: &[u8]) -> Result<ImageBuf, ImportError> {
+    let mut header = Header { bytes, at: 0 };
+    let magic = header.token()?;
+    let (width, height, depth, maxval) = match magic {
+        "P5" | "P6" => {
+            let (width, height, maxval) = (header.number()?, header.number()?, header.number()?);
+            (width, height, if magic == "P5" { 1 } else { 3 }, maxval)
+        }
+        "P7" => {
+            let (mut width, mut height, mut depth, mut maxval) = (0, 0, 0, 0);
+            loop {
+                let (key, value) = header.line()?;
+                let parse = || value.parse().map_err(|_| corrupt(format!("invalid PAM header value: {}", value)));
+                match key {
+                    "WIDTH" => width = parse()?,
+                    "HEIGHT" => height = parse()?,
+                    "DEPTH" => depth = parse()?,
+                    "MAXVAL" => maxval = parse()?,
+                    "ENDHDR" => break,
+                    // TUPLTYPE は DEPTH から判断できる
+                    _ => {}
+                }
+            }
+            (width, height, depth, maxval)
+        }
+        _ => return Err(ImportError::Unsupported(format!("Netpbm type {} (only P5, P6, P7)", magic))),
+    };
+    check_dimensions(width, height)?;
+    if !(1..=4).contains(&depth) || !(1..=65535).contains(&maxval) {
+        return Err(corrupt(format!("invalid Netpbm depth {} / maxval {}", depth, maxval)));
+    }
+    // ヘッダーの最後は空白 1 文字
+    let start = header.at + 1;
+    let sample_size = if maxval > 255 { 2 } else { 1 };
+    let data = slice(bytes, start, width as usize * height as usize * depth as usize * sample_size)?;
+
+    let mut samples = Vec::with_capacity(width as usize * height as usize * 4);
+    let max = maxval as u16;
+    for px in data.chunks_exact(depth as usize * sample_size) {
+        let sample = |i: usize| match sample_size {
+            2 => u16::from_be_bytes([px[i * 2], px[i * 2 + 1]]).min(max),
+            _ => (px[i] as u16).min(max),
+        };
+        let rgba = match depth {
+            1 => [sample(0), sample(0), sample(0), max],
+            2 => [sample(0), sample(0), sample(0), sample(1)],
+            3 => [sample(0), sample(1), sample(2), max],
+            _ => [sample(0), sample(1), sample(2), sample(3)],
+        };
+        samples.extend(rgba);
+    }
+    rgba_image(width, height, &samples, maxval)
+}
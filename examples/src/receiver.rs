@@ -0,0 +1,27 @@
+// ============================================================
+// 受信側の共通型（C ABI / モック共通）
+// ============================================================
+
+use crate::alpha::AlphaOp;
+use crate::image::DxgiFormat;
+use crate::transform::Transform;
+
+/// 受信オプション
+///
+/// C ABI では `spoutdx_receiver_set_transform`、モックでは
+/// `MockReceiver::set_options` で設定する。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ReceiverOptions {
+    pub transform: Transform,
+    /// 変換の後に適用（Rust 側のみ）
+    pub alpha: AlphaOp,
+}
+
+/// センダー情報（`SpoutDxSenderInfo` の Rust 版）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SenderInfo {
+    pub name: String,
+    pub width: u32,
+    pub height: u32,
+    pub format: DxgiFormat,
+}
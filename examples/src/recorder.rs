@@ -0,0 +1,741 @@
+// ============================================================
+// 連番画像の録画（バックグラウンド書き込み・リングバッファ・容量上限）
+// ============================================================
+//
+// 受信ループは Recorder::offer に新しいフレームを渡すだけで、エンコードと
+// 書き込みは別スレッドで行う。キューが満杯のときは捨てるか待つかを選べる。
+// トリガー前はリングバッファに直近のフレームだけを残し、trigger() で
+// まとめて書き出す（「直前の 5 秒を保存」）。リングバッファは枚数とバイト数でも
+// 制限し、trigger() はキューの方針に関係なく全部入るまで待つ。
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{sync_channel, Receiver, SyncSender, TrySendError};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+
+use serde::Serialize;
+
+use crate::export::{encode_image, ImageFormat};
+use crate::image::{DxgiFormat, ImageBuf, ImageError};
+use crate::mock::MockReceiver;
+use crate::pattern::{FrameStamp, Pattern};
+
+/// 既定のファイル名
+pub const DEFAULT_TEMPLATE: &str = "{sender}_{frame:06}_{timestamp}.{ext}";
+/// トリガー前のリングバッファのバイト数の既定の上限（4K RGBA で約 2 秒）
+pub const DEFAULT_PRE_MAX_BYTES: u64 = 1 << 30;
+
+/// テンプレートに書ける項目
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    /// センダー名（ファイル名に使えない文字は `_`）
+    Sender,
+    /// センダーのフレーム番号
+    Frame,
+    /// 受信時刻（ミリ秒）
+    Timestamp,
+    /// 録画を始めてからの連番
+    Index,
+    /// 形式の拡張子
+    Ext,
+}
+
+impl Field {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "sender" => Some(Self::Sender),
+            "frame" => Some(Self::Frame),
+            "timestamp" => Some(Self::Timestamp),
+            "index" => Some(Self::Index),
+            "ext" => Some(Self::Ext),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Literal(String),
+    /// 項目と最小桁数（数値は 0 埋め）
+    Field(Field, usize),
+}
+
+/// 出力ファイル名のテンプレート（`{frame:06}` のように桁数を指定できる）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NameTemplate {
+    source: String,
+    segments: Vec<Segment>,
+}
+
+impl Default for NameTemplate {
+    fn default() -> Self {
+        DEFAULT_TEMPLATE.parse().expect("default template is valid")
+    }
+}
+
+impl NameTemplate {
+    /// ファイル名を組み立てる（`index` は録画内の連番）
+    pub fn render(&self, meta: &FrameMeta, index: u64, format: ImageFormat) -> String {
+        let mut name = String::new();
+        for segment in &self.segments {
+            match segment {
+                Segment::Literal(text) => name.push_str(text),
+                Segment::Field(Field::Sender, _) => name.push_str(&sanitize(&meta.sender)),
+                Segment::Field(Field::Frame, width) => name.push_str(&format!("{:0width$}", meta.frame)),
+                Segment::Field(Field::Timestamp, width) => {
+                    name.push_str(&format!("{:0width$}", meta.timestamp_us / 1000))
+                }
+                Segment::Field(Field::Index, width) => name.push_str(&format!("{:0width$}", index)),
+                Segment::Field(Field::Ext, _) => name.push_str(format.extension()),
+            }
+        }
+        name
+    }
+}
+
+impl fmt::Display for NameTemplate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+impl FromStr for NameTemplate {
+    type Err = String;
+
+    /// `{sender}_{frame:06}.{ext}`（項目は sender, frame, timestamp, index, ext）
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut segments = Vec::new();
+        let mut rest = s;
+        while let Some(start) = rest.find('{') {
+            if rest[..start].contains('}') {
+                return Err(format!("unmatched '}}' in template: {}", s));
+            }
+            if start > 0 {
+                segments.push(Segment::Literal(rest[..start].to_string()));
+            }
+            let end = rest[start..]
+                .find('}')
+                .ok_or_else(|| format!("unterminated placeholder in template: {}", s))?;
+            let placeholder = &rest[start + 1..start + end];
+            let (name, width) = match placeholder.split_once(':') {
+                Some((name, width)) => {
+                    let width = width.parse().map_err(|_| format!("invalid width in placeholder: {{{}}}", placeholder))?;
+                    (name, width)
+                }
+                None => (placeholder, 0),
+            };
+            let field = Field::parse(name).ok_or_else(|| {
+                format!("unknown placeholder: {{{}}} (sender, frame, timestamp, index, ext)", name)
+            })?;
+            segments.push(Segment::Field(field, width));
+            rest = &rest[start + end + 1..];
+        }
+        if rest.contains('}') {
+            return Err(format!("unmatched '}}' in template: {}", s));
+        }
+        if !rest.is_empty() {
+            segments.push(Segment::Literal(rest.to_string()));
+        }
+        // 連番になる項目がないと同じファイルを上書きし続ける
+        if !segments
+            .iter()
+            .any(|segment| matches!(segment, Segment::Field(Field::Frame | Field::Timestamp | Field::Index, _)))
+        {
+            return Err(format!("template needs {{frame}}, {{timestamp}} or {{index}}: {}", s));
+        }
+        Ok(Self { source: s.to_string(), segments })
+    }
+}
+
+/// ファイル名に使えない文字を `_` にする
+fn sanitize(name: &str) -> String {
+    let sanitized: String = name
+        .chars()
+        .map(|c| if c.is_alphanumeric() || matches!(c, '-' | '_' | '.') { c } else { '_' })
+        .collect();
+    if sanitized.is_empty() { "sender".to_string() } else { sanitized }
+}
+
+/// 録画するフレームの情報
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrameMeta {
+    pub sender: String,
+    pub frame: u64,
+    /// 受信時刻（マイクロ秒、Clock の値）
+    pub timestamp_us: u64,
+}
+
+/// キューが満杯のときの動作
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum QueuePolicy {
+    /// フレームを捨てる（受信ループを止めない）
+    #[default]
+    Drop,
+    /// 書き込みが追いつくまで待つ（取りこぼさない）
+    Block,
+}
+
+impl fmt::Display for QueuePolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Drop => write!(f, "drop"),
+            Self::Block => write!(f, "block"),
+        }
+    }
+}
+
+/// 録画の設定
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecorderOptions {
+    pub directory: PathBuf,
+    pub template: NameTemplate,
+    pub format: ImageFormat,
+    /// 新しいフレーム N 枚ごとに 1 枚保存（1 なら全部）
+    pub every: u32,
+    /// 書き込み待ちのフレーム数の上限
+    pub queue: usize,
+    pub policy: QueuePolicy,
+    /// Some ならトリガーまでこの時間分だけリングバッファに残す
+    pub pre_trigger_us: Option<u64>,
+    /// リングバッファの枚数の上限（None なら時間だけで決める）
+    pub pre_max_frames: Option<usize>,
+    /// リングバッファの画素のバイト数の上限
+    pub pre_max_bytes: u64,
+    /// 書き込むバイト数の上限（超えるフレームからは保存しない）
+    pub max_bytes: Option<u64>,
+}
+
+impl Default for RecorderOptions {
+    fn default() -> Self {
+        Self {
+            directory: PathBuf::from("."),
+            template: NameTemplate::default(),
+            format: ImageFormat::Qoi,
+            every: 1,
+            queue: 8,
+            policy: QueuePolicy::Drop,
+            pre_trigger_us: None,
+            pre_max_frames: None,
+            pre_max_bytes: DEFAULT_PRE_MAX_BYTES,
+            max_bytes: None,
+        }
+    }
+}
+
+/// `500M` / `2G` / `64k` のようなバイト数（1024 単位）
+fn parse_bytes(value: &str) -> Option<u64> {
+    let value = value.to_ascii_lowercase();
+    let value = value.trim_end_matches('b');
+    let (digits, scale) = match value.char_indices().last()? {
+        (i, 'k') => (&value[..i], 1u64 << 10),
+        (i, 'm') => (&value[..i], 1 << 20),
+        (i, 'g') => (&value[..i], 1 << 30),
+        _ => (value, 1),
+    };
+    digits.parse::<u64>().ok()?.checked_mul(scale)
+}
+
+impl fmt::Display for RecorderOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} in {} as {}, every {} new frame(s), queue {} ({})",
+            self.template,
+            self.directory.display(),
+            self.format,
+            self.every,
+            self.queue,
+            self.policy
+        )?;
+        if let Some(pre_trigger_us) = self.pre_trigger_us {
+            write!(f, ", pre-trigger {:.1} s", pre_trigger_us as f64 / 1_000_000.0)?;
+            if let Some(frames) = self.pre_max_frames {
+                write!(f, " (max {} frames)", frames)?;
+            }
+            write!(f, " (max {:.1} MiB)", self.pre_max_bytes as f64 / (1 << 20) as f64)?;
+        }
+        if let Some(max_bytes) = self.max_bytes {
+            write!(f, ", max {:.1} MiB", max_bytes as f64 / (1 << 20) as f64)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for RecorderOptions {
+    type Err = String;
+
+    /// `dir=captures,format=png,every=2,queue=16,policy=block,pre=5,max=500M,template={index:05}.{ext}`
+    /// `pre=5,pre-frames=300,pre-bytes=2G`（リングバッファの上限）
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut options = Self::default();
+        for item in s.split(',').filter(|item| !item.is_empty()) {
+            let (key, value) = item
+                .split_once('=')
+                .ok_or_else(|| format!("invalid record option: {} (expected key=value)", item))?;
+            let invalid = || format!("invalid record option value: {}", item);
+            match key {
+                "dir" => options.directory = PathBuf::from(value),
+                "template" => options.template = value.parse()?,
+                "format" => options.format = value.parse()?,
+                "every" => options.every = value.parse().ok().filter(|&every| every > 0).ok_or_else(invalid)?,
+                "queue" => options.queue = value.parse().ok().filter(|&queue| queue > 0).ok_or_else(invalid)?,
+                "policy" => {
+                    options.policy = match value.to_ascii_lowercase().as_str() {
+                        "drop" => QueuePolicy::Drop,
+                        "block" => QueuePolicy::Block,
+                        _ => return Err(invalid()),
+                    }
+                }
+                "pre" => {
+                    let seconds = value
+                        .trim_end_matches('s')
+                        .parse::<f64>()
+                        .ok()
+                        .filter(|seconds| *seconds > 0.0)
+                        .ok_or_else(invalid)?;
+                    options.pre_trigger_us = Some((seconds * 1_000_000.0) as u64);
+                }
+                "pre-frames" => {
+                    options.pre_max_frames = Some(value.parse().ok().filter(|&frames| frames > 0).ok_or_else(invalid)?)
+                }
+                "pre-bytes" => {
+                    options.pre_max_bytes = parse_bytes(value).filter(|&bytes| bytes > 0).ok_or_else(invalid)?
+                }
+                "max" => options.max_bytes = Some(parse_bytes(value).ok_or_else(invalid)?),
+                _ => {
+                    return Err(format!(
+                        "unknown record option: {} \
+                         (dir, template, format, every, queue, policy, pre, pre-frames, pre-bytes, max)",
+                        key
+                    ));
+                }
+            }
+        }
+        Ok(options)
+    }
+}
+
+/// offer() の結果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Offer {
+    /// 書き込みキューに入れた
+    Queued,
+    /// トリガー前なのでリングバッファに入れた
+    Buffered,
+    /// `every` の間引きで保存しない
+    Skipped,
+    /// キューが満杯で捨てた（QueuePolicy::Drop）
+    Dropped,
+    /// 容量上限に達したので保存しない
+    OverBudget,
+}
+
+struct Job {
+    meta: FrameMeta,
+    image: ImageBuf,
+    index: u64,
+}
+
+/// 書き込みスレッドの集計
+#[derive(Debug, Clone, Default)]
+struct WriterStats {
+    written: u64,
+    bytes_written: u64,
+    over_budget: u64,
+    failed: u64,
+    first_error: Option<String>,
+    last_file: Option<PathBuf>,
+}
+
+/// 書き込みスレッド本体（キューが閉じるまで取り出して保存）
+fn run_writer(
+    jobs: Receiver<Job>,
+    options: RecorderOptions,
+    budget_exhausted: Arc<AtomicBool>,
+) -> WriterStats {
+    let mut stats = WriterStats::default();
+    for job in jobs {
+        if budget_exhausted.load(Ordering::Relaxed) {
+            stats.over_budget += 1;
+            continue;
+        }
+        let result = encode_image(&job.image.view(), options.format).map_err(|e| e.to_string()).and_then(|bytes| {
+            if options.max_bytes.is_some_and(|max| stats.bytes_written + bytes.len() as u64 > max) {
+                budget_exhausted.store(true, Ordering::Relaxed);
+                return Ok(None);
+            }
+            let path = options.directory.join(options.template.render(&job.meta, job.index, options.format));
+            std::fs::write(&path, &bytes).map_err(|e| format!("{}: {}", path.display(), e))?;
+            Ok(Some((path, bytes.len() as u64)))
+        });
+        match result {
+            Ok(Some((path, len))) => {
+                stats.written += 1;
+                stats.bytes_written += len;
+                stats.last_file = Some(path);
+            }
+            Ok(None) => stats.over_budget += 1,
+            Err(e) => {
+                stats.failed += 1;
+                stats.first_error.get_or_insert(e);
+            }
+        }
+    }
+    stats
+}
+
+/// 連番画像の録画（新しいフレームごとに offer を呼び、最後に finish）
+pub struct Recorder {
+    options: RecorderOptions,
+    queue: Option<SyncSender<Job>>,
+    writer: Option<JoinHandle<WriterStats>>,
+    budget_exhausted: Arc<AtomicBool>,
+    ring: VecDeque<(FrameMeta, ImageBuf)>,
+    /// リングバッファの画素のバイト数
+    ring_bytes: u64,
+    triggered: bool,
+    new_frames: u64,
+    next_index: u64,
+    report: RecorderReport,
+}
+
+impl Recorder {
+    /// 出力先を作り、書き込みスレッドを起動する
+    pub fn start(options: RecorderOptions) -> io::Result<Self> {
+        std::fs::create_dir_all(&options.directory)?;
+        let (queue, jobs) = sync_channel(options.queue);
+        let budget_exhausted = Arc::new(AtomicBool::new(false));
+        let writer = {
+            let options = options.clone();
+            let budget_exhausted = Arc::clone(&budget_exhausted);
+            std::thread::Builder::new()
+                .name("recorder-writer".to_string())
+                .spawn(move || run_writer(jobs, options, budget_exhausted))?
+        };
+        Ok(Self {
+            triggered: options.pre_trigger_us.is_none(),
+            options,
+            queue: Some(queue),
+            writer: Some(writer),
+            budget_exhausted,
+            ring: VecDeque::new(),
+            ring_bytes: 0,
+            new_frames: 0,
+            next_index: 0,
+            report: RecorderReport::default(),
+        })
+    }
+
+    pub fn options(&self) -> &RecorderOptions {
+        &self.options
+    }
+
+    /// トリガー済み（またはトリガーなし）でフレームを書き出しているか
+    pub fn is_triggered(&self) -> bool {
+        self.triggered
+    }
+
+    /// 新しいフレームを渡す（同じフレームを何度も渡さないこと）
+    pub fn offer(&mut self, image: ImageBuf, meta: FrameMeta) -> Offer {
+        self.report.offered += 1;
+        self.new_frames += 1;
+        if !(self.new_frames - 1).is_multiple_of(self.options.every as u64) {
+            self.report.skipped += 1;
+            return Offer::Skipped;
+        }
+        if self.budget_exhausted.load(Ordering::Relaxed) {
+            self.report.over_budget += 1;
+            return Offer::OverBudget;
+        }
+        if !self.triggered {
+            // 期間より古いフレームを捨てる
+            let window = self.options.pre_trigger_us.unwrap_or(0);
+            let expired = |front: &FrameMeta| meta.timestamp_us.saturating_sub(front.timestamp_us) > window;
+            while self.ring.front().is_some_and(|(front, _)| expired(front)) {
+                self.pop_ring();
+                self.report.expired += 1;
+            }
+            self.ring_bytes += image.data().len() as u64;
+            self.ring.push_back((meta, image));
+            // 枚数・バイト数の上限を超えた分も古い順に捨てる
+            let max_frames = self.options.pre_max_frames.unwrap_or(usize::MAX);
+            let max_bytes = self.options.pre_max_bytes;
+            while !self.ring.is_empty() && (self.ring.len() > max_frames || self.ring_bytes > max_bytes) {
+                self.pop_ring();
+                self.report.over_ring_limit += 1;
+            }
+            return Offer::Buffered;
+        }
+        self.enqueue(meta, image, self.options.policy)
+    }
+
+    fn pop_ring(&mut self) -> Option<(FrameMeta, ImageBuf)> {
+        let entry = self.ring.pop_front()?;
+        self.ring_bytes -= entry.1.data().len() as u64;
+        Some(entry)
+    }
+
+    /// リングバッファの内容を書き出し、以後のフレームはそのまま保存する。キューに入れた数を返す
+    ///
+    /// リングバッファはキューより大きいので、`policy` が Drop でも書き込みを待って全部入れる。
+    pub fn trigger(&mut self) -> usize {
+        self.triggered = true;
+        let mut queued = 0;
+        while let Some((meta, image)) = self.pop_ring() {
+            if self.enqueue(meta, image, QueuePolicy::Block) == Offer::Queued {
+                queued += 1;
+            }
+        }
+        queued
+    }
+
+    fn enqueue(&mut self, meta: FrameMeta, image: ImageBuf, policy: QueuePolicy) -> Offer {
+        let Some(queue) = &self.queue else {
+            self.report.dropped += 1;
+            return Offer::Dropped;
+        };
+        let job = Job { meta, image, index: self.next_index };
+        let sent = match policy {
+            QueuePolicy::Drop => match queue.try_send(job) {
+                Ok(()) => true,
+                Err(TrySendError::Full(_) | TrySendError::Disconnected(_)) => false,
+            },
+            QueuePolicy::Block => queue.send(job).is_ok(),
+        };
+        if sent {
+            self.next_index += 1;
+            self.report.queued += 1;
+            Offer::Queued
+        } else {
+            self.report.dropped += 1;
+            Offer::Dropped
+        }
+    }
+
+    /// キューを閉じて書き込みの完了を待つ（トリガーしなかったリングバッファは捨てる）
+    pub fn finish(mut self) -> RecorderReport {
+        self.report.expired += self.ring.len() as u64;
+        self.ring.clear();
+        self.ring_bytes = 0;
+        drop(self.queue.take());
+        let stats = match self.writer.take().map(JoinHandle::join) {
+            Some(Ok(stats)) => stats,
+            _ => WriterStats { first_error: Some("writer thread panicked".to_string()), ..WriterStats::default() },
+        };
+        let mut report = std::mem::take(&mut self.report);
+        report.written = stats.written;
+        report.bytes_written = stats.bytes_written;
+        report.over_budget += stats.over_budget;
+        report.failed = stats.failed;
+        report.first_error = stats.first_error;
+        report.last_file = stats.last_file;
+        report
+    }
+}
+
+/// 録画の結果
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct RecorderReport {
+    /// offer() に渡された新しいフレーム
+    pub offered: u64,
+    /// `every` で間引いた
+    pub skipped: u64,
+    /// トリガー前にリングバッファから外れた
+    pub expired: u64,
+    /// リングバッファの枚数・バイト数の上限で捨てた
+    pub over_ring_limit: u64,
+    pub queued: u64,
+    /// キューが満杯で捨てた
+    pub dropped: u64,
+    /// 容量上限で保存しなかった
+    pub over_budget: u64,
+    pub written: u64,
+    pub bytes_written: u64,
+    /// エンコード・書き込みに失敗した
+    pub failed: u64,
+    pub first_error: Option<String>,
+    pub last_file: Option<PathBuf>,
+}
+
+impl fmt::Display for RecorderReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "[Recorder] {} frames written ({:.1} MiB) of {} offered",
+            self.written,
+            self.bytes_written as f64 / (1 << 20) as f64,
+            self.offered
+        )?;
+        writeln!(
+            f,
+            "  skipped {}, expired before trigger {}, over ring limit {}, dropped {}, over budget {}, failed {}",
+            self.skipped, self.expired, self.over_ring_limit, self.dropped, self.over_budget, self.failed
+        )?;
+        if let Some(path) = &self.last_file {
+            writeln!(f, "  Last file: {}", path.display())?;
+        }
+        if let Some(error) = &self.first_error {
+            writeln!(f, "  ⚠️  {}", error)?;
+        }
+        Ok(())
+    }
+}
+
+/// モック受信で録画を再現する
+///
+/// `interval_us` ごとにフレームカウンターパターンを `frames` 枚送信して `recorder` に渡す。
+/// `trigger_at` を指定すると、そのフレームを渡した直後に trigger() を呼ぶ。
+pub fn simulate(
+    mut recorder: Recorder,
+    frames: u64,
+    interval_us: u64,
+    width: u32,
+    height: u32,
+    trigger_at: Option<u64>,
+) -> Result<RecorderReport, ImageError> {
+    let mut receiver = MockReceiver::new("recorder-mock");
+    for index in 0..frames {
+        let timestamp_us = 1_000_000 + index * interval_us;
+        let stamp = FrameStamp { frame: index, timestamp_us };
+        receiver.push_frame(Pattern::FrameCounter.generate(width, height, DxgiFormat::B8G8R8A8Unorm, stamp)?);
+        if receiver.receive().is_err() || !receiver.is_frame_new() {
+            continue;
+        }
+        let (Ok(image), Some(info)) = (receiver.receive_image(), receiver.sender_info()) else {
+            continue;
+        };
+        recorder.offer(image, FrameMeta { sender: info.name, frame: receiver.frame_count(), timestamp_us });
+        if trigger_at == Some(index) {
+            recorder.trigger();
+        }
+    }
+    Ok(recorder.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(tag: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("ping-recorder-{}-{}", tag, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn trigger_keeps_the_whole_ring_with_drop_policy() {
+        let directory = temp_dir("ring");
+        let options = RecorderOptions {
+            directory: directory.clone(),
+            pre_trigger_us: Some(1_000_000),
+            ..RecorderOptions::default()
+        };
+        assert_eq!(options.policy, QueuePolicy::Drop);
+        let report = simulate(Recorder::start(options).unwrap(), 90, 16_667, 64, 36, Some(89)).unwrap();
+        let _ = std::fs::remove_dir_all(&directory);
+
+        // 1 秒分の 60 枚がキューの大きさ（8）に関係なく全部書かれる
+        assert_eq!(report.dropped, 0);
+        assert_eq!(report.queued, 60);
+        assert_eq!(report.written, 60);
+        assert_eq!(report.expired, 30);
+    }
+
+    #[test]
+    fn ring_is_capped_by_frames_and_bytes() {
+        let frame_bytes = 64 * 36 * 4;
+        for (pre_max_frames, pre_max_bytes, kept) in [(Some(10), DEFAULT_PRE_MAX_BYTES, 10), (None, frame_bytes * 5, 5)] {
+            let directory = temp_dir("cap");
+            let options = RecorderOptions {
+                directory: directory.clone(),
+                pre_trigger_us: Some(1_000_000),
+                pre_max_frames,
+                pre_max_bytes,
+                ..RecorderOptions::default()
+            };
+            let report = simulate(Recorder::start(options).unwrap(), 90, 16_667, 64, 36, Some(89)).unwrap();
+            let _ = std::fs::remove_dir_all(&directory);
+            assert_eq!(report.written, kept);
+            assert_eq!(report.over_ring_limit + report.expired + kept, 90);
+            assert!(report.to_string().contains(&format!("over ring limit {}", report.over_ring_limit)));
+        }
+    }
+
+    #[test]
+    fn block_policy_drops_nothing() {
+        let directory = temp_dir("block");
+        let mut options: RecorderOptions = "queue=1,policy=block".parse().unwrap();
+        options.directory = directory.clone();
+        let report = simulate(Recorder::start(options).unwrap(), 120, 16_667, 64, 36, None).unwrap();
+        let _ = std::fs::remove_dir_all(&directory);
+
+        // キューが 1 枚でも書き込みを待つので全部保存される
+        assert_eq!(report.dropped, 0);
+        assert_eq!(report.queued, 120);
+        assert_eq!(report.written, 120);
+        assert_eq!(report.failed, 0);
+    }
+
+    #[test]
+    fn max_bytes_stops_writing_at_the_budget() {
+        let directory = temp_dir("budget");
+        let first = Pattern::FrameCounter
+            .generate(64, 36, DxgiFormat::B8G8R8A8Unorm, FrameStamp { frame: 0, timestamp_us: 1_000_000 })
+            .unwrap();
+        let budget = encode_image(&first.view(), ImageFormat::Qoi).unwrap().len() as u64 * 5;
+        let mut options: RecorderOptions = format!("policy=block,max={}", budget).parse().unwrap();
+        options.directory = directory.clone();
+        let report = simulate(Recorder::start(options).unwrap(), 60, 16_667, 64, 36, None).unwrap();
+        let on_disk: u64 = std::fs::read_dir(&directory)
+            .unwrap()
+            .map(|entry| entry.unwrap().metadata().unwrap().len())
+            .sum();
+        let _ = std::fs::remove_dir_all(&directory);
+
+        // 上限を超えるフレームからは書かず、残りは over budget に数える
+        assert!(report.written > 0 && report.written < 60);
+        assert!(report.bytes_written <= budget);
+        assert_eq!(on_disk, report.bytes_written);
+        assert_eq!(report.dropped, 0);
+        assert_eq!(report.written + report.over_budget, 60);
+    }
+
+    #[test]
+    fn files_are_named_by_the_default_template() {
+        let directory = temp_dir("names");
+        let options = RecorderOptions {
+            directory: directory.clone(),
+            policy: QueuePolicy::Block,
+            ..RecorderOptions::default()
+        };
+        let report = simulate(Recorder::start(options).unwrap(), 3, 16_667, 64, 36, None).unwrap();
+        let mut names: Vec<String> = std::fs::read_dir(&directory)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        let _ = std::fs::remove_dir_all(&directory);
+
+        // {sender}_{frame:06}_{timestamp}.{ext}（フレーム番号は 1 から、時刻はミリ秒）
+        assert_eq!(
+            names,
+            ["recorder-mock_000001_1000.qoi", "recorder-mock_000002_1016.qoi", "recorder-mock_000003_1033.qoi"]
+        );
+        assert_eq!(report.last_file, Some(directory.join(&names[2])));
+    }
+
+    #[test]
+    fn ring_limits_parse() {
+        let options: RecorderOptions = "pre=5,pre-frames=300,pre-bytes=2G".parse().unwrap();
+        assert_eq!(options.pre_max_frames, Some(300));
+        assert_eq!(options.pre_max_bytes, 2 << 30);
+        assert!("pre-frames=0".parse::<RecorderOptions>().is_err());
+        assert!("pre-bytes=0".parse::<RecorderOptions>().is_err());
+    }
+}
@@ -0,0 +1,308 @@
+// ============================================================
+// 送信側の共通型（C ABI / モック / 共有メモリ共通）
+// ============================================================
+//
+// spoutdx_sender_* と同じ流れ: 名前とフォーマットを設定し、最初の送信で
+// センダーを作る（サイズやフォーマットが変われば作り直す）。release で登録を消す。
+// CPU の画素は send_image で送る（set_format のフォーマットに変換してから渡す）。
+// バックエンド（DLL、MockSender、Linux の ShmSender）は SenderBackend を実装し、
+// Sender が名前の検証と破棄時の release を受け持つ。
+
+use crate::ffi::SpoutDxResult;
+use crate::image::{DxgiFormat, ImageView};
+use crate::import::convert;
+use crate::receiver::SenderInfo;
+
+/// センダー名の最大バイト数（`SpoutDxSenderInfo::name` は NUL 込みで 256）
+pub const MAX_NAME_LEN: usize = 255;
+
+/// fps の平滑化係数（SpoutFrameCount と同じ）
+const FPS_DAMPING: f64 = 0.95;
+
+/// センダー名として使えるか（空、NUL を含む、255 バイト超はエラー）
+pub fn validate_name(name: &str) -> Result<(), String> {
+    if name.is_empty() {
+        return Err("sender name is empty".to_string());
+    }
+    if name.contains('\0') {
+        return Err("sender name contains NUL".to_string());
+    }
+    if name.len() > MAX_NAME_LEN {
+        return Err(format!("sender name is {} bytes (max {})", name.len(), MAX_NAME_LEN));
+    }
+    Ok(())
+}
+
+/// 送信のバックエンド（`spoutdx_sender_*` と同じ操作）
+pub trait SenderBackend {
+    /// 送るテクスチャ（DLL は ID3D11Texture2D、モック・共有メモリは ImageBuf）
+    type Texture: ?Sized;
+
+    /// `None` は実行ファイル名。登録済みの名前なら `_1` などを付けてずらす
+    fn set_name(&mut self, name: Option<&str>) -> Result<(), SpoutDxResult>;
+    fn set_format(&mut self, format: DxgiFormat) -> Result<(), SpoutDxResult>;
+    /// センダーのフォーマット（既定は B8G8R8A8Unorm）
+    ///
+    /// `set_format` の値。`send_texture` の後はそのテクスチャのフォーマット（SpoutDX の GetSenderFormat と同じ）。
+    fn format(&self) -> DxgiFormat;
+    /// テクスチャを送る（以後のフォーマットはテクスチャのものになる）
+    fn send_texture(&mut self, texture: &Self::Texture) -> Result<(), SpoutDxResult>;
+    /// CPU の画素を送る（`image` は `format()` のフォーマット、row_pitch はそのまま渡す）
+    fn send_image(&mut self, image: &ImageView) -> Result<(), SpoutDxResult>;
+    fn release(&mut self) -> Result<(), SpoutDxResult>;
+    /// 登録中のセンダー情報（最初の送信の前と release の後は None）
+    fn info(&self) -> Option<SenderInfo>;
+    fn frame(&self) -> u64;
+    fn fps(&self) -> f64;
+}
+
+/// センダー（バックエンドを包み、破棄時に release する）
+#[derive(Debug)]
+pub struct Sender<B: SenderBackend> {
+    backend: B,
+}
+
+impl<B: SenderBackend> Sender<B> {
+    pub fn new(backend: B) -> Self {
+        Self { backend }
+    }
+
+    /// 名前を検証して設定（実際に登録される名前は `name()` で確かめる）
+    pub fn set_name(&mut self, name: &str) -> Result<(), SpoutDxResult> {
+        validate_name(name).map_err(|_| SpoutDxResult::ErrorInternal)?;
+        self.backend.set_name(Some(name))
+    }
+
+    /// 実行ファイル名を名前にする
+    pub fn set_default_name(&mut self) -> Result<(), SpoutDxResult> {
+        self.backend.set_name(None)
+    }
+
+    /// `SetSenderFormat` 相当（テクスチャの送信はテクスチャ自身のフォーマットを使う）
+    pub fn set_format(&mut self, format: DxgiFormat) -> Result<(), SpoutDxResult> {
+        if format.bytes_per_pixel().is_none() {
+            return Err(SpoutDxResult::ErrorInternal);
+        }
+        self.backend.set_format(format)
+    }
+
+    pub fn send_texture(&mut self, texture: &B::Texture) -> Result<(), SpoutDxResult> {
+        self.backend.send_texture(texture)
+    }
+
+    /// CPU の画素を送る（フォーマットが違えば `set_format` のフォーマットに変換する）
+    pub fn send_image(&mut self, image: &ImageView) -> Result<(), SpoutDxResult> {
+        let format = self.backend.format();
+        if image.format() == format {
+            return self.backend.send_image(image);
+        }
+        let converted = convert(image, format).map_err(|_| SpoutDxResult::ErrorInternal)?;
+        self.backend.send_image(&converted.view())
+    }
+
+    pub fn release(&mut self) -> Result<(), SpoutDxResult> {
+        self.backend.release()
+    }
+
+    /// 登録中の名前（送信前は None）
+    pub fn name(&self) -> Option<String> {
+        self.backend.info().map(|info| info.name)
+    }
+
+    pub fn info(&self) -> Option<SenderInfo> {
+        self.backend.info()
+    }
+
+    pub fn is_initialized(&self) -> bool {
+        self.backend.info().is_some()
+    }
+
+    pub fn frame(&self) -> u64 {
+        self.backend.frame()
+    }
+
+    pub fn fps(&self) -> f64 {
+        self.backend.fps()
+    }
+
+    pub fn backend(&self) -> &B {
+        &self.backend
+    }
+
+    pub fn backend_mut(&mut self) -> &mut B {
+        &mut self.backend
+    }
+}
+
+impl<B: SenderBackend> Drop for Sender<B> {
+    fn drop(&mut self) {
+        let _ = self.backend.release();
+    }
+}
+
+/// フレーム番号と fps（DLL 以外のバックエンド用、SpoutFrameCount と同じく平滑化）
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FrameCounter {
+    frame: u64,
+    last_us: Option<u64>,
+    fps: f64,
+}
+
+impl FrameCounter {
+    /// 送信したフレームを記録
+    pub fn record(&mut self, timestamp_us: u64) {
+        self.frame += 1;
+        if let Some(last_us) = self.last_us
+            && timestamp_us > last_us
+        {
+            let fps = 1_000_000.0 / (timestamp_us - last_us) as f64;
+            self.fps = if self.fps > 0.0 { FPS_DAMPING * self.fps + (1.0 - FPS_DAMPING) * fps } else { fps };
+        }
+        self.last_us = Some(timestamp_us);
+    }
+
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    /// 平滑化した fps（2 フレーム未満は 0）
+    pub fn fps(&self) -> f64 {
+        self.fps
+    }
+}
+
+/// 実行ファイル名（拡張子なし、`set_name(None)` の既定値）
+pub fn executable_name() -> String {
+    std::env::current_exe()
+        .ok()
+        .and_then(|path| path.file_stem().map(|stem| stem.to_string_lossy().into_owned()))
+        .unwrap_or_else(|| "sender".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use crate::image::ImageBuf;
+    use crate::mock::{MockReceiver, MockSender};
+
+    /// Sender を破棄した後も中身を見られるように MockSender を共有する
+    #[derive(Debug, Default, Clone)]
+    struct SharedMock(Rc<RefCell<MockSender>>);
+
+    impl SenderBackend for SharedMock {
+        type Texture = ImageBuf;
+
+        fn set_name(&mut self, name: Option<&str>) -> Result<(), SpoutDxResult> {
+            self.0.borrow_mut().set_name(name)
+        }
+        fn set_format(&mut self, format: DxgiFormat) -> Result<(), SpoutDxResult> {
+            self.0.borrow_mut().set_format(format)
+        }
+        fn format(&self) -> DxgiFormat {
+            self.0.borrow().format()
+        }
+        fn send_texture(&mut self, texture: &ImageBuf) -> Result<(), SpoutDxResult> {
+            self.0.borrow_mut().send_texture(texture)
+        }
+        fn send_image(&mut self, image: &ImageView) -> Result<(), SpoutDxResult> {
+            self.0.borrow_mut().send_image(image)
+        }
+        fn release(&mut self) -> Result<(), SpoutDxResult> {
+            self.0.borrow_mut().release()
+        }
+        fn info(&self) -> Option<SenderInfo> {
+            self.0.borrow().info()
+        }
+        fn frame(&self) -> u64 {
+            self.0.borrow().frame()
+        }
+        fn fps(&self) -> f64 {
+            self.0.borrow().fps()
+        }
+    }
+
+    fn bgra(width: u32, height: u32) -> ImageBuf {
+        let data = (0..width * height).flat_map(|i| [i as u8, 0x40, 0x80, 0xFF]).collect();
+        ImageBuf::from_vec(data, width, height, width as usize * 4, DxgiFormat::B8G8R8A8Unorm).unwrap()
+    }
+
+    #[test]
+    fn names_are_validated() {
+        assert!(validate_name("").is_err());
+        assert!(validate_name("a\0b").is_err());
+        assert!(validate_name(&"a".repeat(MAX_NAME_LEN + 1)).is_err());
+        assert!(validate_name(&"a".repeat(MAX_NAME_LEN)).is_ok());
+
+        let mut sender = Sender::new(MockSender::new());
+        for name in [String::new(), "a\0b".to_string(), "a".repeat(256)] {
+            assert_eq!(sender.set_name(&name), Err(SpoutDxResult::ErrorInternal));
+        }
+        sender.set_name("ping").unwrap();
+        sender.send_image(&bgra(2, 2).view()).unwrap();
+        assert_eq!(sender.name().as_deref(), Some("ping"));
+    }
+
+    #[test]
+    fn send_image_converts_to_the_sender_format() {
+        let mut sender = Sender::new(MockSender::new());
+        assert_eq!(sender.set_format(DxgiFormat::Unknown(0)), Err(SpoutDxResult::ErrorInternal));
+        sender.set_format(DxgiFormat::R8G8B8A8Unorm).unwrap();
+        sender.send_image(&bgra(3, 2).view()).unwrap();
+        assert_eq!(sender.info().unwrap().format, DxgiFormat::R8G8B8A8Unorm);
+
+        let mut receiver = MockReceiver::new("ping");
+        sender.backend_mut().deliver(&mut receiver);
+        receiver.receive().unwrap();
+        let image = receiver.receive_image().unwrap();
+        assert_eq!(image.format(), DxgiFormat::R8G8B8A8Unorm);
+        // B と R が入れ替わる
+        assert_eq!(&image.row(0)[..8], &[0x80, 0x40, 0, 0xFF, 0x80, 0x40, 1, 0xFF]);
+    }
+
+    #[test]
+    fn send_texture_changes_the_sender_format() {
+        let mut sender = Sender::new(MockSender::new());
+        let texture = convert(&bgra(2, 2).view(), DxgiFormat::R8G8B8A8Unorm).unwrap();
+        sender.send_texture(&texture).unwrap();
+        assert_eq!(sender.backend().format(), DxgiFormat::R8G8B8A8Unorm);
+        // 以後の send_image はテクスチャのフォーマットに変換される
+        sender.send_image(&bgra(2, 2).view()).unwrap();
+        assert_eq!(sender.info().unwrap().format, DxgiFormat::R8G8B8A8Unorm);
+    }
+
+    #[test]
+    fn size_change_recreates_the_sender() {
+        let mut sender = Sender::new(MockSender::new());
+        let mut receiver = MockReceiver::new("ping");
+        for (width, height) in [(4, 4), (4, 4), (8, 2)] {
+            sender.send_image(&bgra(width, height).view()).unwrap();
+            let info = sender.info().unwrap();
+            assert_eq!((info.width, info.height), (width, height));
+            sender.backend_mut().deliver(&mut receiver);
+            receiver.receive().unwrap();
+            // 最初の接続とサイズが変わったときだけ更新される
+            assert_eq!(receiver.is_updated(), receiver.frame_count() != 2);
+        }
+        assert_eq!(sender.frame(), 3);
+    }
+
+    #[test]
+    fn drop_releases_the_sender() {
+        let shared = SharedMock::default();
+        let mut receiver = MockReceiver::new("ping");
+        {
+            let mut sender = Sender::new(shared.clone());
+            sender.send_image(&bgra(2, 2).view()).unwrap();
+            assert!(sender.is_initialized());
+            shared.0.borrow_mut().deliver(&mut receiver);
+            receiver.receive().unwrap();
+        }
+        assert_eq!(shared.info(), None);
+        assert_eq!(shared.frame(), 0);
+        shared.0.borrow_mut().deliver(&mut receiver);
+        assert_eq!(receiver.receive(), Err(SpoutDxResult::ErrorNotConnected));
+    }
+}
@@ -0,0 +1,295 @@
+// ============================================================
+// 受信セッションの解析（フリーズ・重複フレーム・カウンター不一致）
+// ============================================================
+//
+// is_frame_new はセンダーのフレームカウンターしか見ていないため、
+// 画像の内容をハッシュして次の不具合を検出する:
+// - カウンターは進むが内容が変わらない（フリーズ）
+// - 以前のフレームの内容が再び届く（重複）
+// - 内容が変わったのにカウンターが進まない
+
+use std::collections::VecDeque;
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::image::ImageView;
+
+const HASH_PRIME: u64 = 0x9E37_79B9_7F4A_7C15;
+
+/// 64bit の値を攪拌（MurmurHash3 の fmix64）
+fn mix64(mut h: u64) -> u64 {
+    h ^= h >> 33;
+    h = h.wrapping_mul(0xFF51_AFD7_ED55_8CCD);
+    h ^= h >> 33;
+    h = h.wrapping_mul(0xC4CE_B9FE_1A85_EC53);
+    h ^ (h >> 33)
+}
+
+/// 8 バイト単位で畳み込むハッシュ状態
+#[derive(Debug, Clone, Copy)]
+struct HashState(u64);
+
+impl HashState {
+    fn write(&mut self, bytes: &[u8]) {
+        let mut chunks = bytes.chunks_exact(8);
+        for chunk in &mut chunks {
+            let word = u64::from_le_bytes(chunk.try_into().unwrap());
+            self.0 = (self.0 ^ word).wrapping_mul(HASH_PRIME).rotate_left(31);
+        }
+        let rest = chunks.remainder();
+        if !rest.is_empty() {
+            let mut tail = [0u8; 8];
+            tail[..rest.len()].copy_from_slice(rest);
+            self.0 = (self.0 ^ u64::from_le_bytes(tail) ^ rest.len() as u64).wrapping_mul(HASH_PRIME).rotate_left(31);
+        }
+    }
+
+    fn finish(self) -> u64 {
+        mix64(self.0)
+    }
+}
+
+/// フレーム内容の 64bit ハッシュ（暗号学的ではない）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ContentHasher {
+    /// Some(n) なら n x n の格子点のピクセルだけをハッシュする（高速だが小さな変化を見逃す）
+    pub grid: Option<u32>,
+}
+
+impl ContentHasher {
+    pub fn full() -> Self {
+        Self { grid: None }
+    }
+
+    pub fn sampled(grid: u32) -> Self {
+        Self { grid: Some(grid.max(1)) }
+    }
+
+    /// サイズ・フォーマットも含めてハッシュ（row_pitch の余白は含めない）
+    pub fn hash(&self, view: &ImageView) -> u64 {
+        let (width, height) = (view.width(), view.height());
+        let mut state = HashState(mix64(
+            (width as u64) << 32 ^ height as u64 ^ (view.format().raw() as u64) << 48,
+        ));
+        let bpp = view.bytes_per_pixel();
+
+        match self.grid {
+            None => {
+                for y in 0..height {
+                    state.write(&view.row(y)[..width as usize * bpp]);
+                }
+            }
+            Some(grid) => {
+                let (nx, ny) = (grid.min(width), grid.min(height));
+                for j in 0..ny {
+                    let y = (j * height + height / 2) / ny;
+                    for i in 0..nx {
+                        state.write(view.pixel((i * width + width / 2) / nx, y));
+                    }
+                }
+            }
+        }
+        state.finish()
+    }
+}
+
+/// 検出した事象
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SessionEvent {
+    /// カウンターが `frames` 回進む間、内容が変わらなかった
+    Frozen { start_us: u64, end_us: u64, frames: u64 },
+    /// 直前とは違うが、以前に受信した内容と同じフレーム
+    Duplicate { timestamp_us: u64, frame: u64, original_frame: u64 },
+    /// カウンターは進んだが内容が直前と同じ（フリーズ判定に満たない短いもの）
+    CounterWithoutChange { timestamp_us: u64, frame: u64 },
+    /// 内容が変わったがカウンターが進んでいない
+    ChangeWithoutCounter { timestamp_us: u64, frame: u64 },
+}
+
+impl SessionEvent {
+    fn timestamp_us(&self) -> u64 {
+        match self {
+            Self::Frozen { start_us, .. } => *start_us,
+            Self::Duplicate { timestamp_us, .. }
+            | Self::CounterWithoutChange { timestamp_us, .. }
+            | Self::ChangeWithoutCounter { timestamp_us, .. } => *timestamp_us,
+        }
+    }
+}
+
+impl fmt::Display for SessionEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Frozen { start_us, end_us, frames } => write!(
+                f,
+                "frozen content for {} counted frames ({:.1} ms, {} -> {} us)",
+                frames,
+                (end_us - start_us) as f64 / 1000.0,
+                start_us,
+                end_us
+            ),
+            Self::Duplicate { timestamp_us, frame, original_frame } => write!(
+                f,
+                "@{} us: frame #{} repeats the content of frame #{}",
+                timestamp_us, frame, original_frame
+            ),
+            Self::CounterWithoutChange { timestamp_us, frame } => {
+                write!(f, "@{} us: frame #{} counted as new but content unchanged", timestamp_us, frame)
+            }
+            Self::ChangeWithoutCounter { timestamp_us, frame } => {
+                write!(f, "@{} us: frame #{} content changed without a new frame", timestamp_us, frame)
+            }
+        }
+    }
+}
+
+/// 解析の設定
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SessionOptions {
+    /// この回数以上カウンターが進んでも内容が同じならフリーズとする
+    pub frozen_frames: u64,
+    /// 重複検出のために覚えておく直近のフレーム数
+    pub history: usize,
+}
+
+impl Default for SessionOptions {
+    fn default() -> Self {
+        Self { frozen_frames: 30, history: 120 }
+    }
+}
+
+/// 内容が変わらずカウンターだけ進んだ連続区間
+#[derive(Debug, Clone, Default)]
+struct StaleRun {
+    start_us: u64,
+    end_us: u64,
+    frames: Vec<(u64, u64)>,
+}
+
+/// 受信セッションの解析器（1 回の受信ごとに observe を呼ぶ）
+#[derive(Debug, Clone, Default)]
+pub struct SessionAnalyzer {
+    options: SessionOptions,
+    observations: u64,
+    last_hash: Option<u64>,
+    /// (ハッシュ, 観測番号)
+    history: VecDeque<(u64, u64)>,
+    unique_contents: u64,
+    run: StaleRun,
+    events: Vec<SessionEvent>,
+}
+
+impl SessionAnalyzer {
+    pub fn new(options: SessionOptions) -> Self {
+        Self { options, ..Self::default() }
+    }
+
+    /// 受信結果を記録（`counter_advanced` は is_frame_new の値）
+    pub fn observe(&mut self, timestamp_us: u64, counter_advanced: bool, hash: u64) {
+        let frame = self.observations;
+        self.observations += 1;
+
+        let Some(last_hash) = self.last_hash else {
+            self.last_hash = Some(hash);
+            self.unique_contents += 1;
+            self.remember(hash, frame);
+            return;
+        };
+
+        let changed = hash != last_hash;
+        match (counter_advanced, changed) {
+            (true, false) => {
+                if self.run.frames.is_empty() {
+                    self.run.start_us = timestamp_us;
+                }
+                self.run.end_us = timestamp_us;
+                self.run.frames.push((timestamp_us, frame));
+            }
+            (false, true) => {
+                self.events.push(SessionEvent::ChangeWithoutCounter { timestamp_us, frame });
+            }
+            _ => {}
+        }
+
+        if changed {
+            self.end_run();
+            match self.history.iter().rev().find(|(h, _)| *h == hash) {
+                Some(&(_, original_frame)) => {
+                    self.events.push(SessionEvent::Duplicate { timestamp_us, frame, original_frame });
+                }
+                None => self.unique_contents += 1,
+            }
+            self.remember(hash, frame);
+        }
+        self.last_hash = Some(hash);
+    }
+
+    fn remember(&mut self, hash: u64, frame: u64) {
+        self.history.push_back((hash, frame));
+        while self.history.len() > self.options.history {
+            self.history.pop_front();
+        }
+    }
+
+    /// 内容が変わらない区間を閉じて、長さに応じてフリーズか個別の不一致として記録
+    fn end_run(&mut self) {
+        let run = std::mem::take(&mut self.run);
+        if run.frames.is_empty() {
+            return;
+        }
+        let frames = run.frames.len() as u64;
+        if frames >= self.options.frozen_frames {
+            self.events.push(SessionEvent::Frozen { start_us: run.start_us, end_us: run.end_us, frames });
+        } else {
+            self.events.extend(
+                run.frames
+                    .into_iter()
+                    .map(|(timestamp_us, frame)| SessionEvent::CounterWithoutChange { timestamp_us, frame }),
+            );
+        }
+    }
+
+    /// セッションを閉じて結果を返す（事象は時刻順）
+    pub fn finish(mut self) -> SessionReport {
+        self.end_run();
+        self.events.sort_by_key(SessionEvent::timestamp_us);
+        SessionReport {
+            observations: self.observations,
+            unique_contents: self.unique_contents,
+            events: self.events,
+        }
+    }
+}
+
+/// 解析結果
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SessionReport {
+    pub observations: u64,
+    /// 異なる内容の数（履歴から外れた内容の再来は新しい内容として数える）
+    pub unique_contents: u64,
+    pub events: Vec<SessionEvent>,
+}
+
+impl SessionReport {
+    pub fn is_clean(&self) -> bool {
+        self.events.is_empty()
+    }
+}
+
+impl fmt::Display for SessionReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "[Session] {} frames observed, {} unique contents, {} events",
+            self.observations,
+            self.unique_contents,
+            self.events.len()
+        )?;
+        for event in &self.events {
+            writeln!(f, "  ⚠️  {}", event)?;
+        }
+        Ok(())
+    }
+}
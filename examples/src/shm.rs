@@ -0,0 +1,646 @@
+// ============================================================
+// 共有メモリのセンダー登録（Spout のセンダー一覧を Linux で再現）
+// ============================================================
+//
+// Spout はセンダー名の一覧とセンダーごとの情報を名前付き共有メモリに置く。
+// ここでは tmpfs（/dev/shm/spoutdx）のファイルで同じことをする:
+// - <名前>.json: サイズ・フォーマット・フレーム番号・fps・プロセス ID
+// - <名前>.frame: 最新のフレーム（36 バイトのヘッダー + 行を詰めた画素）
+// - active: アクティブなセンダーの名前（名前を指定しない受信の接続先）
+// 書き込みは一時ファイルから置き換えるので、読み手が書きかけの内容を見ることはない。
+// 終了したプロセスのエントリーは一覧を読むときに消す（/proc がある環境のみ）。
+// テクスチャの共有ハンドルに当たるものはないので、送信は毎フレーム画素をコピーする
+// （SendImage と同じ）。ShmReceiver がそれを読む。
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use serde::{Deserialize, Serialize};
+
+use crate::ffi::SpoutDxResult;
+use crate::image::{DxgiFormat, ImageBuf, ImageView};
+use crate::latency::{Clock, MonotonicClock};
+use crate::receiver::{ReceiverOptions, SenderInfo};
+use crate::sender::{executable_name, validate_name, FrameCounter, SenderBackend};
+
+/// 登録先を上書きする環境変数
+pub const ROOT_ENV: &str = "SPOUTDX_SHM_DIR";
+/// 同じ名前のセンダーをずらす上限（`name_1` 〜）
+const MAX_SUFFIX: u32 = 9999;
+/// アクティブなセンダーの名前を書くファイル
+const ACTIVE_FILE: &str = "active";
+const ENTRY_EXTENSION: &str = "json";
+const FRAME_EXTENSION: &str = "frame";
+/// フレームファイルの先頭
+const FRAME_MAGIC: &[u8; 4] = b"SDXF";
+/// magic・幅・高さ・フォーマット・row_pitch（各 u32）、フレーム番号・時刻（各 u64）、すべてリトルエンディアン
+const FRAME_HEADER_LEN: usize = 36;
+/// エスケープしたセンダー名の上限（拡張子と一時ファイルの接尾辞を足しても 255 バイトに収まる長さ）
+const MAX_ESCAPED_LEN: usize = 160;
+
+/// 一時ファイル名の通し番号（同じプロセスの複数のセンダーがぶつからないように）
+static TEMP_SEQUENCE: AtomicU64 = AtomicU64::new(0);
+
+/// 登録されたセンダーの情報
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ShmSenderEntry {
+    pub name: String,
+    pub width: u32,
+    pub height: u32,
+    /// DXGI_FORMAT の数値
+    pub format: u32,
+    pub frame: u64,
+    pub fps: f64,
+    /// 送信しているプロセス
+    pub pid: u32,
+}
+
+impl ShmSenderEntry {
+    pub fn dxgi_format(&self) -> DxgiFormat {
+        DxgiFormat::from_raw(self.format)
+    }
+
+    pub fn info(&self) -> SenderInfo {
+        SenderInfo { name: self.name.clone(), width: self.width, height: self.height, format: self.dxgi_format() }
+    }
+}
+
+impl fmt::Display for ShmSenderEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {}x{} {}, frame {}, {:.2} fps (pid {})",
+            self.name,
+            self.width,
+            self.height,
+            self.dxgi_format(),
+            self.frame,
+            self.fps,
+            self.pid
+        )
+    }
+}
+
+/// 共有メモリから読んだフレーム
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShmFrame {
+    /// センダーのフレーム番号（1 から）
+    pub frame: u64,
+    /// 送信時の MonotonicClock の時刻
+    pub timestamp_us: u64,
+    pub image: ImageBuf,
+}
+
+fn invalid_frame(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("invalid frame file: {}", message))
+}
+
+/// ファイル名に使えない文字を `%XX` にする（先頭の `.` も）
+///
+/// 長い名前は先頭を残して名前全体の FNV-1a を付ける（一時ファイル名も 255 バイトに収める）。
+/// 別の名前と同じファイル名になっても、エントリーの `name` で見分ける。
+fn escape_name(name: &str) -> String {
+    let mut escaped = String::with_capacity(name.len());
+    for (i, byte) in name.bytes().enumerate() {
+        match byte {
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'_' | b'-' | b' ' => escaped.push(byte as char),
+            b'.' if i > 0 => escaped.push('.'),
+            _ => escaped.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    if escaped.len() > MAX_ESCAPED_LEN {
+        let hash = name.bytes().fold(0xCBF2_9CE4_8422_2325u64, |h, b| (h ^ b as u64).wrapping_mul(0x100_0000_01B3));
+        // エスケープは ASCII だけなので、どこで切っても文字の途中にはならない
+        escaped.truncate(MAX_ESCAPED_LEN - 17);
+        escaped.push_str(&format!("~{:016x}", hash));
+    }
+    escaped
+}
+
+/// プロセスが生きているか（/proc がなければ生きているとみなす）
+fn process_alive(pid: u32) -> bool {
+    let proc = Path::new("/proc");
+    !proc.is_dir() || proc.join(pid.to_string()).exists()
+}
+
+/// センダー一覧を置くディレクトリ
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShmRegistry {
+    root: PathBuf,
+}
+
+impl ShmRegistry {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// 既定の登録先（`SPOUTDX_SHM_DIR`、なければ /dev/shm/spoutdx、それもなければ一時ディレクトリ）
+    pub fn open() -> Self {
+        Self::new(Self::default_root())
+    }
+
+    pub fn default_root() -> PathBuf {
+        if let Some(root) = std::env::var_os(ROOT_ENV) {
+            return PathBuf::from(root);
+        }
+        let shm = Path::new("/dev/shm");
+        if shm.is_dir() { shm.join("spoutdx") } else { std::env::temp_dir().join("spoutdx") }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn entry_path(&self, name: &str) -> PathBuf {
+        self.root.join(format!("{}.{}", escape_name(name), ENTRY_EXTENSION))
+    }
+
+    fn frame_path(&self, name: &str) -> PathBuf {
+        self.root.join(format!("{}.{}", escape_name(name), FRAME_EXTENSION))
+    }
+
+    /// 同じディレクトリの一時ファイルに書いてから `publish` で置く
+    fn write_atomic(
+        &self,
+        path: &Path,
+        bytes: &[u8],
+        publish: impl FnOnce(&Path, &Path) -> io::Result<()>,
+    ) -> io::Result<()> {
+        fs::create_dir_all(&self.root)?;
+        let file_name = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+        let sequence = TEMP_SEQUENCE.fetch_add(1, Ordering::Relaxed);
+        let temp = self.root.join(format!(".{}.{}.{}.tmp", file_name, std::process::id(), sequence));
+        fs::write(&temp, bytes)?;
+        let result = publish(&temp, path);
+        let _ = fs::remove_file(&temp);
+        result
+    }
+
+    /// 登録されているセンダー（名前順、終了したプロセスのものは消す）
+    pub fn senders(&self) -> io::Result<Vec<ShmSenderEntry>> {
+        let mut entries = Vec::new();
+        let dir = match fs::read_dir(&self.root) {
+            Ok(dir) => dir,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(entries),
+            Err(e) => return Err(e),
+        };
+        for item in dir {
+            let path = item?.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some(ENTRY_EXTENSION) {
+                continue;
+            }
+            // 読んでいる間に消えたもの・壊れたものは飛ばす
+            let entry = fs::read(&path).ok().and_then(|bytes| serde_json::from_slice::<ShmSenderEntry>(&bytes).ok());
+            let Some(entry) = entry else {
+                continue;
+            };
+            if process_alive(entry.pid) {
+                entries.push(entry);
+            } else {
+                let _ = fs::remove_file(&path);
+                let _ = fs::remove_file(self.frame_path(&entry.name));
+            }
+        }
+        entries.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(entries)
+    }
+
+    /// 名前で探す（終了したプロセスのものは None）
+    pub fn entry(&self, name: &str) -> io::Result<Option<ShmSenderEntry>> {
+        let bytes = match fs::read(self.entry_path(name)) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        let entry: ShmSenderEntry =
+            serde_json::from_slice(&bytes).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        Ok((entry.name == name && process_alive(entry.pid)).then_some(entry))
+    }
+
+    /// アクティブなセンダー（登録が消えていれば None）
+    pub fn active_sender(&self) -> io::Result<Option<ShmSenderEntry>> {
+        match fs::read_to_string(self.root.join(ACTIVE_FILE)) {
+            Ok(name) => self.entry(&name),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn set_active_sender(&self, name: &str) -> io::Result<()> {
+        let path = self.root.join(ACTIVE_FILE);
+        self.write_atomic(&path, name.as_bytes(), |temp, path| fs::rename(temp, path))
+    }
+
+    /// 新しいセンダーとして登録（使用中の名前なら `_1`, `_2`, ... を付ける）
+    ///
+    /// 登録した名前の入った `entry` を返す。アクティブなセンダーがなければこれをアクティブにする。
+    pub fn register(&self, entry: &ShmSenderEntry) -> io::Result<ShmSenderEntry> {
+        // 終了したプロセスのエントリーを先に片付ける
+        self.senders()?;
+        for suffix in 0..=MAX_SUFFIX {
+            let name = if suffix == 0 { entry.name.clone() } else { format!("{}_{}", entry.name, suffix) };
+            if validate_name(&name).is_err() {
+                break;
+            }
+            let registered = ShmSenderEntry { name, ..entry.clone() };
+            let bytes = serde_json::to_vec(&registered).map_err(io::Error::other)?;
+            // hard_link は既にあれば失敗するので、同時に登録しても片方だけが成功する
+            let path = self.entry_path(&registered.name);
+            match self.write_atomic(&path, &bytes, |temp, path| fs::hard_link(temp, path)) {
+                Ok(()) => {
+                    // 異常終了したセンダーのフレームが残っていれば消す
+                    let _ = fs::remove_file(self.frame_path(&registered.name));
+                    if self.active_sender()?.is_none() {
+                        self.set_active_sender(&registered.name)?;
+                    }
+                    return Ok(registered);
+                }
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(e),
+            }
+        }
+        Err(io::Error::new(io::ErrorKind::AlreadyExists, format!("no free sender name for {}", entry.name)))
+    }
+
+    /// 登録済みのエントリーを書き換える
+    pub fn update(&self, entry: &ShmSenderEntry) -> io::Result<()> {
+        let bytes = serde_json::to_vec(entry).map_err(io::Error::other)?;
+        self.write_atomic(&self.entry_path(&entry.name), &bytes, |temp, path| fs::rename(temp, path))
+    }
+
+    /// 最新のフレームを置く（行は詰めて書く）
+    pub fn write_frame(&self, name: &str, frame: u64, timestamp_us: u64, image: &ImageView) -> io::Result<()> {
+        let row_bytes = image.width() as usize * image.bytes_per_pixel();
+        let mut bytes = Vec::with_capacity(FRAME_HEADER_LEN + row_bytes * image.height() as usize);
+        bytes.extend_from_slice(FRAME_MAGIC);
+        for value in [image.width(), image.height(), image.format().raw(), row_bytes as u32] {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        bytes.extend_from_slice(&frame.to_le_bytes());
+        bytes.extend_from_slice(&timestamp_us.to_le_bytes());
+        for y in 0..image.height() {
+            bytes.extend_from_slice(image.row(y));
+        }
+        self.write_atomic(&self.frame_path(name), &bytes, |temp, path| fs::rename(temp, path))
+    }
+
+    /// 最新のフレーム（まだ送られていなければ None）
+    pub fn read_frame(&self, name: &str) -> io::Result<Option<ShmFrame>> {
+        let bytes = match fs::read(self.frame_path(name)) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        let (header, pixels) =
+            bytes.split_at_checked(FRAME_HEADER_LEN).ok_or_else(|| invalid_frame("truncated header"))?;
+        if &header[..4] != FRAME_MAGIC {
+            return Err(invalid_frame("bad magic"));
+        }
+        let u32_at = |at: usize| u32::from_le_bytes([header[at], header[at + 1], header[at + 2], header[at + 3]]);
+        let u64_at = |at: usize| u64::from(u32_at(at)) | (u64::from(u32_at(at + 4)) << 32);
+        let format = DxgiFormat::from_raw(u32_at(12));
+        let image = ImageBuf::from_vec(pixels.to_vec(), u32_at(4), u32_at(8), u32_at(16) as usize, format)
+            .map_err(|e| invalid_frame(&e.to_string()))?;
+        Ok(Some(ShmFrame { frame: u64_at(20), timestamp_us: u64_at(28), image }))
+    }
+
+    /// 登録を消す（アクティブなら active も消す）
+    pub fn unregister(&self, name: &str) -> io::Result<()> {
+        match fs::remove_file(self.entry_path(name)) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+        let _ = fs::remove_file(self.frame_path(name));
+        let active = self.root.join(ACTIVE_FILE);
+        if fs::read_to_string(&active).is_ok_and(|active| active == name) {
+            let _ = fs::remove_file(active);
+        }
+        Ok(())
+    }
+}
+
+/// 共有メモリの一覧に登録し、フレームを書き出すセンダー
+#[derive(Debug)]
+pub struct ShmSender {
+    registry: ShmRegistry,
+    name: Option<String>,
+    format: DxgiFormat,
+    entry: Option<ShmSenderEntry>,
+    counter: FrameCounter,
+}
+
+impl ShmSender {
+    pub fn new(registry: ShmRegistry) -> Self {
+        Self { registry, name: None, format: DxgiFormat::B8G8R8A8Unorm, entry: None, counter: FrameCounter::default() }
+    }
+
+    pub fn registry(&self) -> &ShmRegistry {
+        &self.registry
+    }
+}
+
+impl SenderBackend for ShmSender {
+    type Texture = ImageBuf;
+
+    /// 送信中なら登録し直す（次の送信で新しい名前になる）
+    fn set_name(&mut self, name: Option<&str>) -> Result<(), SpoutDxResult> {
+        self.release()?;
+        self.name = Some(name.map_or_else(executable_name, str::to_string));
+        Ok(())
+    }
+
+    fn set_format(&mut self, format: DxgiFormat) -> Result<(), SpoutDxResult> {
+        self.format = format;
+        Ok(())
+    }
+
+    fn format(&self) -> DxgiFormat {
+        self.format
+    }
+
+    fn send_texture(&mut self, texture: &ImageBuf) -> Result<(), SpoutDxResult> {
+        self.format = texture.format();
+        self.send_image(&texture.view())
+    }
+
+    /// 最初の送信で登録し、フレームを書いてからエントリーを更新する
+    fn send_image(&mut self, image: &ImageView) -> Result<(), SpoutDxResult> {
+        let timestamp_us = MonotonicClock.now_us();
+        self.counter.record(timestamp_us);
+        let mut entry = match self.entry.take() {
+            Some(entry) => entry,
+            None => {
+                let name = self.name.get_or_insert_with(executable_name).clone();
+                let entry = ShmSenderEntry {
+                    name,
+                    width: image.width(),
+                    height: image.height(),
+                    format: image.format().raw(),
+                    frame: 0,
+                    fps: 0.0,
+                    pid: std::process::id(),
+                };
+                self.registry.register(&entry).map_err(|_| SpoutDxResult::ErrorSendFailed)?
+            }
+        };
+        entry.width = image.width();
+        entry.height = image.height();
+        entry.format = image.format().raw();
+        entry.frame = self.counter.frame();
+        entry.fps = self.counter.fps();
+        let result = self
+            .registry
+            .write_frame(&entry.name, entry.frame, timestamp_us, image)
+            .and_then(|()| self.registry.update(&entry))
+            .map_err(|_| SpoutDxResult::ErrorSendFailed);
+        self.entry = Some(entry);
+        result
+    }
+
+    fn release(&mut self) -> Result<(), SpoutDxResult> {
+        self.counter = FrameCounter::default();
+        match self.entry.take() {
+            Some(entry) => self.registry.unregister(&entry.name).map_err(|_| SpoutDxResult::ErrorInternal),
+            None => Ok(()),
+        }
+    }
+
+    fn info(&self) -> Option<SenderInfo> {
+        self.entry.as_ref().map(ShmSenderEntry::info)
+    }
+
+    fn frame(&self) -> u64 {
+        self.counter.frame()
+    }
+
+    fn fps(&self) -> f64 {
+        self.counter.fps()
+    }
+}
+
+/// 共有メモリのフレームを読む受信（MockReceiver と同じ操作）
+#[derive(Debug)]
+pub struct ShmReceiver {
+    registry: ShmRegistry,
+    /// None はアクティブなセンダー
+    sender_name: Option<String>,
+    connected_name: Option<String>,
+    current: Option<ShmFrame>,
+    connected: bool,
+    updated: bool,
+    frame_new: bool,
+    options: ReceiverOptions,
+}
+
+impl ShmReceiver {
+    /// `sender_name` が None ならアクティブなセンダーにつなぐ（受信のたびに選び直す）
+    pub fn new(registry: ShmRegistry, sender_name: Option<&str>) -> Self {
+        Self {
+            registry,
+            sender_name: sender_name.map(str::to_string),
+            connected_name: None,
+            current: None,
+            connected: false,
+            updated: false,
+            frame_new: false,
+            options: ReceiverOptions::default(),
+        }
+    }
+
+    pub fn set_options(&mut self, options: ReceiverOptions) {
+        self.options = options;
+    }
+
+    pub fn options(&self) -> ReceiverOptions {
+        self.options
+    }
+
+    fn disconnect(&mut self) -> Result<(), SpoutDxResult> {
+        self.connected_name = None;
+        self.current = None;
+        self.connected = false;
+        self.frame_new = false;
+        Err(SpoutDxResult::ErrorNotConnected)
+    }
+
+    /// `spoutdx_receiver_receive` 相当（センダーが 1 フレームも送っていなければ未接続）
+    pub fn receive(&mut self) -> Result<(), SpoutDxResult> {
+        let entry = match &self.sender_name {
+            Some(name) => self.registry.entry(name),
+            None => self.registry.active_sender(),
+        };
+        let Some(entry) = entry.map_err(|_| SpoutDxResult::ErrorReceiveFailed)? else {
+            return self.disconnect();
+        };
+        if self.connected_name.as_deref() != Some(entry.name.as_str()) {
+            self.current = None;
+            self.connected_name = Some(entry.name.clone());
+        }
+        let frame = self.registry.read_frame(&entry.name).map_err(|_| SpoutDxResult::ErrorReceiveFailed)?;
+        match frame {
+            Some(frame) if self.current.as_ref().is_none_or(|current| current.frame != frame.frame) => {
+                let changed = self.current.as_ref().is_none_or(|current| {
+                    (current.image.width(), current.image.height(), current.image.format())
+                        != (frame.image.width(), frame.image.height(), frame.image.format())
+                });
+                if changed {
+                    self.updated = true;
+                }
+                self.current = Some(frame);
+                self.frame_new = true;
+            }
+            // release の後に同じ名前で送り直したときなど、フレームが消えていれば未接続
+            None if self.current.is_some() => return self.disconnect(),
+            _ => self.frame_new = false,
+        }
+        if self.current.is_none() {
+            return self.disconnect();
+        }
+        self.connected = true;
+        Ok(())
+    }
+
+    /// `spoutdx_receiver_receive_image` 相当（受信オプションの変換・アルファ操作を適用）
+    pub fn receive_image(&self) -> Result<ImageBuf, SpoutDxResult> {
+        let current = self.current.as_ref().ok_or(SpoutDxResult::ErrorNotConnected)?;
+        let mut image = self
+            .options
+            .transform
+            .apply(&current.image.view())
+            .map_err(|_| SpoutDxResult::ErrorInternal)?;
+        self.options
+            .alpha
+            .apply(&mut image)
+            .map_err(|_| SpoutDxResult::ErrorInternal)?;
+        Ok(image)
+    }
+
+    /// 更新フラグを返してリセットする（SpoutDX::IsUpdated と同じ）
+    pub fn is_updated(&mut self) -> bool {
+        std::mem::take(&mut self.updated)
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.connected
+    }
+
+    pub fn is_frame_new(&self) -> bool {
+        self.frame_new
+    }
+
+    /// センダー側のフレーム番号
+    pub fn frame_count(&self) -> u64 {
+        self.current.as_ref().map_or(0, |current| current.frame)
+    }
+
+    /// 受信したフレームの送信時刻（MonotonicClock）
+    pub fn frame_timestamp_us(&self) -> Option<u64> {
+        self.current.as_ref().map(|current| current.timestamp_us)
+    }
+
+    pub fn sender_info(&self) -> Option<SenderInfo> {
+        let name = self.connected_name.clone()?;
+        self.current.as_ref().map(|current| SenderInfo {
+            name,
+            width: current.image.width(),
+            height: current.image.height(),
+            format: current.image.format(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sender::{Sender, MAX_NAME_LEN};
+
+    fn registry(tag: &str) -> ShmRegistry {
+        let dir = std::env::temp_dir().join(format!("ping-shm-{}-{}", tag, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        ShmRegistry::new(dir)
+    }
+
+    fn bgra(width: u32, height: u32) -> ImageBuf {
+        let data = (0..width * height).flat_map(|i| [i as u8, 0x40, 0x80, 0xFF]).collect();
+        ImageBuf::from_vec(data, width, height, width as usize * 4, DxgiFormat::B8G8R8A8Unorm).unwrap()
+    }
+
+    fn sender(registry: &ShmRegistry, name: &str) -> Sender<ShmSender> {
+        let mut sender = Sender::new(ShmSender::new(registry.clone()));
+        sender.set_name(name).unwrap();
+        sender
+    }
+
+    #[test]
+    fn colliding_names_get_a_suffix() {
+        let registry = registry("suffix");
+        let mut first = sender(&registry, "ping");
+        let mut second = sender(&registry, "ping");
+        first.send_image(&bgra(2, 2).view()).unwrap();
+        second.send_image(&bgra(2, 2).view()).unwrap();
+        assert_eq!(first.name().as_deref(), Some("ping"));
+        assert_eq!(second.name().as_deref(), Some("ping_1"));
+        let names: Vec<String> = registry.senders().unwrap().into_iter().map(|entry| entry.name).collect();
+        assert_eq!(names, ["ping", "ping_1"]);
+        assert_eq!(registry.active_sender().unwrap().map(|entry| entry.name).as_deref(), Some("ping"));
+
+        // 最大長の名前は `_1` を付けられないので送信に失敗する
+        let long = "a".repeat(MAX_NAME_LEN);
+        let mut third = sender(&registry, &long);
+        let mut fourth = sender(&registry, &long);
+        third.send_image(&bgra(2, 2).view()).unwrap();
+        assert_eq!(fourth.send_image(&bgra(2, 2).view()), Err(SpoutDxResult::ErrorSendFailed));
+        // エスケープで長くなる名前も登録できる
+        let mut wide = sender(&registry, &"あ".repeat(MAX_NAME_LEN / 3));
+        wide.send_image(&bgra(2, 2).view()).unwrap();
+        assert!(registry.entry(&"あ".repeat(MAX_NAME_LEN / 3)).unwrap().is_some());
+        let _ = fs::remove_dir_all(registry.root());
+    }
+
+    #[test]
+    fn send_image_converts_and_follows_size_changes() {
+        let registry = registry("convert");
+        let mut sender = sender(&registry, "ping");
+        let mut receiver = ShmReceiver::new(registry.clone(), Some("ping"));
+        sender.set_format(DxgiFormat::R8G8B8A8Unorm).unwrap();
+        for (width, height) in [(4, 4), (4, 4), (8, 2)] {
+            sender.send_image(&bgra(width, height).view()).unwrap();
+            let entry = registry.entry("ping").unwrap().unwrap();
+            assert_eq!((entry.width, entry.height, entry.dxgi_format()), (width, height, DxgiFormat::R8G8B8A8Unorm));
+            receiver.receive().unwrap();
+            // 最初の接続とサイズが変わったときだけ更新される
+            assert_eq!(receiver.is_updated(), receiver.frame_count() != 2);
+        }
+        let image = receiver.receive_image().unwrap();
+        assert_eq!(image.format(), DxgiFormat::R8G8B8A8Unorm);
+        assert_eq!(&image.row(0)[..8], &[0x80, 0x40, 0, 0xFF, 0x80, 0x40, 1, 0xFF]);
+        assert_eq!(sender.frame(), 3);
+        let _ = fs::remove_dir_all(registry.root());
+    }
+
+    #[test]
+    fn drop_releases_the_registry_entry() {
+        let registry = registry("drop");
+        let mut receiver = ShmReceiver::new(registry.clone(), None);
+        {
+            let mut sender = sender(&registry, "ping");
+            sender.send_image(&bgra(2, 2).view()).unwrap();
+            receiver.receive().unwrap();
+            assert!(registry.entry("ping").unwrap().is_some());
+        }
+        assert_eq!(registry.entry("ping").unwrap(), None);
+        assert_eq!(registry.read_frame("ping").unwrap(), None);
+        assert_eq!(registry.active_sender().unwrap(), None);
+        assert_eq!(receiver.receive(), Err(SpoutDxResult::ErrorNotConnected));
+
+        // 名前が空いたので次のセンダーは `_1` を付けずに登録される
+        let mut next = sender(&registry, "ping");
+        next.send_image(&bgra(2, 2).view()).unwrap();
+        assert_eq!(next.name().as_deref(), Some("ping"));
+        let _ = fs::remove_dir_all(registry.root());
+    }
+}
@@ -0,0 +1,180 @@
+// ============================================================
+// 画像統計（ヒストグラム・最小/最大・標準偏差・クリッピング）
+// ============================================================
+//
+// 格納値（0..1、sRGB エンコードのまま）を 1 パスで集計する。
+// 大きな画像は step 間隔で間引いて高速化できる。
+
+use serde::Serialize;
+
+use crate::hdr::decode_pixel;
+use crate::image::{DxgiFormat, ImageBuf, ImageError, ImageView};
+
+/// ヒストグラムのビン数
+pub const HISTOGRAM_BINS: usize = 256;
+
+/// 輝度の重み（BT.709、格納値に対して適用）
+const LUMA_WEIGHTS: [f32; 3] = [0.2126, 0.7152, 0.0722];
+
+/// 1 チャンネル分の統計
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ChannelHistogram {
+    /// 0..1 を 256 分割（範囲外はクランプ）
+    pub bins: Vec<u64>,
+    pub min: f32,
+    pub max: f32,
+    pub mean: f64,
+    pub stddev: f64,
+}
+
+/// 集計途中の値
+#[derive(Debug, Clone)]
+struct Accumulator {
+    bins: Vec<u64>,
+    min: f32,
+    max: f32,
+    sum: f64,
+    sum_sq: f64,
+}
+
+impl Accumulator {
+    fn new() -> Self {
+        Self {
+            bins: vec![0; HISTOGRAM_BINS],
+            min: f32::INFINITY,
+            max: f32::NEG_INFINITY,
+            sum: 0.0,
+            sum_sq: 0.0,
+        }
+    }
+
+    fn add(&mut self, value: f32) {
+        self.bins[bin_index(value)] += 1;
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+        self.sum += value as f64;
+        self.sum_sq += value as f64 * value as f64;
+    }
+
+    fn finish(self, count: u64) -> ChannelHistogram {
+        let n = count.max(1) as f64;
+        let mean = self.sum / n;
+        let variance = (self.sum_sq / n - mean * mean).max(0.0);
+        ChannelHistogram {
+            bins: self.bins,
+            min: if count == 0 { 0.0 } else { self.min },
+            max: if count == 0 { 0.0 } else { self.max },
+            mean,
+            stddev: variance.sqrt(),
+        }
+    }
+}
+
+fn bin_index(value: f32) -> usize {
+    // NaN は 0 に落ちる
+    ((value * 255.0).round().max(0.0) as usize).min(HISTOGRAM_BINS - 1)
+}
+
+/// 画像全体の統計
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ImageStats {
+    pub width: u32,
+    pub height: u32,
+    /// 間引き間隔（1 なら全ピクセル）
+    pub step: u32,
+    pub sampled_pixels: u64,
+    /// R, G, B, A の順
+    pub channels: [ChannelHistogram; 4],
+    /// 格納値から計算した輝度（BT.709 の重み）
+    pub luminance: ChannelHistogram,
+    /// RGB すべてが 0 のピクセルの割合（%）
+    pub clipped_black_percent: f64,
+    /// RGB すべてが 1.0 以上のピクセルの割合（%）
+    pub clipped_white_percent: f64,
+}
+
+/// 統計を 1 パスで計算（`step` ピクセルごとに縦横を間引く）
+pub fn compute_stats(view: &ImageView, step: u32) -> Result<ImageStats, ImageError> {
+    let step = step.max(1);
+    let format = view.format();
+    let bpp = view.bytes_per_pixel();
+
+    let mut channels: [Accumulator; 4] = std::array::from_fn(|_| Accumulator::new());
+    let mut luminance = Accumulator::new();
+    let (mut count, mut black, mut white) = (0u64, 0u64, 0u64);
+
+    for y in (0..view.height()).step_by(step as usize) {
+        let row = view.row(y);
+        for px in row.chunks_exact(bpp).step_by(step as usize) {
+            let rgba = decode_pixel(px, format)?;
+            for (acc, value) in channels.iter_mut().zip(rgba) {
+                acc.add(value);
+            }
+            let [r, g, b, _] = rgba;
+            luminance.add(LUMA_WEIGHTS[0] * r + LUMA_WEIGHTS[1] * g + LUMA_WEIGHTS[2] * b);
+            if r <= 0.0 && g <= 0.0 && b <= 0.0 {
+                black += 1;
+            }
+            if r >= 1.0 && g >= 1.0 && b >= 1.0 {
+                white += 1;
+            }
+            count += 1;
+        }
+    }
+
+    let percent = |n: u64| if count == 0 { 0.0 } else { n as f64 * 100.0 / count as f64 };
+    Ok(ImageStats {
+        width: view.width(),
+        height: view.height(),
+        step,
+        sampled_pixels: count,
+        channels: channels.map(|acc| acc.finish(count)),
+        luminance: luminance.finish(count),
+        clipped_black_percent: percent(black),
+        clipped_white_percent: percent(white),
+    })
+}
+
+/// ヒストグラムを ASCII で描画（`columns` 列 x `rows` 行、最大ビンで正規化）
+pub fn render_ascii(histogram: &ChannelHistogram, columns: usize, rows: usize) -> String {
+    let columns = columns.clamp(1, HISTOGRAM_BINS);
+    let rows = rows.max(1);
+    let per_column = HISTOGRAM_BINS.div_ceil(columns);
+    let sums: Vec<u64> = histogram.bins.chunks(per_column).map(|c| c.iter().sum()).collect();
+    let peak = sums.iter().copied().max().unwrap_or(0).max(1);
+
+    let mut out = String::new();
+    for row in (0..rows).rev() {
+        for &sum in &sums {
+            let height = sum as f64 * rows as f64 / peak as f64;
+            out.push(if height > row as f64 + 0.5 { '#' } else if height > row as f64 { '.' } else { ' ' });
+        }
+        out.push('\n');
+    }
+    out.push_str(&"-".repeat(sums.len()));
+    out.push('\n');
+    out
+}
+
+/// R/G/B のヒストグラムを重ねた画像（幅 256、BGRA8、重なった部分は加算色）
+pub fn render_image(stats: &ImageStats, height: u32) -> Result<ImageBuf, ImageError> {
+    let height = height.max(1);
+    let mut image = ImageBuf::new(HISTOGRAM_BINS as u32, height, DxgiFormat::B8G8R8A8Unorm)?;
+    let peak = stats.channels[..3]
+        .iter()
+        .flat_map(|c| c.bins.iter().copied())
+        .max()
+        .unwrap_or(0)
+        .max(1);
+
+    for x in 0..HISTOGRAM_BINS {
+        // R, G, B のバー高さ（ピクセル）
+        let bars = [0, 1, 2].map(|c| (stats.channels[c].bins[x] * height as u64).div_ceil(peak) as u32);
+        for y in 0..height {
+            let level = height - y;
+            let on = |c: usize| if bars[c] >= level { 255 } else { 0 };
+            image.pixel_mut(x as u32, y).copy_from_slice(&[on(2), on(1), on(0), 255]);
+        }
+    }
+    Ok(image)
+}
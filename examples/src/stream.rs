@@ -0,0 +1,410 @@
+// ============================================================
+// 動画ストリーム出力（YUV4MPEG2 / 生の BGRA）
+// ============================================================
+//
+// 受信したフレームを一時ファイルなしで外部エンコーダーへ渡す。
+// Y4M は yuv モジュールで I420 に変換し、ヘッダーにサイズとレートを書く。
+// 生の BGRA はフレームだけを並べる（ヘッダーは ffmpeg の入力引数として返す）。
+// センダーのサイズが変わったら、固定サイズへ拡大縮小するかエラーで終了する。
+
+use std::fmt;
+use std::io::{self, Write};
+use std::str::FromStr;
+
+use serde::Serialize;
+
+use crate::hdr::{to_bgra8, Tonemap};
+use crate::image::{DxgiFormat, ImageBuf, ImageError, ImageView};
+use crate::transform::resize;
+use crate::yuv::{to_yuv, YuvFormat, YuvMatrix, YuvRange, YuvSettings};
+
+/// ストリームの形式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StreamFormat {
+    /// YUV4MPEG2（4:2:0、I420）
+    #[default]
+    Y4m,
+    /// 8bit BGRA をパディングなしで並べる
+    Raw,
+}
+
+impl fmt::Display for StreamFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Y4m => write!(f, "y4m"),
+            Self::Raw => write!(f, "raw"),
+        }
+    }
+}
+
+/// フレームレート（分数）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct FrameRate {
+    pub num: u32,
+    pub den: u32,
+}
+
+impl Default for FrameRate {
+    fn default() -> Self {
+        Self { num: 60, den: 1 }
+    }
+}
+
+fn gcd(a: u32, b: u32) -> u32 {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
+
+impl FrameRate {
+    /// 実測の fps から（29.97 などの NTSC レートは 30000/1001 に合わせる）
+    pub fn from_fps(fps: f64) -> Option<Self> {
+        if !fps.is_finite() || fps <= 0.0 || fps > 1000.0 {
+            return None;
+        }
+        let rounded = fps.round();
+        if (fps - rounded).abs() < 0.005 {
+            return Some(Self { num: rounded as u32, den: 1 });
+        }
+        let ntsc = (fps * 1.001).round();
+        if (fps - ntsc / 1.001).abs() < 0.005 {
+            return Some(Self { num: ntsc as u32 * 1000, den: 1001 });
+        }
+        let (num, den) = ((fps * 1000.0).round() as u32, 1000);
+        let divisor = gcd(num, den);
+        Some(Self { num: num / divisor, den: den / divisor })
+    }
+
+    pub fn fps(self) -> f64 {
+        self.num as f64 / self.den as f64
+    }
+}
+
+impl fmt::Display for FrameRate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.num, self.den)
+    }
+}
+
+impl FromStr for FrameRate {
+    type Err = String;
+
+    /// `60` / `29.97` / `30000/1001`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid frame rate: {} (e.g. 60, 29.97, 30000/1001)", s);
+        match s.split_once('/') {
+            Some((num, den)) => {
+                let num = num.parse().ok().filter(|&num| num > 0).ok_or_else(invalid)?;
+                let den = den.parse().ok().filter(|&den| den > 0).ok_or_else(invalid)?;
+                Ok(Self { num, den })
+            }
+            None => s.parse().ok().and_then(Self::from_fps).ok_or_else(invalid),
+        }
+    }
+}
+
+/// 受信時刻からフレームレートを推定する（セッション全体の平均）
+#[derive(Debug, Clone, Default)]
+pub struct FpsEstimator {
+    first_us: Option<u64>,
+    last_us: u64,
+    frames: u64,
+}
+
+impl FpsEstimator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 新しいフレームの受信時刻を記録
+    pub fn record(&mut self, timestamp_us: u64) {
+        self.first_us.get_or_insert(timestamp_us);
+        self.last_us = timestamp_us;
+        self.frames += 1;
+    }
+
+    pub fn frames(&self) -> u64 {
+        self.frames
+    }
+
+    /// 平均 fps（2 フレーム未満か経過時間 0 なら None）
+    pub fn fps(&self) -> Option<f64> {
+        let elapsed_us = self.last_us.saturating_sub(self.first_us?);
+        (self.frames >= 2 && elapsed_us > 0).then(|| (self.frames - 1) as f64 * 1_000_000.0 / elapsed_us as f64)
+    }
+
+    /// 平均 fps に近い分数（NTSC レートに合わせる）
+    pub fn rate(&self) -> Option<FrameRate> {
+        self.fps().and_then(FrameRate::from_fps)
+    }
+}
+
+/// センダーのサイズが変わったときの動作
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ResizePolicy {
+    /// ストリームを終了してエラーを返す
+    #[default]
+    Error,
+    /// 縦横比を無視して出力サイズに合わせる
+    Stretch,
+    /// 縦横比を保って収め、余白は黒
+    Fit,
+}
+
+impl fmt::Display for ResizePolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Error => write!(f, "error"),
+            Self::Stretch => write!(f, "stretch"),
+            Self::Fit => write!(f, "fit"),
+        }
+    }
+}
+
+/// ストリームの設定
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct StreamOptions {
+    pub format: StreamFormat,
+    /// None ならセンダーの fps（わからなければ 60）
+    pub rate: Option<FrameRate>,
+    /// 出力サイズ（None なら最初のフレームのサイズ）
+    pub size: Option<(u32, u32)>,
+    pub resize: ResizePolicy,
+    /// Y4M の変換設定
+    pub yuv: YuvSettings,
+    /// FLOAT フォーマットを 8bit にするときのトーンマップ
+    pub tonemap: Tonemap,
+}
+
+impl fmt::Display for StreamOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.format)?;
+        match self.size {
+            Some((width, height)) => write!(f, " {}x{}", width, height)?,
+            None => write!(f, " (sender size)")?,
+        }
+        match self.rate {
+            Some(rate) => write!(f, " @ {}", rate)?,
+            None => write!(f, " @ sender rate")?,
+        }
+        write!(f, ", on resize: {}", self.resize)
+    }
+}
+
+impl FromStr for StreamOptions {
+    type Err = String;
+
+    /// `format=raw,fps=30000/1001,size=1280x720,resize=fit,matrix=601,range=full`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut options = Self::default();
+        for item in s.split(',').filter(|item| !item.is_empty()) {
+            let (key, value) = item
+                .split_once('=')
+                .ok_or_else(|| format!("invalid stream option: {} (expected key=value)", item))?;
+            let invalid = || format!("invalid stream option value: {}", item);
+            match (key, value.to_ascii_lowercase().as_str()) {
+                ("format", "y4m") => options.format = StreamFormat::Y4m,
+                ("format", "raw" | "bgra") => options.format = StreamFormat::Raw,
+                ("fps", _) => options.rate = Some(value.parse()?),
+                ("size", size) => {
+                    let (width, height) = size.split_once('x').ok_or_else(invalid)?;
+                    let width = width.parse().ok().filter(|&width| width > 0).ok_or_else(invalid)?;
+                    let height = height.parse().ok().filter(|&height| height > 0).ok_or_else(invalid)?;
+                    options.size = Some((width, height));
+                }
+                ("resize", "error") => options.resize = ResizePolicy::Error,
+                ("resize", "stretch") => options.resize = ResizePolicy::Stretch,
+                ("resize", "fit") => options.resize = ResizePolicy::Fit,
+                ("matrix", "601" | "bt601") => options.yuv.matrix = YuvMatrix::Bt601,
+                ("matrix", "709" | "bt709") => options.yuv.matrix = YuvMatrix::Bt709,
+                ("range", "limited" | "tv") => options.yuv.range = YuvRange::Limited,
+                ("range", "full" | "pc") => options.yuv.range = YuvRange::Full,
+                ("format" | "resize" | "matrix" | "range", _) => return Err(invalid()),
+                _ => return Err(format!("unknown stream option: {} (format, fps, size, resize, matrix, range)", key)),
+            }
+        }
+        Ok(options)
+    }
+}
+
+/// ストリームの書き込みの失敗
+#[derive(Debug)]
+pub enum StreamError {
+    Io(io::Error),
+    Image(ImageError),
+    /// センダーのサイズが変わった（ResizePolicy::Error）
+    SizeChanged { expected: (u32, u32), actual: (u32, u32) },
+    /// 以前のエラーでストリームは終了している
+    Ended,
+}
+
+impl fmt::Display for StreamError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "I/O error: {}", e),
+            Self::Image(e) => write!(f, "{}", e),
+            Self::SizeChanged { expected, actual } => write!(
+                f,
+                "sender size changed from {}x{} to {}x{}; stream ended (use resize=fit or resize=stretch to keep going)",
+                expected.0, expected.1, actual.0, actual.1
+            ),
+            Self::Ended => write!(f, "stream already ended"),
+        }
+    }
+}
+
+impl std::error::Error for StreamError {}
+
+impl From<io::Error> for StreamError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<ImageError> for StreamError {
+    fn from(e: ImageError) -> Self {
+        Self::Image(e)
+    }
+}
+
+/// 縦横比を保って (width, height) に収め、余白を黒で埋める
+fn fit(view: &ImageView, width: u32, height: u32) -> Result<ImageBuf, ImageError> {
+    let scale = (width as f64 / view.width() as f64).min(height as f64 / view.height() as f64);
+    let inner_width = ((view.width() as f64 * scale).round() as u32).clamp(1, width);
+    let inner_height = ((view.height() as f64 * scale).round() as u32).clamp(1, height);
+    let inner = resize(view, inner_width, inner_height)?;
+
+    let mut out = ImageBuf::new(width, height, view.format())?;
+    for y in 0..height {
+        for px in out.row_mut(y).chunks_exact_mut(4) {
+            px.copy_from_slice(&[0, 0, 0, 255]);
+        }
+    }
+    let (left, top) = ((width - inner_width) / 2, (height - inner_height) / 2);
+    for y in 0..inner_height {
+        let start = left as usize * 4;
+        out.row_mut(top + y)[start..start + inner_width as usize * 4].copy_from_slice(inner.row(y));
+    }
+    Ok(out)
+}
+
+/// フレームを 1 枚ずつ書き込むストリーム（最初のフレームでヘッダーを書く）
+pub struct VideoStreamWriter<W: Write> {
+    out: W,
+    options: StreamOptions,
+    /// 出力サイズ（最初のフレームで決まる）
+    size: Option<(u32, u32)>,
+    frames: u64,
+    ended: bool,
+}
+
+impl<W: Write> VideoStreamWriter<W> {
+    pub fn new(out: W, options: StreamOptions) -> Self {
+        Self { out, size: options.size, options, frames: 0, ended: false }
+    }
+
+    pub fn options(&self) -> &StreamOptions {
+        &self.options
+    }
+
+    pub fn frames(&self) -> u64 {
+        self.frames
+    }
+
+    /// 出力サイズ（`size` 未指定なら最初のフレームまで None）
+    pub fn size(&self) -> Option<(u32, u32)> {
+        self.size
+    }
+
+    /// 読み手の ffmpeg に渡す入力引数（生の BGRA はサイズ・レートをここで伝える）
+    pub fn ffmpeg_input_args(&self) -> Option<String> {
+        let rate = self.options.rate.unwrap_or_default();
+        match self.options.format {
+            StreamFormat::Y4m => Some("-f yuv4mpegpipe -i -".to_string()),
+            StreamFormat::Raw => self.size.map(|(width, height)| {
+                format!("-f rawvideo -pix_fmt bgra -video_size {}x{} -framerate {} -i -", width, height, rate)
+            }),
+        }
+    }
+
+    fn write_header(&mut self, width: u32, height: u32) -> io::Result<()> {
+        if self.options.format != StreamFormat::Y4m {
+            return Ok(());
+        }
+        let rate = self.options.rate.unwrap_or_default();
+        // 色差はブロックの平均なので中央配置（420jpeg）。行列は Y4M では伝えられない
+        let range = match self.options.yuv.range {
+            YuvRange::Limited => "LIMITED",
+            YuvRange::Full => "FULL",
+        };
+        writeln!(
+            self.out,
+            "YUV4MPEG2 W{} H{} F{}:{} Ip A1:1 C420jpeg XCOLORRANGE={}",
+            width, height, rate.num, rate.den, range
+        )
+    }
+
+    /// 1 フレームを書き込む（エラーの後はストリームを終了し、以後は Ended）
+    pub fn write_frame(&mut self, view: &ImageView) -> Result<(), StreamError> {
+        if self.ended {
+            return Err(StreamError::Ended);
+        }
+        let result = self.write_frame_inner(view);
+        if result.is_err() {
+            self.ended = true;
+            let _ = self.out.flush();
+        }
+        result
+    }
+
+    fn write_frame_inner(&mut self, view: &ImageView) -> Result<(), StreamError> {
+        // 8bit BGRA にそろえる
+        let converted;
+        let view = match view.format() {
+            DxgiFormat::B8G8R8A8Unorm | DxgiFormat::B8G8R8A8UnormSrgb => *view,
+            _ => {
+                converted = to_bgra8(view, self.options.tonemap)?;
+                converted.view()
+            }
+        };
+
+        let actual = (view.width(), view.height());
+        let first = self.frames == 0;
+        let (width, height) = *self.size.get_or_insert(actual);
+        let resized;
+        let view = if actual == (width, height) {
+            view
+        } else {
+            resized = match self.options.resize {
+                ResizePolicy::Error => return Err(StreamError::SizeChanged { expected: (width, height), actual }),
+                ResizePolicy::Stretch => resize(&view, width, height)?,
+                ResizePolicy::Fit => fit(&view, width, height)?,
+            };
+            resized.view()
+        };
+
+        if first {
+            self.write_header(width, height)?;
+        }
+        match self.options.format {
+            StreamFormat::Y4m => {
+                let frame = to_yuv(&view, YuvFormat::I420, self.options.yuv)?;
+                self.out.write_all(b"FRAME\n")?;
+                self.out.write_all(&frame.data)?;
+            }
+            StreamFormat::Raw => {
+                for y in 0..height {
+                    self.out.write_all(view.row(y))?;
+                }
+            }
+        }
+        self.frames += 1;
+        Ok(())
+    }
+
+    /// バッファを書き出して出力先を返す
+    pub fn finish(mut self) -> Result<W, StreamError> {
+        self.out.flush()?;
+        Ok(self.out)
+    }
+}
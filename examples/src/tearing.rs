@@ -0,0 +1,423 @@
+// ============================================================
+// ティアリング・部分更新の検出（行帯ごとのフレーム番号）
+// ============================================================
+//
+// テクスチャのアクセスロックが効かないと、1 枚の受信フレームに
+// 2 つの送信フレームが混ざる。画像を横長の帯に分け、各行に
+// フレーム番号を埋め込んでおき、帯ごとの番号が食い違えば
+// 行単位で境目（ティアリング位置）を特定する。
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::Serialize;
+
+use crate::hdr::{decode_pixel, encode_pixel};
+use crate::image::{DxgiFormat, ImageBuf, ImageError, ImageView};
+use crate::mock::MockReceiver;
+use crate::pattern::{FrameStamp, Pattern};
+
+/// 1 行に埋め込むブロック数（番号 32bit + 反転した番号 32bit）
+const ROW_BITS: u32 = 64;
+
+/// 行帯パターン（各行にフレーム番号の下位 32bit を白黒ブロックで埋め込む）
+///
+/// 帯の数は `new` で 1 以上にそろえる（高さを帯の数で割るため）。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RowBands {
+    bands: u32,
+}
+
+impl Default for RowBands {
+    fn default() -> Self {
+        Self { bands: 16 }
+    }
+}
+
+impl RowBands {
+    pub fn new(bands: u32) -> Self {
+        Self { bands: bands.max(1) }
+    }
+
+    pub fn bands(&self) -> u32 {
+        self.bands
+    }
+
+    fn check_size(&self, width: u32, height: u32) -> Result<(), ImageError> {
+        if width < ROW_BITS || height < self.bands {
+            return Err(ImageError::InvalidDimensions { width, height, row_pitch: 0 });
+        }
+        Ok(())
+    }
+
+    /// 行帯パターンを生成（帯ごとに地の明るさを変えて目視でも境目がわかるようにする）
+    pub fn generate(&self, width: u32, height: u32, format: DxgiFormat, frame: u64) -> Result<ImageBuf, ImageError> {
+        self.check_size(width, height)?;
+        let mut image = ImageBuf::new(width, height, format)?;
+        let value = frame as u32;
+        let block_width = width / ROW_BITS;
+        let band_height = height / self.bands;
+
+        for y in 0..height {
+            let band = (y / band_height).min(self.bands - 1);
+            let shade = if band.is_multiple_of(2) { 0.4 } else { 0.6 };
+            for x in 0..width {
+                let bit = x / block_width;
+                let level = if bit >= ROW_BITS {
+                    shade
+                } else {
+                    let word = if bit < 32 { value } else { !value };
+                    ((word >> (31 - bit % 32)) & 1) as f32
+                };
+                encode_pixel([level, level, level, 1.0], format, image.pixel_mut(x, y))?;
+            }
+        }
+        Ok(image)
+    }
+
+    /// 1 行分の番号を読む（反転ビットと一致しなければ None）
+    fn read_row(view: &ImageView, y: u32) -> Result<Option<u32>, ImageError> {
+        let block_width = view.width() / ROW_BITS;
+        let mut bits = 0u64;
+        for bit in 0..ROW_BITS {
+            let x = bit * block_width + block_width / 2;
+            let [r, g, b, _] = decode_pixel(view.pixel(x, y), view.format())?;
+            bits = bits << 1 | ((r + g + b) / 3.0 > 0.5) as u64;
+        }
+        let (value, check) = ((bits >> 32) as u32, bits as u32);
+        Ok((value == !check).then_some(value))
+    }
+
+    /// 受信フレームの帯ごとの番号を読み、食い違う帯の境目を行単位で探す
+    pub fn inspect(&self, view: &ImageView) -> Result<TearReport, ImageError> {
+        self.check_size(view.width(), view.height())?;
+        let band_height = view.height() / self.bands;
+        let centers: Vec<u32> = (0..self.bands).map(|b| b * band_height + band_height / 2).collect();
+        let mut bands = Vec::with_capacity(centers.len());
+        for &y in &centers {
+            bands.push(Self::read_row(view, y)?);
+        }
+
+        // 帯の中心に加えて先頭・末尾の行も読み、端の帯の中のティアリングも拾う
+        let last = view.height() - 1;
+        let mut rows = vec![(0, Self::read_row(view, 0)?)];
+        rows.extend(centers.iter().copied().zip(bands.iter().copied()).filter(|&(y, _)| y != 0 && y != last));
+        rows.push((last, Self::read_row(view, last)?));
+
+        // 読めた行どうしを順に比べ、番号が変わったら間を走査
+        let mut tears = Vec::new();
+        let mut previous: Option<(u32, u32)> = None;
+        for (y, value) in rows {
+            let Some(value) = value else { continue };
+            if let Some((prev_y, prev_value)) = previous
+                && prev_value != value
+            {
+                let mut row = y;
+                for scan in prev_y + 1..y {
+                    if Self::read_row(view, scan)? == Some(value) {
+                        row = scan;
+                        break;
+                    }
+                }
+                tears.push(Tear { row, above: prev_value as u64, below: value as u64 });
+            }
+            previous = Some((y, value));
+        }
+
+        Ok(TearReport {
+            bands: bands.into_iter().map(|v| v.map(u64::from)).collect(),
+            tears,
+        })
+    }
+}
+
+/// 番号が切り替わった位置
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Tear {
+    /// 下側のフレームが始まる行
+    pub row: u32,
+    pub above: u64,
+    pub below: u64,
+}
+
+/// 1 フレーム分の検査結果
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TearReport {
+    /// 上から順の帯ごとの番号（読めなければ None）
+    pub bands: Vec<Option<u64>>,
+    pub tears: Vec<Tear>,
+}
+
+impl TearReport {
+    pub fn is_torn(&self) -> bool {
+        !self.tears.is_empty()
+    }
+
+    pub fn unreadable_bands(&self) -> usize {
+        self.bands.iter().filter(|b| b.is_none()).count()
+    }
+
+    /// 最も多くの帯が持っている番号
+    pub fn frame(&self) -> Option<u64> {
+        let mut counts: Vec<(u64, usize)> = Vec::new();
+        for value in self.bands.iter().flatten() {
+            match counts.iter_mut().find(|(v, _)| v == value) {
+                Some((_, count)) => *count += 1,
+                None => counts.push((*value, 1)),
+            }
+        }
+        counts.into_iter().max_by_key(|&(_, count)| count).map(|(value, _)| value)
+    }
+}
+
+impl fmt::Display for TearReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let bands: Vec<String> = self
+            .bands
+            .iter()
+            .map(|b| b.map_or_else(|| "?".to_string(), |v| v.to_string()))
+            .collect();
+        writeln!(f, "[Tearing] bands: {}", bands.join(" "))?;
+        for tear in &self.tears {
+            writeln!(f, "  ⚠️  torn at row {}: frame {} above, frame {} below", tear.row, tear.above, tear.below)?;
+        }
+        Ok(())
+    }
+}
+
+/// 複数フレームの検査結果を集計
+#[derive(Debug, Clone, Default)]
+pub struct TearingTracker {
+    bands: u32,
+    frames: u64,
+    torn: u64,
+    unreadable: u64,
+    /// 帯の境目ごとのティアリング回数（境目 i は帯 i と i+1 の間）
+    boundaries: Vec<u64>,
+}
+
+impl TearingTracker {
+    pub fn new(bands: RowBands) -> Self {
+        Self { bands: bands.bands(), ..Self::default() }
+    }
+
+    /// `height` は検査したフレームの高さ（境目の集計に使う）
+    pub fn record(&mut self, report: &TearReport, height: u32) {
+        self.frames += 1;
+        if report.unreadable_bands() > 0 {
+            self.unreadable += 1;
+        }
+        if report.is_torn() {
+            self.torn += 1;
+        }
+        let band_height = (height / self.bands.max(1)).max(1);
+        self.boundaries.resize(self.bands.saturating_sub(1) as usize, 0);
+        for tear in &report.tears {
+            // 帯 i と i+1 の中心行の間なら境目 i（端の帯の中は最も近い境目）
+            let boundary = (tear.row.saturating_sub(band_height / 2 + 1) / band_height) as usize;
+            let boundary = boundary.min(self.boundaries.len().saturating_sub(1));
+            if let Some(count) = self.boundaries.get_mut(boundary) {
+                *count += 1;
+            }
+        }
+    }
+
+    pub fn summary(&self) -> TearingSummary {
+        TearingSummary {
+            frames: self.frames,
+            torn: self.torn,
+            unreadable: self.unreadable,
+            torn_percent: if self.frames == 0 { 0.0 } else { self.torn as f64 * 100.0 / self.frames as f64 },
+            boundaries: self.boundaries.clone(),
+        }
+    }
+}
+
+/// 集計結果
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TearingSummary {
+    pub frames: u64,
+    pub torn: u64,
+    /// 読めない帯があったフレーム数
+    pub unreadable: u64,
+    pub torn_percent: f64,
+    /// 帯の境目ごとのティアリング回数
+    pub boundaries: Vec<u64>,
+}
+
+impl fmt::Display for TearingSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "[Tearing] {} frames, {} torn ({:.2}%), {} with unreadable bands",
+            self.frames, self.torn, self.torn_percent, self.unreadable
+        )?;
+        if self.torn > 0 {
+            let counts: Vec<String> = self.boundaries.iter().map(u64::to_string).collect();
+            writeln!(f, "  tears per band boundary: {}", counts.join(" "))?;
+        }
+        Ok(())
+    }
+}
+
+// ============================================================
+// 送信パターン
+// ============================================================
+
+/// センダーが送るパターン（ティアリング検査の相手には `RowBands` を送る）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SendPattern {
+    /// フレーム番号と送信時刻（遅延計測用）
+    #[default]
+    FrameCounter,
+    /// 行帯ごとのフレーム番号（帯の数は受信側の `--bands` とそろえる）
+    RowBands,
+}
+
+impl SendPattern {
+    pub fn generate(
+        self,
+        bands: RowBands,
+        width: u32,
+        height: u32,
+        format: DxgiFormat,
+        stamp: FrameStamp,
+    ) -> Result<ImageBuf, ImageError> {
+        match self {
+            Self::FrameCounter => Pattern::FrameCounter.generate(width, height, format, stamp),
+            Self::RowBands => bands.generate(width, height, format, stamp.frame),
+        }
+    }
+}
+
+impl FromStr for SendPattern {
+    type Err = String;
+
+    /// `counter` / `bands`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "counter" => Ok(Self::FrameCounter),
+            "bands" => Ok(Self::RowBands),
+            _ => Err(format!("unknown send pattern: {} (counter, bands)", s)),
+        }
+    }
+}
+
+impl fmt::Display for SendPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::FrameCounter => write!(f, "frame counter"),
+            Self::RowBands => write!(f, "row bands"),
+        }
+    }
+}
+
+// ============================================================
+// モック（合成したティアリングで送受信を再現）
+// ============================================================
+
+/// 上を `top`、`row` 行目以降を `bottom` から取ったフレーム（サイズ・フォーマットは同じであること）
+pub fn torn_frame(top: &ImageView, bottom: &ImageView, row: u32) -> Result<ImageBuf, ImageError> {
+    if (top.width(), top.height()) != (bottom.width(), bottom.height()) {
+        return Err(ImageError::SizeMismatch {
+            expected: (top.width(), top.height()),
+            actual: (bottom.width(), bottom.height()),
+        });
+    }
+    if top.format() != bottom.format() {
+        return Err(ImageError::UnsupportedFormat(bottom.format()));
+    }
+    let mut image = top.to_image_buf();
+    let len = top.width() as usize * top.bytes_per_pixel();
+    for y in row.min(top.height())..top.height() {
+        image.row_mut(y)[..len].copy_from_slice(&bottom.row(y)[..len]);
+    }
+    Ok(image)
+}
+
+/// モック受信でティアリング検査を再現する
+///
+/// `tears[i]` が Some(row) なら、フレーム i の `row` 行目以降に次のフレームが
+/// 混ざった状態で届く（書き込み途中に読まれた状況）。
+pub fn simulate(tears: &[Option<u32>], bands: RowBands, width: u32, height: u32) -> Result<TearingTracker, ImageError> {
+    let mut receiver = MockReceiver::new("tearing-mock");
+    let mut tracker = TearingTracker::new(bands);
+    let format = DxgiFormat::B8G8R8A8Unorm;
+
+    for (index, tear) in tears.iter().enumerate() {
+        let frame = bands.generate(width, height, format, index as u64)?;
+        let sent = match tear {
+            Some(row) => {
+                let next = bands.generate(width, height, format, index as u64 + 1)?;
+                torn_frame(&frame.view(), &next.view(), *row)?
+            }
+            None => frame,
+        };
+        receiver.push_frame(sent);
+        if receiver.receive().is_err() {
+            continue;
+        }
+        if let Ok(image) = receiver.receive_image() {
+            tracker.record(&bands.inspect(&image.view())?, image.height());
+        }
+    }
+    Ok(tracker)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WIDTH: u32 = 256;
+    const HEIGHT: u32 = 144;
+
+    #[test]
+    fn zero_bands_is_one_band() {
+        let bands = RowBands::new(0);
+        assert_eq!(bands.bands(), 1);
+        let image = bands.generate(WIDTH, HEIGHT, DxgiFormat::B8G8R8A8Unorm, 7).unwrap();
+        let report = bands.inspect(&image.view()).unwrap();
+        assert_eq!(report.bands, [Some(7)]);
+        assert!(!report.is_torn());
+    }
+
+    #[test]
+    fn injected_tear_is_found_at_its_row() {
+        let bands = RowBands::default();
+        for row in [1, 40, 95, HEIGHT - 1] {
+            let top = bands.generate(WIDTH, HEIGHT, DxgiFormat::R8G8B8A8Unorm, 41).unwrap();
+            let bottom = bands.generate(WIDTH, HEIGHT, DxgiFormat::R8G8B8A8Unorm, 42).unwrap();
+            let torn = torn_frame(&top.view(), &bottom.view(), row).unwrap();
+            let report = bands.inspect(&torn.view()).unwrap();
+            assert_eq!(report.tears, [Tear { row, above: 41, below: 42 }], "row {}", row);
+        }
+    }
+
+    #[test]
+    fn simulate_counts_injected_tears() {
+        let bands = RowBands::new(4);
+        // 帯の高さは 36 行。境目 0（帯 0 と 1）と境目 2（帯 2 と 3）に 1 回ずつ
+        let tears = [None, Some(30), None, None, Some(100), None];
+        let summary = simulate(&tears, bands, WIDTH, HEIGHT).unwrap().summary();
+        assert_eq!((summary.frames, summary.torn, summary.unreadable), (6, 2, 0));
+        assert_eq!(summary.boundaries, [1, 0, 1]);
+
+        let clean = simulate(&[None; 10], bands, WIDTH, HEIGHT).unwrap().summary();
+        assert_eq!((clean.frames, clean.torn), (10, 0));
+    }
+
+    #[test]
+    fn send_pattern_matches_the_inspectors() {
+        let bands = RowBands::new(8);
+        let stamp = FrameStamp { frame: 1234, timestamp_us: 5678 };
+        let pattern: SendPattern = "bands".parse().unwrap();
+        let image = pattern.generate(bands, WIDTH, HEIGHT, DxgiFormat::B8G8R8A8Unorm, stamp).unwrap();
+        let report = bands.inspect(&image.view()).unwrap();
+        assert_eq!((report.frame(), report.is_torn()), (Some(1234), false));
+
+        let pattern: SendPattern = "counter".parse().unwrap();
+        let image = pattern.generate(bands, WIDTH, HEIGHT, DxgiFormat::B8G8R8A8Unorm, stamp).unwrap();
+        assert_eq!(Pattern::read_stamp(&image.view()), Some(stamp));
+        assert!("stripes".parse::<SendPattern>().is_err());
+    }
+}
@@ -0,0 +1,138 @@
+// ============================================================
+// TGA 書き出し（非圧縮 32bit BGRA）・読み込み
+// ============================================================
+//
+// 画像タイプ 2（非圧縮トゥルーカラー）、原点は左上、アルファ 8bit。
+// 末尾に TGA 2.0 のフッターを付ける。
+// 読み込みはカラーマップ・トゥルーカラー・グレースケールと、それぞれの RLE に対応する。
+
+use crate::export::push_row8;
+use crate::image::{ImageBuf, ImageError, ImageView};
+use crate::import::{check_dimensions, corrupt, le_u16, rgba8_image, slice, ImportError};
+
+/// 画像記述子: アルファ 8bit、原点は左上
+const DESCRIPTOR: u8 = 0x08 | 0x20;
+const FOOTER_SIGNATURE: &[u8; 18] = b"TRUEVISION-XFILE.\0";
+
+/// TGA ファイルのバイト列にエンコード（幅・高さは 65535 まで）
+pub fn encode_tga(view: &ImageView) -> Result<Vec<u8>, ImageError> {
+    let (width, height) = (view.width(), view.height());
+    let (Ok(w), Ok(h)) = (u16::try_from(width), u16::try_from(height)) else {
+        return Err(ImageError::InvalidDimensions { width, height, row_pitch: view.row_pitch() });
+    };
+
+    let mut out = Vec::with_capacity(18 + width as usize * height as usize * 4 + 26);
+    // ID 長, カラーマップなし, 画像タイプ 2, カラーマップ仕様（5 バイト）
+    out.extend([0, 0, 2, 0, 0, 0, 0, 0]);
+    // 原点 x, y
+    out.extend([0u8; 4]);
+    out.extend(w.to_le_bytes());
+    out.extend(h.to_le_bytes());
+    out.extend([32, DESCRIPTOR]);
+
+    for y in 0..height {
+        push_row8(view, y, &[2, 1, 0, 3], &mut out)?;
+    }
+
+    // 拡張領域・開発者領域なし
+    out.extend([0u8; 8]);
+    out.extend(FOOTER_SIGNATURE);
+    Ok(out)
+}
+
+/// 1 ピクセル（カラーマップの項目も同じ）を RGBA に
+fn unpack(px: &[u8], alpha_bits: u8) -> [u8; 4] {
+    match px.len() {
+        1 => [px[0], px[0], px[0], 255],
+        2 if alpha_bits == 8 => [px[0], px[0], px[0], px[1]],
+        2 => {
+            // 15/16bit: ARRRRRGG GGGBBBBB（リトルエンディアン）
+            let v = u16::from_le_bytes([px[0], px[1]]);
+            let five = |shift: u16| (((v >> shift) & 0x1F) * 255 / 31) as u8;
+            let alpha = if alpha_bits == 0 || v & 0x8000 != 0 { 255 } else { 0 };
+            [five(10), five(5), five(0), alpha]
+        }
+        3 => [px[2], px[1], px[0], 255],
+        _ => [px[2], px[1], px[0], if alpha_bits == 0 { 255 } else { px[3] }],
+    }
+}
+
+/// TGA ファイルを 8bit RGBA にデコード
+pub fn decode_tga(bytes: &[u8]) -> Result<ImageBuf, ImportError> {
+    let header = slice(bytes, 0, 18)?;
+    let (id_length, colormap_type, image_type) = (header[0], header[1], header[2]);
+    let (colormap_first, colormap_length, colormap_bits) = (le_u16(header, 3)?, le_u16(header, 5)?, header[7]);
+    let (width, height) = (le_u16(header, 12)? as u32, le_u16(header, 14)? as u32);
+    let (depth, descriptor) = (header[16], header[17]);
+    let alpha_bits = descriptor & 0x0F;
+    check_dimensions(width, height)?;
+
+    let mut at = 18 + id_length as usize;
+    let colormap: Vec<[u8; 4]> = if colormap_type == 1 {
+        let entry = (colormap_bits as usize).div_ceil(8);
+        let table = slice(bytes, at, colormap_length as usize * entry)?;
+        at += table.len();
+        table.chunks_exact(entry.max(1)).map(|px| unpack(px, alpha_bits)).collect()
+    } else {
+        Vec::new()
+    };
+
+    let colormapped = matches!(image_type, 1 | 9);
+    match (image_type, depth) {
+        (1 | 9, 8) if colormap_type == 1 => {}
+        (2 | 10, 15 | 16 | 24 | 32) | (3 | 11, 8 | 16) => {}
+        _ => return Err(ImportError::Unsupported(format!("TGA image type {} with {} bits", image_type, depth))),
+    }
+    let bpp = (depth as usize).div_ceil(8);
+    let pixel = |px: &[u8]| -> Result<[u8; 4], ImportError> {
+        if colormapped {
+            let index = (px[0] as usize).wrapping_sub(colormap_first as usize);
+            colormap.get(index).copied().ok_or_else(|| corrupt(format!("colormap index {} out of range", px[0])))
+        } else {
+            Ok(unpack(px, if image_type == 3 || image_type == 11 { depth - 8 } else { alpha_bits }))
+        }
+    };
+
+    // ファイルの並び（原点の位置に関係なく読んだ順）で展開
+    let pixels = width as usize * height as usize;
+    let mut decoded = Vec::with_capacity(pixels * 4);
+    if image_type >= 9 {
+        while decoded.len() < pixels * 4 {
+            let packet = *bytes.get(at).ok_or_else(|| corrupt("unexpected end of TGA RLE data"))?;
+            let count = (packet & 0x7F) as usize + 1;
+            at += 1;
+            if packet & 0x80 != 0 {
+                let rgba = pixel(slice(bytes, at, bpp)?)?;
+                at += bpp;
+                for _ in 0..count {
+                    decoded.extend(rgba);
+                }
+            } else {
+                for px in slice(bytes, at, count * bpp)?.chunks_exact(bpp) {
+                    decoded.extend(pixel(px)?);
+                }
+                at += count * bpp;
+            }
+        }
+        decoded.truncate(pixels * 4);
+    } else {
+        for px in slice(bytes, at, pixels * bpp)?.chunks_exact(bpp) {
+            decoded.extend(pixel(px)?);
+        }
+    }
+
+    // 原点: bit 5 が立っていれば上から、bit 4 が立っていれば右から
+    let (top_down, right_to_left) = (descriptor & 0x20 != 0, descriptor & 0x10 != 0);
+    let stride = width as usize * 4;
+    let mut out = vec![0u8; pixels * 4];
+    for (y, row) in decoded.chunks_exact(stride).enumerate() {
+        let dst = &mut out[if top_down { y } else { height as usize - 1 - y } * stride..][..stride];
+        dst.copy_from_slice(row);
+        if right_to_left {
+            let mut pixels: Vec<[u8; 4]> = dst.chunks_exact(4).map(|px| [px[0], px[1], px[2], px[3]]).collect();
+            pixels.reverse();
+            dst.copy_from_slice(pixels.as_flattened());
+        }
+    }
+    rgba8_image(width, height, out)
+}
@@ -0,0 +1,388 @@
+// ============================================================
+// 画像変換（上下反転・左右反転・R/B 入れ替え・回転）
+// ============================================================
+//
+// SpoutDX の SetMirror / SetSwap、SpoutCopy の FlipBuffer、
+// ReceiveImage の bInvert に相当する処理を CPU 側で行う。
+
+use crate::image::{DxgiFormat, ImageBuf, ImageError, ImageView};
+
+/// 時計回りの回転
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Rotation {
+    #[default]
+    None,
+    Cw90,
+    Cw180,
+    Cw270,
+}
+
+impl Rotation {
+    /// 角度（度）から変換
+    pub fn from_degrees(degrees: u32) -> Option<Self> {
+        match degrees % 360 {
+            0 => Some(Self::None),
+            90 => Some(Self::Cw90),
+            180 => Some(Self::Cw180),
+            270 => Some(Self::Cw270),
+            _ => None,
+        }
+    }
+
+    /// 幅と高さが入れ替わるか
+    pub fn swaps_dimensions(self) -> bool {
+        matches!(self, Self::Cw90 | Self::Cw270)
+    }
+}
+
+// SpoutDxTransform（spoutdx_ffi.h）のビット値
+pub const TRANSFORM_FLIP_VERTICAL: u32 = 1 << 0;
+pub const TRANSFORM_MIRROR_HORIZONTAL: u32 = 1 << 1;
+pub const TRANSFORM_SWAP_RB: u32 = 1 << 2;
+pub const TRANSFORM_ROTATE_90: u32 = 1 << 3;
+pub const TRANSFORM_ROTATE_180: u32 = 2 << 3;
+pub const TRANSFORM_ROTATE_270: u32 = 3 << 3;
+pub const TRANSFORM_ROTATE_MASK: u32 = 3 << 3;
+
+/// 受信画像に適用する変換の組み合わせ
+///
+/// 適用順は C ABI 側と同じく R/B 入れ替え → 上下反転 → 左右反転 → 回転。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Transform {
+    pub flip_vertical: bool,
+    pub mirror_horizontal: bool,
+    pub swap_rb: bool,
+    pub rotation: Rotation,
+}
+
+impl Transform {
+    pub fn is_identity(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// `spoutdx_receiver_set_transform` に渡すフラグへ変換
+    pub fn to_flags(&self) -> u32 {
+        let mut flags = 0;
+        if self.flip_vertical {
+            flags |= TRANSFORM_FLIP_VERTICAL;
+        }
+        if self.mirror_horizontal {
+            flags |= TRANSFORM_MIRROR_HORIZONTAL;
+        }
+        if self.swap_rb {
+            flags |= TRANSFORM_SWAP_RB;
+        }
+        flags | match self.rotation {
+            Rotation::None => 0,
+            Rotation::Cw90 => TRANSFORM_ROTATE_90,
+            Rotation::Cw180 => TRANSFORM_ROTATE_180,
+            Rotation::Cw270 => TRANSFORM_ROTATE_270,
+        }
+    }
+
+    /// フラグから変換（未定義ビットがあれば None）
+    pub fn from_flags(flags: u32) -> Option<Self> {
+        let known = TRANSFORM_FLIP_VERTICAL | TRANSFORM_MIRROR_HORIZONTAL | TRANSFORM_SWAP_RB | TRANSFORM_ROTATE_MASK;
+        if flags & !known != 0 {
+            return None;
+        }
+        let rotation = match flags & TRANSFORM_ROTATE_MASK {
+            TRANSFORM_ROTATE_90 => Rotation::Cw90,
+            TRANSFORM_ROTATE_180 => Rotation::Cw180,
+            TRANSFORM_ROTATE_270 => Rotation::Cw270,
+            _ => Rotation::None,
+        };
+        Some(Self {
+            flip_vertical: flags & TRANSFORM_FLIP_VERTICAL != 0,
+            mirror_horizontal: flags & TRANSFORM_MIRROR_HORIZONTAL != 0,
+            swap_rb: flags & TRANSFORM_SWAP_RB != 0,
+            rotation,
+        })
+    }
+
+    /// 変換後の (幅, 高さ)
+    pub fn output_size(&self, width: u32, height: u32) -> (u32, u32) {
+        if self.rotation.swaps_dimensions() {
+            (height, width)
+        } else {
+            (width, height)
+        }
+    }
+
+    /// 変換しながら新しいバッファへコピー（row_pitch のパディングは除去される）
+    pub fn apply(&self, src: &ImageView) -> Result<ImageBuf, ImageError> {
+        let format = src.format();
+        let bpp = src.bytes_per_pixel();
+        let (width, height) = (src.width(), src.height());
+        let (out_width, out_height) = self.output_size(width, height);
+        let mut dst = ImageBuf::new(out_width, out_height, format)?;
+
+        for y in 0..height {
+            let sy = if self.flip_vertical { height - 1 - y } else { y };
+            let src_row = src.row(sy);
+            for x in 0..width {
+                let sx = if self.mirror_horizontal { width - 1 - x } else { x };
+                let (dx, dy) = match self.rotation {
+                    Rotation::None => (x, y),
+                    Rotation::Cw90 => (height - 1 - y, x),
+                    Rotation::Cw180 => (width - 1 - x, height - 1 - y),
+                    Rotation::Cw270 => (y, width - 1 - x),
+                };
+                let offset = sx as usize * bpp;
+                let px = dst.pixel_mut(dx, dy);
+                px.copy_from_slice(&src_row[offset..offset + bpp]);
+                if self.swap_rb {
+                    swap_rb_pixel(px, format)?;
+                }
+            }
+        }
+        Ok(dst)
+    }
+
+    /// バッファ上で直接変換（90/270 度回転のみ再確保する）
+    pub fn apply_in_place(&self, buf: &mut ImageBuf) -> Result<(), ImageError> {
+        if self.rotation.swaps_dimensions() {
+            *buf = self.apply(&buf.view())?;
+            return Ok(());
+        }
+
+        let format = buf.format();
+        if self.swap_rb {
+            for y in 0..buf.height() {
+                for px in buf.row_mut(y).chunks_exact_mut(format.bytes_per_pixel().unwrap_or(4)) {
+                    swap_rb_pixel(px, format)?;
+                }
+            }
+        }
+
+        // 180 度回転 = 上下反転 + 左右反転
+        let flip = self.flip_vertical != (self.rotation == Rotation::Cw180);
+        let mirror = self.mirror_horizontal != (self.rotation == Rotation::Cw180);
+        if flip {
+            flip_vertical_in_place(buf);
+        }
+        if mirror {
+            mirror_horizontal_in_place(buf);
+        }
+        Ok(())
+    }
+}
+
+/// 行を入れ替えて上下反転（SpoutCopy::FlipBuffer 相当）
+pub fn flip_vertical_in_place(buf: &mut ImageBuf) {
+    let height = buf.height();
+    let row_pitch = buf.row_pitch();
+    let row_bytes = buf.row(0).len();
+    let data = buf.data_mut();
+    for y in 0..(height / 2) as usize {
+        let bottom = height as usize - 1 - y;
+        let (upper, lower) = data.split_at_mut(bottom * row_pitch);
+        upper[y * row_pitch..y * row_pitch + row_bytes].swap_with_slice(&mut lower[..row_bytes]);
+    }
+}
+
+/// 各行のピクセル順を反転して左右反転
+pub fn mirror_horizontal_in_place(buf: &mut ImageBuf) {
+    let bpp = buf.view().bytes_per_pixel();
+    for y in 0..buf.height() {
+        let row = buf.row_mut(y);
+        let width = row.len() / bpp;
+        for x in 0..width / 2 {
+            let (left, right) = row.split_at_mut((width - 1 - x) * bpp);
+            left[x * bpp..(x + 1) * bpp].swap_with_slice(&mut right[..bpp]);
+        }
+    }
+}
+
+/// バイリニアで拡大縮小（8bit の RGBA / BGRA のみ、チャンネルの並びはそのまま）
+pub fn resize(src: &ImageView, width: u32, height: u32) -> Result<ImageBuf, ImageError> {
+    let format = src.format();
+    if !matches!(
+        format,
+        DxgiFormat::R8G8B8A8Unorm | DxgiFormat::R8G8B8A8UnormSrgb | DxgiFormat::B8G8R8A8Unorm | DxgiFormat::B8G8R8A8UnormSrgb
+    ) {
+        return Err(ImageError::UnsupportedFormat(format));
+    }
+    let mut dst = ImageBuf::new(width, height, format)?;
+    if (width, height) == (src.width(), src.height()) {
+        for y in 0..height {
+            dst.row_mut(y).copy_from_slice(src.row(y));
+        }
+        return Ok(dst);
+    }
+
+    // ピクセル中心を合わせた元画像上の位置（左右の 2 点と重み）
+    let sample = |dst_pos: u32, dst_len: u32, src_len: u32| {
+        let pos = ((dst_pos as f32 + 0.5) * src_len as f32 / dst_len as f32 - 0.5).max(0.0);
+        let i0 = (pos as u32).min(src_len - 1);
+        let i1 = (i0 + 1).min(src_len - 1);
+        (i0, i1, pos - i0 as f32)
+    };
+    let columns: Vec<_> = (0..width).map(|x| sample(x, width, src.width())).collect();
+    for y in 0..height {
+        let (y0, y1, fy) = sample(y, height, src.height());
+        let (row0, row1) = (src.row(y0), src.row(y1));
+        for (x, &(x0, x1, fx)) in columns.iter().enumerate() {
+            let px = dst.pixel_mut(x as u32, y);
+            for (c, value) in px.iter_mut().enumerate() {
+                let at = |row: &[u8], i: u32| row[i as usize * 4 + c] as f32;
+                let top = at(row0, x0) + (at(row0, x1) - at(row0, x0)) * fx;
+                let bottom = at(row1, x0) + (at(row1, x1) - at(row1, x0)) * fx;
+                *value = (top + (bottom - top) * fy + 0.5) as u8;
+            }
+        }
+    }
+    Ok(dst)
+}
+
+/// 1 ピクセルの R と B を入れ替える
+fn swap_rb_pixel(px: &mut [u8], format: DxgiFormat) -> Result<(), ImageError> {
+    match format {
+        DxgiFormat::R8G8B8A8Unorm
+        | DxgiFormat::R8G8B8A8UnormSrgb
+        | DxgiFormat::B8G8R8A8Unorm
+        | DxgiFormat::B8G8R8A8UnormSrgb => px.swap(0, 2),
+        DxgiFormat::R16G16B16A16Float => {
+            px.swap(0, 4);
+            px.swap(1, 5);
+        }
+        DxgiFormat::R32G32B32A32Float => {
+            let (r, rest) = px.split_at_mut(4);
+            r.swap_with_slice(&mut rest[4..8]);
+        }
+        DxgiFormat::R10G10B10A2Unorm => {
+            let v = u32::from_le_bytes([px[0], px[1], px[2], px[3]]);
+            let r = v & 0x3FF;
+            let b = (v >> 20) & 0x3FF;
+            let swapped = (v & !(0x3FF | (0x3FF << 20))) | b | (r << 20);
+            px.copy_from_slice(&swapped.to_le_bytes());
+        }
+        DxgiFormat::Unknown(_) => return Err(ImageError::UnsupportedFormat(format)),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ROTATIONS: [Rotation; 4] = [Rotation::None, Rotation::Cw90, Rotation::Cw180, Rotation::Cw270];
+
+    /// 全部の組み合わせ（3 つのフラグ × 4 つの回転）
+    fn all_transforms() -> Vec<Transform> {
+        (0..32u32)
+            .map(|bits| Transform {
+                flip_vertical: bits & 1 != 0,
+                mirror_horizontal: bits & 2 != 0,
+                swap_rb: bits & 4 != 0,
+                rotation: ROTATIONS[(bits >> 3) as usize],
+            })
+            .collect()
+    }
+
+    /// 画素ごとに値の違う 5x3 の画像（行末に 8 バイトのパディング）
+    fn sample(format: DxgiFormat) -> ImageBuf {
+        let (width, height) = (5u32, 3u32);
+        let bpp = format.bytes_per_pixel().unwrap();
+        let row_pitch = width as usize * bpp + 8;
+        let data = (0..row_pitch * height as usize).map(|i| (i * 7 + 3) as u8).collect();
+        ImageBuf::from_vec(data, width, height, row_pitch, format).unwrap()
+    }
+
+    fn rows(image: &ImageBuf) -> Vec<Vec<u8>> {
+        (0..image.height()).map(|y| image.row(y).to_vec()).collect()
+    }
+
+    #[test]
+    fn apply_in_place_matches_apply() {
+        for format in [DxgiFormat::B8G8R8A8Unorm, DxgiFormat::R16G16B16A16Float, DxgiFormat::R10G10B10A2Unorm] {
+            let image = sample(format);
+            for transform in all_transforms() {
+                let copied = transform.apply(&image.view()).unwrap();
+                let mut in_place = image.clone();
+                transform.apply_in_place(&mut in_place).unwrap();
+                assert_eq!((in_place.width(), in_place.height()), (copied.width(), copied.height()));
+                assert_eq!(rows(&in_place), rows(&copied), "{:?} {:?}", format, transform);
+            }
+        }
+    }
+
+    #[test]
+    fn quarter_turns_swap_the_output_size() {
+        let image = sample(DxgiFormat::B8G8R8A8Unorm);
+        for rotation in ROTATIONS {
+            let transform = Transform { rotation, ..Transform::default() };
+            let expected = if rotation.swaps_dimensions() { (3, 5) } else { (5, 3) };
+            assert_eq!(transform.output_size(5, 3), expected);
+            let rotated = transform.apply(&image.view()).unwrap();
+            assert_eq!((rotated.width(), rotated.height()), expected);
+        }
+        // 90 度: 左下の画素が左上へ、270 度: 右上の画素が左上へ
+        let cw90 = Transform { rotation: Rotation::Cw90, ..Transform::default() }.apply(&image.view()).unwrap();
+        assert_eq!(cw90.pixel(0, 0), image.pixel(0, 2));
+        let cw270 = Transform { rotation: Rotation::Cw270, ..Transform::default() }.apply(&image.view()).unwrap();
+        assert_eq!(cw270.pixel(0, 0), image.pixel(4, 0));
+    }
+
+    #[test]
+    fn flags_round_trip() {
+        for transform in all_transforms() {
+            assert_eq!(Transform::from_flags(transform.to_flags()), Some(transform));
+        }
+        assert_eq!(Transform::from_flags(0), Some(Transform::default()));
+        assert_eq!(Transform::from_flags(1 << 5), None);
+    }
+
+    /// spoutdx_ffi.cpp の受信と同じ手順: ReadPixelData で R/B 入れ替えと上下反転、
+    /// TransformPixels で左右反転と回転（添字の計算もそのまま）
+    fn transform_pixels(src: &[u32], width: u32, height: u32, flags: u32) -> Vec<u32> {
+        let read: Vec<u32> = (0..height)
+            .flat_map(|y| {
+                let sy = if flags & TRANSFORM_FLIP_VERTICAL != 0 { height - 1 - y } else { y };
+                (0..width).map(move |x| (sy * width + x) as usize)
+            })
+            .map(|i| {
+                let px = src[i];
+                if flags & TRANSFORM_SWAP_RB != 0 {
+                    (px & 0xFF00FF00) | ((px >> 16) & 0xFF) | ((px & 0xFF) << 16)
+                } else {
+                    px
+                }
+            })
+            .collect();
+
+        let (w, h) = (width as usize, height as usize);
+        let mut dst = vec![0; w * h];
+        for y in 0..h {
+            for x in 0..w {
+                let sx = if flags & TRANSFORM_MIRROR_HORIZONTAL != 0 { w - 1 - x } else { x };
+                let index = match flags & TRANSFORM_ROTATE_MASK {
+                    TRANSFORM_ROTATE_90 => x * h + (h - 1 - y),
+                    TRANSFORM_ROTATE_180 => (h - 1 - y) * w + (w - 1 - x),
+                    TRANSFORM_ROTATE_270 => (w - 1 - x) * h + y,
+                    _ => y * w + x,
+                };
+                dst[index] = read[y * w + sx];
+            }
+        }
+        dst
+    }
+
+    #[test]
+    fn order_matches_the_c_abi() {
+        let (width, height) = (5u32, 3u32);
+        let pixels: Vec<u32> = (0..width * height).map(|i| 0x80000000 | (i << 16) | (i << 8) | (i * 3)).collect();
+        let data = pixels.iter().flat_map(|px| px.to_le_bytes()).collect();
+        let image = ImageBuf::from_vec(data, width, height, width as usize * 4, DxgiFormat::R8G8B8A8Unorm).unwrap();
+        for transform in all_transforms() {
+            let expected = transform_pixels(&pixels, width, height, transform.to_flags());
+            let actual = transform.apply(&image.view()).unwrap();
+            let actual: Vec<u32> = actual
+                .data()
+                .chunks_exact(4)
+                .map(|px| u32::from_le_bytes([px[0], px[1], px[2], px[3]]))
+                .collect();
+            assert_eq!(actual, expected, "{:?}", transform);
+        }
+    }
+}
@@ -0,0 +1,99 @@
+// ============================================================
+// WIC（Windows Imaging Component）での画像の読み書き
+// ============================================================
+//
+// Windows だけのバックエンド。PNG の書き出しは通常 png モジュールの
+// Rust 実装を使い、`PngBackend::Wic` を選んだときだけここを通る。
+
+use std::path::Path;
+
+use windows::{
+    core::{Error, Result, HRESULT, PCWSTR},
+    Win32::Graphics::Imaging::*,
+    Win32::System::Com::*,
+};
+
+use crate::image::{DxgiFormat, ImageBuf};
+
+const GENERIC_READ: u32 = 0x80000000;
+const GENERIC_WRITE: u32 = 0x40000000;
+const E_INVALIDARG: HRESULT = HRESULT(0x80070057_u32 as i32);
+
+/// COM を初期化して WIC のファクトリーを作る
+unsafe fn factory() -> Result<IWICImagingFactory> {
+    unsafe {
+        // 既に初期化されている場合は S_FALSE が返るが無視してよい
+        let _ = CoInitializeEx(None, COINIT_MULTITHREADED);
+        CoCreateInstance(&CLSID_WICImagingFactory, None, CLSCTX_INPROC_SERVER)
+    }
+}
+
+/// ファイルを開いたストリーム
+unsafe fn open_stream(factory: &IWICImagingFactory, path: &Path, access: u32) -> Result<IWICStream> {
+    unsafe {
+        let stream = factory.CreateStream()?;
+        let wide_path: Vec<u16> = path.to_string_lossy().encode_utf16().chain(std::iter::once(0)).collect();
+        stream.InitializeFromFilename(PCWSTR(wide_path.as_ptr()), access)?;
+        Ok(stream)
+    }
+}
+
+/// 詰めて並べた 8bit RGBA を PNG で保存
+pub fn save_rgba8(path: &Path, width: u32, height: u32, rgba: &[u8]) -> Result<()> {
+    unsafe {
+        let factory = factory()?;
+        let stream = open_stream(&factory, path, GENERIC_WRITE)?;
+
+        let encoder = factory.CreateEncoder(&GUID_ContainerFormatPng, std::ptr::null())?;
+        encoder.Initialize(&stream, WICBitmapEncoderNoCache)?;
+
+        let mut frame: Option<IWICBitmapFrameEncode> = None;
+        let mut property_bag: Option<windows::Win32::System::Com::StructuredStorage::IPropertyBag2> = None;
+        encoder.CreateNewFrame(&mut frame, &mut property_bag)?;
+        let frame = frame.ok_or_else(Error::empty)?;
+        frame.Initialize(property_bag.as_ref())?;
+        frame.SetSize(width, height)?;
+
+        // WIC は要求と違うフォーマットを採用することがある（BGRA なら並べ替えて渡す）
+        let mut pixel_format = GUID_WICPixelFormat32bppRGBA;
+        frame.SetPixelFormat(&mut pixel_format)?;
+        let swapped: Vec<u8>;
+        let data = if pixel_format == GUID_WICPixelFormat32bppRGBA {
+            rgba
+        } else if pixel_format == GUID_WICPixelFormat32bppBGRA {
+            swapped = rgba.chunks_exact(4).flat_map(|px| [px[2], px[1], px[0], px[3]]).collect();
+            &swapped
+        } else {
+            return Err(Error::new(
+                E_INVALIDARG,
+                format!("WIC chose an unexpected pixel format: {:?}", pixel_format),
+            ));
+        };
+
+        frame.WritePixels(height, width * 4, data)?;
+        frame.Commit()?;
+        encoder.Commit()?;
+        Ok(())
+    }
+}
+
+/// 画像ファイル（PNG など）を読み込んで BGRA8 に変換
+pub fn load_bgra8(path: &Path) -> Result<ImageBuf> {
+    unsafe {
+        let factory = factory()?;
+        let stream = open_stream(&factory, path, GENERIC_READ)?;
+
+        let decoder = factory.CreateDecoderFromStream(&stream, std::ptr::null(), WICDecodeMetadataCacheOnDemand)?;
+        let frame = decoder.GetFrame(0)?;
+
+        // どのピクセルフォーマットでも BGRA8 に変換してから読み出す
+        let source = WICConvertBitmapSource(&GUID_WICPixelFormat32bppBGRA, &frame)?;
+        let (mut width, mut height) = (0u32, 0u32);
+        source.GetSize(&mut width, &mut height)?;
+
+        let mut image = ImageBuf::new(width, height, DxgiFormat::B8G8R8A8Unorm).map_err(|_| Error::from_win32())?;
+        let row_pitch = image.row_pitch() as u32;
+        source.CopyPixels(std::ptr::null(), row_pitch, image.data_mut())?;
+        Ok(image)
+    }
+}
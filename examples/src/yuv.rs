@@ -0,0 +1,318 @@
+// ============================================================
+// YUV 変換（NV12 / I420 / UYVY）
+// ============================================================
+//
+// 受信した 8bit BGRA / RGBA を、エンコーダーやキャプチャカード向けの
+// YUV に変換する。往復テスト用に逆変換も用意する。
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::image::{DxgiFormat, ImageBuf, ImageError, ImageView};
+
+/// 変換行列
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum YuvMatrix {
+    Bt601,
+    #[default]
+    Bt709,
+}
+
+impl YuvMatrix {
+    /// (Kr, Kb)
+    fn coefficients(self) -> (f32, f32) {
+        match self {
+            Self::Bt601 => (0.299, 0.114),
+            Self::Bt709 => (0.2126, 0.0722),
+        }
+    }
+}
+
+/// 値の範囲
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum YuvRange {
+    /// Y: 16-235, C: 16-240（放送規格）
+    #[default]
+    Limited,
+    /// Y / C: 0-255
+    Full,
+}
+
+impl YuvRange {
+    /// (Y のオフセット, Y のスケール, C のスケール)
+    fn scale(self) -> (f32, f32, f32) {
+        match self {
+            Self::Limited => (16.0, 219.0, 224.0),
+            Self::Full => (0.0, 255.0, 255.0),
+        }
+    }
+}
+
+/// YUV のメモリレイアウト
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum YuvFormat {
+    /// Y 平面 + UV インターリーブ平面（4:2:0）
+    Nv12,
+    /// Y / U / V の 3 平面（4:2:0）
+    I420,
+    /// U0 Y0 V0 Y1 のパック形式（4:2:2）
+    Uyvy,
+}
+
+impl fmt::Display for YuvFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Nv12 => write!(f, "nv12"),
+            Self::I420 => write!(f, "i420"),
+            Self::Uyvy => write!(f, "uyvy"),
+        }
+    }
+}
+
+impl FromStr for YuvFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "nv12" => Ok(Self::Nv12),
+            "i420" | "yuv420p" => Ok(Self::I420),
+            "uyvy" => Ok(Self::Uyvy),
+            _ => Err(format!("unknown YUV format: {} (nv12, i420, uyvy)", s)),
+        }
+    }
+}
+
+/// 変換設定
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct YuvSettings {
+    pub matrix: YuvMatrix,
+    pub range: YuvRange,
+}
+
+/// YUV フレーム（パディングなし）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct YuvFrame {
+    pub format: YuvFormat,
+    pub width: u32,
+    pub height: u32,
+    pub data: Vec<u8>,
+}
+
+impl YuvFrame {
+    /// 色差のサイズ（奇数サイズは切り上げ）
+    pub fn chroma_size(format: YuvFormat, width: u32, height: u32) -> (u32, u32) {
+        match format {
+            YuvFormat::Nv12 | YuvFormat::I420 => (width.div_ceil(2), height.div_ceil(2)),
+            YuvFormat::Uyvy => (width.div_ceil(2), height),
+        }
+    }
+
+    /// 1 フレームのバイト数
+    pub fn frame_len(format: YuvFormat, width: u32, height: u32) -> usize {
+        let (cw, ch) = Self::chroma_size(format, width, height);
+        let luma = width as usize * height as usize;
+        let chroma = cw as usize * ch as usize;
+        match format {
+            YuvFormat::Nv12 | YuvFormat::I420 => luma + chroma * 2,
+            YuvFormat::Uyvy => chroma * 4,
+        }
+    }
+}
+
+/// 8bit BGRA / RGBA の R, G, B のバイト位置
+fn rgb_offsets(format: DxgiFormat) -> Result<[usize; 3], ImageError> {
+    match format {
+        DxgiFormat::B8G8R8A8Unorm | DxgiFormat::B8G8R8A8UnormSrgb => Ok([2, 1, 0]),
+        DxgiFormat::R8G8B8A8Unorm | DxgiFormat::R8G8B8A8UnormSrgb => Ok([0, 1, 2]),
+        _ => Err(ImageError::UnsupportedFormat(format)),
+    }
+}
+
+fn clamp_u8(value: f32) -> u8 {
+    value.round().clamp(0.0, 255.0) as u8
+}
+
+/// RGB（0-255）→ (Y, Cb, Cr)（範囲適用前、Y: 0..1、C: -0.5..0.5）
+fn rgb_to_ycbcr(rgb: [f32; 3], matrix: YuvMatrix) -> (f32, f32, f32) {
+    let (kr, kb) = matrix.coefficients();
+    let [r, g, b] = rgb.map(|v| v / 255.0);
+    let y = kr * r + (1.0 - kr - kb) * g + kb * b;
+    (y, (b - y) / (2.0 * (1.0 - kb)), (r - y) / (2.0 * (1.0 - kr)))
+}
+
+/// 8bit BGRA / RGBA を YUV へ変換（色差は 2x2 / 2x1 ブロックの平均）
+pub fn to_yuv(view: &ImageView, format: YuvFormat, settings: YuvSettings) -> Result<YuvFrame, ImageError> {
+    let offsets = rgb_offsets(view.format())?;
+    let read = |px: &[u8]| offsets.map(|i| px[i] as f32);
+    let (width, height) = (view.width(), view.height());
+    let (cw, ch) = YuvFrame::chroma_size(format, width, height);
+    let (y_offset, y_scale, c_scale) = settings.range.scale();
+
+    let mut luma = vec![0u8; width as usize * height as usize];
+    let mut cb_plane = vec![0u8; cw as usize * ch as usize];
+    let mut cr_plane = vec![0u8; cw as usize * ch as usize];
+    let (block_w, block_h) = match format {
+        YuvFormat::Nv12 | YuvFormat::I420 => (2, 2),
+        YuvFormat::Uyvy => (2, 1),
+    };
+
+    for cy in 0..ch {
+        for cx in 0..cw {
+            let (mut cb_sum, mut cr_sum, mut count) = (0.0, 0.0, 0.0);
+            for y in (cy * block_h)..((cy + 1) * block_h).min(height) {
+                for x in (cx * block_w)..((cx + 1) * block_w).min(width) {
+                    let (yv, cb, cr) = rgb_to_ycbcr(read(view.pixel(x, y)), settings.matrix);
+                    luma[(y * width + x) as usize] = clamp_u8(y_offset + y_scale * yv);
+                    cb_sum += cb;
+                    cr_sum += cr;
+                    count += 1.0;
+                }
+            }
+            let index = (cy * cw + cx) as usize;
+            cb_plane[index] = clamp_u8(128.0 + c_scale * cb_sum / count);
+            cr_plane[index] = clamp_u8(128.0 + c_scale * cr_sum / count);
+        }
+    }
+
+    let data = match format {
+        YuvFormat::I420 => [luma, cb_plane, cr_plane].concat(),
+        YuvFormat::Nv12 => {
+            let uv = cb_plane.iter().zip(&cr_plane).flat_map(|(&u, &v)| [u, v]);
+            luma.into_iter().chain(uv).collect()
+        }
+        YuvFormat::Uyvy => {
+            let mut data = Vec::with_capacity(YuvFrame::frame_len(format, width, height));
+            for y in 0..height {
+                for cx in 0..cw {
+                    let index = (y * cw + cx) as usize;
+                    let x0 = cx * 2;
+                    let x1 = (x0 + 1).min(width - 1);
+                    data.extend_from_slice(&[
+                        cb_plane[index],
+                        luma[(y * width + x0) as usize],
+                        cr_plane[index],
+                        luma[(y * width + x1) as usize],
+                    ]);
+                }
+            }
+            data
+        }
+    };
+    Ok(YuvFrame { format, width, height, data })
+}
+
+/// YUV を 8bit BGRA（アルファ 255）へ戻す
+pub fn from_yuv(frame: &YuvFrame, settings: YuvSettings) -> Result<ImageBuf, ImageError> {
+    let (width, height) = (frame.width, frame.height);
+    let expected = YuvFrame::frame_len(frame.format, width, height);
+    if frame.data.len() < expected {
+        return Err(ImageError::BufferTooSmall { expected, actual: frame.data.len() });
+    }
+
+    let (cw, ch) = YuvFrame::chroma_size(frame.format, width, height);
+    let luma_len = width as usize * height as usize;
+    let chroma_len = cw as usize * ch as usize;
+    let (kr, kb) = settings.matrix.coefficients();
+    let kg = 1.0 - kr - kb;
+    let (y_offset, y_scale, c_scale) = settings.range.scale();
+    let mut dst = ImageBuf::new(width, height, DxgiFormat::B8G8R8A8Unorm)?;
+
+    for y in 0..height {
+        for x in 0..width {
+            let (cx, cy) = (x / 2, if frame.format == YuvFormat::Uyvy { y } else { y / 2 });
+            let chroma_index = (cy * cw + cx) as usize;
+            let (yv, cb, cr) = match frame.format {
+                YuvFormat::I420 => (
+                    frame.data[(y * width + x) as usize],
+                    frame.data[luma_len + chroma_index],
+                    frame.data[luma_len + chroma_len + chroma_index],
+                ),
+                YuvFormat::Nv12 => (
+                    frame.data[(y * width + x) as usize],
+                    frame.data[luma_len + chroma_index * 2],
+                    frame.data[luma_len + chroma_index * 2 + 1],
+                ),
+                YuvFormat::Uyvy => {
+                    let base = chroma_index * 4;
+                    let luma_offset = if x % 2 == 0 { 1 } else { 3 };
+                    (frame.data[base + luma_offset], frame.data[base], frame.data[base + 2])
+                }
+            };
+
+            let yv = (yv as f32 - y_offset) / y_scale;
+            let cb = (cb as f32 - 128.0) / c_scale;
+            let cr = (cr as f32 - 128.0) / c_scale;
+            let r = yv + 2.0 * (1.0 - kr) * cr;
+            let b = yv + 2.0 * (1.0 - kb) * cb;
+            let g = (yv - kr * r - kb * b) / kg;
+            dst.pixel_mut(x, y).copy_from_slice(&[
+                clamp_u8(b * 255.0),
+                clamp_u8(g * 255.0),
+                clamp_u8(r * 255.0),
+                255,
+            ]);
+        }
+    }
+    Ok(dst)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 色差ブロック（2x2）ごとに 1 色で塗った画像（`colors` 色を横に並べる）
+    fn block_image(colors: &[[u8; 3]]) -> ImageBuf {
+        let mut image = ImageBuf::new(colors.len() as u32 * 2, 2, DxgiFormat::B8G8R8A8Unorm).unwrap();
+        for (i, &[r, g, b]) in colors.iter().enumerate() {
+            for (x, y) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+                image.pixel_mut(i as u32 * 2 + x, y).copy_from_slice(&[b, g, r, 255]);
+            }
+        }
+        image
+    }
+
+    fn max_error(a: &ImageBuf, b: &ImageBuf) -> u8 {
+        a.data().iter().zip(b.data()).map(|(a, b)| a.abs_diff(*b)).max().unwrap_or(0)
+    }
+
+    #[test]
+    fn rgb_yuv_rgb_roundtrip_is_within_rounding() {
+        // 色空間を 15 刻みで埋め、両端の値も含める
+        let levels: Vec<u8> = (0..=255).step_by(15).chain([1, 128, 254]).collect();
+        let mut colors = Vec::new();
+        for &r in &levels {
+            for &g in &levels {
+                for &b in &levels {
+                    colors.push([r, g, b]);
+                }
+            }
+        }
+        let image = block_image(&colors);
+        for matrix in [YuvMatrix::Bt601, YuvMatrix::Bt709] {
+            // limited は 8bit の段数が少ない分、丸めだけで 2 ずれる色がある
+            for (range, limit) in [(YuvRange::Limited, 2), (YuvRange::Full, 1)] {
+                let settings = YuvSettings { matrix, range };
+                for format in [YuvFormat::Nv12, YuvFormat::I420, YuvFormat::Uyvy] {
+                    let frame = to_yuv(&image.view(), format, settings).unwrap();
+                    assert_eq!(frame.data.len(), YuvFrame::frame_len(format, image.width(), image.height()));
+                    let back = from_yuv(&frame, settings).unwrap();
+                    let error = max_error(&image, &back);
+                    assert!(error <= limit, "{:?} {:?} {}: max error {}", matrix, range, format, error);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn odd_sizes_and_rgba_input() {
+        let settings = YuvSettings::default();
+        let mut image = ImageBuf::new(5, 3, DxgiFormat::R8G8B8A8Unorm).unwrap();
+        image.data_mut().chunks_exact_mut(4).for_each(|px| px.copy_from_slice(&[200, 100, 50, 255]));
+        for format in [YuvFormat::Nv12, YuvFormat::I420, YuvFormat::Uyvy] {
+            let back = from_yuv(&to_yuv(&image.view(), format, settings).unwrap(), settings).unwrap();
+            for px in back.data().chunks_exact(4) {
+                assert!(px[0].abs_diff(50) <= 2 && px[1].abs_diff(100) <= 2 && px[2].abs_diff(200) <= 2, "{}", format);
+            }
+        }
+    }
+}
@@ -44,6 +44,8 @@ typedef enum SpoutDxResult {
     SPOUTDX_ERROR_NOT_CONNECTED  = -3,
     SPOUTDX_ERROR_INIT_FAILED    = -4,
     SPOUTDX_ERROR_RECEIVE_FAILED = -5,
+    SPOUTDX_ERROR_SEND_FAILED    = -6,
+    SPOUTDX_ERROR_SENDER_UPDATED = -7,  // receive_image: sender changed, resize and call again
     SPOUTDX_ERROR_INTERNAL       = -99
 } SpoutDxResult;
 
@@ -54,6 +56,19 @@ typedef struct SpoutDxSenderInfo {
     unsigned int format;  // DXGI_FORMAT
 } SpoutDxSenderInfo;
 
+// Transform flags for spoutdx_receiver_receive_image (combine with |)
+// Applied in order: swap R/B -> vertical flip -> horizontal mirror -> rotation (clockwise)
+typedef enum SpoutDxTransform {
+    SPOUTDX_TRANSFORM_NONE              = 0,
+    SPOUTDX_TRANSFORM_FLIP_VERTICAL     = 1 << 0,  // ReceiveImage bInvert / FlipBuffer
+    SPOUTDX_TRANSFORM_MIRROR_HORIZONTAL = 1 << 1,  // SetMirror
+    SPOUTDX_TRANSFORM_SWAP_RB           = 1 << 2,  // SetSwap
+    SPOUTDX_TRANSFORM_ROTATE_90         = 1 << 3,
+    SPOUTDX_TRANSFORM_ROTATE_180        = 2 << 3,
+    SPOUTDX_TRANSFORM_ROTATE_270        = 3 << 3,
+    SPOUTDX_TRANSFORM_ROTATE_MASK       = 3 << 3
+} SpoutDxTransform;
+
 // -- Lifecycle --
 
 SPOUTDX_FFI_API SpoutDxReceiverHandle spoutdx_receiver_create(void);
@@ -74,6 +89,16 @@ SPOUTDX_FFI_API int spoutdx_receiver_set_sender_name(
     const char* sender_name  // NULL for active sender
 );
 
+// Set transform flags (SpoutDxTransform) used by spoutdx_receiver_receive_image
+SPOUTDX_FFI_API int spoutdx_receiver_set_transform(
+    SpoutDxReceiverHandle handle,
+    unsigned int flags
+);
+SPOUTDX_FFI_API int spoutdx_receiver_get_transform(
+    SpoutDxReceiverHandle handle,
+    unsigned int* out_flags
+);
+
 // -- Receive --
 
 // Receive to user-provided texture
@@ -94,6 +119,24 @@ SPOUTDX_FFI_API void* spoutdx_receiver_get_received_texture(SpoutDxReceiverHandl
 // Returns: ID3D11DeviceContext* or NULL
 SPOUTDX_FFI_API void* spoutdx_receiver_get_dx11_context(SpoutDxReceiverHandle handle);
 
+// Receive to CPU pixel buffer (4 bytes per pixel, tightly packed, sender channel order)
+// Required sequence:
+//   1. Call with any buffer. The first call after connecting (or after the sender changes
+//      size/format) writes nothing and returns SPOUTDX_ERROR_SENDER_UPDATED.
+//   2. Read the size with spoutdx_receiver_get_sender_info and allocate width * height * 4 bytes.
+//   3. Call again with that buffer and size every frame. SPOUTDX_OK means the buffer holds the
+//      latest frame (without mirror/rotate it is rewritten only when the sender has a new one).
+//   4. On SPOUTDX_ERROR_SENDER_UPDATED go back to 2.
+// width/height must be the sender size from step 2 (SPOUTDX_ERROR_INTERNAL otherwise).
+// With ROTATE_90/270 the buffer is filled as height x width.
+// This call consumes the update flag itself; spoutdx_receiver_is_updated is not needed.
+SPOUTDX_FFI_API int spoutdx_receiver_receive_image(
+    SpoutDxReceiverHandle handle,
+    unsigned char* pixels,
+    unsigned int width,
+    unsigned int height
+);
+
 SPOUTDX_FFI_API int spoutdx_receiver_release(SpoutDxReceiverHandle handle);
 
 // -- State query --
@@ -103,10 +146,93 @@ SPOUTDX_FFI_API int spoutdx_receiver_get_sender_info(
     SpoutDxSenderInfo* out_info
 );
 
+// Sender frame counter and frame rate (0 if the sender does not enable frame counting)
+SPOUTDX_FFI_API int spoutdx_receiver_get_sender_frame(SpoutDxReceiverHandle handle, long* out_frame);
+SPOUTDX_FFI_API int spoutdx_receiver_get_sender_fps(SpoutDxReceiverHandle handle, double* out_fps);
+
 SPOUTDX_FFI_API int spoutdx_receiver_is_updated(SpoutDxReceiverHandle handle);
 SPOUTDX_FFI_API int spoutdx_receiver_is_connected(SpoutDxReceiverHandle handle);
 SPOUTDX_FFI_API int spoutdx_receiver_is_frame_new(SpoutDxReceiverHandle handle);
 
+// ============================================================
+// Sender API
+// ============================================================
+
+// -- Type definitions --
+
+typedef void* SpoutDxSenderHandle;
+
+// -- Lifecycle --
+
+SPOUTDX_FFI_API SpoutDxSenderHandle spoutdx_sender_create(void);
+SPOUTDX_FFI_API int spoutdx_sender_destroy(SpoutDxSenderHandle handle);
+
+// -- DirectX initialization --
+
+SPOUTDX_FFI_API int spoutdx_sender_open_dx11(
+    SpoutDxSenderHandle handle,
+    void* device  // ID3D11Device*
+);
+SPOUTDX_FFI_API int spoutdx_sender_close_dx11(SpoutDxSenderHandle handle);
+
+// -- Send configuration --
+
+// Set the sender name (NULL for the executable name).
+// If the name is already registered, "_1", "_2", ... is appended (see spoutdx_sender_get_info).
+// Names must be shorter than 256 bytes.
+SPOUTDX_FFI_API int spoutdx_sender_set_name(
+    SpoutDxSenderHandle handle,
+    const char* sender_name
+);
+
+// Set the sender texture format (DXGI_FORMAT, default DXGI_FORMAT_B8G8R8A8_UNORM).
+// Texture and back buffer sends use the format of the texture itself;
+// spoutdx_sender_send_image uses the current sender format.
+SPOUTDX_FFI_API int spoutdx_sender_set_format(
+    SpoutDxSenderHandle handle,
+    unsigned int format
+);
+
+// -- Send --
+
+// Send a texture (created on the device passed to spoutdx_sender_open_dx11).
+// The sender is created on the first call and updated when the size or format changes.
+SPOUTDX_FFI_API int spoutdx_sender_send_texture(
+    SpoutDxSenderHandle handle,
+    void* texture  // ID3D11Texture2D*
+);
+
+// Send CPU pixels in the current sender format
+// (spoutdx_sender_set_format, or the format of the last texture sent).
+// pitch: bytes per row (0 for width * bytes per pixel). The buffer holds pitch * height bytes.
+SPOUTDX_FFI_API int spoutdx_sender_send_image(
+    SpoutDxSenderHandle handle,
+    const unsigned char* pixels,
+    unsigned int width,
+    unsigned int height,
+    unsigned int pitch
+);
+
+// Send the render target view currently bound to the device context
+SPOUTDX_FFI_API int spoutdx_sender_send_back_buffer(SpoutDxSenderHandle handle);
+
+// Close the sender (it is created again by the next send)
+SPOUTDX_FFI_API int spoutdx_sender_release(SpoutDxSenderHandle handle);
+
+// -- State query --
+
+// Registered name, size and format (SPOUTDX_ERROR_NOT_CONNECTED before the first send)
+SPOUTDX_FFI_API int spoutdx_sender_get_info(
+    SpoutDxSenderHandle handle,
+    SpoutDxSenderInfo* out_info
+);
+
+// Frame counter and frame rate of this sender
+SPOUTDX_FFI_API int spoutdx_sender_get_frame(SpoutDxSenderHandle handle, long* out_frame);
+SPOUTDX_FFI_API int spoutdx_sender_get_fps(SpoutDxSenderHandle handle, double* out_fps);
+
+SPOUTDX_FFI_API int spoutdx_sender_is_initialized(SpoutDxSenderHandle handle);
+
 #ifdef __cplusplus
 }
 #endif
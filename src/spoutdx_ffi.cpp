@@ -6,7 +6,9 @@
 // Spout source integration
 #include <SpoutDX.h>
 
+#include <cstdint>
 #include <string>
+#include <vector>
 
 // ============================================================
 // Existing API
@@ -45,14 +47,64 @@ int spoutdx_ffi_test_dx11_init() {
 class SpoutDxReceiver {
 public:
     spoutDX dx;  // SpoutDX instance
+    unsigned int transform = SPOUTDX_TRANSFORM_NONE;  // SpoutDxTransform flags
+    std::vector<unsigned char> scratch;  // receive_image work buffer (mirror/rotate)
+    // Sender size/format the ReceiveImage staging textures were created for (0 = none)
+    unsigned int staging_width = 0;
+    unsigned int staging_height = 0;
+    DXGI_FORMAT staging_format = DXGI_FORMAT_UNKNOWN;
 
     SpoutDxReceiver() = default;
+
+    // The staging textures are released with the receiver or DX11, and only ReceiveImage
+    // re-creates them, so forget them whenever another path may have released or reconnected
+    void ForgetStaging() {
+        staging_width = 0;
+        staging_height = 0;
+        staging_format = DXGI_FORMAT_UNKNOWN;
+    }
+
     ~SpoutDxReceiver() {
         dx.ReleaseReceiver();
         dx.CloseDirectX11();
     }
 };
 
+static constexpr unsigned int kTransformKnownFlags =
+    SPOUTDX_TRANSFORM_FLIP_VERTICAL | SPOUTDX_TRANSFORM_MIRROR_HORIZONTAL |
+    SPOUTDX_TRANSFORM_SWAP_RB | SPOUTDX_TRANSFORM_ROTATE_MASK;
+
+// Mirror / rotate on CPU (flip and R/B swap are done by ReceiveImage)
+static void TransformPixels(const unsigned char* src, unsigned char* dst,
+                            unsigned int width, unsigned int height, unsigned int flags) {
+    const auto* s = reinterpret_cast<const uint32_t*>(src);
+    auto* d = reinterpret_cast<uint32_t*>(dst);
+    const bool mirror = (flags & SPOUTDX_TRANSFORM_MIRROR_HORIZONTAL) != 0;
+    const unsigned int rotation = flags & SPOUTDX_TRANSFORM_ROTATE_MASK;
+
+    for (unsigned int y = 0; y < height; y++) {
+        for (unsigned int x = 0; x < width; x++) {
+            const unsigned int sx = mirror ? width - 1 - x : x;
+            size_t index;
+            switch (rotation) {
+                case SPOUTDX_TRANSFORM_ROTATE_90:
+                    index = static_cast<size_t>(x) * height + (height - 1 - y);
+                    break;
+                case SPOUTDX_TRANSFORM_ROTATE_180:
+                    index = static_cast<size_t>(height - 1 - y) * width + (width - 1 - x);
+                    break;
+                case SPOUTDX_TRANSFORM_ROTATE_270:
+                    index = static_cast<size_t>(width - 1 - x) * height + y;
+                    break;
+                default:
+                    index = static_cast<size_t>(y) * width + x;
+                    break;
+            }
+            d[index] = s[static_cast<size_t>(y) * width + sx];
+        }
+    }
+}
+
 // -- Lifecycle --
 
 SpoutDxReceiverHandle spoutdx_receiver_create() {
@@ -94,6 +146,7 @@ int spoutdx_receiver_close_dx11(SpoutDxReceiverHandle handle) {
     if (!handle) return SPOUTDX_ERROR_NULL_HANDLE;
     try {
         auto* rx = static_cast<SpoutDxReceiver*>(handle);
+        rx->ForgetStaging();
         rx->dx.CloseDirectX11();
         return SPOUTDX_OK;
     } catch (...) {
@@ -114,6 +167,31 @@ int spoutdx_receiver_set_sender_name(SpoutDxReceiverHandle handle, const char* s
     }
 }
 
+int spoutdx_receiver_set_transform(SpoutDxReceiverHandle handle, unsigned int flags) {
+    if (!handle) return SPOUTDX_ERROR_NULL_HANDLE;
+    if (flags & ~kTransformKnownFlags) return SPOUTDX_ERROR_INTERNAL;
+    try {
+        auto* rx = static_cast<SpoutDxReceiver*>(handle);
+        rx->transform = flags;
+        rx->dx.SetSwap((flags & SPOUTDX_TRANSFORM_SWAP_RB) != 0);
+        return SPOUTDX_OK;
+    } catch (...) {
+        return SPOUTDX_ERROR_INTERNAL;
+    }
+}
+
+int spoutdx_receiver_get_transform(SpoutDxReceiverHandle handle, unsigned int* out_flags) {
+    if (!handle) return SPOUTDX_ERROR_NULL_HANDLE;
+    if (!out_flags) return SPOUTDX_ERROR_INTERNAL;
+    try {
+        auto* rx = static_cast<SpoutDxReceiver*>(handle);
+        *out_flags = rx->transform;
+        return SPOUTDX_OK;
+    } catch (...) {
+        return SPOUTDX_ERROR_INTERNAL;
+    }
+}
+
 // -- Receive --
 
 int spoutdx_receiver_receive_texture(SpoutDxReceiverHandle handle, void* dst_texture) {
@@ -122,6 +200,7 @@ int spoutdx_receiver_receive_texture(SpoutDxReceiverHandle handle, void* dst_tex
         auto* rx = static_cast<SpoutDxReceiver*>(handle);
         ID3D11Texture2D* pTexture = static_cast<ID3D11Texture2D*>(dst_texture);
         ID3D11Texture2D* pTexturePtr = pTexture;
+        rx->ForgetStaging();
 
         // Use ReceiveTexture(ID3D11Texture2D**)
         // This function searches for sender and copies from shared texture to dst
@@ -141,6 +220,7 @@ int spoutdx_receiver_receive(SpoutDxReceiverHandle handle) {
     if (!handle) return SPOUTDX_ERROR_NULL_HANDLE;
     try {
         auto* rx = static_cast<SpoutDxReceiver*>(handle);
+        rx->ForgetStaging();
 
         // Use ReceiveTexture() - receives to internal class texture
         if (!rx->dx.ReceiveTexture()) {
@@ -176,10 +256,74 @@ void* spoutdx_receiver_get_dx11_context(SpoutDxReceiverHandle handle) {
     }
 }
 
+int spoutdx_receiver_receive_image(SpoutDxReceiverHandle handle, unsigned char* pixels,
+                                   unsigned int width, unsigned int height) {
+    if (!handle) return SPOUTDX_ERROR_NULL_HANDLE;
+    if (!pixels) return SPOUTDX_ERROR_INTERNAL;
+    try {
+        auto* rx = static_cast<SpoutDxReceiver*>(handle);
+        const unsigned int flags = rx->transform;
+        const bool invert = (flags & SPOUTDX_TRANSFORM_FLIP_VERTICAL) != 0;
+        const bool cpu_pass = (flags & (SPOUTDX_TRANSFORM_MIRROR_HORIZONTAL | SPOUTDX_TRANSFORM_ROTATE_MASK)) != 0;
+
+        // ReceiveImage creates its staging textures only while handling a sender update.
+        // A connection made by ReceiveTexture (or a size change it consumed) leaves none or
+        // stale ones, so reconnect to make ReceiveImage see the sender as new.
+        // (Not connected: the next connection goes through the update below anyway.)
+        const bool staged = rx->staging_width != 0
+            && rx->staging_width == rx->dx.GetSenderWidth()
+            && rx->staging_height == rx->dx.GetSenderHeight()
+            && rx->staging_format == rx->dx.GetSenderFormat();
+        if (rx->dx.IsConnected() && !staged) {
+            rx->ForgetStaging();
+            rx->dx.ReleaseReceiver();
+        } else if (rx->dx.IsConnected() && (width != rx->staging_width || height != rx->staging_height)) {
+            // The buffer must match the sender size reported after the last update
+            return SPOUTDX_ERROR_INTERNAL;
+        }
+
+        // Mirror/rotate need the unmodified frame, so receive into the work buffer first
+        unsigned char* target = pixels;
+        if (cpu_pass) {
+            rx->scratch.resize(static_cast<size_t>(width) * height * 4);
+            target = rx->scratch.data();
+        }
+
+        // bRGB = false: 4 bytes per pixel. R/B swap is applied via SetSwap.
+        const bool received = rx->dx.ReceiveImage(target, width, height, false, invert);
+
+        // On an update ReceiveImage only re-creates the staging textures and copies nothing.
+        // The flag is consumed here and reported so the caller can resize and call again.
+        if (rx->dx.IsUpdated()) {
+            rx->staging_width = rx->dx.GetSenderWidth();
+            rx->staging_height = rx->dx.GetSenderHeight();
+            rx->staging_format = rx->dx.GetSenderFormat();
+            return SPOUTDX_ERROR_SENDER_UPDATED;
+        }
+        if (!received) {
+            if (!rx->dx.IsConnected()) {
+                // ReceiveImage released the receiver along with the staging textures
+                rx->ForgetStaging();
+                return SPOUTDX_ERROR_NOT_CONNECTED;
+            }
+            return SPOUTDX_ERROR_RECEIVE_FAILED;
+        }
+
+        // The work buffer keeps the last frame, so transforming every call is always current
+        if (cpu_pass) {
+            TransformPixels(rx->scratch.data(), pixels, width, height, flags);
+        }
+        return SPOUTDX_OK;
+    } catch (...) {
+        return SPOUTDX_ERROR_INTERNAL;
+    }
+}
+
 int spoutdx_receiver_release(SpoutDxReceiverHandle handle) {
     if (!handle) return SPOUTDX_ERROR_NULL_HANDLE;
     try {
         auto* rx = static_cast<SpoutDxReceiver*>(handle);
+        rx->ForgetStaging();
         rx->dx.ReleaseReceiver();
         return SPOUTDX_OK;
     } catch (...) {
@@ -214,11 +358,38 @@ int spoutdx_receiver_get_sender_info(SpoutDxReceiverHandle handle, SpoutDxSender
     }
 }
 
+int spoutdx_receiver_get_sender_frame(SpoutDxReceiverHandle handle, long* out_frame) {
+    if (!handle) return SPOUTDX_ERROR_NULL_HANDLE;
+    if (!out_frame) return SPOUTDX_ERROR_INTERNAL;
+    try {
+        auto* rx = static_cast<SpoutDxReceiver*>(handle);
+        *out_frame = rx->dx.GetSenderFrame();
+        return SPOUTDX_OK;
+    } catch (...) {
+        return SPOUTDX_ERROR_INTERNAL;
+    }
+}
+
+int spoutdx_receiver_get_sender_fps(SpoutDxReceiverHandle handle, double* out_fps) {
+    if (!handle) return SPOUTDX_ERROR_NULL_HANDLE;
+    if (!out_fps) return SPOUTDX_ERROR_INTERNAL;
+    try {
+        auto* rx = static_cast<SpoutDxReceiver*>(handle);
+        *out_fps = rx->dx.GetSenderFps();
+        return SPOUTDX_OK;
+    } catch (...) {
+        return SPOUTDX_ERROR_INTERNAL;
+    }
+}
+
 int spoutdx_receiver_is_updated(SpoutDxReceiverHandle handle) {
     if (!handle) return 0;
     try {
         auto* rx = static_cast<SpoutDxReceiver*>(handle);
-        return rx->dx.IsUpdated() ? 1 : 0;
+        if (!rx->dx.IsUpdated()) return 0;
+        // receive_image consumes its own updates, so this one left no staging textures behind
+        rx->ForgetStaging();
+        return 1;
     } catch (...) {
         return 0;
     }
@@ -243,3 +414,249 @@ int spoutdx_receiver_is_frame_new(SpoutDxReceiverHandle handle) {
         return 0;
     }
 }
+
+// ============================================================
+// Sender API implementation
+// ============================================================
+
+// Bytes per pixel of the formats a sender can use (0 if unknown)
+static unsigned int SenderBytesPerPixel(DXGI_FORMAT format) {
+    switch (format) {
+        case DXGI_FORMAT_R32G32B32A32_FLOAT:
+            return 16;
+        case DXGI_FORMAT_R16G16B16A16_FLOAT:
+        case DXGI_FORMAT_R16G16B16A16_UNORM:
+            return 8;
+        case DXGI_FORMAT_R10G10B10A2_UNORM:
+        case DXGI_FORMAT_R8G8B8A8_UNORM:
+        case DXGI_FORMAT_R8G8B8A8_UNORM_SRGB:
+        case DXGI_FORMAT_B8G8R8A8_UNORM:
+        case DXGI_FORMAT_B8G8R8A8_UNORM_SRGB:
+        case DXGI_FORMAT_B8G8R8X8_UNORM:
+            return 4;
+        default:
+            return 0;
+    }
+}
+
+// Internal sender wrapper class (C++ only)
+class SpoutDxSender {
+public:
+    spoutDX dx;  // SpoutDX instance
+
+    SpoutDxSender() = default;
+    ~SpoutDxSender() {
+        dx.ReleaseSender();
+        dx.CloseDirectX11();
+    }
+};
+
+// -- Lifecycle --
+
+SpoutDxSenderHandle spoutdx_sender_create() {
+    try {
+        return new SpoutDxSender();
+    } catch (...) {
+        return nullptr;
+    }
+}
+
+int spoutdx_sender_destroy(SpoutDxSenderHandle handle) {
+    if (!handle) return SPOUTDX_ERROR_NULL_HANDLE;
+    try {
+        delete static_cast<SpoutDxSender*>(handle);
+        return SPOUTDX_OK;
+    } catch (...) {
+        return SPOUTDX_ERROR_INTERNAL;
+    }
+}
+
+// -- DirectX initialization --
+
+int spoutdx_sender_open_dx11(SpoutDxSenderHandle handle, void* device) {
+    if (!handle) return SPOUTDX_ERROR_NULL_HANDLE;
+    if (!device) return SPOUTDX_ERROR_NULL_DEVICE;
+    try {
+        auto* tx = static_cast<SpoutDxSender*>(handle);
+        ID3D11Device* pDevice = static_cast<ID3D11Device*>(device);
+        if (!tx->dx.OpenDirectX11(pDevice)) {
+            return SPOUTDX_ERROR_INIT_FAILED;
+        }
+        return SPOUTDX_OK;
+    } catch (...) {
+        return SPOUTDX_ERROR_INTERNAL;
+    }
+}
+
+int spoutdx_sender_close_dx11(SpoutDxSenderHandle handle) {
+    if (!handle) return SPOUTDX_ERROR_NULL_HANDLE;
+    try {
+        auto* tx = static_cast<SpoutDxSender*>(handle);
+        tx->dx.ReleaseSender();
+        tx->dx.CloseDirectX11();
+        return SPOUTDX_OK;
+    } catch (...) {
+        return SPOUTDX_ERROR_INTERNAL;
+    }
+}
+
+// -- Send configuration --
+
+int spoutdx_sender_set_name(SpoutDxSenderHandle handle, const char* sender_name) {
+    if (!handle) return SPOUTDX_ERROR_NULL_HANDLE;
+    // SetSenderName copies with strcpy_s, which aborts on overlong names
+    if (sender_name && (sender_name[0] == '\0' || strnlen(sender_name, 256) >= 256)) {
+        return SPOUTDX_ERROR_INTERNAL;
+    }
+    try {
+        auto* tx = static_cast<SpoutDxSender*>(handle);
+        if (!tx->dx.SetSenderName(sender_name)) {  // NULL for the executable name
+            return SPOUTDX_ERROR_INTERNAL;
+        }
+        return SPOUTDX_OK;
+    } catch (...) {
+        return SPOUTDX_ERROR_INTERNAL;
+    }
+}
+
+int spoutdx_sender_set_format(SpoutDxSenderHandle handle, unsigned int format) {
+    if (!handle) return SPOUTDX_ERROR_NULL_HANDLE;
+    try {
+        auto* tx = static_cast<SpoutDxSender*>(handle);
+        tx->dx.SetSenderFormat(static_cast<DXGI_FORMAT>(format));
+        return SPOUTDX_OK;
+    } catch (...) {
+        return SPOUTDX_ERROR_INTERNAL;
+    }
+}
+
+// -- Send --
+
+int spoutdx_sender_send_texture(SpoutDxSenderHandle handle, void* texture) {
+    if (!handle) return SPOUTDX_ERROR_NULL_HANDLE;
+    if (!texture) return SPOUTDX_ERROR_INTERNAL;
+    try {
+        auto* tx = static_cast<SpoutDxSender*>(handle);
+        ID3D11Texture2D* pTexture = static_cast<ID3D11Texture2D*>(texture);
+
+        // Creates the sender on the first call and re-creates it on size/format changes
+        if (!tx->dx.SendTexture(pTexture)) {
+            return SPOUTDX_ERROR_SEND_FAILED;
+        }
+        return SPOUTDX_OK;
+    } catch (...) {
+        return SPOUTDX_ERROR_INTERNAL;
+    }
+}
+
+int spoutdx_sender_send_image(SpoutDxSenderHandle handle, const unsigned char* pixels,
+                              unsigned int width, unsigned int height, unsigned int pitch) {
+    if (!handle) return SPOUTDX_ERROR_NULL_HANDLE;
+    if (!pixels || width == 0 || height == 0) return SPOUTDX_ERROR_INTERNAL;
+    try {
+        auto* tx = static_cast<SpoutDxSender*>(handle);
+
+        // SendImage assumes 4 bytes per pixel when pitch is 0, so resolve it from the sender format
+        const unsigned int bpp = SenderBytesPerPixel(tx->dx.GetSenderFormat());
+        if (pitch == 0) {
+            if (bpp == 0) return SPOUTDX_ERROR_INTERNAL;
+            pitch = width * bpp;
+        } else if (bpp != 0 && pitch < width * bpp) {
+            return SPOUTDX_ERROR_INTERNAL;
+        }
+
+        // Creates the sender on the first call and re-creates it on size changes
+        if (!tx->dx.SendImage(pixels, width, height, pitch)) {
+            return SPOUTDX_ERROR_SEND_FAILED;
+        }
+        return SPOUTDX_OK;
+    } catch (...) {
+        return SPOUTDX_ERROR_INTERNAL;
+    }
+}
+
+int spoutdx_sender_send_back_buffer(SpoutDxSenderHandle handle) {
+    if (!handle) return SPOUTDX_ERROR_NULL_HANDLE;
+    try {
+        auto* tx = static_cast<SpoutDxSender*>(handle);
+        if (!tx->dx.SendBackBuffer()) {
+            return SPOUTDX_ERROR_SEND_FAILED;
+        }
+        return SPOUTDX_OK;
+    } catch (...) {
+        return SPOUTDX_ERROR_INTERNAL;
+    }
+}
+
+int spoutdx_sender_release(SpoutDxSenderHandle handle) {
+    if (!handle) return SPOUTDX_ERROR_NULL_HANDLE;
+    try {
+        auto* tx = static_cast<SpoutDxSender*>(handle);
+        tx->dx.ReleaseSender();
+        return SPOUTDX_OK;
+    } catch (...) {
+        return SPOUTDX_ERROR_INTERNAL;
+    }
+}
+
+// -- State query --
+
+int spoutdx_sender_get_info(SpoutDxSenderHandle handle, SpoutDxSenderInfo* out_info) {
+    if (!handle) return SPOUTDX_ERROR_NULL_HANDLE;
+    if (!out_info) return SPOUTDX_ERROR_INTERNAL;
+    try {
+        auto* tx = static_cast<SpoutDxSender*>(handle);
+        if (!tx->dx.IsInitialized()) {
+            return SPOUTDX_ERROR_NOT_CONNECTED;
+        }
+
+        const char* name = tx->dx.GetName();
+        if (name) {
+            strncpy_s(out_info->name, sizeof(out_info->name), name, _TRUNCATE);
+        } else {
+            out_info->name[0] = '\0';
+        }
+
+        out_info->width = tx->dx.GetWidth();
+        out_info->height = tx->dx.GetHeight();
+        out_info->format = static_cast<unsigned int>(tx->dx.GetSenderFormat());
+
+        return SPOUTDX_OK;
+    } catch (...) {
+        return SPOUTDX_ERROR_INTERNAL;
+    }
+}
+
+int spoutdx_sender_get_frame(SpoutDxSenderHandle handle, long* out_frame) {
+    if (!handle) return SPOUTDX_ERROR_NULL_HANDLE;
+    if (!out_frame) return SPOUTDX_ERROR_INTERNAL;
+    try {
+        auto* tx = static_cast<SpoutDxSender*>(handle);
+        *out_frame = tx->dx.GetFrame();
+        return SPOUTDX_OK;
+    } catch (...) {
+        return SPOUTDX_ERROR_INTERNAL;
+    }
+}
+
+int spoutdx_sender_get_fps(SpoutDxSenderHandle handle, double* out_fps) {
+    if (!handle) return SPOUTDX_ERROR_NULL_HANDLE;
+    if (!out_fps) return SPOUTDX_ERROR_INTERNAL;
+    try {
+        auto* tx = static_cast<SpoutDxSender*>(handle);
+        *out_fps = tx->dx.GetFps();
+        return SPOUTDX_OK;
+    } catch (...) {
+        return SPOUTDX_ERROR_INTERNAL;
+    }
+}
+
+int spoutdx_sender_is_initialized(SpoutDxSenderHandle handle) {
+    if (!handle) return 0;
+    try {
+        auto* tx = static_cast<SpoutDxSender*>(handle);
+        return tx->dx.IsInitialized() ? 1 : 0;
+    } catch (...) {
+        return 0;
+    }
+}